@@ -13,7 +13,7 @@ use once_cell::sync::Lazy;
 pub use parser::Hunk;
 pub use parser::ParseError;
 use parser::ParseError::*;
-use parser::UpdateFileChunk;
+pub use parser::UpdateFileChunk;
 pub use parser::parse_patch;
 use similar::TextDiff;
 use thiserror::Error;
@@ -40,6 +40,9 @@ pub enum ApplyPatchError {
     /// Error that occurs while computing replacements when applying patch chunks
     #[error("{0}")]
     ComputeReplacements(String),
+    /// Error that occurs when a patch cannot be reversed
+    #[error("{0}")]
+    Reverse(String),
 }
 
 impl From<std::io::Error> for ApplyPatchError {
@@ -120,8 +123,11 @@ pub fn maybe_parse_apply_patch(argv: &[String]) -> MaybeApplyPatch {
 pub enum ApplyPatchFileChange {
     Add {
         content: String,
+        /// Permission bits to give the new file, e.g. `0o755`.
+        mode: Option<u32>,
     },
     Delete {
+        /// Empty if the file is not valid UTF-8.
         content: String,
     },
     Update {
@@ -129,6 +135,14 @@ pub enum ApplyPatchFileChange {
         move_path: Option<PathBuf>,
         /// new_content that will result after the unified_diff is applied.
         new_content: String,
+        /// Permission bits to give the file, e.g. `0o755`.
+        mode: Option<u32>,
+    },
+    /// The file is a byte-for-byte copy of `source`.
+    Copy {
+        source: PathBuf,
+        /// Permission bits to give the copy; those of `source` if unset.
+        mode: Option<u32>,
     },
 }
 
@@ -191,7 +205,13 @@ impl ApplyPatchAction {
 + {content}
 *** End Patch"#,
         );
-        let changes = HashMap::from([(path.to_path_buf(), ApplyPatchFileChange::Add { content })]);
+        let changes = HashMap::from([(
+            path.to_path_buf(),
+            ApplyPatchFileChange::Add {
+                content,
+                mode: None,
+            },
+        )]);
         #[expect(clippy::expect_used)]
         Self {
             changes,
@@ -228,12 +248,18 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
             for hunk in hunks {
                 let path = hunk.resolve_path(&effective_cwd);
                 match hunk {
-                    Hunk::AddFile { contents, .. } => {
-                        changes.insert(path, ApplyPatchFileChange::Add { content: contents });
+                    Hunk::AddFile { contents, mode, .. } => {
+                        changes.insert(
+                            path,
+                            ApplyPatchFileChange::Add {
+                                content: contents,
+                                mode,
+                            },
+                        );
                     }
                     Hunk::DeleteFile { .. } => {
-                        let content = match std::fs::read_to_string(&path) {
-                            Ok(content) => content,
+                        let content = match std::fs::read(&path) {
+                            Ok(bytes) => String::from_utf8(bytes).unwrap_or_default(),
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(
                                     ApplyPatchError::IoError(IoError {
@@ -246,12 +272,32 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                         changes.insert(path, ApplyPatchFileChange::Delete { content });
                     }
                     Hunk::UpdateFile {
-                        move_path, chunks, ..
+                        move_path,
+                        mode,
+                        chunks,
+                        ..
                     } => {
+                        let update = if chunks.is_empty() {
+                            // Only the mode or location changes, so the file
+                            // may be binary.
+                            std::fs::read(&path)
+                                .map(|bytes| ApplyPatchFileUpdate {
+                                    unified_diff: String::new(),
+                                    content: String::from_utf8_lossy(&bytes).into_owned(),
+                                })
+                                .map_err(|e| {
+                                    ApplyPatchError::IoError(IoError {
+                                        context: format!("Failed to read {}", path.display()),
+                                        source: e,
+                                    })
+                                })
+                        } else {
+                            unified_diff_from_chunks(&path, &chunks)
+                        };
                         let ApplyPatchFileUpdate {
                             unified_diff,
                             content: contents,
-                        } = match unified_diff_from_chunks(&path, &chunks) {
+                        } = match update {
                             Ok(diff) => diff,
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(e);
@@ -263,9 +309,26 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                                 unified_diff,
                                 move_path: move_path.map(|p| cwd.join(p)),
                                 new_content: contents,
+                                mode,
                             },
                         );
                     }
+                    Hunk::CopyFile {
+                        copy_path, mode, ..
+                    } => {
+                        if let Err(e) = std::fs::metadata(&path) {
+                            return MaybeApplyPatchVerified::CorrectnessError(
+                                ApplyPatchError::IoError(IoError {
+                                    context: format!("Failed to read {}", path.display()),
+                                    source: e,
+                                }),
+                            );
+                        }
+                        changes.insert(
+                            effective_cwd.join(copy_path),
+                            ApplyPatchFileChange::Copy { source: path, mode },
+                        );
+                    }
                 }
             }
             MaybeApplyPatchVerified::Body(ApplyPatchAction {
@@ -447,8 +510,20 @@ pub fn apply_patch(
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
-    let hunks = match parse_patch(patch) {
-        Ok(source) => source.hunks,
+    let hunks = parse_patch_reporting_errors(patch, stderr)?;
+
+    apply_hunks(&hunks, stdout, stderr)?;
+
+    Ok(())
+}
+
+/// Parses the patch into hunks, describing any parse error on stderr.
+pub(crate) fn parse_patch_reporting_errors(
+    patch: &str,
+    stderr: &mut impl std::io::Write,
+) -> Result<Vec<Hunk>, ApplyPatchError> {
+    match parse_patch(patch) {
+        Ok(source) => Ok(source.hunks),
         Err(e) => {
             match &e {
                 InvalidPatchError(message) => {
@@ -465,13 +540,9 @@ pub fn apply_patch(
                     .map_err(ApplyPatchError::from)?;
                 }
             }
-            return Err(ApplyPatchError::ParseError(e));
+            Err(ApplyPatchError::ParseError(e))
         }
-    };
-
-    apply_hunks(&hunks, stdout, stderr)?;
-
-    Ok(())
+    }
 }
 
 /// Applies hunks and continues to update stdout/stderr
@@ -503,15 +574,31 @@ pub fn apply_hunks(
                 }
                 None => Some(path.as_path()),
             },
+            Hunk::CopyFile { path, .. } => Some(path.as_path()),
         })
         .collect::<Vec<&Path>>();
 
-    // Delegate to a helper that applies each hunk to the filesystem.
-    match apply_hunks_to_files(hunks) {
-        Ok(affected) => {
-            print_summary(&affected, stdout).map_err(ApplyPatchError::from)?;
-            Ok(())
-        }
+    let affected = apply_or_check_hunks(hunks, false, stderr)?;
+    print_summary(&affected, stdout).map_err(ApplyPatchError::from)?;
+    Ok(())
+}
+
+/// Applies the hunks to the filesystem, or with `dry_run` only checks that
+/// every hunk would apply, and returns the paths that are (or would be)
+/// affected. Errors are also written to stderr.
+pub fn apply_or_check_hunks(
+    hunks: &[Hunk],
+    dry_run: bool,
+    stderr: &mut impl std::io::Write,
+) -> Result<AffectedPaths, ApplyPatchError> {
+    let result = if dry_run {
+        check_hunks(hunks)
+    } else {
+        // Delegate to a helper that applies each hunk to the filesystem.
+        apply_hunks_to_files(hunks)
+    };
+    match result {
+        Ok(affected) => Ok(affected),
         Err(err) => {
             let msg = err.to_string();
             writeln!(stderr, "{msg}").map_err(ApplyPatchError::from)?;
@@ -538,68 +625,384 @@ pub struct AffectedPaths {
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
 /// Returns an error if the patch could not be applied.
+///
+/// The patch is applied all-or-nothing: every hunk is first applied in memory,
+/// and only once all of them succeed are the files written. If a write fails,
+/// the files written before it are restored.
 fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
 
+    let PatchPlan { files, affected } = plan_hunks(hunks)?;
+    write_planned_files(&files)?;
+    Ok(affected)
+}
+
+/// Checks that every hunk applies, without touching the filesystem.
+fn check_hunks(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
+    if hunks.is_empty() {
+        anyhow::bail!("No files were modified.");
+    }
+
+    Ok(plan_hunks(hunks)?.affected)
+}
+
+/// Result of applying every hunk of a patch in memory.
+struct PatchPlan {
+    /// Final state of every path the patch touches, in the order they are
+    /// first touched. `None` means the path is deleted.
+    files: Vec<(PathBuf, Option<PlannedFile>)>,
+    affected: AffectedPaths,
+}
+
+/// Planned contents of a file. Kept as bytes so that copies, moves and mode
+/// changes work for binary files too.
+#[derive(Clone)]
+struct PlannedFile {
+    contents: Vec<u8>,
+    /// Permission bits to write the file with; `None` keeps those of the file
+    /// being replaced, or the default for a new file.
+    mode: Option<u32>,
+}
+
+/// Applies the hunks in order against an in-memory view of the files, so
+/// that a later hunk sees the result of earlier ones. Nothing is written.
+fn plan_hunks(hunks: &[Hunk]) -> anyhow::Result<PatchPlan> {
+    let mut files: Vec<(PathBuf, Option<PlannedFile>)> = Vec::new();
     let mut added: Vec<PathBuf> = Vec::new();
     let mut modified: Vec<PathBuf> = Vec::new();
     let mut deleted: Vec<PathBuf> = Vec::new();
-    for hunk in hunks {
+
+    fn set(
+        files: &mut Vec<(PathBuf, Option<PlannedFile>)>,
+        path: &Path,
+        planned: Option<PlannedFile>,
+    ) {
+        match files.iter_mut().find(|(p, _)| p == path) {
+            Some((_, existing)) => *existing = planned,
+            None => files.push((path.to_path_buf(), planned)),
+        }
+    }
+
+    /// The file at `path` as earlier hunks left it.
+    fn current(
+        files: &[(PathBuf, Option<PlannedFile>)],
+        path: &Path,
+    ) -> std::io::Result<Option<PlannedFile>> {
+        if let Some((_, planned)) = files.iter().find(|(p, _)| p == path) {
+            return Ok(planned.clone());
+        }
+        match std::fs::read(path) {
+            Ok(contents) => Ok(Some(PlannedFile {
+                contents,
+                mode: None,
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    let total = hunks.len();
+    for (index, hunk) in hunks.iter().enumerate() {
+        let describe = |err: &dyn std::fmt::Display| {
+            anyhow::anyhow!(
+                "Failed to apply hunk {} of {total} ({}): {err}\nNo files were modified.",
+                index + 1,
+                describe_hunk(hunk),
+            )
+        };
         match hunk {
-            Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+            Hunk::AddFile {
+                path,
+                contents,
+                mode,
+            } => {
+                let planned = PlannedFile {
+                    contents: contents.clone().into_bytes(),
+                    mode: *mode,
+                };
+                set(&mut files, path, Some(planned));
                 added.push(path.clone());
             }
             Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
+                match current(&files, path) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Err(describe(&"file does not exist")),
+                    Err(err) => return Err(describe(&err)),
+                }
+                set(&mut files, path, None);
                 deleted.push(path.clone());
             }
             Hunk::UpdateFile {
                 path,
                 move_path,
+                mode,
                 chunks,
             } => {
-                let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
-                if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
-                    modified.push(dest.clone());
+                let original = match current(&files, path) {
+                    Ok(Some(original)) => original,
+                    Ok(None) => return Err(describe(&"file does not exist")),
+                    Err(err) => return Err(describe(&err)),
+                };
+                let contents = if chunks.is_empty() {
+                    original.contents
                 } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
-                    modified.push(path.clone());
+                    let original_contents = String::from_utf8(original.contents)
+                        .map_err(|_| describe(&"file is not valid UTF-8"))?;
+                    let AppliedPatch { new_contents, .. } =
+                        derive_new_contents_from_original(original_contents, path, chunks)
+                            .map_err(|err| describe(&err))?;
+                    new_contents.into_bytes()
+                };
+                match move_path {
+                    Some(dest) => {
+                        // A moved file keeps its mode, like `mv` does.
+                        let planned = PlannedFile {
+                            contents,
+                            mode: mode.or(original.mode).or_else(|| file_mode(path)),
+                        };
+                        set(&mut files, path, None);
+                        set(&mut files, dest, Some(planned));
+                        modified.push(dest.clone());
+                    }
+                    None => {
+                        let planned = PlannedFile {
+                            contents,
+                            mode: mode.or(original.mode),
+                        };
+                        set(&mut files, path, Some(planned));
+                        modified.push(path.clone());
+                    }
                 }
             }
+            Hunk::CopyFile {
+                path,
+                copy_path,
+                mode,
+            } => {
+                let source = match current(&files, path) {
+                    Ok(Some(source)) => source,
+                    Ok(None) => return Err(describe(&"file does not exist")),
+                    Err(err) => return Err(describe(&err)),
+                };
+                let planned = PlannedFile {
+                    contents: source.contents,
+                    mode: mode.or(source.mode).or_else(|| file_mode(path)),
+                };
+                set(&mut files, copy_path, Some(planned));
+                added.push(copy_path.clone());
+            }
         }
     }
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
+
+    Ok(PatchPlan {
+        files,
+        affected: AffectedPaths {
+            added,
+            modified,
+            deleted,
+        },
     })
 }
 
+/// Permission bits of the file at `path` on disk, where the platform has
+/// them.
+#[cfg(unix)]
+fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Permissions for a file written with the given mode. Without a mode, or
+/// where the platform has no permission bits, those of `original` are kept.
+fn permissions_for(
+    mode: Option<u32>,
+    original: Option<&std::fs::Permissions>,
+) -> Option<std::fs::Permissions> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        return Some(std::fs::Permissions::from_mode(mode));
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    original.cloned()
+}
+
+fn describe_hunk(hunk: &Hunk) -> String {
+    match hunk {
+        Hunk::AddFile { path, .. } => format!("Add File: {}", path.display()),
+        Hunk::DeleteFile { path } => format!("Delete File: {}", path.display()),
+        Hunk::UpdateFile { path, .. } => format!("Update File: {}", path.display()),
+        Hunk::CopyFile { path, .. } => format!("Copy File: {}", path.display()),
+    }
+}
+
+/// What a path looked like before the patch, to restore it on failure.
+struct Backup {
+    path: PathBuf,
+    original: Option<(Vec<u8>, std::fs::Permissions)>,
+    /// Directories created for the new file, deepest first.
+    created_dirs: Vec<PathBuf>,
+}
+
+/// Writes the planned contents, each file through a temporary file renamed
+/// over the original. On failure, every path already written is restored.
+fn write_planned_files(files: &[(PathBuf, Option<PlannedFile>)]) -> anyhow::Result<()> {
+    let mut backups: Vec<Backup> = Vec::with_capacity(files.len());
+    for (path, planned) in files {
+        let original = match std::fs::read(path) {
+            Ok(bytes) => {
+                let permissions = std::fs::metadata(path)
+                    .with_context(|| format!("Failed to read file {}", path.display()))?
+                    .permissions();
+                // Renaming would replace a read-only file that a plain write
+                // could not modify, unless the patch sets the mode itself.
+                if planned.as_ref().is_some_and(|p| p.mode.is_none()) && permissions.readonly() {
+                    anyhow::bail!("Failed to write file {}: file is read-only", path.display());
+                }
+                Some((bytes, permissions))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(anyhow::Error::new(err))
+                    .with_context(|| format!("Failed to read file {}", path.display()));
+            }
+        };
+        backups.push(Backup {
+            path: path.clone(),
+            original,
+            created_dirs: Vec::new(),
+        });
+    }
+
+    for index in 0..files.len() {
+        let (path, planned) = &files[index];
+        let result = match planned {
+            Some(planned) => {
+                create_parent_dirs(path, &mut backups[index].created_dirs).and_then(|()| {
+                    let original = backups[index].original.as_ref().map(|(_, p)| p);
+                    let permissions = permissions_for(planned.mode, original);
+                    write_atomically(path, &planned.contents, permissions.as_ref())
+                        .with_context(|| format!("Failed to write file {}", path.display()))
+                })
+            }
+            None => std::fs::remove_file(path)
+                .with_context(|| format!("Failed to delete file {}", path.display())),
+        };
+        if let Err(err) = result {
+            for backup in backups[..=index].iter().rev() {
+                restore(backup);
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Creates the missing parent directories of `path`, recording them in
+/// `created_dirs` so that they can be removed again.
+fn create_parent_dirs(path: &Path, created_dirs: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+    created_dirs.extend(
+        parent
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .map(Path::to_path_buf),
+    );
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create parent directories for {}", path.display()))
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into
+/// place, so that `path` never holds partially written contents.
+///
+/// A symlink is followed so that its target is replaced rather than the link.
+/// A file with other hard links is overwritten in place instead, since a rename
+/// would detach it from them; the caller's backup covers a failed write.
+fn write_atomically(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<&std::fs::Permissions>,
+) -> std::io::Result<()> {
+    let is_symlink = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    let path = if is_symlink {
+        match std::fs::canonicalize(path) {
+            Ok(target) => target,
+            // A dangling link: writing through it creates its target.
+            Err(_) => return write_in_place(path, contents, permissions),
+        }
+    } else {
+        path.to_path_buf()
+    };
+    if has_other_hard_links(&path) {
+        return write_in_place(&path, contents, permissions);
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = path.with_file_name(format!(
+        ".{file_name}.{}.apply_patch.tmp",
+        std::process::id()
+    ));
+    let result = std::fs::write(&tmp_path, contents)
+        .and_then(|()| match permissions {
+            Some(permissions) => std::fs::set_permissions(&tmp_path, permissions.clone()),
+            None => Ok(()),
+        })
+        .and_then(|()| std::fs::rename(&tmp_path, &path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn write_in_place(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<&std::fs::Permissions>,
+) -> std::io::Result<()> {
+    std::fs::write(path, contents)?;
+    match permissions {
+        Some(permissions) => std::fs::set_permissions(path, permissions.clone()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn has_other_hard_links(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.nlink() > 1)
+}
+
+#[cfg(not(unix))]
+fn has_other_hard_links(_path: &Path) -> bool {
+    false
+}
+
+/// Best-effort restoration of a path to its state before the patch.
+fn restore(backup: &Backup) {
+    match &backup.original {
+        Some((bytes, permissions)) => {
+            let _ = write_atomically(&backup.path, bytes, Some(permissions));
+        }
+        None => {
+            let _ = std::fs::remove_file(&backup.path);
+        }
+    }
+    for dir in &backup.created_dirs {
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
 struct AppliedPatch {
     original_contents: String,
     new_contents: String,
@@ -620,7 +1023,16 @@ fn derive_new_contents_from_chunks(
             }));
         }
     };
+    derive_new_contents_from_original(original_contents, path, chunks)
+}
 
+/// Applies the chunks to `original_contents`, the current contents of the
+/// file at `path`.
+fn derive_new_contents_from_original(
+    original_contents: String,
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+) -> std::result::Result<AppliedPatch, ApplyPatchError> {
     let mut original_lines: Vec<String> = original_contents
         .split('\n')
         .map(|s| s.to_string())
@@ -656,7 +1068,7 @@ fn compute_replacements(
     let mut replacements: Vec<(usize, usize, Vec<String>)> = Vec::new();
     let mut line_index: usize = 0;
 
-    for chunk in chunks {
+    for (chunk_index, chunk) in chunks.iter().enumerate() {
         // If a chunk has a `change_context`, we use seek_sequence to find it, then
         // adjust our `line_index` to continue from there.
         if let Some(ctx_line) = &chunk.change_context {
@@ -669,9 +1081,16 @@ fn compute_replacements(
                 line_index = idx + 1;
             } else {
                 return Err(ApplyPatchError::ComputeReplacements(format!(
-                    "Failed to find context '{}' in {}",
+                    "Failed to find context '{}' in {} (chunk {} of {}){}",
                     ctx_line,
-                    path.display()
+                    path.display(),
+                    chunk_index + 1,
+                    chunks.len(),
+                    describe_surroundings(
+                        original_lines,
+                        std::slice::from_ref(ctx_line),
+                        line_index
+                    ),
                 )));
             }
         }
@@ -726,9 +1145,12 @@ fn compute_replacements(
             line_index = start_idx + pattern.len();
         } else {
             return Err(ApplyPatchError::ComputeReplacements(format!(
-                "Failed to find expected lines {:?} in {}",
+                "Failed to find expected lines {:?} in {} (chunk {} of {}){}",
                 chunk.old_lines,
-                path.display()
+                path.display(),
+                chunk_index + 1,
+                chunks.len(),
+                describe_surroundings(original_lines, &chunk.old_lines, line_index),
             )));
         }
     }
@@ -736,6 +1158,46 @@ fn compute_replacements(
     Ok(replacements)
 }
 
+/// Quotes the lines of the file where `pattern` was expected, so that the
+/// caller can see why it did not match: around the closest line matching the
+/// first non-blank line of `pattern`, or else from `line_index` on.
+fn describe_surroundings(
+    original_lines: &[String],
+    pattern: &[String],
+    line_index: usize,
+) -> String {
+    const CONTEXT_LINES: usize = 2;
+    const MAX_LINES: usize = 12;
+
+    let anchor = pattern
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .and_then(|offset| {
+            let found = seek_sequence::seek_sequence(
+                original_lines,
+                std::slice::from_ref(&pattern[offset]),
+                line_index,
+                false,
+            )?;
+            Some(found.saturating_sub(offset))
+        });
+    let (heading, start) = match anchor {
+        Some(idx) => ("Closest match", idx.saturating_sub(CONTEXT_LINES)),
+        None => ("File contents", line_index.saturating_sub(CONTEXT_LINES)),
+    };
+    let len = (pattern.len() + 2 * CONTEXT_LINES).min(MAX_LINES);
+    let end = (start + len).min(original_lines.len());
+    if start >= end {
+        return String::new();
+    }
+
+    let mut out = format!("\n{heading} at line {}:", start + 1);
+    for (offset, line) in original_lines[start..end].iter().enumerate() {
+        out.push_str(&format!("\n{:>5} | {line}", start + offset + 1));
+    }
+    out
+}
+
 /// Apply the `(start_index, old_len, new_lines)` replacements to `original_lines`,
 /// returning the modified file contents as a vector of lines.
 fn apply_replacements(
@@ -795,6 +1257,103 @@ pub fn unified_diff_from_chunks_with_context(
     })
 }
 
+/// Returns hunks that undo `hunks`, assuming they were applied. Fails if the
+/// patch does not record enough to undo it (deleted contents or previous
+/// modes), or if a file it added has changed since.
+pub fn reverse_hunks(hunks: &[Hunk]) -> Result<Vec<Hunk>, ApplyPatchError> {
+    let mut reversed = Vec::with_capacity(hunks.len());
+    for hunk in hunks.iter().rev() {
+        let reverse_hunk = match hunk {
+            Hunk::AddFile { path, contents, .. } => {
+                ensure_unchanged(path, contents.as_bytes())?;
+                Hunk::DeleteFile { path: path.clone() }
+            }
+            Hunk::DeleteFile { path } => {
+                return Err(ApplyPatchError::Reverse(format!(
+                    "Cannot reverse Delete File: {}: the patch does not record the deleted contents",
+                    path.display()
+                )));
+            }
+            Hunk::UpdateFile {
+                path,
+                move_path,
+                mode: Some(_),
+                ..
+            } => {
+                let path = move_path.as_ref().unwrap_or(path);
+                return Err(ApplyPatchError::Reverse(format!(
+                    "Cannot reverse Update File: {}: the patch does not record the previous mode",
+                    path.display()
+                )));
+            }
+            Hunk::UpdateFile {
+                path,
+                move_path,
+                mode: None,
+                chunks,
+            } => {
+                let mut reversed_chunks = Vec::with_capacity(chunks.len());
+                for chunk in chunks {
+                    // A chunk without new lines would be reversed into a pure
+                    // addition, which is always appended at the end of the file.
+                    if chunk.new_lines.is_empty() && !chunk.is_end_of_file {
+                        return Err(ApplyPatchError::Reverse(format!(
+                            "Cannot reverse Update File: {}: a chunk removes lines without any surrounding context",
+                            path.display()
+                        )));
+                    }
+                    reversed_chunks.push(UpdateFileChunk {
+                        change_context: chunk.change_context.clone(),
+                        old_lines: chunk.new_lines.clone(),
+                        new_lines: chunk.old_lines.clone(),
+                        is_end_of_file: chunk.is_end_of_file,
+                    });
+                }
+                Hunk::UpdateFile {
+                    path: move_path.clone().unwrap_or_else(|| path.clone()),
+                    move_path: move_path.as_ref().map(|_| path.clone()),
+                    mode: None,
+                    chunks: reversed_chunks,
+                }
+            }
+            Hunk::CopyFile {
+                path, copy_path, ..
+            } => {
+                let source = std::fs::read(path).map_err(|err| {
+                    ApplyPatchError::IoError(IoError {
+                        context: format!("Failed to read {}", path.display()),
+                        source: err,
+                    })
+                })?;
+                ensure_unchanged(copy_path, &source)?;
+                Hunk::DeleteFile {
+                    path: copy_path.clone(),
+                }
+            }
+        };
+        reversed.push(reverse_hunk);
+    }
+    Ok(reversed)
+}
+
+/// Fails unless the file at `path` still holds `expected`, so that reversing
+/// a patch never deletes edits made after it.
+fn ensure_unchanged(path: &Path, expected: &[u8]) -> Result<(), ApplyPatchError> {
+    let actual = std::fs::read(path).map_err(|err| {
+        ApplyPatchError::IoError(IoError {
+            context: format!("Failed to read {}", path.display()),
+            source: err,
+        })
+    })?;
+    if actual != expected {
+        return Err(ApplyPatchError::Reverse(format!(
+            "Cannot reverse the patch: {} has changed since the patch created it",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
 /// Print the summary of changes in git-style format.
 /// Write a summary of changes to the given writer.
 pub fn print_summary(
@@ -802,6 +1361,14 @@ pub fn print_summary(
     out: &mut impl std::io::Write,
 ) -> std::io::Result<()> {
     writeln!(out, "Success. Updated the following files:")?;
+    print_affected_paths(affected, out)
+}
+
+/// Write one `A`/`M`/`D` line per affected path.
+pub(crate) fn print_affected_paths(
+    affected: &AffectedPaths,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
     for path in &affected.added {
         writeln!(out, "A {}", path.display())?;
     }
@@ -851,6 +1418,7 @@ mod tests {
         vec![Hunk::AddFile {
             path: PathBuf::from("foo"),
             contents: "hi\n".to_string(),
+            mode: None,
         }]
     }
 
@@ -890,7 +1458,8 @@ mod tests {
                     hunks,
                     vec![Hunk::AddFile {
                         path: PathBuf::from("foo"),
-                        contents: "hi\n".to_string()
+                        contents: "hi\n".to_string(),
+                        mode: None,
                     }]
                 );
             }
@@ -915,7 +1484,8 @@ mod tests {
                     hunks,
                     vec![Hunk::AddFile {
                         path: PathBuf::from("foo"),
-                        contents: "hi\n".to_string()
+                        contents: "hi\n".to_string(),
+                        mode: None,
                     }]
                 );
             }
@@ -948,7 +1518,8 @@ PATCH"#,
                     hunks,
                     vec![Hunk::AddFile {
                         path: PathBuf::from("foo"),
-                        contents: "hi\n".to_string()
+                        contents: "hi\n".to_string(),
+                        mode: None,
                     }]
                 );
             }
@@ -1065,6 +1636,18 @@ PATCH"#,
         assert!(!path.exists());
     }
 
+    #[test]
+    fn test_delete_file_hunk_removes_binary_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("del.bin");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        let patch = wrap_patch(&format!("*** Delete File: {}", path.display()));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_update_file_hunk_modifies_content() {
         let dir = tempdir().unwrap();
@@ -1518,6 +2101,7 @@ g
                         .to_string(),
                         move_path: None,
                         new_content: "updated session directory content\n".to_string(),
+                        mode: None,
                     },
                 )]),
                 patch: argv[1].clone(),
@@ -1545,4 +2129,242 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_failed_hunk_leaves_earlier_files_untouched() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "one\n").unwrap();
+        fs::write(&second, "alpha\nbeta\ngamma\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+-one
++uno
+*** Update File: {}
+@@
+ beta
+-delta
++epsilon"#,
+            first.display(),
+            second.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = apply_patch(&patch, &mut stdout, &mut stderr);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            format!(
+                "Failed to apply hunk 2 of 2 (Update File: {}): Failed to find expected lines [\"beta\", \"delta\"] in {} (chunk 1 of 1)\n\
+                 Closest match at line 1:\n    1 | alpha\n    2 | beta\n    3 | gamma\n\
+                 No files were modified.\n",
+                second.display(),
+                second.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_failed_write_restores_written_files() {
+        let dir = tempdir().unwrap();
+        let added = dir.path().join("new/added.txt");
+        let updated = dir.path().join("updated.txt");
+        let blocker = dir.path().join("blocker");
+        fs::write(&updated, "before\n").unwrap();
+        fs::write(&blocker, "not a directory\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Add File: {}
++hello
+*** Update File: {}
+@@
+-before
++after
+*** Add File: {}
++unreachable"#,
+            added.display(),
+            updated.display(),
+            blocker.join("child.txt").display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = apply_patch(&patch, &mut stdout, &mut stderr);
+
+        assert!(result.is_err());
+        assert!(!added.exists());
+        assert!(!dir.path().join("new").exists());
+        assert_eq!(fs::read_to_string(&updated).unwrap(), "before\n");
+        assert_eq!(fs::read_to_string(&blocker).unwrap(), "not a directory\n");
+    }
+
+    #[test]
+    fn test_later_hunks_see_earlier_hunks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("twice.txt");
+        let patch = wrap_patch(&format!(
+            r#"*** Add File: {}
++first
+*** Update File: {}
+@@
+-first
++second"#,
+            path.display(),
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+    }
+
+    #[test]
+    fn test_unified_diff_and_apply_patch_produce_the_same_action() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("greeting.txt"), "hello\nworld\n").unwrap();
+        fs::write(dir.path().join("stale.txt"), "old\n").unwrap();
+
+        let apply_patch_argv = strs_to_strings(&[
+            "apply_patch",
+            r#"*** Begin Patch
+*** Update File: greeting.txt
+*** Move to: farewell.txt
+@@
+-hello
++goodbye
+ world
+*** Delete File: stale.txt
+*** Add File: new.txt
++fresh
+*** End Patch"#,
+        ]);
+        let unified_diff_argv = strs_to_strings(&[
+            "apply_patch",
+            r#"diff --git a/greeting.txt b/farewell.txt
+similarity index 50%
+rename from greeting.txt
+rename to farewell.txt
+--- a/greeting.txt
++++ b/farewell.txt
+@@ -1,2 +1,2 @@
+-hello
++goodbye
+ world
+diff --git a/stale.txt b/stale.txt
+deleted file mode 100644
+--- a/stale.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-old
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++fresh"#,
+        ]);
+
+        let changes = |argv: &[String]| match maybe_parse_apply_patch_verified(argv, dir.path()) {
+            MaybeApplyPatchVerified::Body(action) => action.changes,
+            other => panic!("expected a patch, got {other:?}"),
+        };
+        assert_eq!(changes(&unified_diff_argv), changes(&apply_patch_argv));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_mode_and_copy_binary_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        let binary = dir.path().join("logo.png");
+        fs::write(&binary, [0x89, b'P', b'N', b'G', 0xff, 0x00]).unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o640)).unwrap();
+        let script = dir.path().join("run.sh");
+        let copy = dir.path().join("copy.png");
+        let patch = wrap_patch(&format!(
+            "*** Add File: {}\n*** Set Mode: 755\n+#!/bin/sh\n*** Copy File: {}\n*** Copy to: {}\n*** Update File: {}\n*** Set Mode: 600",
+            script.display(),
+            binary.display(),
+            copy.display(),
+            binary.display(),
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(mode(&script), 0o755);
+        assert_eq!(fs::read(&copy).unwrap(), fs::read(&binary).unwrap());
+        assert_eq!(mode(&copy), 0o640);
+        assert_eq!(mode(&binary), 0o600);
+        let expected_out = format!(
+            "Success. Updated the following files:\nA {}\nA {}\nM {}\n",
+            script.display(),
+            copy.display(),
+            binary.display(),
+        );
+        assert_eq!(String::from_utf8(stdout).unwrap(), expected_out);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_update_keeps_symlinks_and_hard_links() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link.txt");
+        let original = dir.path().join("original.txt");
+        let hard_link = dir.path().join("hard_link.txt");
+        fs::write(&target, "symlinked\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        fs::write(&original, "hard linked\n").unwrap();
+        fs::hard_link(&original, &hard_link).unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-symlinked\n+updated\n*** Update File: {}\n@@\n-hard linked\n+updated",
+            link.display(),
+            original.display(),
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "updated\n");
+        assert_eq!(fs::read_to_string(&hard_link).unwrap(), "updated\n");
+    }
+
+    #[test]
+    fn test_copy_is_verified_against_the_destination() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "a\n").unwrap();
+        let argv = strs_to_strings(&[
+            "apply_patch",
+            "*** Begin Patch\n*** Copy File: a.txt\n*** Copy to: b.txt\n*** Set Mode: 755\n*** End Patch",
+        ]);
+
+        match maybe_parse_apply_patch_verified(&argv, dir.path()) {
+            MaybeApplyPatchVerified::Body(action) => assert_eq!(
+                action.changes,
+                HashMap::from([(
+                    dir.path().join("b.txt"),
+                    ApplyPatchFileChange::Copy {
+                        source: dir.path().join("a.txt"),
+                        mode: Some(0o755),
+                    },
+                )])
+            ),
+            other => panic!("expected a patch, got {other:?}"),
+        }
+    }
 }
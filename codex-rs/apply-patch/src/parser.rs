@@ -7,14 +7,16 @@
 //! begin_patch: "*** Begin Patch" LF
 //! end_patch: "*** End Patch" LF?
 //!
-//! hunk: add_hunk | delete_hunk | update_hunk
-//! add_hunk: "*** Add File: " filename LF add_line+
+//! hunk: add_hunk | delete_hunk | update_hunk | copy_hunk
+//! add_hunk: "*** Add File: " filename LF set_mode? add_line+
 //! delete_hunk: "*** Delete File: " filename LF
-//! update_hunk: "*** Update File: " filename LF change_move? change?
+//! update_hunk: "*** Update File: " filename LF change_move? set_mode? change?
+//! copy_hunk: "*** Copy File: " filename LF "*** Copy to: " filename LF set_mode?
 //! filename: /(.+)/
 //! add_line: "+" /(.+)/ LF -> line
 //!
 //! change_move: "*** Move to: " filename LF
+//! set_mode: "*** Set Mode: " /[0-7]{3,4}/ LF
 //! change: (change_context | change_line)+ eof_line?
 //! change_context: ("@@" | "@@ " /(.+)/) LF
 //! change_line: ("+" | "-" | " ") /(.+)/ LF
@@ -22,6 +24,9 @@
 //!
 //! The parser below is a little more lenient than the explicit spec and allows for
 //! leading/trailing whitespace around patch markers.
+//!
+//! Standard unified diffs (`git diff` or `diff -u` output) are accepted as
+//! well and converted into the same hunks; see [`parse_unified_diff`].
 use crate::ApplyPatchArgs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -34,10 +39,19 @@ const ADD_FILE_MARKER: &str = "*** Add File: ";
 const DELETE_FILE_MARKER: &str = "*** Delete File: ";
 const UPDATE_FILE_MARKER: &str = "*** Update File: ";
 const MOVE_TO_MARKER: &str = "*** Move to: ";
+const COPY_FILE_MARKER: &str = "*** Copy File: ";
+const COPY_TO_MARKER: &str = "*** Copy to: ";
+const SET_MODE_MARKER: &str = "*** Set Mode: ";
 const EOF_MARKER: &str = "*** End of File";
 const CHANGE_CONTEXT_MARKER: &str = "@@ ";
 const EMPTY_CHANGE_CONTEXT_MARKER: &str = "@@";
 
+const GIT_DIFF_MARKER: &str = "diff --git ";
+const UNIFIED_OLD_FILE_MARKER: &str = "--- ";
+const UNIFIED_NEW_FILE_MARKER: &str = "+++ ";
+const UNIFIED_HUNK_MARKER: &str = "@@ -";
+const DEV_NULL: &str = "/dev/null";
+
 /// Currently, the only OpenAI model that knowingly requires lenient parsing is
 /// gpt-4.1. While we could try to require everyone to pass in a strictness
 /// param when invoking apply_patch, it is a pain to thread it through all of
@@ -61,6 +75,8 @@ pub enum Hunk {
     AddFile {
         path: PathBuf,
         contents: String,
+        /// Permission bits to give the file, e.g. `0o755`.
+        mode: Option<u32>,
     },
     DeleteFile {
         path: PathBuf,
@@ -68,11 +84,21 @@ pub enum Hunk {
     UpdateFile {
         path: PathBuf,
         move_path: Option<PathBuf>,
+        /// Permission bits to give the file, e.g. `0o755`.
+        mode: Option<u32>,
 
         /// Chunks should be in order, i.e. the `change_context` of one chunk
         /// should occur later in the file than the previous chunk.
         chunks: Vec<UpdateFileChunk>,
     },
+    /// Copies the file at `path` byte for byte to `copy_path`, so it works
+    /// for binary files too.
+    CopyFile {
+        path: PathBuf,
+        copy_path: PathBuf,
+        /// Permission bits to give the copy; the original's are kept if unset.
+        mode: Option<u32>,
+    },
 }
 
 impl Hunk {
@@ -81,6 +107,7 @@ impl Hunk {
             Hunk::AddFile { path, .. } => cwd.join(path),
             Hunk::DeleteFile { path } => cwd.join(path),
             Hunk::UpdateFile { path, .. } => cwd.join(path),
+            Hunk::CopyFile { path, .. } => cwd.join(path),
         }
     }
 }
@@ -153,6 +180,13 @@ enum ParseMode {
 
 fn parse_patch_text(patch: &str, mode: ParseMode) -> Result<ApplyPatchArgs, ParseError> {
     let lines: Vec<&str> = patch.trim().lines().collect();
+    if is_unified_diff(&lines) {
+        return Ok(ApplyPatchArgs {
+            hunks: parse_unified_diff(&lines)?,
+            patch: lines.join("\n"),
+            workdir: None,
+        });
+    }
     let lines: &[&str] = match check_patch_boundaries_strict(&lines) {
         Ok(()) => &lines,
         Err(e) => match mode {
@@ -249,7 +283,11 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
         // Add File
         let mut contents = String::new();
         let mut parsed_lines = 1;
-        for add_line in &lines[1..] {
+        let mode = parse_set_mode(lines.get(1), line_number + 1)?;
+        if mode.is_some() {
+            parsed_lines += 1;
+        }
+        for add_line in &lines[parsed_lines..] {
             if let Some(line_to_add) = add_line.strip_prefix('+') {
                 contents.push_str(line_to_add);
                 contents.push('\n');
@@ -262,6 +300,7 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             AddFile {
                 path: PathBuf::from(path),
                 contents,
+                mode,
             },
             parsed_lines,
         ));
@@ -288,6 +327,13 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             parsed_lines += 1;
         }
 
+        // Optional: set mode line
+        let mode = parse_set_mode(remaining_lines.first(), line_number + parsed_lines)?;
+        if mode.is_some() {
+            remaining_lines = &remaining_lines[1..];
+            parsed_lines += 1;
+        }
+
         let mut chunks = Vec::new();
         // NOTE: we need to know to stop once we reach the next special marker header.
         while !remaining_lines.is_empty() {
@@ -312,7 +358,7 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             remaining_lines = &remaining_lines[chunk_lines..]
         }
 
-        if chunks.is_empty() {
+        if chunks.is_empty() && mode.is_none() {
             return Err(InvalidHunkError {
                 message: format!("Update file hunk for path '{path}' is empty"),
                 line_number,
@@ -323,20 +369,57 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             UpdateFile {
                 path: PathBuf::from(path),
                 move_path: move_path.map(PathBuf::from),
+                mode,
                 chunks,
             },
             parsed_lines,
         ));
+    } else if let Some(path) = first_line.strip_prefix(COPY_FILE_MARKER) {
+        // Copy File
+        let Some(copy_path) = lines.get(1).and_then(|x| x.strip_prefix(COPY_TO_MARKER)) else {
+            return Err(InvalidHunkError {
+                message: format!(
+                    "Copy file hunk for path '{path}' must be followed by '*** Copy to: {{path}}'"
+                ),
+                line_number,
+            });
+        };
+        let mode = parse_set_mode(lines.get(2), line_number + 2)?;
+        return Ok((
+            CopyFile {
+                path: PathBuf::from(path),
+                copy_path: PathBuf::from(copy_path),
+                mode,
+            },
+            2 + usize::from(mode.is_some()),
+        ));
     }
 
     Err(InvalidHunkError {
         message: format!(
-            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}'"
+            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}', '*** Copy File: {{path}}'"
         ),
         line_number,
     })
 }
 
+/// Parses an optional `*** Set Mode: 755` line into permission bits.
+fn parse_set_mode(line: Option<&&str>, line_number: usize) -> Result<Option<u32>, ParseError> {
+    let Some(mode) = line.and_then(|line| line.trim().strip_prefix(SET_MODE_MARKER)) else {
+        return Ok(None);
+    };
+    let mode = mode.trim();
+    match u32::from_str_radix(mode, 8) {
+        Ok(bits) if (3..=4).contains(&mode.len()) && bits <= 0o7777 => Ok(Some(bits)),
+        _ => Err(InvalidHunkError {
+            message: format!(
+                "Invalid file mode '{mode}'; expected octal permission bits such as 755"
+            ),
+            line_number,
+        }),
+    }
+}
+
 fn parse_update_file_chunk(
     lines: &[&str],
     line_number: usize,
@@ -430,6 +513,365 @@ fn parse_update_file_chunk(
     Ok((chunk, parsed_lines + start_index))
 }
 
+/// Whether `lines` hold a unified diff rather than an apply_patch patch.
+fn is_unified_diff(lines: &[&str]) -> bool {
+    match lines {
+        [first, ..] if first.starts_with(GIT_DIFF_MARKER) => true,
+        // `diff -u` prints its command line before each file with `-r`.
+        [first, second, third, ..] if first.starts_with("diff ") => {
+            is_unified_file_header(second, third)
+        }
+        [first, second, ..] => is_unified_file_header(first, second),
+        _ => false,
+    }
+}
+
+fn is_unified_file_header(line: &str, next_line: &str) -> bool {
+    line.starts_with(UNIFIED_OLD_FILE_MARKER) && next_line.starts_with(UNIFIED_NEW_FILE_MARKER)
+}
+
+/// Converts a unified diff, as printed by `git diff` or `diff -u`, into hunks.
+///
+/// Each file becomes an add, delete or update hunk (renames become an update
+/// with a move), and each `@@` hunk becomes an [`UpdateFileChunk`]. The line
+/// numbers and counts in `@@` headers are ignored, like the context line of
+/// an apply_patch chunk: chunks are located by their content. A
+/// `\ No newline at end of file` marker pins the chunk to the end of the file.
+fn parse_unified_diff(lines: &[&str]) -> Result<Vec<Hunk>, ParseError> {
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        if lines[index].trim().is_empty() {
+            index += 1;
+            continue;
+        }
+        let (hunk, parsed_lines) = parse_unified_file(&lines[index..], index + 1)?;
+        hunks.push(hunk);
+        index += parsed_lines;
+    }
+    Ok(hunks)
+}
+
+/// Parses the diff of a single file from the start of `lines`. Returns the
+/// hunk and the number of lines parsed.
+fn parse_unified_file(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), ParseError> {
+    let is_git = lines[0].starts_with(GIT_DIFF_MARKER);
+    let git_paths = if is_git {
+        split_git_diff_paths(&lines[0][GIT_DIFF_MARKER.len()..])
+    } else {
+        None
+    };
+    let mut index = usize::from(is_git);
+
+    // Extended git headers, up to the `---`/`+++` lines or the first chunk.
+    let mut new_file = false;
+    let mut deleted_file = false;
+    let mut rename_from = None;
+    let mut rename_to = None;
+    let mut copy_from = None;
+    let mut copy_to = None;
+    let mut mode = None;
+    while let Some(line) = lines.get(index) {
+        if line.starts_with(GIT_DIFF_MARKER)
+            || line.starts_with(UNIFIED_HUNK_MARKER)
+            || lines
+                .get(index + 1)
+                .is_some_and(|next_line| is_unified_file_header(line, next_line))
+        {
+            break;
+        }
+        if !is_git && index == 0 && line.starts_with("diff ") {
+            index += 1;
+            continue;
+        }
+        if !is_git {
+            return Err(InvalidHunkError {
+                message: format!(
+                    "'{line}' is not a valid unified diff file header. Expected 'diff --git' or '---' followed by '+++'"
+                ),
+                line_number: line_number + index,
+            });
+        }
+        if line.starts_with("Binary files ") || *line == "GIT binary patch" {
+            return Err(InvalidHunkError {
+                message: "Binary diffs are not supported".to_string(),
+                line_number: line_number + index,
+            });
+        }
+        if let Some(path) = line.strip_prefix("rename from ") {
+            rename_from = Some(unquote_path(path));
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            rename_to = Some(unquote_path(path));
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            copy_from = Some(unquote_path(path));
+        } else if let Some(path) = line.strip_prefix("copy to ") {
+            copy_to = Some(unquote_path(path));
+        } else if let Some(git_mode) = line.strip_prefix("new file mode ") {
+            new_file = true;
+            // Only executables need anything beyond the default mode.
+            mode = permission_bits(git_mode).filter(|bits| *bits != 0o644);
+        } else if let Some(git_mode) = line.strip_prefix("new mode ") {
+            mode = permission_bits(git_mode);
+        } else if line.starts_with("deleted file mode ") {
+            deleted_file = true;
+        }
+        // Other extended headers (`index`, `similarity index`, `old mode`)
+        // carry nothing the hunks need.
+        index += 1;
+    }
+
+    let mut old_path = None;
+    let mut new_path = None;
+    if let Some((old_line, new_line)) = lines.get(index).zip(lines.get(index + 1))
+        && is_unified_file_header(old_line, new_line)
+    {
+        old_path = Some(header_path(&old_line[UNIFIED_OLD_FILE_MARKER.len()..]));
+        new_path = Some(header_path(&new_line[UNIFIED_NEW_FILE_MARKER.len()..]));
+        index += 2;
+    }
+
+    let mut chunks = Vec::new();
+    let mut new_side_lacks_final_newline = false;
+    while lines
+        .get(index)
+        .is_some_and(|line| line.starts_with(UNIFIED_HUNK_MARKER))
+    {
+        let (chunk, lacks_final_newline, parsed_lines) =
+            parse_unified_chunk(&lines[index..], line_number + index)?;
+        chunks.push(chunk);
+        new_side_lacks_final_newline = lacks_final_newline;
+        index += parsed_lines;
+    }
+
+    // `/dev/null` marks the missing side of an added or deleted file; `a/`
+    // and `b/` are the prefixes git puts in front of both sides.
+    let strip_prefixes = is_git
+        || old_path.as_deref().is_some_and(|p| p.starts_with("a/"))
+            && new_path.as_deref().is_some_and(|p| p.starts_with("b/"));
+    let resolve = |path: Option<String>, prefix: &str| {
+        path.filter(|p| p != DEV_NULL)
+            .map(|p| match p.strip_prefix(prefix) {
+                Some(stripped) if strip_prefixes => stripped.to_string(),
+                _ => p,
+            })
+    };
+    let is_add = new_file || old_path.as_deref() == Some(DEV_NULL);
+    let is_delete = deleted_file || new_path.as_deref() == Some(DEV_NULL);
+    let (git_old_path, git_new_path) = git_paths.unzip();
+    let source = rename_from
+        .or_else(|| resolve(old_path, "a/"))
+        .or_else(|| resolve(git_old_path, "a/"));
+    let dest = rename_to
+        .or_else(|| resolve(new_path, "b/"))
+        .or_else(|| resolve(git_new_path, "b/"));
+
+    let missing_path = || InvalidHunkError {
+        message: "Unified diff does not name the file it changes".to_string(),
+        line_number,
+    };
+    let hunk = if let (Some(path), Some(copy_path)) = (copy_from, copy_to) {
+        if !chunks.is_empty() {
+            return Err(InvalidHunkError {
+                message: format!(
+                    "Copying '{path}' with changes is not supported; copy it first, then update the copy in a separate patch"
+                ),
+                line_number,
+            });
+        }
+        CopyFile {
+            path: PathBuf::from(path),
+            copy_path: PathBuf::from(copy_path),
+            mode,
+        }
+    } else if is_add {
+        let path = dest.ok_or_else(missing_path)?;
+        let mut contents = chunks
+            .iter()
+            .flat_map(|chunk| chunk.new_lines.iter())
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        if new_side_lacks_final_newline {
+            contents.pop();
+        }
+        AddFile {
+            path: PathBuf::from(path),
+            contents,
+            mode,
+        }
+    } else if is_delete {
+        DeleteFile {
+            path: PathBuf::from(source.ok_or_else(missing_path)?),
+        }
+    } else {
+        let path = source.ok_or_else(missing_path)?;
+        let move_path = dest.filter(|dest| *dest != path);
+        if chunks.is_empty() && move_path.is_none() && mode.is_none() {
+            return Err(InvalidHunkError {
+                message: format!("Update file hunk for path '{path}' is empty"),
+                line_number,
+            });
+        }
+        UpdateFile {
+            path: PathBuf::from(path),
+            move_path: move_path.map(PathBuf::from),
+            mode,
+            chunks,
+        }
+    };
+    Ok((hunk, index))
+}
+
+/// Parses one `@@ -a,b +c,d @@` chunk from the start of `lines`. Returns the
+/// chunk, whether the new side of the file lacks a final newline, and the
+/// number of lines parsed.
+fn parse_unified_chunk(
+    lines: &[&str],
+    line_number: usize,
+) -> Result<(UpdateFileChunk, bool, usize), ParseError> {
+    let mut chunk = UpdateFileChunk {
+        change_context: None,
+        old_lines: Vec::new(),
+        new_lines: Vec::new(),
+        is_end_of_file: false,
+    };
+    let mut lacks_final_newline = false;
+    let mut previous_kind = None;
+    let mut parsed_lines = 1;
+    while let Some(line) = lines.get(parsed_lines) {
+        let is_body_line = |line: &str, index: usize| match line.chars().next() {
+            Some(' ' | '+' | '\\') => true,
+            Some('-') => !lines
+                .get(index + 1)
+                .is_some_and(|next_line| is_unified_file_header(line, next_line)),
+            _ => false,
+        };
+        let kind = line.chars().next();
+        match kind {
+            Some(' ') => {
+                chunk.old_lines.push(line[1..].to_string());
+                chunk.new_lines.push(line[1..].to_string());
+            }
+            Some('+') => chunk.new_lines.push(line[1..].to_string()),
+            Some('-') if is_body_line(line, parsed_lines) => {
+                chunk.old_lines.push(line[1..].to_string())
+            }
+            // `\ No newline at end of file`, about the line before it.
+            Some('\\') => {
+                chunk.is_end_of_file = true;
+                lacks_final_newline |= matches!(previous_kind, Some(' ' | '+'));
+            }
+            // Blank context lines often lose their leading space; only take
+            // this one if the chunk goes on after it.
+            None if lines[parsed_lines..]
+                .iter()
+                .enumerate()
+                .find(|(_, line)| !line.is_empty())
+                .is_some_and(|(offset, line)| is_body_line(line, parsed_lines + offset)) =>
+            {
+                chunk.old_lines.push(String::new());
+                chunk.new_lines.push(String::new());
+            }
+            _ => break,
+        }
+        previous_kind = kind;
+        parsed_lines += 1;
+    }
+
+    if chunk.old_lines.is_empty() && chunk.new_lines.is_empty() {
+        return Err(InvalidHunkError {
+            message: "Unified diff hunk does not contain any lines".to_string(),
+            line_number,
+        });
+    }
+    Ok((chunk, lacks_final_newline, parsed_lines))
+}
+
+/// Permission bits of a git file mode such as `100755`. Symlinks and
+/// submodules have no permission bits to set.
+fn permission_bits(git_mode: &str) -> Option<u32> {
+    let git_mode = u32::from_str_radix(git_mode.trim(), 8).ok()?;
+    (git_mode & 0o170000 == 0o100000).then_some(git_mode & 0o7777)
+}
+
+/// Splits the `a/<old> b/<new>` paths of a `diff --git` line.
+fn split_git_diff_paths(paths: &str) -> Option<(String, String)> {
+    if paths.starts_with('"') {
+        let (old_path, new_path) = split_quoted_path(paths)?;
+        return Some((old_path, unquote_path(new_path.trim_start())));
+    }
+    // Without a rename both sides are the same path, which may contain " b/".
+    let half = paths.len() / 2;
+    if paths.len() % 2 == 1
+        && paths.is_char_boundary(half)
+        && paths[..half]
+            .strip_prefix("a/")
+            .is_some_and(|path| paths[half + 1..].strip_prefix("b/") == Some(path))
+    {
+        return Some((paths[..half].to_string(), paths[half + 1..].to_string()));
+    }
+    let (old_path, new_path) = paths.split_once(" b/")?;
+    Some((old_path.to_string(), format!("b/{new_path}")))
+}
+
+/// Path from a `---` or `+++` line, without the timestamp `diff -u` appends.
+fn header_path(header: &str) -> String {
+    let path = match header.split_once('\t') {
+        Some((path, _timestamp)) => path,
+        None => header,
+    };
+    unquote_path(path.trim_end())
+}
+
+/// Undoes the C-style quoting git applies to paths with unusual characters.
+fn unquote_path(path: &str) -> String {
+    if path.starts_with('"')
+        && let Some((unquoted, rest)) = split_quoted_path(path)
+        && rest.is_empty()
+    {
+        return unquoted;
+    }
+    path.to_string()
+}
+
+/// Splits a leading C-style quoted string off `s`, returning it unquoted
+/// along with the rest of `s`.
+fn split_quoted_path(s: &str) -> Option<(String, &str)> {
+    let mut bytes = Vec::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => {
+                let unquoted = String::from_utf8_lossy(&bytes).into_owned();
+                return Some((unquoted, &s[index + 1..]));
+            }
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                match escaped {
+                    'n' => bytes.push(b'\n'),
+                    't' => bytes.push(b'\t'),
+                    '0'..='7' => {
+                        let mut value = escaped.to_digit(8)?;
+                        for _ in 0..2 {
+                            let (_, digit) = chars.next()?;
+                            value = value * 8 + digit.to_digit(8)?;
+                        }
+                        bytes.push(u8::try_from(value).ok()?);
+                    }
+                    other => {
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+            }
+            other => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    None
+}
+
 #[test]
 fn test_parse_patch() {
     assert_eq!(
@@ -486,7 +928,8 @@ fn test_parse_patch() {
         vec![
             AddFile {
                 path: PathBuf::from("path/add.py"),
-                contents: "abc\ndef\n".to_string()
+                contents: "abc\ndef\n".to_string(),
+                mode: None,
             },
             DeleteFile {
                 path: PathBuf::from("path/delete.py")
@@ -494,6 +937,7 @@ fn test_parse_patch() {
             UpdateFile {
                 path: PathBuf::from("path/update.py"),
                 move_path: Some(PathBuf::from("path/update2.py")),
+                mode: None,
                 chunks: vec![UpdateFileChunk {
                     change_context: Some("def f():".to_string()),
                     old_lines: vec!["    pass".to_string()],
@@ -521,6 +965,7 @@ fn test_parse_patch() {
             UpdateFile {
                 path: PathBuf::from("file.py"),
                 move_path: None,
+                mode: None,
                 chunks: vec![UpdateFileChunk {
                     change_context: None,
                     old_lines: vec![],
@@ -530,7 +975,8 @@ fn test_parse_patch() {
             },
             AddFile {
                 path: PathBuf::from("other.py"),
-                contents: "content\n".to_string()
+                contents: "content\n".to_string(),
+                mode: None,
             }
         ]
     );
@@ -551,6 +997,7 @@ fn test_parse_patch() {
         vec![UpdateFile {
             path: PathBuf::from("file2.py"),
             move_path: None,
+            mode: None,
             chunks: vec![UpdateFileChunk {
                 change_context: None,
                 old_lines: vec!["import foo".to_string()],
@@ -571,6 +1018,7 @@ fn test_parse_patch_lenient() {
     let expected_patch = vec![UpdateFile {
         path: PathBuf::from("file2.py"),
         move_path: None,
+        mode: None,
         chunks: vec![UpdateFileChunk {
             change_context: None,
             old_lines: vec!["import foo".to_string()],
@@ -653,7 +1101,7 @@ fn test_parse_one_hunk() {
         parse_one_hunk(&["bad"], 234),
         Err(InvalidHunkError {
             message: "'bad' is not a valid hunk header. \
-            Valid hunk headers: '*** Add File: {path}', '*** Delete File: {path}', '*** Update File: {path}'".to_string(),
+            Valid hunk headers: '*** Add File: {path}', '*** Delete File: {path}', '*** Update File: {path}', '*** Copy File: {path}'".to_string(),
             line_number: 234
         })
     );
@@ -739,3 +1187,272 @@ fn test_update_file_chunk() {
         ))
     );
 }
+
+#[test]
+fn test_parse_unified_diff() {
+    let patch = "diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a9c3f4d 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@ mod parser;
+ use std::path::Path;
+
+-fn old() {}
++fn new() {}
+@@ -20,2 +20,3 @@ fn tail() {
+ }
++// end
+\\ No newline at end of file
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+index 0000000..ce01362
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1,2 @@
++hello
++world
+\\ No newline at end of file
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index ce01362..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/old name.txt b/new name.txt
+similarity index 90%
+rename from old name.txt
+rename to new name.txt
+index 1111111..2222222 100644
+--- a/old name.txt
++++ b/new name.txt
+@@ -1 +1 @@
+-before
++after
+diff --git a/empty.txt b/empty.txt
+new file mode 100644
+index 0000000..e69de29
+diff --git a/a.txt b/b.txt
+similarity index 100%
+rename from a.txt
+rename to b.txt";
+
+    assert_eq!(
+        parse_patch_text(patch, ParseMode::Strict),
+        Ok(ApplyPatchArgs {
+            hunks: vec![
+                UpdateFile {
+                    path: PathBuf::from("src/lib.rs"),
+                    move_path: None,
+                    mode: None,
+                    chunks: vec![
+                        UpdateFileChunk {
+                            change_context: None,
+                            old_lines: vec![
+                                "use std::path::Path;".to_string(),
+                                "".to_string(),
+                                "fn old() {}".to_string(),
+                            ],
+                            new_lines: vec![
+                                "use std::path::Path;".to_string(),
+                                "".to_string(),
+                                "fn new() {}".to_string(),
+                            ],
+                            is_end_of_file: false,
+                        },
+                        UpdateFileChunk {
+                            change_context: None,
+                            old_lines: vec!["}".to_string()],
+                            new_lines: vec!["}".to_string(), "// end".to_string()],
+                            is_end_of_file: true,
+                        },
+                    ],
+                },
+                AddFile {
+                    path: PathBuf::from("notes.txt"),
+                    contents: "hello\nworld".to_string(),
+                    mode: None,
+                },
+                DeleteFile {
+                    path: PathBuf::from("gone.txt"),
+                },
+                UpdateFile {
+                    path: PathBuf::from("old name.txt"),
+                    move_path: Some(PathBuf::from("new name.txt")),
+                    mode: None,
+                    chunks: vec![UpdateFileChunk {
+                        change_context: None,
+                        old_lines: vec!["before".to_string()],
+                        new_lines: vec!["after".to_string()],
+                        is_end_of_file: false,
+                    }],
+                },
+                AddFile {
+                    path: PathBuf::from("empty.txt"),
+                    contents: String::new(),
+                    mode: None,
+                },
+                UpdateFile {
+                    path: PathBuf::from("a.txt"),
+                    move_path: Some(PathBuf::from("b.txt")),
+                    mode: None,
+                    chunks: vec![],
+                },
+            ],
+            patch: patch.to_string(),
+            workdir: None,
+        })
+    );
+}
+
+#[test]
+fn test_parse_plain_unified_diff() {
+    let patch = "--- hello.txt\t2024-01-01 00:00:00.000000000 +0000
++++ hello.txt\t2024-01-02 00:00:00.000000000 +0000
+@@ -1,2 +1,2 @@
+-hello
++goodbye
+ world";
+    assert_eq!(
+        parse_patch_text(patch, ParseMode::Strict).map(|args| args.hunks),
+        Ok(vec![UpdateFile {
+            path: PathBuf::from("hello.txt"),
+            move_path: None,
+            mode: None,
+            chunks: vec![UpdateFileChunk {
+                change_context: None,
+                old_lines: vec!["hello".to_string(), "world".to_string()],
+                new_lines: vec!["goodbye".to_string(), "world".to_string()],
+                is_end_of_file: false,
+            }],
+        }])
+    );
+
+    assert_eq!(
+        parse_patch_text(
+            "diff --git a/logo.png b/logo.png\nindex 1111111..2222222 100644\nBinary files a/logo.png and b/logo.png differ",
+            ParseMode::Strict
+        ),
+        Err(InvalidHunkError {
+            message: "Binary diffs are not supported".to_string(),
+            line_number: 3,
+        })
+    );
+    assert_eq!(
+        parse_patch_text(
+            "--- a.txt\n+++ a.txt\n@@ -1 +1 @@\n-a\n+b\ngarbage",
+            ParseMode::Strict
+        ),
+        Err(InvalidHunkError {
+            message: "'garbage' is not a valid unified diff file header. Expected 'diff --git' or '---' followed by '+++'".to_string(),
+            line_number: 6,
+        })
+    );
+}
+
+#[test]
+fn test_split_git_diff_paths() {
+    assert_eq!(
+        split_git_diff_paths("a/dir b/x.txt b/dir b/x.txt"),
+        Some(("a/dir b/x.txt".to_string(), "b/dir b/x.txt".to_string()))
+    );
+    assert_eq!(
+        split_git_diff_paths("a/old.txt b/new.txt"),
+        Some(("a/old.txt".to_string(), "b/new.txt".to_string()))
+    );
+    assert_eq!(
+        split_git_diff_paths(r#""a/caf\303\251.txt" "b/caf\303\251.txt""#),
+        Some(("a/café.txt".to_string(), "b/café.txt".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_set_mode_and_copy() {
+    assert_eq!(
+        parse_patch_text(
+            "*** Begin Patch\n\
+             *** Add File: run.sh\n\
+             *** Set Mode: 755\n\
+             +#!/bin/sh\n\
+             *** Update File: tool\n\
+             *** Set Mode: 0644\n\
+             *** Copy File: logo.png\n\
+             *** Copy to: assets/logo.png\n\
+             *** End Patch",
+            ParseMode::Strict
+        )
+        .map(|args| args.hunks),
+        Ok(vec![
+            AddFile {
+                path: PathBuf::from("run.sh"),
+                contents: "#!/bin/sh\n".to_string(),
+                mode: Some(0o755),
+            },
+            UpdateFile {
+                path: PathBuf::from("tool"),
+                move_path: None,
+                mode: Some(0o644),
+                chunks: vec![],
+            },
+            CopyFile {
+                path: PathBuf::from("logo.png"),
+                copy_path: PathBuf::from("assets/logo.png"),
+                mode: None,
+            },
+        ])
+    );
+    assert_eq!(
+        parse_one_hunk(&["*** Add File: run.sh", "*** Set Mode: 9z"], 3),
+        Err(InvalidHunkError {
+            message: "Invalid file mode '9z'; expected octal permission bits such as 755"
+                .to_string(),
+            line_number: 4,
+        })
+    );
+    assert_eq!(
+        parse_one_hunk(&["*** Copy File: a.txt", "+oops"], 7),
+        Err(InvalidHunkError {
+            message: "Copy file hunk for path 'a.txt' must be followed by '*** Copy to: {path}'"
+                .to_string(),
+            line_number: 7,
+        })
+    );
+}
+
+#[test]
+fn test_parse_unified_diff_modes_and_copies() {
+    let patch = "diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/a.txt b/b.txt
+similarity index 100%
+copy from a.txt
+copy to b.txt
+diff --git a/tool b/tool
+new file mode 100755
+--- /dev/null
++++ b/tool
+@@ -0,0 +1 @@
++echo";
+    assert_eq!(
+        parse_patch_text(patch, ParseMode::Strict).map(|args| args.hunks),
+        Ok(vec![
+            UpdateFile {
+                path: PathBuf::from("run.sh"),
+                move_path: None,
+                mode: Some(0o755),
+                chunks: vec![],
+            },
+            CopyFile {
+                path: PathBuf::from("a.txt"),
+                copy_path: PathBuf::from("b.txt"),
+                mode: None,
+            },
+            AddFile {
+                path: PathBuf::from("tool"),
+                contents: "echo\n".to_string(),
+                mode: Some(0o755),
+            },
+        ])
+    );
+}
@@ -1,5 +1,24 @@
 use std::io::Read;
 use std::io::Write;
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::AffectedPaths;
+use crate::ApplyPatchError;
+
+const USAGE: &str = "Usage: apply_patch [--check] [--reverse] [--json] 'PATCH'\n       echo 'PATCH' | apply_patch [--check] [--reverse] [--json]";
+
+/// Flags that may accompany the PATCH argument.
+#[derive(Debug, Default)]
+struct Options {
+    /// Only verify that every hunk applies; touch nothing.
+    check: bool,
+    /// Undo a previously applied patch.
+    reverse: bool,
+    /// Print a machine-readable summary on stdout.
+    json: bool,
+}
 
 pub fn main() -> ! {
     let exit_code = run_main();
@@ -10,24 +29,37 @@ pub fn main() -> ! {
 /// method is still a nightly API and we want main() to return !.
 pub fn run_main() -> i32 {
     // Expect either one argument (the full apply_patch payload) or read it from stdin.
-    let mut args = std::env::args_os();
-    let _argv0 = args.next();
-
-    let patch_arg = match args.next() {
-        Some(arg) => match arg.into_string() {
-            Ok(s) => s,
-            Err(_) => {
-                eprintln!("Error: apply_patch requires a UTF-8 PATCH argument.");
-                return 1;
+    let mut options = Options::default();
+    let mut patch_arg = None;
+    for arg in std::env::args_os().skip(1) {
+        let Ok(arg) = arg.into_string() else {
+            eprintln!("Error: apply_patch requires a UTF-8 PATCH argument.");
+            return 1;
+        };
+        // Anything else is the patch, which may itself start with `--` (a
+        // plain unified diff).
+        match arg.as_str() {
+            "--check" => options.check = true,
+            "--reverse" => options.reverse = true,
+            "--json" => options.json = true,
+            _ if patch_arg.is_none() => patch_arg = Some(arg),
+            _ => {
+                // Refuse extra args to avoid ambiguity.
+                eprintln!("Error: apply_patch accepts exactly one argument.");
+                return 2;
             }
-        },
+        }
+    }
+
+    let patch_arg = match patch_arg {
+        Some(patch_arg) => patch_arg,
         None => {
             // No argument provided; attempt to read the patch from stdin.
             let mut buf = String::new();
             match std::io::stdin().read_to_string(&mut buf) {
                 Ok(_) => {
                     if buf.is_empty() {
-                        eprintln!("Usage: apply_patch 'PATCH'\n       echo 'PATCH' | apply-patch");
+                        eprintln!("{USAGE}");
                         return 2;
                     }
                     buf
@@ -40,20 +72,74 @@ pub fn run_main() -> i32 {
         }
     };
 
-    // Refuse extra args to avoid ambiguity.
-    if args.next().is_some() {
-        eprintln!("Error: apply_patch accepts exactly one argument.");
-        return 2;
-    }
-
     let mut stdout = std::io::stdout();
     let mut stderr = std::io::stderr();
-    match crate::apply_patch(&patch_arg, &mut stdout, &mut stderr) {
-        Ok(()) => {
-            // Flush to ensure output ordering when used in pipelines.
-            let _ = stdout.flush();
-            0
+    let exit_code = match run_patch(&patch_arg, &options, &mut stderr) {
+        Ok(affected) => match print_success(&affected, &options, &mut stdout) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        },
+        Err(err) => {
+            if options.json {
+                let summary = json!({
+                    "success": false,
+                    "check": options.check,
+                    "reverse": options.reverse,
+                    "error": err.to_string(),
+                });
+                let _ = writeln!(stdout, "{summary}");
+            }
+            1
         }
-        Err(_) => 1,
+    };
+    // Flush to ensure output ordering when used in pipelines.
+    let _ = stdout.flush();
+    exit_code
+}
+
+/// Parses the patch and applies (or checks) it, reversed if requested.
+fn run_patch(
+    patch: &str,
+    options: &Options,
+    stderr: &mut impl Write,
+) -> Result<AffectedPaths, ApplyPatchError> {
+    let mut hunks = crate::parse_patch_reporting_errors(patch, stderr)?;
+    if options.reverse {
+        hunks = crate::reverse_hunks(&hunks).inspect_err(|err| {
+            let _ = writeln!(stderr, "{err}");
+        })?;
+    }
+    crate::apply_or_check_hunks(&hunks, options.check, stderr)
+}
+
+fn print_success(
+    affected: &AffectedPaths,
+    options: &Options,
+    stdout: &mut impl Write,
+) -> std::io::Result<()> {
+    if options.json {
+        let paths = |paths: &[PathBuf]| -> Vec<String> {
+            paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect()
+        };
+        let summary = json!({
+            "success": true,
+            "check": options.check,
+            "reverse": options.reverse,
+            "added": paths(&affected.added),
+            "modified": paths(&affected.modified),
+            "deleted": paths(&affected.deleted),
+        });
+        writeln!(stdout, "{summary}")
+    } else if options.check {
+        writeln!(
+            stdout,
+            "Check passed. Applying the patch would update the following files:"
+        )?;
+        crate::print_affected_paths(affected, stdout)
+    } else {
+        crate::print_summary(affected, stdout)
     }
 }
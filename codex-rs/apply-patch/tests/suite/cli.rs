@@ -88,3 +88,139 @@ fn test_apply_patch_cli_stdin_add_and_update() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_apply_patch_cli_check_touches_nothing() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let file = "cli_check.txt";
+    let absolute_path = tmp.path().join(file);
+    fs::write(&absolute_path, "hello\n")?;
+
+    let update_patch = format!(
+        r#"*** Begin Patch
+*** Update File: {file}
+@@
+-hello
++world
+*** End Patch"#
+    );
+    Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--check")
+        .arg(&update_patch)
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(format!(
+            "Check passed. Applying the patch would update the following files:\nM {file}\n"
+        ));
+    assert_eq!(fs::read_to_string(&absolute_path)?, "hello\n");
+
+    // A hunk that does not match fails the check.
+    fs::write(&absolute_path, "goodbye\n")?;
+    Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--check")
+        .arg(&update_patch)
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&absolute_path)?, "goodbye\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_apply_patch_cli_reverse_undoes_patch() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    fs::write(tmp.path().join("kept.txt"), "one\ntwo\n")?;
+
+    let patch = r#"*** Begin Patch
+*** Add File: added.txt
++new
+*** Update File: kept.txt
+*** Move to: renamed.txt
+@@
+ one
+-two
++three
+*** End Patch"#;
+    Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg(patch)
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("renamed.txt"))?,
+        "one\nthree\n"
+    );
+
+    Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--reverse")
+        .arg(patch)
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("Success. Updated the following files:\nM kept.txt\nD added.txt\n");
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("kept.txt"))?,
+        "one\ntwo\n"
+    );
+    assert!(!tmp.path().join("renamed.txt").exists());
+    assert!(!tmp.path().join("added.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_apply_patch_cli_json_summary() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    fs::write(tmp.path().join("gone.txt"), "bye\n")?;
+
+    let patch = r#"*** Begin Patch
+*** Add File: new.txt
++hi
+*** Delete File: gone.txt
+*** End Patch"#;
+    let output = Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--json")
+        .arg("--check")
+        .arg(patch)
+        .current_dir(tmp.path())
+        .output()?;
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        summary,
+        serde_json::json!({
+            "success": true,
+            "check": true,
+            "reverse": false,
+            "added": ["new.txt"],
+            "modified": [],
+            "deleted": ["gone.txt"],
+        })
+    );
+    assert!(tmp.path().join("gone.txt").exists());
+
+    let output = Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--json")
+        .arg("*** Begin Patch\n*** Delete File: missing.txt\n*** End Patch")
+        .current_dir(tmp.path())
+        .output()?;
+    assert!(!output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(summary["success"], false);
+    assert!(
+        summary["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("missing.txt"))
+    );
+
+    Ok(())
+}
@@ -12,6 +12,10 @@ use codex_cli::login::run_login_with_api_key;
 use codex_cli::login::run_login_with_chatgpt;
 use codex_cli::login::run_logout;
 use codex_cli::proto;
+use codex_cli::sessions::SessionsCommand;
+use codex_cli::sessions::run_sessions_command;
+use codex_cli::stats::StatsCommand;
+use codex_cli::stats::run_stats_command;
 use codex_common::CliConfigOverrides;
 use codex_exec::Cli as ExecCli;
 use codex_tui::Cli as TuiCli;
@@ -45,6 +49,7 @@ struct MultitoolCli {
 }
 
 #[derive(Debug, clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Subcommand {
     /// Run Codex non-interactively.
     #[clap(visible_alias = "e")]
@@ -79,6 +84,12 @@ enum Subcommand {
     /// Load a saved conversation by name or session id.
     Load(LoadCommand),
 
+    /// Browse recorded sessions.
+    Sessions(SessionsCommand),
+
+    /// Summarize token usage, estimated cost and activity across sessions.
+    Stats(StatsCommand),
+
     /// Internal: generate TypeScript protocol bindings.
     #[clap(hide = true)]
     GenerateTs(GenerateTsCommand),
@@ -239,6 +250,13 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
         Some(Subcommand::Load(cmd)) => {
             handle_load(cmd).await?;
         }
+        Some(Subcommand::Sessions(cmd)) => {
+            run_sessions_command(cmd).await?;
+        }
+        Some(Subcommand::Stats(mut stats_cli)) => {
+            prepend_config_flags(&mut stats_cli.config_overrides, cli.config_overrides);
+            run_stats_command(stats_cli).await?;
+        }
         Some(Subcommand::GenerateTs(gen_cli)) => {
             codex_protocol_ts::generate_ts(&gen_cli.out_dir, gen_cli.prettier.as_deref())?;
         }
@@ -305,21 +323,16 @@ async fn handle_save(cmd: SaveCommand) -> anyhow::Result<()> {
         serde_json::json!({"by_name":{}, "by_id":{}})
     };
     // Insert name and id mapping
-    let by_name = root["by_name"].as_object_mut().unwrap();
-    by_name.insert(
-        cmd.name.clone(),
-        serde_json::Value::String(target_path.to_string_lossy().to_string()),
-    );
+    root["by_name"][cmd.name.as_str()] =
+        serde_json::Value::String(target_path.to_string_lossy().to_string());
     // Best-effort id: infer from filename
-    if let Some(file) = target_path.file_name().and_then(|s| s.to_str()) {
-        if let Some(id) = file
-            .split('-')
-            .last()
-            .and_then(|s| s.strip_suffix(".jsonl"))
-        {
-            root["by_id"][id] =
-                serde_json::Value::String(target_path.to_string_lossy().to_string());
-        }
+    if let Some(file) = target_path.file_name().and_then(|s| s.to_str())
+        && let Some(id) = file.split('-').next_back().and_then(|s| {
+            s.strip_suffix(".jsonl.gz")
+                .or_else(|| s.strip_suffix(".jsonl"))
+        })
+    {
+        root["by_id"][id] = serde_json::Value::String(target_path.to_string_lossy().to_string());
     }
     std::fs::write(saves_path, serde_json::to_string_pretty(&root)?)?;
     println!("saved `{}` -> {}", cmd.name, target_path.display());
@@ -354,7 +367,7 @@ async fn handle_load(cmd: LoadCommand) -> anyhow::Result<()> {
     };
 
     // Build a TUI CLI that loads the specified path.
-    let mut tui_cli = TuiCli::parse_from(["codex", "--load-path", &path.to_string_lossy()]);
+    let tui_cli = TuiCli::parse_from(["codex", "--load-path", &path.to_string_lossy()]);
     // Forward any root-level overrides later if needed.
     let usage = codex_tui::run_main(tui_cli, None).await?;
     if !usage.is_zero() {
@@ -0,0 +1,203 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use clap::ValueEnum;
+use codex_core::ExportFormat;
+use codex_core::ExportOptions;
+use codex_core::SessionIndex;
+use codex_core::SessionSearchHit;
+use codex_core::SessionSearchQuery;
+use codex_core::config::find_codex_home;
+use codex_core::export_rollout;
+use codex_core::parse_search_date;
+
+#[derive(Debug, Parser)]
+pub struct SessionsCommand {
+    #[command(subcommand)]
+    pub action: SessionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SessionsSubcommand {
+    /// Search recorded sessions by messages, commands run and files touched.
+    Search(SearchCommand),
+
+    /// Render a session as a Markdown, HTML or JSON transcript.
+    Export(ExportCommand),
+}
+
+#[derive(Debug, Parser)]
+pub struct SearchCommand {
+    /// Words that must all appear in a session (case-insensitive).
+    #[arg(value_name = "QUERY")]
+    pub query: Vec<String>,
+
+    /// Only sessions started in this directory or below it.
+    #[arg(long = "cwd", value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Only sessions started on this git branch.
+    #[arg(long = "branch", value_name = "BRANCH")]
+    pub branch: Option<String>,
+
+    /// Only sessions started on or after this date (YYYY-MM-DD, UTC).
+    #[arg(long = "since", value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Only sessions started on or before this date (YYYY-MM-DD, UTC).
+    #[arg(long = "until", value_name = "DATE")]
+    pub until: Option<String>,
+
+    /// Maximum number of sessions to print.
+    #[arg(long = "limit", value_name = "N", default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportCommand {
+    /// Session id (or a unique prefix of it), or the path to a rollout file.
+    #[arg(value_name = "ID_OR_PATH")]
+    pub session: String,
+
+    /// Output format.
+    #[arg(long = "format", value_enum, default_value_t = ExportFormatArg::Md)]
+    pub format: ExportFormatArg,
+
+    /// Leave out the environment-context and user-instructions blocks.
+    #[arg(long = "redact-context", default_value_t = false)]
+    pub redact_context: bool,
+
+    /// Write the transcript to this file instead of stdout.
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormatArg {
+    Md,
+    Html,
+    Json,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Md => ExportFormat::Markdown,
+            ExportFormatArg::Html => ExportFormat::Html,
+            ExportFormatArg::Json => ExportFormat::Json,
+        }
+    }
+}
+
+pub async fn run_sessions_command(cmd: SessionsCommand) -> anyhow::Result<()> {
+    match cmd.action {
+        SessionsSubcommand::Search(search) => run_search(search).await,
+        SessionsSubcommand::Export(export) => run_export(export).await,
+    }
+}
+
+async fn run_search(cmd: SearchCommand) -> anyhow::Result<()> {
+    let cwd = match cmd.cwd {
+        Some(cwd) if cwd.is_relative() => Some(std::env::current_dir()?.join(cwd)),
+        cwd => cwd,
+    };
+    let query = SessionSearchQuery {
+        terms: cmd
+            .query
+            .iter()
+            .flat_map(|q| q.split_whitespace())
+            .map(str::to_string)
+            .collect(),
+        cwd,
+        branch: cmd.branch,
+        since: cmd
+            .since
+            .as_deref()
+            .map(parse_search_date)
+            .transpose()
+            .map_err(anyhow::Error::msg)?,
+        until: cmd
+            .until
+            .as_deref()
+            .map(parse_search_date)
+            .transpose()
+            .map_err(anyhow::Error::msg)?,
+    };
+
+    let codex_home = find_codex_home()?;
+    let index = SessionIndex::update_in_background(&codex_home).await?;
+    let hits = index.search(&query, cmd.limit);
+    if hits.is_empty() {
+        eprintln!("No matching sessions.");
+        return Ok(());
+    }
+    for hit in &hits {
+        print_hit(hit);
+    }
+    Ok(())
+}
+
+async fn run_export(cmd: ExportCommand) -> anyhow::Result<()> {
+    let path = resolve_session(&cmd.session).await?;
+    let options = ExportOptions {
+        format: cmd.format.into(),
+        redact_context: cmd.redact_context,
+    };
+    let transcript = export_rollout(&path, &options)?;
+    match cmd.output {
+        Some(output) => {
+            std::fs::write(&output, transcript)?;
+            eprintln!("exported {} -> {}", path.display(), output.display());
+        }
+        None => print!("{transcript}"),
+    }
+    Ok(())
+}
+
+/// Treats `session` as a rollout path when such a file exists, otherwise as
+/// a session id prefix looked up in the session index.
+async fn resolve_session(session: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(session);
+    if path.is_file() {
+        return Ok(path);
+    }
+    let codex_home = find_codex_home()?;
+    let index = SessionIndex::update_in_background(&codex_home).await?;
+    let mut matches = index
+        .sessions()
+        .iter()
+        .filter(|s| s.id.as_deref().is_some_and(|id| id.starts_with(session)));
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Ok(found.path.clone()),
+        (Some(_), Some(_)) => anyhow::bail!("session id `{session}` is ambiguous"),
+        (None, _) => anyhow::bail!("no session found for `{session}`"),
+    }
+}
+
+fn print_hit(hit: &SessionSearchHit) {
+    // "2025-01-02T10:00:00.000Z" -> "2025-01-02 10:00"
+    let started = hit
+        .timestamp
+        .as_deref()
+        .and_then(|ts| ts.get(..16))
+        .map(|ts| ts.replacen('T', " ", 1))
+        .unwrap_or_else(|| "unknown date".to_string());
+    let mut header = started;
+    if let Some(cwd) = &hit.cwd {
+        header.push_str(&format!("  {}", cwd.display()));
+    }
+    if let Some(branch) = &hit.branch {
+        header.push_str(&format!(" ({branch})"));
+    }
+    println!("{header}");
+    println!("  {}", hit.path.display());
+    if let Some(preview) = &hit.preview
+        && let Some(first_line) = preview.lines().next()
+    {
+        println!("  > {first_line}");
+    }
+    for m in &hit.matches {
+        println!("  {}: {}", m.field.label(), m.snippet);
+    }
+    println!();
+}
@@ -0,0 +1,177 @@
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::UsageStats;
+use codex_core::UsageTotals;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::parse_search_date;
+use codex_protocol::num_format::format_with_separators;
+
+#[derive(Debug, Parser)]
+pub struct StatsCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Only tokens used, and sessions started, on or after this date
+    /// (YYYY-MM-DD, UTC).
+    #[arg(long = "since", value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Print the statistics as JSON.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Also break the statistics down per session.
+    #[arg(long = "by-session", default_value_t = false)]
+    pub by_session: bool,
+}
+
+pub async fn run_stats_command(cmd: StatsCommand) -> anyhow::Result<()> {
+    let since = cmd
+        .since
+        .as_deref()
+        .map(parse_search_date)
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+
+    let mut stats = tokio::task::spawn_blocking(move || {
+        UsageStats::collect(&config.codex_home, since, &config.model_prices)
+    })
+    .await??;
+    if !cmd.by_session {
+        stats.sessions.clear();
+    }
+
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if stats.total.sessions == 0 {
+        eprintln!("No recorded sessions.");
+        return Ok(());
+    }
+    print_summary(&stats.total);
+    for (title, groups) in [
+        ("Day", &stats.by_day),
+        ("Model", &stats.by_model),
+        ("Provider", &stats.by_provider),
+        ("Repository", &stats.by_repository),
+    ] {
+        print_table(
+            title,
+            groups.iter().map(|(key, totals)| (key.clone(), totals)),
+        );
+    }
+    if !stats.sessions.is_empty() {
+        print_table(
+            "Session",
+            stats.sessions.iter().map(|session| {
+                // "2025-01-02T10:00:00.000Z" -> "2025-01-02 10:00"
+                let started = session
+                    .timestamp
+                    .as_deref()
+                    .and_then(|ts| ts.get(..16))
+                    .map(|ts| ts.replacen('T', " ", 1))
+                    .unwrap_or_default();
+                let id = session.id.as_deref().and_then(|id| id.get(..8));
+                (format!("{started} {}", id.unwrap_or("")), &session.totals)
+            }),
+        );
+    }
+    Ok(())
+}
+
+fn print_summary(total: &UsageTotals) {
+    println!("Sessions: {}", format_with_separators(total.sessions));
+    println!(
+        "Tokens:   {} input ({} cached), {} output ({} reasoning)",
+        format_with_separators(total.input_tokens),
+        format_with_separators(total.cached_input_tokens),
+        format_with_separators(total.output_tokens),
+        format_with_separators(total.reasoning_output_tokens),
+    );
+    println!(
+        "Activity: {} commands, {} patches, {} approval requests, {} aborted turns",
+        format_with_separators(total.commands),
+        format_with_separators(total.patches),
+        format_with_separators(total.approval_requests),
+        format_with_separators(total.aborted_turns),
+    );
+    if let Some(cost) = total.cost {
+        println!("Cost:     ${cost:.2} (estimated from [model_prices])");
+    }
+}
+
+fn print_table<'a>(title: &str, rows: impl Iterator<Item = (String, &'a UsageTotals)>) {
+    const HEADERS: [&str; 10] = [
+        "SESSIONS",
+        "INPUT",
+        "CACHED",
+        "OUTPUT",
+        "REASONING",
+        "COMMANDS",
+        "PATCHES",
+        "APPROVALS",
+        "ABORTED",
+        "COST",
+    ];
+    let rows: Vec<(String, Vec<String>)> = rows
+        .map(|(key, t)| {
+            let mut cells: Vec<String> = [
+                t.sessions,
+                t.input_tokens,
+                t.cached_input_tokens,
+                t.output_tokens,
+                t.reasoning_output_tokens,
+                t.commands,
+                t.patches,
+                t.approval_requests,
+                t.aborted_turns,
+            ]
+            .into_iter()
+            .map(format_with_separators)
+            .collect();
+            cells.push(
+                t.cost
+                    .map_or_else(|| "-".to_string(), |c| format!("${c:.2}")),
+            );
+            (key, cells)
+        })
+        .collect();
+
+    let key_width = rows
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .chain(std::iter::once(title.len()))
+        .max()
+        .unwrap_or_default();
+    let widths: Vec<usize> = HEADERS
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|(_, cells)| cells[i].len())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let line = |key: &str, cells: &mut dyn Iterator<Item = &str>| {
+        let mut out = format!("{key:<key_width$}");
+        for (cell, width) in cells.zip(&widths) {
+            out.push_str(&format!("  {cell:>width$}"));
+        }
+        println!("{}", out.trim_end());
+    };
+    println!();
+    line(&title.to_uppercase(), &mut HEADERS.iter().copied());
+    for (key, cells) in &rows {
+        line(key, &mut cells.iter().map(String::as_str));
+    }
+}
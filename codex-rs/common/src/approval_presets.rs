@@ -1,3 +1,6 @@
+use std::path::Path;
+
+use codex_core::config::default_workspace_write_policy;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 
@@ -19,7 +22,8 @@ pub struct ApprovalPreset {
 /// Built-in list of approval presets that pair approval and sandbox policy.
 ///
 /// Keep this UI-agnostic so it can be reused by both TUI and MCP server.
-pub fn builtin_approval_presets() -> Vec<ApprovalPreset> {
+/// `codex_home` is the one whose `auth.json` the sandbox keeps unreadable.
+pub fn builtin_approval_presets(codex_home: &Path) -> Vec<ApprovalPreset> {
     vec![
         ApprovalPreset {
             id: "read-only",
@@ -33,7 +37,7 @@ pub fn builtin_approval_presets() -> Vec<ApprovalPreset> {
             label: "Auto",
             description: "Codex can read files, make edits, and run commands in the workspace. Codex requires approval to work outside the workspace or access network",
             approval: AskForApproval::OnRequest,
-            sandbox: SandboxPolicy::new_workspace_write_policy(),
+            sandbox: default_workspace_write_policy(codex_home),
         },
         ApprovalPreset {
             id: "full-access",
@@ -9,6 +9,9 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             network_access,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
+            deny_read: _,
+            allowed_domains,
+            isolate_namespaces: _,
         } => {
             let mut summary = "workspace-write".to_string();
 
@@ -29,6 +32,11 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
             if *network_access {
                 summary.push_str(" (network access enabled)");
+            } else if !allowed_domains.is_empty() {
+                summary.push_str(&format!(
+                    " (network access to {})",
+                    allowed_domains.join(", ")
+                ));
             }
             summary
         }
@@ -1,11 +1,15 @@
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::hooks::HookDecision;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
 use crate::safety::SafetyCheck;
+use crate::safety::allow_without_prompt;
 use crate::safety::assess_patch_safety;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::MaybeApplyPatchVerified;
+use codex_apply_patch::maybe_parse_apply_patch_verified;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 use std::collections::HashMap;
@@ -31,6 +35,9 @@ pub(crate) enum InternalApplyPatchInvocation {
 pub(crate) struct ApplyPatchExec {
     pub(crate) action: ApplyPatchAction,
     pub(crate) user_explicitly_approved_this_action: bool,
+    /// A `pre_apply_patch` hook allowed the patch, so it must not prompt
+    /// again when the exec is assessed. It still runs sandboxed.
+    pub(crate) allowed_by_hook: bool,
 }
 
 impl From<ResponseInputItem> for InternalApplyPatchInvocation {
@@ -46,16 +53,52 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
-    match assess_patch_safety(
+    let decision = sess
+        .run_pre_apply_patch_hook(
+            sub_id,
+            call_id,
+            &action.cwd,
+            convert_apply_patch_to_protocol(&action),
+        )
+        .await;
+    let (action, decision) = match decision {
+        Some(HookDecision::Rewrite { patch, .. }) => match rewrite_patch(&action, patch) {
+            Ok(rewritten) => {
+                sess.notify_background_event(sub_id, "pre_apply_patch hook rewrote the patch")
+                    .await;
+                (rewritten, None)
+            }
+            Err(reason) => (
+                action,
+                Some(HookDecision::Deny {
+                    reason: Some(reason),
+                }),
+            ),
+        },
+        decision => (action, decision),
+    };
+    let sandbox_policy = sess.effective_sandbox_policy(&turn_context.sandbox_policy);
+    let assessed = assess_patch_safety(
         &action,
         turn_context.approval_policy,
-        &turn_context.sandbox_policy,
+        &sandbox_policy,
         &turn_context.cwd,
-    ) {
+    );
+    let allowed_by_hook = decision == Some(HookDecision::Allow);
+    let safety = match decision {
+        Some(HookDecision::Allow) => allow_without_prompt(assessed, &sandbox_policy),
+        Some(HookDecision::Ask) => SafetyCheck::AskUser,
+        Some(HookDecision::Deny { reason }) => SafetyCheck::Reject {
+            reason: reason.unwrap_or_else(|| "denied by pre_apply_patch hook".to_string()),
+        },
+        Some(HookDecision::Rewrite { .. }) | None => assessed,
+    };
+    match safety {
         SafetyCheck::AutoApprove { .. } => {
             InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
                 action,
                 user_explicitly_approved_this_action: false,
+                allowed_by_hook,
             })
         }
         SafetyCheck::AskUser => {
@@ -74,18 +117,20 @@ pub(crate) async fn apply_patch(
                     InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
                         action,
                         user_explicitly_approved_this_action: true,
+                        allowed_by_hook: false,
                     })
                 }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
-                    ResponseInputItem::FunctionCallOutput {
-                        call_id: call_id.to_owned(),
-                        output: FunctionCallOutputPayload {
-                            content: "patch rejected by user".to_string(),
-                            success: Some(false),
-                        },
-                    }
-                    .into()
+                // The request offered no directory to grant.
+                ReviewDecision::ApprovedWritableRootForSession
+                | ReviewDecision::Denied
+                | ReviewDecision::Abort => ResponseInputItem::FunctionCallOutput {
+                    call_id: call_id.to_owned(),
+                    output: FunctionCallOutputPayload {
+                        content: "patch rejected by user".to_string(),
+                        success: Some(false),
+                    },
                 }
+                .into(),
             }
         }
         SafetyCheck::Reject { reason } => ResponseInputItem::FunctionCallOutput {
@@ -99,6 +144,27 @@ pub(crate) async fn apply_patch(
     }
 }
 
+/// Parses the patch a `pre_apply_patch` hook asked to apply instead of
+/// `original`, relative to the same directory.
+fn rewrite_patch(
+    original: &ApplyPatchAction,
+    patch: Option<String>,
+) -> Result<ApplyPatchAction, String> {
+    let Some(patch) = patch else {
+        return Err("pre_apply_patch hook asked for a rewrite without a patch".to_string());
+    };
+    let argv = vec!["apply_patch".to_string(), patch];
+    match maybe_parse_apply_patch_verified(&argv, &original.cwd) {
+        MaybeApplyPatchVerified::Body(action) => Ok(action),
+        MaybeApplyPatchVerified::CorrectnessError(e) => Err(format!(
+            "pre_apply_patch hook rewrote the patch into an invalid one: {e}"
+        )),
+        MaybeApplyPatchVerified::ShellParseError(_) | MaybeApplyPatchVerified::NotApplyPatch => {
+            Err("pre_apply_patch hook rewrote the patch into an invalid one".to_string())
+        }
+    }
+}
+
 pub(crate) fn convert_apply_patch_to_protocol(
     action: &ApplyPatchAction,
 ) -> HashMap<PathBuf, FileChange> {
@@ -106,8 +172,9 @@ pub(crate) fn convert_apply_patch_to_protocol(
     let mut result = HashMap::with_capacity(changes.len());
     for (path, change) in changes {
         let protocol_change = match change {
-            ApplyPatchFileChange::Add { content } => FileChange::Add {
+            ApplyPatchFileChange::Add { content, mode } => FileChange::Add {
                 content: content.clone(),
+                mode: *mode,
             },
             ApplyPatchFileChange::Delete { content } => FileChange::Delete {
                 content: content.clone(),
@@ -116,12 +183,45 @@ pub(crate) fn convert_apply_patch_to_protocol(
                 unified_diff,
                 move_path,
                 new_content: _new_content,
+                mode,
             } => FileChange::Update {
                 unified_diff: unified_diff.clone(),
                 move_path: move_path.clone(),
+                mode: *mode,
+            },
+            ApplyPatchFileChange::Copy { source, mode } => FileChange::Copy {
+                source: source.clone(),
+                mode: *mode,
             },
         };
         result.insert(path.clone(), protocol_change);
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn hook_rewrites_replace_the_patch_or_reject_it() {
+        let dir = TempDir::new().unwrap();
+        let original =
+            ApplyPatchAction::new_add_for_test(&dir.path().join("a.txt"), "a".to_string());
+
+        let rewritten = rewrite_patch(
+            &original,
+            Some("*** Begin Patch\n*** Add File: b.txt\n+b\n*** End Patch".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            rewritten.changes().keys().collect::<Vec<_>>(),
+            vec![&dir.path().join("b.txt")]
+        );
+
+        assert!(rewrite_patch(&original, None).is_err());
+        assert!(rewrite_patch(&original, Some("not a patch".to_string())).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use tree_sitter::Node;
 use tree_sitter::Parser;
 use tree_sitter::Tree;
 use tree_sitter_bash::LANGUAGE as BASH;
@@ -130,6 +131,187 @@ fn parse_plain_command_from_node(cmd: tree_sitter::Node, src: &str) -> Option<Ve
     Some(words)
 }
 
+/// Stands in for a word whose value comes from command substitution in
+/// [`ShellScript::commands`]. It contains a NUL byte, so it cannot be confused
+/// with a real argument.
+pub const SUBSTITUTED_WORD: &str = "\0$(...)";
+
+/// A bash script decomposed into the simple commands it runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellScript {
+    /// Every simple command in the script, including those inside subshells
+    /// and command substitutions.
+    pub commands: Vec<Vec<String>>,
+    /// Files that output is redirected to with `>`, `>>`, `&>` and the like.
+    pub write_redirects: Vec<String>,
+    /// Whether the script contains a `( ... )` subshell.
+    pub has_subshell: bool,
+    /// Whether the script contains `$( ... )` or backtick command substitution.
+    pub has_command_substitution: bool,
+}
+
+/// Decompose a script into the simple commands joined by `&&`, `||`, `;` and
+/// `|`, recording file redirections, subshells and command substitutions
+/// along the way.
+///
+/// Returns `None` if the script uses any other construct (variable expansion,
+/// globs, control flow, here-documents, etc.) or a word whose value cannot be
+/// determined without running the script.
+pub fn try_parse_shell_script(tree: &Tree, src: &str) -> Option<ShellScript> {
+    let root = tree.root_node();
+    if root.has_error() {
+        return None;
+    }
+    let mut script = ShellScript::default();
+    collect_statement(root, src, &mut script)?;
+    Some(script)
+}
+
+fn collect_statement(node: Node, src: &str, script: &mut ShellScript) -> Option<()> {
+    let mut cursor = node.walk();
+    match node.kind() {
+        "program" | "list" | "pipeline" | "subshell" => {
+            if node.kind() == "subshell" {
+                script.has_subshell = true;
+            }
+            for child in node.children(&mut cursor) {
+                if child.is_named() {
+                    collect_statement(child, src, script)?;
+                } else if !matches!(child.kind(), "&&" | "||" | ";" | "|" | "(" | ")") {
+                    return None;
+                }
+            }
+        }
+        "redirected_statement" => {
+            for child in node.children(&mut cursor) {
+                if child.kind() == "file_redirect" {
+                    collect_redirect(child, src, script)?;
+                } else if child.is_named() {
+                    collect_statement(child, src, script)?;
+                } else {
+                    return None;
+                }
+            }
+        }
+        "command" => {
+            let mut words = Vec::new();
+            for child in node.children(&mut cursor) {
+                let word = if child.kind() == "command_name" {
+                    let name = child.named_child(0)?;
+                    if name.kind() != "word" {
+                        return None;
+                    }
+                    literal_word(name, src)?
+                } else {
+                    collect_word(child, src, script)?
+                };
+                words.push(word);
+            }
+            script.commands.push(words);
+        }
+        "comment" => {}
+        _ => return None,
+    }
+    Some(())
+}
+
+fn collect_redirect(node: Node, src: &str, script: &mut ShellScript) -> Option<()> {
+    let mut operator = None;
+    let mut destination = None;
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() == "file_descriptor" {
+            continue;
+        }
+        if !child.is_named() {
+            operator = Some(child.kind());
+        } else if destination.replace(child).is_some() {
+            return None;
+        }
+    }
+
+    match (operator?, destination) {
+        // Duplicating or closing a file descriptor touches no files.
+        (">&" | "<&", Some(dest)) if dest.kind() == "number" => {}
+        (">&-" | "<&-", None) => {}
+        ("<", Some(dest)) => {
+            collect_word(dest, src, script)?;
+        }
+        (">" | ">>" | ">|" | "&>" | "&>>" | ">&", Some(dest)) => {
+            let target = collect_word(dest, src, script)?;
+            if target.contains(SUBSTITUTED_WORD) {
+                return None;
+            }
+            script.write_redirects.push(target);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn collect_word(node: Node, src: &str, script: &mut ShellScript) -> Option<String> {
+    match node.kind() {
+        "word" | "number" => literal_word(node, src),
+        "raw_string" => {
+            let raw_string = node.utf8_text(src.as_bytes()).ok()?;
+            raw_string
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .map(str::to_owned)
+        }
+        "string" => {
+            let mut value = String::new();
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                match child.kind() {
+                    "\"" => {}
+                    "string_content" => {
+                        let text = child.utf8_text(src.as_bytes()).ok()?;
+                        if text.contains('\\') {
+                            return None;
+                        }
+                        value.push_str(text);
+                    }
+                    "command_substitution" => {
+                        collect_substitution(child, src, script)?;
+                        value.push_str(SUBSTITUTED_WORD);
+                    }
+                    _ => return None,
+                }
+            }
+            Some(value)
+        }
+        "command_substitution" => {
+            collect_substitution(node, src, script)?;
+            Some(SUBSTITUTED_WORD.to_owned())
+        }
+        _ => None,
+    }
+}
+
+fn collect_substitution(node: Node, src: &str, script: &mut ShellScript) -> Option<()> {
+    script.has_command_substitution = true;
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.is_named() {
+            collect_statement(child, src, script)?;
+        } else if !matches!(child.kind(), "$(" | "`" | ")") {
+            return None;
+        }
+    }
+    Some(())
+}
+
+/// An unquoted word, rejected if the shell would expand it (globs, braces,
+/// `~`) or if it contains escapes.
+fn literal_word(node: Node, src: &str) -> Option<String> {
+    let text = node.utf8_text(src.as_bytes()).ok()?;
+    if text.contains(['*', '?', '[', ']', '{', '}', '~', '\\']) {
+        return None;
+    }
+    Some(text.to_owned())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,4 +397,60 @@ mod tests {
     fn rejects_trailing_operator_parse_error() {
         assert!(parse_seq("ls &&").is_none());
     }
+
+    fn parse_script(src: &str) -> Option<ShellScript> {
+        let tree = try_parse_bash(src)?;
+        try_parse_shell_script(&tree, src)
+    }
+
+    fn words(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn shell_script_is_decomposed_into_commands_and_redirects() {
+        let script =
+            parse_script("rg foo | head -n 20 > out.txt 2>&1 && cat 'a b' 2>/dev/null").unwrap();
+        assert_eq!(
+            script,
+            ShellScript {
+                commands: vec![
+                    words(&["rg", "foo"]),
+                    words(&["head", "-n", "20"]),
+                    words(&["cat", "a b"]),
+                ],
+                write_redirects: words(&["out.txt", "/dev/null"]),
+                has_subshell: false,
+                has_command_substitution: false,
+            }
+        );
+    }
+
+    #[test]
+    fn shell_script_records_subshells_and_substitutions() {
+        let script = parse_script("(cd src && ls) ; cat \"$(git ls-files)\" < in.txt").unwrap();
+        assert_eq!(
+            script.commands,
+            vec![
+                words(&["cd", "src"]),
+                words(&["ls"]),
+                words(&["git", "ls-files"]),
+                vec!["cat".to_string(), SUBSTITUTED_WORD.to_string()],
+            ]
+        );
+        assert!(script.write_redirects.is_empty());
+        assert!(script.has_subshell);
+        assert!(script.has_command_substitution);
+    }
+
+    #[test]
+    fn shell_script_rejects_words_with_unknown_values() {
+        assert!(parse_script("cp a ~/b").is_none());
+        assert!(parse_script("rm *.rs").is_none());
+        assert!(parse_script("echo $HOME").is_none());
+        assert!(parse_script("FOO=bar ls").is_none());
+        assert!(parse_script("echo hi > $(pwd)/out").is_none());
+        assert!(parse_script("cat <<EOF\nhi\nEOF").is_none());
+        assert!(parse_script("for f in a b; do rm $f; done").is_none());
+    }
 }
@@ -0,0 +1,227 @@
+//! Enforcement of the per-turn and per-session budgets configured in
+//! [`crate::config_types::Budgets`].
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::config_types::Budget;
+use crate::config_types::Budgets;
+use crate::protocol::BudgetExceededEvent;
+use crate::protocol::BudgetKind;
+use crate::protocol::BudgetScope;
+use crate::protocol::TokenUsage;
+
+/// Resources consumed so far within one budget scope.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct BudgetUsage {
+    pub(crate) tool_calls: u64,
+    pub(crate) tokens: u64,
+}
+
+impl BudgetUsage {
+    pub(crate) fn record(&mut self, tool_calls: u64, tokens: u64) {
+        self.tool_calls = self.tool_calls.saturating_add(tool_calls);
+        self.tokens = self.tokens.saturating_add(tokens);
+    }
+}
+
+/// Returns the first limit in `budget` that `usage` (or `elapsed`) has
+/// reached. A budget is exhausted once usage reaches its limit, so the task
+/// stops before the next model request.
+pub(crate) fn check_budget(
+    scope: BudgetScope,
+    budget: &Budget,
+    usage: &BudgetUsage,
+    elapsed: Duration,
+) -> Option<BudgetExceededEvent> {
+    let exceeded = |kind, limit, used| {
+        (used >= limit).then_some(BudgetExceededEvent {
+            scope,
+            kind,
+            limit,
+            used,
+        })
+    };
+    check_tool_call_budget(scope, budget, usage)
+        .or_else(|| {
+            budget
+                .max_tokens
+                .and_then(|limit| exceeded(BudgetKind::Tokens, limit, usage.tokens))
+        })
+        .or_else(|| {
+            budget
+                .max_duration_secs
+                .and_then(|limit| exceeded(BudgetKind::DurationSecs, limit, elapsed.as_secs()))
+        })
+}
+
+/// Returns the tool-call limit in `budget` if `usage` leaves no call for it.
+/// Checked before every call, so a single response cannot run past the limit.
+pub(crate) fn check_tool_call_budget(
+    scope: BudgetScope,
+    budget: &Budget,
+    usage: &BudgetUsage,
+) -> Option<BudgetExceededEvent> {
+    let limit = budget.max_tool_calls?;
+    (usage.tool_calls >= limit).then_some(BudgetExceededEvent {
+        scope,
+        kind: BudgetKind::ToolCalls,
+        limit,
+        used: usage.tool_calls,
+    })
+}
+
+/// Tokens a model response adds on top of the `previous` response of the
+/// conversation: its output plus the input that was not already in the
+/// previous context window. Every request re-sends the history, so summing
+/// `total_tokens` would count it again for every response.
+pub(crate) fn new_tokens(usage: &TokenUsage, previous: Option<&TokenUsage>) -> u64 {
+    let resent = previous.map_or(0, TokenUsage::tokens_in_context_window);
+    usage
+        .input_tokens
+        .saturating_sub(resent)
+        .saturating_add(usage.output_tokens)
+}
+
+/// Earliest point in time at which a wall-clock budget runs out, together
+/// with the event to report when it does.
+pub(crate) fn wall_clock_deadline(
+    budgets: &Budgets,
+    turn_started_at: Instant,
+    session_started_at: Instant,
+) -> Option<(Instant, BudgetExceededEvent)> {
+    [
+        (BudgetScope::Turn, budgets.turn, turn_started_at),
+        (BudgetScope::Session, budgets.session, session_started_at),
+    ]
+    .into_iter()
+    .filter_map(|(scope, budget, started_at)| {
+        let limit = budget.max_duration_secs?;
+        let event = BudgetExceededEvent {
+            scope,
+            kind: BudgetKind::DurationSecs,
+            limit,
+            used: limit,
+        };
+        Some((started_at + Duration::from_secs(limit), event))
+    })
+    .min_by_key(|(deadline, _)| *deadline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unlimited_budget_is_never_exhausted() {
+        let usage = BudgetUsage {
+            tool_calls: u64::MAX,
+            tokens: u64::MAX,
+        };
+        assert_eq!(
+            check_budget(
+                BudgetScope::Turn,
+                &Budget::default(),
+                &usage,
+                Duration::from_secs(u64::MAX)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn budget_is_exhausted_once_limit_is_reached() {
+        let budget = Budget {
+            max_tool_calls: Some(3),
+            max_tokens: Some(1_000),
+            max_duration_secs: None,
+        };
+        let mut usage = BudgetUsage::default();
+        usage.record(2, 400);
+        assert_eq!(
+            check_budget(BudgetScope::Session, &budget, &usage, Duration::ZERO),
+            None
+        );
+
+        usage.record(0, 600);
+        assert_eq!(
+            check_budget(BudgetScope::Session, &budget, &usage, Duration::ZERO),
+            Some(BudgetExceededEvent {
+                scope: BudgetScope::Session,
+                kind: BudgetKind::Tokens,
+                limit: 1_000,
+                used: 1_000,
+            })
+        );
+    }
+
+    #[test]
+    fn tool_calls_are_refused_once_the_limit_is_reached() {
+        let budget = Budget {
+            max_tool_calls: Some(2),
+            ..Default::default()
+        };
+        let mut usage = BudgetUsage::default();
+        usage.record(1, 0);
+        assert_eq!(
+            check_tool_call_budget(BudgetScope::Turn, &budget, &usage),
+            None
+        );
+        usage.record(1, 0);
+        assert_eq!(
+            check_tool_call_budget(BudgetScope::Turn, &budget, &usage),
+            Some(BudgetExceededEvent {
+                scope: BudgetScope::Turn,
+                kind: BudgetKind::ToolCalls,
+                limit: 2,
+                used: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn new_tokens_do_not_count_the_resent_history() {
+        let first = TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 200,
+            total_tokens: 1_200,
+            ..Default::default()
+        };
+        assert_eq!(new_tokens(&first, None), 1_200);
+
+        // The second request re-sends the first one's input and output.
+        let second = TokenUsage {
+            input_tokens: 1_300,
+            output_tokens: 50,
+            total_tokens: 1_350,
+            ..Default::default()
+        };
+        assert_eq!(new_tokens(&second, Some(&first)), 150);
+    }
+
+    #[test]
+    fn wall_clock_deadline_picks_the_earliest_scope() {
+        let session_started_at = Instant::now();
+        let turn_started_at = session_started_at + Duration::from_secs(50);
+        let budgets = Budgets {
+            turn: Budget {
+                max_duration_secs: Some(60),
+                ..Default::default()
+            },
+            session: Budget {
+                max_duration_secs: Some(100),
+                ..Default::default()
+            },
+        };
+
+        let (deadline, event) =
+            wall_clock_deadline(&budgets, turn_started_at, session_started_at).unwrap();
+        assert_eq!(deadline, session_started_at + Duration::from_secs(100));
+        assert_eq!(event.scope, BudgetScope::Session);
+        assert_eq!(
+            wall_clock_deadline(&Budgets::default(), turn_started_at, session_started_at),
+            None
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Per-command cgroup v2 subtrees that cap how many processes a command may
+//! run at once (`sandbox_limits.max_processes`).
+//!
+//! Codex only ever writes inside the cgroup it was started in, so the limit is
+//! available when that cgroup is delegated to the user running Codex (a
+//! systemd user service, `systemd-run --user --scope -p Delegate=yes`, or a
+//! container that mounts its own cgroup tree). Codex enables the `pids`
+//! controller for the children of its cgroup and moves each command into a
+//! child cgroup of its own before it executes.
+
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Prefix of the child cgroups created for commands. It is followed by the
+/// pid of the Codex process that created the cgroup and a counter.
+const COMMAND_CGROUP_PREFIX: &str = "codex-exec-";
+
+static NEXT_COMMAND_CGROUP: AtomicU64 = AtomicU64::new(0);
+
+/// Cgroups whose command has been spawned. They are removed once the command
+/// and everything it started have exited, which is when removing them stops
+/// failing with `EBUSY`.
+static SPAWNED_COMMAND_CGROUPS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// A child cgroup of Codex's own cgroup that limits one command.
+pub(crate) struct CommandCgroup {
+    dir: PathBuf,
+    procs: CString,
+}
+
+impl CommandCgroup {
+    /// Creates a cgroup whose `pids.max` is `max_processes`. Fails when
+    /// cgroup v2 is not mounted or Codex's cgroup is not delegated to it.
+    pub(crate) fn create(max_processes: u64) -> io::Result<Self> {
+        let parent = own_cgroup_dir()?;
+        enable_pids_controller(&parent)?;
+        remove_finished_command_cgroups(&parent);
+
+        let dir = parent.join(format!(
+            "{COMMAND_CGROUP_PREFIX}{}-{}",
+            std::process::id(),
+            NEXT_COMMAND_CGROUP.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&dir)?;
+        match limit_processes(&dir, max_processes) {
+            Ok(procs) => Ok(Self { dir, procs }),
+            Err(err) => {
+                let _ = fs::remove_dir(&dir);
+                Err(err)
+            }
+        }
+    }
+
+    /// Path of the `cgroup.procs` file the command writes itself to with
+    /// [`join_cgroup`].
+    pub(crate) fn procs_path(&self) -> &CStr {
+        &self.procs
+    }
+
+    /// Hands the cgroup over for removal once `spawned` command exits, or
+    /// removes it right away when the command could not be spawned.
+    pub(crate) fn finish_spawn(self, spawned: bool) {
+        if !spawned {
+            let _ = fs::remove_dir(&self.dir);
+            return;
+        }
+        if let Ok(mut cgroups) = SPAWNED_COMMAND_CGROUPS.lock() {
+            cgroups.push(self.dir);
+        }
+    }
+}
+
+/// Sets `pids.max` of the cgroup at `dir` and returns the path of its
+/// `cgroup.procs` file.
+fn limit_processes(dir: &Path, max_processes: u64) -> io::Result<CString> {
+    fs::write(dir.join("pids.max"), max_processes.to_string())?;
+    CString::new(dir.join("cgroup.procs").as_os_str().as_bytes()).map_err(io::Error::other)
+}
+
+/// Moves the calling process into the cgroup whose `cgroup.procs` file is
+/// `procs`. Runs between fork and exec, so it only calls async-signal-safe
+/// functions.
+pub(crate) fn join_cgroup(procs: &CStr) -> io::Result<()> {
+    let fd = unsafe { libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // Writing 0 moves the writing process.
+    let written = unsafe { libc::write(fd, b"0".as_ptr().cast(), 1) };
+    let result = if written == 1 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    };
+    unsafe { libc::close(fd) };
+    result
+}
+
+/// The directory of the cgroup v2 hierarchy this process belongs to.
+fn own_cgroup_dir() -> io::Result<PathBuf> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+    // The unified hierarchy is listed as `0::/path`.
+    let path = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "cgroup v2 is not mounted"))?;
+    Ok(Path::new(CGROUP_ROOT).join(path.trim_start_matches('/')))
+}
+
+fn enable_pids_controller(cgroup: &Path) -> io::Result<()> {
+    let subtree_control = cgroup.join("cgroup.subtree_control");
+    let enabled = fs::read_to_string(&subtree_control)?;
+    if enabled
+        .split_whitespace()
+        .any(|controller| controller == "pids")
+    {
+        return Ok(());
+    }
+    // `pids` is a threaded controller, so it can be enabled for the children
+    // even though Codex itself stays in this cgroup.
+    fs::write(&subtree_control, "+pids")
+}
+
+/// Removes the cgroups of commands that have exited, including those left
+/// behind by Codex processes that are no longer running.
+fn remove_finished_command_cgroups(parent: &Path) {
+    if let Ok(mut cgroups) = SPAWNED_COMMAND_CGROUPS.lock() {
+        cgroups.retain(|dir| fs::remove_dir(dir).is_err());
+    }
+    let Ok(entries) = fs::read_dir(parent) else {
+        return;
+    };
+    let own_pid = std::process::id().to_string();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(pid) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(COMMAND_CGROUP_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+        else {
+            continue;
+        };
+        if pid != own_pid && !Path::new("/proc").join(pid).exists() {
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    #[test]
+    fn pids_limit_stops_a_command_from_forking_past_it() {
+        let cgroup = match CommandCgroup::create(4) {
+            Ok(cgroup) => cgroup,
+            Err(err) => {
+                println!("Skipping test because no delegated cgroup is available: {err}");
+                return;
+            }
+        };
+        let procs = cgroup.procs_path().to_owned();
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args([
+            "-c",
+            "for i in 1 2 3 4 5 6 7 8; do sleep 2 & done; wait; echo done",
+        ]);
+        unsafe {
+            cmd.pre_exec(move || join_cgroup(&procs));
+        }
+        let output = cmd.output();
+        cgroup.finish_spawn(output.is_ok());
+        let output = output.unwrap();
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.to_ascii_lowercase().contains("fork"),
+            "expected fork failures, got {stderr:?}"
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! Workspace checkpoints backed by a shadow git object store.
+//!
+//! Before each turn the whole git worktree containing the session cwd is
+//! snapshotted into a bare repository under `CODEX_HOME/checkpoints`, so
+//! changes made by `apply_patch` *and* by shell commands can be rolled back.
+//! The user's own repository (index, refs, objects) is never touched: every
+//! command runs with an explicit `--git-dir` pointing at the shadow store and
+//! `--work-tree` pointing at the workspace.
+//!
+//! Snapshots honor the workspace's `.gitignore` files. Files outside the
+//! worktree are not covered.
+//!
+//! Sessions on the same worktree share the object store but each stages
+//! into its own index (`GIT_INDEX_FILE`), so they never contend for
+//! `index.lock`. Checkpoints of sessions idle for longer than
+//! [`CHECKPOINT_RETENTION`] are pruned when a session starts.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use sha1::Digest;
+use sha1::Sha1;
+use tokio::process::Command;
+use tokio::time::timeout;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::git_info::get_git_repo_root;
+
+/// Directory under `CODEX_HOME` that holds the shadow repositories.
+const CHECKPOINTS_SUBDIR: &str = "checkpoints";
+
+/// Upper bound for a single git invocation so a huge workspace cannot stall
+/// the turn indefinitely.
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Identity used for checkpoint commits; never visible in the user's repo.
+const CHECKPOINT_IDENTITY: &str = "codex";
+
+/// Prefix of the refs that keep each session's checkpoints reachable.
+const SESSION_REFS: &str = "refs/codex/sessions/";
+
+/// Directory inside the shadow repository that holds the per-session indexes.
+const INDEXES_SUBDIR: &str = "codex-indexes";
+
+/// Checkpoints of a session are pruned once its latest one is this old.
+const CHECKPOINT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    /// Submission id of the turn this checkpoint precedes.
+    sub_id: String,
+    commit: String,
+}
+
+/// Files touched by [`CheckpointStore::undo`], relative to the worktree.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct UndoOutcome {
+    pub(crate) restored: Vec<PathBuf>,
+    pub(crate) removed: Vec<PathBuf>,
+}
+
+/// Per-session list of checkpoints stored in the shadow repository for one
+/// worktree.
+pub(crate) struct CheckpointStore {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+    /// Ref that keeps this session's checkpoint commits reachable.
+    session_ref: String,
+    /// Index this session stages snapshots into.
+    index_file: PathBuf,
+    checkpoints: Vec<Checkpoint>,
+    initialized: bool,
+}
+
+impl CheckpointStore {
+    /// Returns a store for the git worktree containing `cwd`, or `None` when
+    /// `cwd` is not inside a git repository.
+    pub(crate) fn for_cwd(codex_home: &Path, cwd: &Path, session_id: Uuid) -> Option<Self> {
+        let work_tree = get_git_repo_root(cwd)?;
+        let mut hasher = Sha1::new();
+        hasher.update(work_tree.to_string_lossy().as_bytes());
+        let git_dir = codex_home
+            .join(CHECKPOINTS_SUBDIR)
+            .join(format!("{:x}.git", hasher.finalize()));
+        Some(Self {
+            index_file: git_dir.join(INDEXES_SUBDIR).join(session_id.to_string()),
+            git_dir,
+            work_tree,
+            session_ref: format!("{SESSION_REFS}{session_id}"),
+            checkpoints: Vec::new(),
+            initialized: false,
+        })
+    }
+
+    pub(crate) fn work_tree(&self) -> &Path {
+        &self.work_tree
+    }
+
+    /// Records the current state of the worktree as the checkpoint preceding
+    /// turn `sub_id`.
+    pub(crate) async fn create(&mut self, sub_id: &str) -> io::Result<()> {
+        self.ensure_initialized().await?;
+        let tree = self.snapshot_tree().await?;
+        let parent = self.checkpoints.last().map(|c| c.commit.clone());
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", sub_id];
+        if let Some(parent) = parent.as_deref() {
+            args.extend(["-p", parent]);
+        }
+        let commit = self.git_stdout(&args).await?;
+        self.git(&["update-ref", &self.session_ref, &commit])
+            .await?;
+        self.checkpoints.push(Checkpoint {
+            sub_id: sub_id.to_string(),
+            commit,
+        });
+        Ok(())
+    }
+
+    /// Restores the worktree to the checkpoint taken before the `turns`-th most
+    /// recent turn. Files that did not exist at that point are deleted. The
+    /// undone checkpoints are dropped.
+    pub(crate) async fn undo(&mut self, turns: usize) -> io::Result<UndoOutcome> {
+        let available = self.checkpoints.len();
+        if turns == 0 || turns > available {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot undo {turns} turn(s): {available} checkpoint(s) available"),
+            ));
+        }
+        let target = self.checkpoints[available - turns].clone();
+        let current_tree = self.snapshot_tree().await?;
+        let diff = self
+            .git_stdout_raw(&[
+                "diff-tree",
+                "-r",
+                "-z",
+                "--no-renames",
+                "--name-status",
+                &target.commit,
+                &current_tree,
+            ])
+            .await?;
+
+        let mut outcome = UndoOutcome::default();
+        let mut fields = diff.split(|b| *b == 0).filter(|f| !f.is_empty());
+        while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+            let path = PathBuf::from(String::from_utf8_lossy(path).into_owned());
+            if status == b"A" {
+                outcome.removed.push(path);
+            } else {
+                outcome.restored.push(path);
+            }
+        }
+
+        for path in &outcome.removed {
+            let abs = self.work_tree.join(path);
+            if let Err(e) = tokio::fs::remove_file(&abs).await
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                return Err(e);
+            }
+        }
+        if !outcome.restored.is_empty() {
+            let mut args = vec![
+                "checkout".to_string(),
+                target.commit.clone(),
+                "--".to_string(),
+            ];
+            args.extend(
+                outcome
+                    .restored
+                    .iter()
+                    .map(|p| format!(":(literal){}", p.to_string_lossy())),
+            );
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.git(&args).await?;
+        }
+
+        self.checkpoints.truncate(available - turns);
+        let head = self.checkpoints.last().map(|c| c.commit.clone());
+        match head {
+            Some(commit) => {
+                self.git(&["update-ref", &self.session_ref, &commit])
+                    .await?
+            }
+            None => self.git(&["update-ref", "-d", &self.session_ref]).await?,
+        }
+        tracing::info!(
+            "reverted workspace to checkpoint before turn {}: {} restored, {} removed",
+            target.sub_id,
+            outcome.restored.len(),
+            outcome.removed.len()
+        );
+        Ok(outcome)
+    }
+
+    async fn ensure_initialized(&mut self) -> io::Result<()> {
+        if self.initialized {
+            return Ok(());
+        }
+        tokio::fs::create_dir_all(&self.git_dir.join(INDEXES_SUBDIR)).await?;
+        let init = run_git(
+            Command::new("git")
+                .arg("init")
+                .arg("--bare")
+                .arg("--quiet")
+                .arg(&self.git_dir),
+        )
+        .await;
+        // Another session on the same worktree may be initializing the shared
+        // repository concurrently; its result is as good as ours.
+        if let Err(e) = init
+            && !self.git_dir.join("HEAD").is_file()
+        {
+            return Err(e);
+        }
+        self.initialized = true;
+        if let Err(e) = self.prune(SystemTime::now()).await {
+            warn!("failed to prune old checkpoints: {e}");
+        }
+        Ok(())
+    }
+
+    /// Drops the refs and indexes of sessions whose latest checkpoint is older
+    /// than [`CHECKPOINT_RETENTION`] at `now`, and lets git collect the
+    /// objects that are no longer reachable.
+    async fn prune(&self, now: SystemTime) -> io::Result<()> {
+        let cutoff = now
+            .checked_sub(CHECKPOINT_RETENTION)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let refs = self
+            .git_stdout(&[
+                "for-each-ref",
+                "--format=%(committerdate:unix) %(refname)",
+                SESSION_REFS,
+            ])
+            .await?;
+        let mut pruned = false;
+        for line in refs.lines() {
+            let Some((date, refname)) = line.split_once(' ') else {
+                continue;
+            };
+            if refname == self.session_ref || date.parse::<u64>().map_or(true, |d| d >= cutoff) {
+                continue;
+            }
+            self.git(&["update-ref", "-d", refname]).await?;
+            if let Some(session) = refname.strip_prefix(SESSION_REFS) {
+                let index = self.git_dir.join(INDEXES_SUBDIR).join(session);
+                let _ = tokio::fs::remove_file(index).await;
+            }
+            pruned = true;
+        }
+        if pruned {
+            self.git(&["gc", "--auto", "--quiet"]).await?;
+        }
+        Ok(())
+    }
+
+    /// Stages the entire worktree into the shadow index and returns the tree id.
+    async fn snapshot_tree(&self) -> io::Result<String> {
+        self.git(&["add", "--all"]).await?;
+        self.git_stdout(&["write-tree"]).await
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("--work-tree")
+            .arg(&self.work_tree)
+            .args(args)
+            .current_dir(&self.work_tree)
+            .env("GIT_INDEX_FILE", &self.index_file)
+            .env("GIT_AUTHOR_NAME", CHECKPOINT_IDENTITY)
+            .env("GIT_AUTHOR_EMAIL", CHECKPOINT_IDENTITY)
+            .env("GIT_COMMITTER_NAME", CHECKPOINT_IDENTITY)
+            .env("GIT_COMMITTER_EMAIL", CHECKPOINT_IDENTITY)
+            .kill_on_drop(true);
+        cmd
+    }
+
+    async fn git(&self, args: &[&str]) -> io::Result<()> {
+        run_git(&mut self.command(args)).await.map(|_| ())
+    }
+
+    async fn git_stdout(&self, args: &[&str]) -> io::Result<String> {
+        let stdout = self.git_stdout_raw(args).await?;
+        Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+    }
+
+    async fn git_stdout_raw(&self, args: &[&str]) -> io::Result<Vec<u8>> {
+        run_git(&mut self.command(args)).await
+    }
+}
+
+impl Drop for CheckpointStore {
+    fn drop(&mut self) {
+        // The index only speeds up snapshots within this session.
+        let _ = std::fs::remove_file(&self.index_file);
+    }
+}
+
+async fn run_git(cmd: &mut Command) -> io::Result<Vec<u8>> {
+    let output = timeout(GIT_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "git command timed out"))??;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!("checkpoint git command failed: {stderr}");
+        Err(io::Error::other(format!(
+            "git exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn init_repo(dir: &Path) {
+        let status = Command::new("git")
+            .args(["init", "--quiet"])
+            .current_dir(dir)
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn undo_restores_modified_files_and_removes_new_ones() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+        std::fs::write(repo.path().join("kept.txt"), "original\n").unwrap();
+        std::fs::write(repo.path().join("deleted.txt"), "bye\n").unwrap();
+
+        let mut store =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        store.create("turn-1").await.unwrap();
+
+        std::fs::write(repo.path().join("kept.txt"), "changed\n").unwrap();
+        std::fs::remove_file(repo.path().join("deleted.txt")).unwrap();
+        std::fs::write(repo.path().join("added.txt"), "new\n").unwrap();
+
+        let outcome = store.undo(1).await.unwrap();
+        assert_eq!(
+            outcome,
+            UndoOutcome {
+                restored: vec![PathBuf::from("deleted.txt"), PathBuf::from("kept.txt")],
+                removed: vec![PathBuf::from("added.txt")],
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("kept.txt")).unwrap(),
+            "original\n"
+        );
+        assert!(repo.path().join("deleted.txt").exists());
+        assert!(!repo.path().join("added.txt").exists());
+        assert_eq!(store.checkpoints.len(), 0);
+
+        // The user's repository is untouched.
+        assert!(!repo.path().join(".git").join("refs").join("codex").exists());
+    }
+
+    #[tokio::test]
+    async fn undo_rejects_more_turns_than_recorded() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+        let mut store =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        store.create("turn-1").await.unwrap();
+
+        let err = store.undo(2).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(store.checkpoints.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn sessions_use_their_own_index() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+        std::fs::write(repo.path().join("a.txt"), "a\n").unwrap();
+
+        let mut first =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        let mut second =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        assert_eq!(first.git_dir, second.git_dir);
+        assert_ne!(first.index_file, second.index_file);
+
+        let (a, b) = tokio::join!(first.create("turn-1"), second.create("turn-1"));
+        a.unwrap();
+        b.unwrap();
+        assert!(first.index_file.exists());
+        assert!(!first.git_dir.join("index").exists());
+
+        let index_file = first.index_file.clone();
+        drop(first);
+        assert!(!index_file.exists());
+    }
+
+    #[tokio::test]
+    async fn prune_drops_stale_sessions_only() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+
+        let mut old =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        old.create("turn-1").await.unwrap();
+        let mut current =
+            CheckpointStore::for_cwd(codex_home.path(), repo.path(), Uuid::new_v4()).unwrap();
+        current.create("turn-1").await.unwrap();
+
+        let refs = |store: &CheckpointStore| {
+            let output = std::process::Command::new("git")
+                .arg("--git-dir")
+                .arg(&store.git_dir)
+                .args(["for-each-ref", "--format=%(refname)", SESSION_REFS])
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        // Nothing is stale yet.
+        current.prune(SystemTime::now()).await.unwrap();
+        assert_eq!(refs(&current).lines().count(), 2);
+
+        // Long after both sessions, only the other session is pruned.
+        let later = SystemTime::now() + CHECKPOINT_RETENTION + Duration::from_secs(60);
+        current.prune(later).await.unwrap();
+        assert_eq!(refs(&current).trim(), current.session_ref);
+    }
+
+    #[test]
+    fn no_store_outside_git_repositories() {
+        let codex_home = TempDir::new().unwrap();
+        let dir = TempDir::new().unwrap();
+        assert!(CheckpointStore::for_cwd(codex_home.path(), dir.path(), Uuid::new_v4()).is_none());
+    }
+}
@@ -47,6 +47,10 @@ use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::models::ResponseItem;
 use std::sync::Arc;
 
+/// Percentage of the model context window at which history is compacted
+/// automatically when no explicit limit is configured.
+const AUTO_COMPACT_CONTEXT_WINDOW_PERCENT: u64 = 90;
+
 #[derive(Debug, Deserialize)]
 struct ErrorResponse {
     error: Error,
@@ -103,6 +107,15 @@ impl ModelClient {
             .or_else(|| get_model_info(&self.config.model_family).map(|info| info.context_window))
     }
 
+    /// Token usage at which history should be compacted before the next
+    /// request. Falls back to a fraction of the model context window.
+    pub fn get_auto_compact_token_limit(&self) -> Option<u64> {
+        self.config.model_auto_compact_token_limit.or_else(|| {
+            self.get_model_context_window()
+                .map(|window| window * AUTO_COMPACT_CONTEXT_WINDOW_PERCENT / 100)
+        })
+    }
+
     /// Dispatches to either the Responses or Chat implementation depending on
     /// the provider config.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
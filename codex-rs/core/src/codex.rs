@@ -1,12 +1,15 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::event_mapping::map_response_item_to_event_messages;
@@ -24,6 +27,8 @@ use futures::prelude::*;
 use mcp_types::CallToolResult;
 use serde::Serialize;
 use serde_json;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::OwnedMutexGuard;
 use tokio::sync::oneshot;
 use tokio::task::AbortHandle;
 use tracing::debug;
@@ -39,13 +44,25 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::budget::BudgetUsage;
+use crate::budget::check_budget;
+use crate::budget::check_tool_call_budget;
+use crate::budget::new_tokens;
+use crate::budget::wall_clock_deadline;
+use crate::checkpoints::CheckpointStore;
+use crate::checkpoints::UndoOutcome;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::SandboxLimits;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
 use crate::conversation_manager::InitialHistory;
+use crate::conversation_manager::ResumedHistory;
+use crate::delegate_tool::DELEGATE_TOOL_NAME;
+use crate::delegate_tool::handle_delegate;
+use crate::egress_proxy::EgressProxy;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -62,8 +79,14 @@ use crate::exec_command::ExecCommandParams;
 use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
+use crate::exec_env::EGRESS_PROXY_UNAVAILABLE_MESSAGE;
 use crate::exec_env::create_env;
+use crate::exec_policy::ExecPolicy;
+use crate::hooks::HookDecision;
+use crate::hooks::HookEvent;
 use crate::hooks::HookOnError;
+use crate::hooks::HookOutcome;
+use crate::hooks::HookPayload;
 use crate::hooks::HookRoute;
 use crate::hooks::HookSpec;
 use crate::hooks::HooksConfig;
@@ -75,6 +98,10 @@ use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::parallel_tools::CallPhase;
+use crate::parallel_tools::CallSequencer;
+use crate::parallel_tools::is_parallel_safe;
+use crate::parallel_tools::tool_call_id;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
@@ -86,6 +113,9 @@ use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::BudgetExceededEvent;
+use crate::protocol::BudgetScope;
+use crate::protocol::ContextCompactedEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -104,12 +134,22 @@ use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
 use crate::protocol::TaskCompleteEvent;
+use crate::protocol::TokenCountEvent;
+use crate::protocol::TokenUsage;
+use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::UndoCompletedEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::rollout::DelegateRecord;
+use crate::rollout::ResumeRecord;
 use crate::rollout::RolloutRecorder;
+use crate::rollout::SessionStateSnapshot;
+use crate::rollout::TurnSettings;
 use crate::safety::SafetyCheck;
+use crate::safety::allow_without_prompt;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::writable_root_to_grant;
 use crate::shell;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::user_instructions::UserInstructions;
@@ -124,6 +164,7 @@ use codex_protocol::models::LocalShellAction;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::plan_tool::UpdatePlanArgs;
 
 // A convenience extension trait for acquiring mutex locks where poisoning is
 // unrecoverable and should abort the program. This avoids scattered `.unwrap()`
@@ -173,10 +214,29 @@ impl Codex {
         config: Config,
         auth_manager: Arc<AuthManager>,
         conversation_history: InitialHistory,
+    ) -> CodexResult<CodexSpawnOk> {
+        Self::spawn_with_parent(config, auth_manager, conversation_history, None).await
+    }
+
+    /// Like [`Codex::spawn`], but records `parent_conversation_id` as the
+    /// conversation that delegated work to the new session.
+    pub(crate) async fn spawn_with_parent(
+        config: Config,
+        auth_manager: Arc<AuthManager>,
+        conversation_history: InitialHistory,
+        parent_conversation_id: Option<ConversationId>,
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
         let (tx_event, rx_event) = async_channel::unbounded();
 
+        // A resumed session continues with the model and policies it ended with.
+        let mut config = config;
+        if let InitialHistory::Resumed(resumed) = &conversation_history
+            && let Some(turn) = &resumed.state.turn
+        {
+            restore_turn_settings(&mut config, turn);
+        }
+
         let user_instructions = get_user_instructions(&config).await;
 
         let config = Arc::new(config);
@@ -193,6 +253,7 @@ impl Codex {
             disable_response_storage: config.disable_response_storage,
             notify: config.notify.clone(),
             cwd: config.cwd.clone(),
+            parent_conversation_id,
         };
 
         // Generate a unique ID for the lifetime of this Codex session.
@@ -265,21 +326,205 @@ impl Codex {
     }
 }
 
+/// Applies the turn settings recorded in a resumed rollout to `config`, except
+/// those the user set explicitly. The recorded approval and sandbox policies
+/// are only taken when they are no more permissive than the configured ones.
+fn restore_turn_settings(config: &mut Config, turn: &TurnSettings) {
+    let explicit = config.explicit_settings;
+    if !explicit.model && turn.model != config.model {
+        config.model_family =
+            find_family_for_model(&turn.model).unwrap_or_else(|| config.model_family.clone());
+        if let Some(model_info) = get_model_info(&config.model_family) {
+            config.model_context_window = Some(model_info.context_window);
+        }
+        config.model = turn.model.clone();
+    }
+    if !explicit.model_reasoning_effort {
+        config.model_reasoning_effort = turn.reasoning_effort;
+    }
+    if !explicit.model_reasoning_summary {
+        config.model_reasoning_summary = turn.reasoning_summary;
+    }
+    if !explicit.approval_policy
+        && approval_permissiveness(turn.approval_policy)
+            <= approval_permissiveness(config.approval_policy)
+    {
+        config.approval_policy = turn.approval_policy;
+    }
+    if !explicit.sandbox_policy && sandbox_is_no_wider(&turn.sandbox_policy, &config.sandbox_policy)
+    {
+        config.sandbox_policy = turn.sandbox_policy.clone();
+    }
+    // The session may be resumed on a machine where its directory is gone.
+    if !explicit.cwd && turn.cwd.is_dir() {
+        config.cwd = turn.cwd.clone();
+    }
+}
+
+/// Whether the approvals and writable roots granted in a resumed session
+/// still apply: they were given under the recorded approval and sandbox
+/// policies, so they only carry over when the session resumes with exactly
+/// those policies.
+fn recorded_grants_apply(config: &Config, turn: Option<&TurnSettings>) -> bool {
+    turn.is_some_and(|turn| {
+        turn.approval_policy == config.approval_policy
+            && turn.sandbox_policy == config.sandbox_policy
+    })
+}
+
+/// Orders approval policies from the one that asks the user most often to
+/// the one that never asks.
+fn approval_permissiveness(policy: AskForApproval) -> u8 {
+    match policy {
+        AskForApproval::UnlessTrusted => 0,
+        AskForApproval::OnRequest => 1,
+        AskForApproval::OnFailure => 2,
+        AskForApproval::Never => 3,
+    }
+}
+
+/// Whether `recorded` grants nothing that `current` does not: no more
+/// writable paths, no more network access, no fewer unreadable paths and no
+/// weaker isolation.
+fn sandbox_is_no_wider(recorded: &SandboxPolicy, current: &SandboxPolicy) -> bool {
+    let writes = match (recorded, current) {
+        (_, SandboxPolicy::DangerFullAccess) | (SandboxPolicy::ReadOnly, _) => true,
+        (SandboxPolicy::DangerFullAccess, _)
+        | (SandboxPolicy::WorkspaceWrite { .. }, SandboxPolicy::ReadOnly) => false,
+        (
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: recorded_roots,
+                exclude_tmpdir_env_var: recorded_excludes_tmpdir,
+                exclude_slash_tmp: recorded_excludes_slash_tmp,
+                ..
+            },
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: current_roots,
+                exclude_tmpdir_env_var: current_excludes_tmpdir,
+                exclude_slash_tmp: current_excludes_slash_tmp,
+                ..
+            },
+        ) => {
+            recorded_roots
+                .iter()
+                .all(|root| current_roots.contains(root))
+                && (*recorded_excludes_tmpdir || !*current_excludes_tmpdir)
+                && (*recorded_excludes_slash_tmp || !*current_excludes_slash_tmp)
+        }
+    };
+    let network = current.has_full_network_access()
+        || (!recorded.has_full_network_access()
+            && recorded
+                .get_allowed_domains()
+                .iter()
+                .all(|domain| current.get_allowed_domains().contains(domain)));
+    let reads = current
+        .get_deny_read_paths()
+        .iter()
+        .all(|path| recorded.get_deny_read_paths().contains(path));
+    let isolation = recorded.isolates_namespaces() || !current.isolates_namespaces();
+    writes && network && reads && isolation
+}
+
+/// Instructions sent as a user turn when summarizing the conversation history.
+const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
+
+/// An approval request the user has not answered yet.
+struct PendingApproval {
+    tx_approve: oneshot::Sender<ReviewDecision>,
+    /// `Op::ExecApproval` and `Op::PatchApproval` identify a request only by
+    /// its submission id, which the calls of a parallel batch share. Holding
+    /// the session's approval slot until the answer arrives keeps a second
+    /// call from prompting while this one is still pending.
+    _slot: OwnedMutexGuard<()>,
+}
+
 /// Mutable state of the agent
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// Directories the user made writable for the rest of the session, on
+    /// top of the writable roots of the turn's sandbox policy.
+    granted_writable_roots: Vec<PathBuf>,
     current_task: Option<AgentTask>,
-    pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    pending_approvals: HashMap<String, PendingApproval>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
+    /// Usage reported by the most recent model response; used to decide when
+    /// to compact the history automatically.
+    latest_token_usage: Option<TokenUsage>,
+    /// Tool calls and tokens consumed by the running task.
+    turn_budget_usage: BudgetUsage,
+    /// Tool calls and tokens consumed across every task in this session.
+    session_budget_usage: BudgetUsage,
+    /// Token usage of the whole session and of the latest response, as
+    /// reported in `EventMsg::TokenCount`.
+    token_info: Option<TokenUsageInfo>,
+    /// Latest plan from the `update_plan` tool.
+    plan: Option<UpdatePlanArgs>,
+    /// Settings of the current turn context.
+    turn_settings: Option<TurnSettings>,
+    /// Approval requests shown to the user.
+    approval_requests: u64,
+    /// Turns that were interrupted, replaced or stopped by a budget.
+    aborted_turns: u64,
+    /// Snapshot most recently written to the rollout.
+    recorded_state: Option<SessionStateSnapshot>,
+}
+
+impl State {
+    /// The parts of this state that are persisted to the rollout.
+    fn snapshot(&self) -> SessionStateSnapshot {
+        let mut approved_commands: Vec<Vec<String>> =
+            self.approved_commands.iter().cloned().collect();
+        approved_commands.sort();
+        SessionStateSnapshot {
+            approved_commands,
+            granted_writable_roots: self.granted_writable_roots.clone(),
+            plan: self.plan.clone(),
+            turn: self.turn_settings.clone(),
+            total_token_usage: self
+                .token_info
+                .as_ref()
+                .map(|info| info.total_token_usage.clone()),
+            approval_requests: self.approval_requests,
+            aborted_turns: self.aborted_turns,
+        }
+    }
+
+    /// Restores the state persisted by a previous run of the session. The
+    /// approved commands and granted writable roots are only restored when
+    /// `keep_grants` is set, since they widen what the session may do.
+    fn restore(
+        &mut self,
+        snapshot: &SessionStateSnapshot,
+        model_context_window: Option<u64>,
+        keep_grants: bool,
+    ) {
+        if keep_grants {
+            self.approved_commands = snapshot.approved_commands.iter().cloned().collect();
+            self.granted_writable_roots = snapshot.granted_writable_roots.clone();
+        }
+        self.plan = snapshot.plan.clone();
+        self.token_info =
+            snapshot
+                .total_token_usage
+                .clone()
+                .map(|total_token_usage| TokenUsageInfo {
+                    total_token_usage,
+                    last_token_usage: TokenUsage::default(),
+                    model_context_window,
+                });
+        self.approval_requests = snapshot.approval_requests;
+        self.aborted_turns = snapshot.aborted_turns;
+    }
 }
 
 /// Context for an initialized model agent
 ///
 /// A session has at most 1 running task at a time, and can be interrupted by user input.
 pub(crate) struct Session {
-    session_id: Uuid,
+    pub(crate) session_id: Uuid,
     tx_event: Sender<Event>,
 
     /// Manager for external MCP servers/tools.
@@ -300,6 +545,32 @@ pub(crate) struct Session {
 
     /// Optional event hooks configuration.
     hooks: Option<HooksConfig>,
+
+    /// User-supplied exec policies consulted by `assess_command_safety`.
+    exec_policy: ExecPolicy,
+
+    /// Proxy through which sandboxed commands reach
+    /// `sandbox_workspace_write.allowed_domains`, if any are configured.
+    egress_proxy: Option<EgressProxy>,
+
+    /// Configuration and credentials the session was created with. Child
+    /// conversations spawned by the `delegate` tool start from these.
+    pub(crate) config: Arc<Config>,
+    pub(crate) auth_manager: Arc<AuthManager>,
+
+    /// When the session started; the reference point for session budgets.
+    started_at: Instant,
+
+    /// Workspace checkpoints taken before each turn, used by `Op::Undo`.
+    /// `None` when checkpoints are disabled or the cwd is not in a git repo.
+    checkpoints: tokio::sync::Mutex<Option<CheckpointStore>>,
+
+    /// Orders the begin/end events of the tool calls currently running
+    /// concurrently, if any.
+    call_sequencer: Mutex<Option<Arc<CallSequencer>>>,
+
+    /// Held by the approval request currently waiting for the user, if any.
+    approval_slot: Arc<tokio::sync::Mutex<()>>,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -318,9 +589,22 @@ pub(crate) struct TurnContext {
     pub(crate) disable_response_storage: bool,
     pub(crate) tools_config: ToolsConfig,
     pub(crate) exec_timeout_ms: u64,
+    pub(crate) sandbox_limits: SandboxLimits,
 }
 
 impl TurnContext {
+    /// The model and policy settings persisted with the session state.
+    fn settings(&self) -> TurnSettings {
+        TurnSettings {
+            model: self.client.get_model(),
+            reasoning_effort: self.client.get_reasoning_effort(),
+            reasoning_summary: self.client.get_reasoning_summary(),
+            approval_policy: self.approval_policy,
+            sandbox_policy: self.sandbox_policy.clone(),
+            cwd: self.cwd.clone(),
+        }
+    }
+
     fn resolve_path(&self, path: Option<String>) -> PathBuf {
         path.as_ref()
             .map(PathBuf::from)
@@ -365,6 +649,10 @@ struct ConfigureSession {
     /// `ConfigureSession` operation so that the business-logic layer can
     /// operate deterministically.
     cwd: PathBuf,
+
+    /// Conversation that delegated work to this session, if any. Recorded in
+    /// the rollout so child sessions can be traced back to their parent.
+    parent_conversation_id: Option<ConversationId>,
 }
 
 impl Session {
@@ -388,6 +676,7 @@ impl Session {
             disable_response_storage,
             notify,
             cwd,
+            parent_conversation_id,
         } = configure_session;
         debug!("Configuring session: model={model}; provider={provider:?}");
         if !cwd.is_absolute() {
@@ -407,6 +696,7 @@ impl Session {
             &config,
             ConversationId(session_id),
             user_instructions.clone(),
+            parent_conversation_id,
         );
 
         let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
@@ -422,10 +712,14 @@ impl Session {
             anyhow::anyhow!("failed to initialize rollout recorder: {e:#}")
         })?;
         // Create the mutable state for the Session.
-        let state = State {
+        let mut state = State {
             history: ConversationHistory::new(),
             ..Default::default()
         };
+        if let InitialHistory::Resumed(resumed) = &initial_history {
+            let keep_grants = recorded_grants_apply(&config, resumed.state.turn.as_ref());
+            state.restore(&resumed.state, config.model_context_window, keep_grants);
+        }
 
         // Handle MCP manager result and record any startup failures.
         let (mcp_connection_manager, failed_clients) = match mcp_res {
@@ -453,6 +747,34 @@ impl Session {
             }
         }
 
+        let (exec_policy, exec_policy_errors) = ExecPolicy::load(&config.exec_policy_files);
+        for message in exec_policy_errors {
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
+        let egress_proxy = if sandbox_policy.has_full_network_access()
+            || sandbox_policy.get_allowed_domains().is_empty()
+        {
+            None
+        } else {
+            match EgressProxy::start(sandbox_policy.get_allowed_domains().to_vec()) {
+                Ok(proxy) => Some(proxy),
+                Err(e) => {
+                    let message = format!("failed to start egress proxy: {e}");
+                    error!("{message}");
+                    post_session_configured_error_events.push(Event {
+                        id: INITIAL_SUBMIT_ID.to_owned(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    });
+                    None
+                }
+            }
+        };
+
         // Now that `session_id` is final (may have been updated by resume),
         // construct the model client.
         let client = ModelClient::new(
@@ -474,6 +796,7 @@ impl Session {
                 include_web_search_request: config.tools_web_search_request,
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
+                include_delegate_tool: config.include_delegate_tool,
             }),
             user_instructions,
             base_instructions,
@@ -483,7 +806,25 @@ impl Session {
             cwd,
             disable_response_storage,
             exec_timeout_ms: config.exec_timeout_ms,
+            sandbox_limits: config.sandbox_limits,
         };
+        state.turn_settings = Some(turn_context.settings());
+        // Replay the restored plan and token totals so UIs can show them.
+        let mut restored_state_events = Vec::<Event>::new();
+        if let Some(plan) = &state.plan {
+            restored_state_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::PlanUpdate(plan.clone()),
+            });
+        }
+        if let Some(info) = &state.token_info {
+            restored_state_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::TokenCount(TokenCountEvent {
+                    info: Some(info.clone()),
+                }),
+            });
+        }
         let rollout_path = rollout_recorder.path().to_path_buf();
         let sess = Arc::new(Session {
             session_id,
@@ -497,13 +838,28 @@ impl Session {
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             hooks: config.hooks.clone(),
+            exec_policy,
+            egress_proxy,
+            started_at: Instant::now(),
+            call_sequencer: Mutex::new(None),
+            approval_slot: Arc::new(tokio::sync::Mutex::new(())),
+            checkpoints: tokio::sync::Mutex::new(
+                config
+                    .checkpoints
+                    .then(|| {
+                        CheckpointStore::for_cwd(&config.codex_home, &turn_context.cwd, session_id)
+                    })
+                    .flatten(),
+            ),
+            config: Arc::clone(&config),
+            auth_manager: Arc::clone(&auth_manager),
         });
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
         // If resuming, include converted initial messages in the payload so UIs can render them immediately.
         let initial_messages = match &initial_history {
             InitialHistory::New => None,
-            InitialHistory::Resumed(items) => Some(sess.build_initial_messages(items)),
+            InitialHistory::Resumed(resumed) => Some(sess.build_initial_messages(&resumed.items)),
         };
 
         let events = std::iter::once(Event {
@@ -517,6 +873,7 @@ impl Session {
                 rollout_path,
             }),
         })
+        .chain(restored_state_events)
         .chain(post_session_configured_error_events.into_iter());
         for event in events {
             if let Err(e) = tx_event.send(event).await {
@@ -526,16 +883,16 @@ impl Session {
 
         // Fire session_start hook if configured. Emit output as BackgroundEvent and/or
         // enqueue LLM input based on routing.
-        if let Some(hooks) = &sess.hooks {
-            if let Some(hook) = &hooks.session_start {
-                sess.run_hook_and_route(
-                    INITIAL_SUBMIT_ID,
-                    "session_start",
-                    hook,
-                    &turn_context.cwd,
-                )
-                .await;
-            }
+        if let Some(hooks) = &sess.hooks
+            && let Some(hook) = &hooks.session_start
+        {
+            sess.run_hook_and_route(
+                INITIAL_SUBMIT_ID,
+                hook,
+                &turn_context.cwd,
+                HookEvent::SessionStart,
+            )
+            .await;
         }
 
         Ok((sess, turn_context))
@@ -544,6 +901,9 @@ impl Session {
     pub fn set_task(&self, task: AgentTask) {
         let mut state = self.state.lock_unchecked();
         if let Some(current_task) = state.current_task.take() {
+            state.aborted_turns += 1;
+            // Nobody will answer the replaced task's approval requests.
+            state.pending_approvals.clear();
             current_task.abort(TurnAbortReason::Replaced);
         }
         state.current_task = Some(task);
@@ -567,8 +927,8 @@ impl Session {
             InitialHistory::New => {
                 self.record_initial_history_new(turn_context).await;
             }
-            InitialHistory::Resumed(items) => {
-                self.record_initial_history_resumed(items).await;
+            InitialHistory::Resumed(resumed) => {
+                self.record_initial_history_resumed(*resumed).await;
             }
         }
     }
@@ -590,8 +950,24 @@ impl Session {
         self.record_conversation_items(&conversation_items).await;
     }
 
-    async fn record_initial_history_resumed(&self, items: Vec<ResponseItem>) {
-        self.record_conversation_items(&items).await;
+    async fn record_initial_history_resumed(&self, resumed: ResumedHistory) {
+        self.record_conversation_items(&resumed.items).await;
+
+        // Statistics must not count the replayed history a second time.
+        let resume = ResumeRecord {
+            total_token_usage: resumed.state.total_token_usage,
+            approval_requests: resumed.state.approval_requests,
+            aborted_turns: resumed.state.aborted_turns,
+        };
+        let recorder = {
+            let guard = self.rollout.lock_unchecked();
+            guard.as_ref().cloned()
+        };
+        if let Some(rec) = recorder
+            && let Err(e) = rec.record_resume(resume).await
+        {
+            error!("failed to record rollout resume: {e:#}");
+        }
     }
 
     /// build the initial messages vector for SessionConfigured by converting
@@ -613,6 +989,20 @@ impl Session {
         }
     }
 
+    /// Sends the begin or end event of tool call `call_id`. While a batch of
+    /// tool calls runs concurrently, the event is held back until the calls
+    /// issued before `call_id` have sent theirs.
+    pub(crate) async fn send_tool_call_event(&self, call_id: &str, phase: CallPhase, event: Event) {
+        let sequencer = self.call_sequencer.lock_unchecked().clone();
+        if let Some(sequencer) = &sequencer {
+            sequencer.wait(call_id, phase).await;
+        }
+        self.send_event(event).await;
+        if let Some(sequencer) = &sequencer {
+            sequencer.mark(call_id, phase);
+        }
+    }
+
     pub async fn request_command_approval(
         &self,
         sub_id: String,
@@ -620,17 +1010,11 @@ impl Session {
         command: Vec<String>,
         cwd: PathBuf,
         reason: Option<String>,
+        grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
-        // Add the tx_approve callback to the map before sending the request.
-        let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
-        let prev_entry = {
-            let mut state = self.state.lock_unchecked();
-            state.pending_approvals.insert(sub_id, tx_approve)
-        };
-        if prev_entry.is_some() {
-            warn!("Overwriting existing pending approval for sub_id: {event_id}");
-        }
+        let rx_approve = self.register_approval(sub_id, true).await;
+        self.persist_state().await;
 
         let event = Event {
             id: event_id,
@@ -639,6 +1023,7 @@ impl Session {
                 command,
                 cwd,
                 reason,
+                grant_root,
             }),
         };
         let _ = self.tx_event.send(event).await;
@@ -653,16 +1038,9 @@ impl Session {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
-        // Add the tx_approve callback to the map before sending the request.
-        let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
-        let prev_entry = {
-            let mut state = self.state.lock_unchecked();
-            state.pending_approvals.insert(sub_id, tx_approve)
-        };
-        if prev_entry.is_some() {
-            warn!("Overwriting existing pending approval for sub_id: {event_id}");
-        }
+        let rx_approve = self.register_approval(sub_id, true).await;
+        self.persist_state().await;
 
         let event = Event {
             id: event_id,
@@ -677,13 +1055,53 @@ impl Session {
         rx_approve
     }
 
+    /// Surfaces an approval request raised by a delegated child conversation
+    /// as if it came from this session, so the user answers it through the
+    /// usual `Op::ExecApproval` / `Op::PatchApproval` path.
+    pub(crate) async fn request_delegated_approval(
+        &self,
+        sub_id: String,
+        msg: EventMsg,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let event_id = sub_id.clone();
+        let rx_approve = self.register_approval(sub_id, false).await;
+
+        let event = Event { id: event_id, msg };
+        let _ = self.tx_event.send(event).await;
+        rx_approve
+    }
+
+    /// Waits until no other approval request is pending, then adds the
+    /// callback for `sub_id` to the map. Must be called before the request
+    /// event is sent.
+    async fn register_approval(
+        &self,
+        sub_id: String,
+        count_request: bool,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let slot = Arc::clone(&self.approval_slot).lock_owned().await;
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let mut state = self.state.lock_unchecked();
+        if count_request {
+            state.approval_requests += 1;
+        }
+        state.pending_approvals.insert(
+            sub_id,
+            PendingApproval {
+                tx_approve,
+                _slot: slot,
+            },
+        );
+        rx_approve
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut state = self.state.lock_unchecked();
             state.pending_approvals.remove(sub_id)
         };
         match entry {
-            Some(tx_approve) => {
+            Some(PendingApproval { tx_approve, .. }) => {
                 tx_approve.send(decision).ok();
             }
             None => {
@@ -692,9 +1110,101 @@ impl Session {
         }
     }
 
-    pub fn add_approved_command(&self, cmd: Vec<String>) {
-        let mut state = self.state.lock_unchecked();
-        state.approved_commands.insert(cmd);
+    pub async fn add_approved_command(&self, cmd: Vec<String>) {
+        self.state.lock_unchecked().approved_commands.insert(cmd);
+        self.persist_state().await;
+    }
+
+    async fn grant_writable_root(&self, root: PathBuf) {
+        {
+            let mut state = self.state.lock_unchecked();
+            if !state.granted_writable_roots.contains(&root) {
+                state.granted_writable_roots.push(root);
+            }
+        }
+        self.persist_state().await;
+    }
+
+    pub(crate) async fn set_plan(&self, plan: UpdatePlanArgs) {
+        self.state.lock_unchecked().plan = Some(plan);
+        self.persist_state().await;
+    }
+
+    async fn set_turn_settings(&self, settings: TurnSettings) {
+        self.state.lock_unchecked().turn_settings = Some(settings);
+        self.persist_state().await;
+    }
+
+    /// Adds the usage of one response of `model` to the session totals,
+    /// records it in the rollout and returns the updated totals.
+    async fn add_token_usage(
+        &self,
+        model: &str,
+        usage: &TokenUsage,
+        model_context_window: Option<u64>,
+    ) -> Option<TokenUsageInfo> {
+        let info = {
+            let mut state = self.state.lock_unchecked();
+            let info = TokenUsageInfo::new_or_append(
+                &state.token_info,
+                &Some(usage.clone()),
+                model_context_window,
+            );
+            state.token_info = info.clone();
+            info
+        };
+        self.persist_state().await;
+        if !usage.is_zero() {
+            let recorder = {
+                let guard = self.rollout.lock_unchecked();
+                guard.as_ref().cloned()
+            };
+            if let Some(rec) = recorder
+                && let Err(e) = rec.record_usage(model, usage).await
+            {
+                error!("failed to record rollout usage: {e:#}");
+            }
+        }
+        info
+    }
+
+    /// `sandbox_policy` with the writable roots granted during this session
+    /// added to it.
+    pub(crate) fn effective_sandbox_policy(&self, sandbox_policy: &SandboxPolicy) -> SandboxPolicy {
+        let mut sandbox_policy = sandbox_policy.clone();
+        if let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = &mut sandbox_policy {
+            let state = self.state.lock_unchecked();
+            for root in &state.granted_writable_roots {
+                if !writable_roots.contains(root) {
+                    writable_roots.push(root.clone());
+                }
+            }
+        }
+        sandbox_policy
+    }
+
+    /// Socket of the egress proxy, if `sandbox_policy` sends network access
+    /// through it.
+    fn egress_proxy_socket(&self, sandbox_policy: &SandboxPolicy) -> Option<&Path> {
+        if sandbox_policy.has_full_network_access()
+            || sandbox_policy.get_allowed_domains().is_empty()
+        {
+            return None;
+        }
+        self.egress_proxy.as_ref().map(EgressProxy::socket_path)
+    }
+
+    /// Describes the hosts the egress proxy refused since the last call, for
+    /// the model to read alongside the command output.
+    fn take_denied_hosts_note(&self) -> Option<String> {
+        let hosts = self.egress_proxy.as_ref()?.take_denied_hosts();
+        if hosts.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "\nnetwork access blocked by the sandbox: {} not in allowed_domains",
+            hosts.join(", ")
+        ))
     }
 
     /// Records items to both the rollout and the chat completions/ZDR
@@ -707,7 +1217,7 @@ impl Session {
     }
 
     async fn record_state_snapshot(&self, items: &[ResponseItem]) {
-        let snapshot = { crate::rollout::SessionStateSnapshot {} };
+        let snapshot = self.changed_state_snapshot();
 
         let recorder = {
             let guard = self.rollout.lock_unchecked();
@@ -715,7 +1225,9 @@ impl Session {
         };
 
         if let Some(rec) = recorder {
-            if let Err(e) = rec.record_state(snapshot).await {
+            if let Some(snapshot) = snapshot
+                && let Err(e) = rec.record_state(snapshot).await
+            {
                 error!("failed to record rollout state: {e:#}");
             }
             if let Err(e) = rec.record_items(items).await {
@@ -724,6 +1236,69 @@ impl Session {
         }
     }
 
+    /// Writes the session state to the rollout if it changed since it was
+    /// last written.
+    async fn persist_state(&self) {
+        self.record_state_snapshot(&[]).await;
+    }
+
+    /// Counts a tool call against the turn and session budgets, or returns the
+    /// budget that has no tool call left.
+    fn take_tool_call(&self) -> Result<(), BudgetExceededEvent> {
+        let budgets = &self.config.budgets;
+        let mut state = self.state.lock_unchecked();
+        if let Some(exceeded) =
+            check_tool_call_budget(BudgetScope::Turn, &budgets.turn, &state.turn_budget_usage)
+                .or_else(|| {
+                    check_tool_call_budget(
+                        BudgetScope::Session,
+                        &budgets.session,
+                        &state.session_budget_usage,
+                    )
+                })
+        {
+            return Err(exceeded);
+        }
+        state.turn_budget_usage.record(1, 0);
+        state.session_budget_usage.record(1, 0);
+        Ok(())
+    }
+
+    /// Makes `token_usage` the latest usage and counts the tokens it added
+    /// against the turn and session budgets.
+    fn record_budget_tokens(&self, token_usage: &TokenUsage) {
+        let mut state = self.state.lock_unchecked();
+        let previous = state.latest_token_usage.replace(token_usage.clone());
+        let tokens = new_tokens(token_usage, previous.as_ref());
+        state.turn_budget_usage.record(0, tokens);
+        state.session_budget_usage.record(0, tokens);
+    }
+
+    /// Links a child conversation started by the `delegate` tool from this
+    /// session's rollout.
+    pub(crate) async fn record_delegate(&self, delegate: DelegateRecord) {
+        let recorder = {
+            let guard = self.rollout.lock_unchecked();
+            guard.as_ref().cloned()
+        };
+        if let Some(rec) = recorder
+            && let Err(e) = rec.record_delegate(delegate).await
+        {
+            error!("failed to record rollout delegate: {e:#}");
+        }
+    }
+
+    /// The current state snapshot, unless it was already written.
+    fn changed_state_snapshot(&self) -> Option<SessionStateSnapshot> {
+        let mut state = self.state.lock_unchecked();
+        let snapshot = state.snapshot();
+        if state.recorded_state.as_ref() == Some(&snapshot) {
+            return None;
+        }
+        state.recorded_state = Some(snapshot.clone());
+        Some(snapshot)
+    }
+
     async fn on_exec_command_begin(
         &self,
         turn_diff_tracker: &mut TurnDiffTracker,
@@ -745,13 +1320,13 @@ impl Session {
                 turn_diff_tracker.on_patch_begin(&changes);
 
                 EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
-                    call_id,
+                    call_id: call_id.clone(),
                     auto_approved: !user_explicitly_approved_this_action,
                     changes,
                 })
             }
             None => EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
-                call_id,
+                call_id: call_id.clone(),
                 command: command_for_display.clone(),
                 cwd,
                 parsed_cmd: parse_command(&command_for_display)
@@ -764,7 +1339,8 @@ impl Session {
             id: sub_id.to_string(),
             msg,
         };
-        let _ = self.tx_event.send(event).await;
+        self.send_tool_call_event(&call_id, CallPhase::Begin, event)
+            .await;
     }
 
     async fn on_exec_command_end(
@@ -774,6 +1350,7 @@ impl Session {
         call_id: &str,
         output: &ExecToolCallOutput,
         is_apply_patch: bool,
+        files_changed: bool,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -781,6 +1358,7 @@ impl Session {
             aggregated_output,
             duration,
             exit_code,
+            ..
         } = output;
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
@@ -811,11 +1389,13 @@ impl Session {
             id: sub_id.to_string(),
             msg,
         };
-        let _ = self.tx_event.send(event).await;
+        self.send_tool_call_event(call_id, CallPhase::End, event)
+            .await;
 
-        // If this is an apply_patch, after we emit the end patch, emit a second event
-        // with the full turn diff if there is one.
-        if is_apply_patch {
+        // If this is an apply_patch, or a command that changed files, after we
+        // emit the end event, emit a second event with the full turn diff if
+        // there is one.
+        if is_apply_patch || files_changed {
             let unified_diff = turn_diff_tracker.get_unified_diff();
             if let Ok(Some(unified_diff)) = unified_diff {
                 let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
@@ -845,37 +1425,10 @@ impl Session {
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
-        // Run pre_command hook (best-effort). If configured as fail-closed and
-        // the hook fails or times out, short-circuit the exec call with an error-like output.
-        if let Some(hooks) = &self.hooks {
-            if let Some(hook) = &hooks.pre_command {
-                let ok = self
-                    .run_hook_and_route(&sub_id, "pre_command", hook, &begin_ctx.cwd)
-                    .await;
-                if !ok && matches!(hook.on_error, HookOnError::FailClosed) {
-                    let msg = format!(
-                        "pre_command hook failed; skipping exec: {}",
-                        command_for_display.join(" ")
-                    );
-                    let output = ExecToolCallOutput {
-                        exit_code: -1,
-                        stdout: StreamOutput::new(String::new()),
-                        stderr: StreamOutput::new(msg.clone()),
-                        aggregated_output: StreamOutput::new(msg),
-                        duration: std::time::Duration::default(),
-                    };
-                    // Emit end event with the synthesized failure and return early.
-                    self.on_exec_command_end(
-                        turn_diff_tracker,
-                        &sub_id,
-                        &call_id,
-                        &output,
-                        is_apply_patch,
-                    )
-                    .await;
-                    return Ok(output);
-                }
-            }
+        // apply_patch reports its changes up front; for other commands,
+        // snapshot the worktree to find out what they change.
+        if !is_apply_patch {
+            turn_diff_tracker.on_exec_begin(&begin_ctx.cwd).await;
         }
 
         let result = process_exec_tool_call(
@@ -897,35 +1450,121 @@ impl Session {
                     stderr: StreamOutput::new(get_error_message_ui(e)),
                     aggregated_output: StreamOutput::new(get_error_message_ui(e)),
                     duration: Duration::default(),
+                    resource_limit_exceeded: None,
                 };
                 &output_stderr
             }
         };
+        let files_changed = !is_apply_patch && turn_diff_tracker.on_exec_end().await;
         self.on_exec_command_end(
             turn_diff_tracker,
             &sub_id,
             &call_id,
             borrowed,
             is_apply_patch,
+            files_changed,
         )
         .await;
 
-        // Run post_command hook after emitting end event.
-        if let Some(hooks) = &self.hooks {
-            if let Some(hook) = &hooks.post_command {
-                let _ = self
-                    .run_hook_and_route(&sub_id, "post_command", hook, &begin_ctx.cwd)
-                    .await;
+        // Run the post_command (or post_apply_patch) hook after emitting the
+        // end event.
+        let post_hook = self.hooks.as_ref().and_then(|hooks| {
+            if is_apply_patch {
+                hooks.post_apply_patch.as_ref().map(|hook| {
+                    let event = HookEvent::PostApplyPatch {
+                        call_id: call_id.clone(),
+                        success: borrowed.exit_code == 0,
+                        output: borrowed.aggregated_output.text.clone(),
+                        diff: turn_diff_tracker.get_unified_diff().ok().flatten(),
+                    };
+                    (hook, event)
+                })
+            } else {
+                hooks.post_command.as_ref().map(|hook| {
+                    let event = HookEvent::PostCommand {
+                        call_id: call_id.clone(),
+                        command: command_for_display.clone(),
+                        exit_code: borrowed.exit_code,
+                        duration_ms: borrowed.duration.as_millis(),
+                        output: borrowed.aggregated_output.text.clone(),
+                    };
+                    (hook, event)
+                })
             }
+        });
+        if let Some((hook, event)) = post_hook {
+            let _ = self
+                .run_hook_and_route(&sub_id, hook, &begin_ctx.cwd, event)
+                .await;
         }
 
         result
     }
 
+    /// Snapshot the workspace before turn `sub_id` runs. Failures are logged
+    /// and only mean this turn cannot be undone.
+    async fn create_checkpoint(&self, sub_id: &str) {
+        let mut checkpoints = self.checkpoints.lock().await;
+        if let Some(store) = checkpoints.as_mut()
+            && let Err(e) = store.create(sub_id).await
+        {
+            warn!("failed to record workspace checkpoint: {e}");
+        }
+    }
+
+    /// Restore the workspace to the checkpoint before the last `turns` turns
+    /// and record a note in the conversation so the model knows which files
+    /// were reverted.
+    async fn undo_turns(&self, sub_id: &str, turns: u32) {
+        let result = {
+            let mut checkpoints = self.checkpoints.lock().await;
+            match checkpoints.as_mut() {
+                Some(store) => store
+                    .undo(turns as usize)
+                    .await
+                    .map(|outcome| (outcome, store.work_tree().to_path_buf())),
+                None => Err(std::io::Error::other(
+                    "workspace checkpoints are disabled (set `checkpoints = true`) or the cwd is not inside a git repository",
+                )),
+            }
+        };
+        let (outcome, work_tree) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: format!("undo failed: {e}"),
+                    }),
+                })
+                .await;
+                return;
+            }
+        };
+
+        let note = ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: format_undo_note(turns, &work_tree, &outcome),
+            }],
+        };
+        self.record_conversation_items(&[note]).await;
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::UndoCompleted(UndoCompletedEvent {
+                turns,
+                restored: outcome.restored,
+                removed: outcome.removed,
+            }),
+        })
+        .await;
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
-    async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
+    pub(crate) async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
             msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
@@ -991,6 +1630,7 @@ impl Session {
         state.pending_approvals.clear();
         state.pending_input.clear();
         if let Some(task) = state.current_task.take() {
+            state.aborted_turns += 1;
             task.abort(TurnAbortReason::Interrupted);
         }
     }
@@ -1038,7 +1678,6 @@ pub(crate) struct ExecCommandContext {
     pub(crate) command_for_display: Vec<String>,
     pub(crate) cwd: PathBuf,
     pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
-    pub(crate) timeout_ms: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -1128,6 +1767,7 @@ async fn submission_loop(
         match sub.op {
             Op::Interrupt => {
                 sess.interrupt_task();
+                sess.persist_state().await;
             }
             Op::OverrideTurnContext {
                 cwd,
@@ -1188,6 +1828,7 @@ async fn submission_loop(
                     include_web_search_request: config.tools_web_search_request,
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
+                    include_delegate_tool: config.include_delegate_tool,
                 });
 
                 let new_turn_context = TurnContext {
@@ -1201,10 +1842,12 @@ async fn submission_loop(
                     cwd: new_cwd.clone(),
                     disable_response_storage: prev.disable_response_storage,
                     exec_timeout_ms: prev.exec_timeout_ms,
+                    sandbox_limits: prev.sandbox_limits,
                 };
 
                 // Install the new persistent context for subsequent tasks/turns.
                 turn_context = Arc::new(new_turn_context);
+                sess.set_turn_settings(turn_context.settings()).await;
                 if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
                     sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
                         cwd,
@@ -1217,6 +1860,12 @@ async fn submission_loop(
                 }
             }
             Op::UserInput { items } => {
+                let Some(items) = sess
+                    .run_user_prompt_submit_hook(&sub.id, &turn_context.cwd, items)
+                    .await
+                else {
+                    continue;
+                };
                 // attempt to inject input into current task
                 if let Err(items) = sess.inject_input(items) {
                     // no current task, spawn a new one
@@ -1234,6 +1883,10 @@ async fn submission_loop(
                 effort,
                 summary,
             } => {
+                let Some(items) = sess.run_user_prompt_submit_hook(&sub.id, &cwd, items).await
+                else {
+                    continue;
+                };
                 // attempt to inject input into current task
                 if let Err(items) = sess.inject_input(items) {
                     // Derive a fresh TurnContext for this turn using the provided overrides.
@@ -1275,6 +1928,7 @@ async fn submission_loop(
                             use_streamable_shell_tool: config
                                 .use_experimental_streamable_shell_tool,
                             include_view_image_tool: config.include_view_image_tool,
+                            include_delegate_tool: config.include_delegate_tool,
                         }),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1284,6 +1938,7 @@ async fn submission_loop(
                         cwd,
                         disable_response_storage: turn_context.disable_response_storage,
                         exec_timeout_ms: turn_context.exec_timeout_ms,
+                        sandbox_limits: turn_context.sandbox_limits,
                     };
                     // TODO: record the new environment context in the conversation history
                     // no current task, spawn a new one with the per‑turn context
@@ -1388,9 +2043,6 @@ async fn submission_loop(
                 }
             }
             Op::Compact => {
-                // Create a summarization request as user input
-                const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
-
                 // Attempt to inject input into current task
                 if let Err(items) = sess.inject_input(vec![InputItem::Text {
                     text: "Start Summarization".to_string(),
@@ -1405,15 +2057,34 @@ async fn submission_loop(
                     sess.set_task(task);
                 }
             }
+            Op::Undo { turns } => {
+                let task_running = sess.state.lock_unchecked().current_task.is_some();
+                if task_running {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: "cannot undo while a turn is running".to_string(),
+                        }),
+                    };
+                    sess.send_event(event).await;
+                } else {
+                    sess.undo_turns(&sub.id, turns).await;
+                }
+            }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
 
                 // Run session_end hook prior to shutdown notification.
-                if let Some(hooks) = &sess.hooks {
-                    if let Some(hook) = &hooks.session_end {
-                        sess.run_hook_and_route(&sub.id, "session_end", hook, &turn_context.cwd)
-                            .await;
-                    }
+                if let Some(hooks) = &sess.hooks
+                    && let Some(hook) = &hooks.session_end
+                {
+                    sess.run_hook_and_route(
+                        &sub.id,
+                        hook,
+                        &turn_context.cwd,
+                        HookEvent::SessionEnd,
+                    )
+                    .await;
                 }
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
@@ -1467,32 +2138,178 @@ async fn submission_loop(
 }
 
 impl Session {
-    /// Run a configured hook and route its output according to the spec.
-    /// Returns true if the hook executed with exit code 0 within the timeout.
+    /// Runs the user_prompt_submit hook, if configured, for a prompt made of
+    /// `items`. Returns the items to submit, which the hook may have
+    /// rewritten, or `None` when the hook denied the prompt.
+    async fn run_user_prompt_submit_hook(
+        &self,
+        sub_id: &str,
+        cwd: &Path,
+        items: Vec<InputItem>,
+    ) -> Option<Vec<InputItem>> {
+        let Some(hook) = self
+            .hooks
+            .as_ref()
+            .and_then(|hooks| hooks.user_prompt_submit.as_ref())
+        else {
+            return Some(items);
+        };
+        let prompt = items
+            .iter()
+            .filter_map(|item| match item {
+                InputItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let event = HookEvent::UserPromptSubmit { prompt };
+        match self.run_decision_hook(sub_id, hook, cwd, event).await {
+            Some(HookDecision::Deny { reason }) => {
+                let message = match reason {
+                    Some(reason) => format!("prompt was not submitted: {reason}"),
+                    None => {
+                        "prompt was not submitted: denied by user_prompt_submit hook".to_string()
+                    }
+                };
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent { message }),
+                })
+                .await;
+                None
+            }
+            Some(HookDecision::Rewrite {
+                prompt: Some(prompt),
+                ..
+            }) => {
+                // Keep attachments such as images; replace the text.
+                let mut rewritten = vec![InputItem::Text { text: prompt }];
+                rewritten.extend(
+                    items
+                        .into_iter()
+                        .filter(|item| !matches!(item, InputItem::Text { .. })),
+                );
+                Some(rewritten)
+            }
+            _ => Some(items),
+        }
+    }
+
+    /// Runs the pre_command hook, if configured, before `command` is assessed
+    /// for safety.
+    async fn run_pre_command_hook(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        cwd: &Path,
+        command: &[String],
+    ) -> Option<HookDecision> {
+        let hook = self.hooks.as_ref()?.pre_command.as_ref()?;
+        let event = HookEvent::PreCommand {
+            call_id: call_id.to_string(),
+            command: command.to_vec(),
+        };
+        self.run_decision_hook(sub_id, hook, cwd, event).await
+    }
+
+    /// Runs the pre_apply_patch hook, if configured, before `changes` are
+    /// assessed for safety.
+    pub(crate) async fn run_pre_apply_patch_hook(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        cwd: &Path,
+        changes: HashMap<PathBuf, FileChange>,
+    ) -> Option<HookDecision> {
+        let hook = self.hooks.as_ref()?.pre_apply_patch.as_ref()?;
+        let event = HookEvent::PreApplyPatch {
+            call_id: call_id.to_string(),
+            changes,
+        };
+        self.run_decision_hook(sub_id, hook, cwd, event).await
+    }
+
+    /// Runs a hook that may print a [`HookDecision`]. A fail-closed hook that
+    /// fails or times out is treated as a denial; a fail-open one as no
+    /// decision.
+    async fn run_decision_hook(
+        &self,
+        sub_id: &str,
+        hook: &HookSpec,
+        cwd: &Path,
+        event: HookEvent,
+    ) -> Option<HookDecision> {
+        let point = event.name();
+        let outcome = self.run_hook_and_route(sub_id, hook, cwd, event).await;
+        if outcome.ok {
+            return outcome.decision;
+        }
+        match hook.on_error {
+            HookOnError::FailOpen => None,
+            HookOnError::FailClosed => Some(HookDecision::Deny {
+                reason: Some(format!("{point} hook failed")),
+            }),
+        }
+    }
+
+    /// Run a configured hook and route its output according to the spec. The
+    /// hook receives a JSON [`HookPayload`] describing `event` on stdin.
     async fn run_hook_and_route(
         &self,
         sub_id: &str,
-        point: &str,
         hook: &HookSpec,
-        cwd: &PathBuf,
-    ) -> bool {
+        cwd: &Path,
+        event: HookEvent,
+    ) -> HookOutcome {
+        let point = event.name();
         let start = std::time::Instant::now();
         let mut cmd = if hook.command.is_empty() {
-            return true;
+            return HookOutcome {
+                ok: true,
+                decision: None,
+            };
         } else {
-            std::process::Command::new(&hook.command[0])
+            tokio::process::Command::new(&hook.command[0])
         };
         if hook.command.len() > 1 {
             cmd.args(&hook.command[1..]);
         }
-        cmd.current_dir(cwd);
+        cmd.current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
         // Inherit a minimal environment; no special vars for now.
 
-        let outcome = tokio::time::timeout(
-            std::time::Duration::from_millis(hook.timeout_ms),
-            tokio::task::spawn_blocking(move || cmd.output()),
-        )
-        .await;
+        let payload = HookPayload {
+            session_id: self.session_id,
+            cwd: cwd.to_path_buf(),
+            event,
+        };
+        let input = match serde_json::to_vec(&payload) {
+            Ok(input) => input,
+            Err(e) => {
+                error!("failed to serialize {point} hook payload: {e}");
+                Vec::new()
+            }
+        };
+
+        let run = async {
+            let mut child = cmd.spawn()?;
+            let stdin = child.stdin.take();
+            // Write the payload while collecting output so that a hook which
+            // does not read stdin (or writes a lot) cannot deadlock us.
+            let write = async move {
+                if let Some(mut stdin) = stdin {
+                    // The hook may exit without reading its input.
+                    let _ = stdin.write_all(&input).await;
+                }
+            };
+            let ((), output) = tokio::join!(write, child.wait_with_output());
+            output
+        };
+        let outcome =
+            tokio::time::timeout(std::time::Duration::from_millis(hook.timeout_ms), run).await;
 
         let (ok, stdout, stderr, note) = match outcome {
             Err(_) => (
@@ -1501,38 +2318,29 @@ impl Session {
                 String::new(),
                 format!("hook {point} timed out after {}ms", hook.timeout_ms),
             ),
-            Ok(join_res) => match join_res {
-                Ok(Ok(out)) => {
-                    let code_ok = out.status.success();
-                    let so = String::from_utf8_lossy(&out.stdout).to_string();
-                    let se = String::from_utf8_lossy(&out.stderr).to_string();
-                    let note = format!(
-                        "hook {point} exited {} in {}ms",
-                        out.status.code().unwrap_or(-1),
-                        start.elapsed().as_millis()
-                    );
-                    (code_ok, so, se, note)
-                }
-                Ok(Err(e)) => (
-                    false,
-                    String::new(),
-                    String::new(),
-                    format!("hook {point} spawn failed: {e}"),
-                ),
-                Err(e) => (
-                    false,
-                    String::new(),
-                    String::new(),
-                    format!("hook {point} join failed: {e}"),
-                ),
-            },
+            Ok(Ok(out)) => {
+                let code_ok = out.status.success();
+                let so = String::from_utf8_lossy(&out.stdout).to_string();
+                let se = String::from_utf8_lossy(&out.stderr).to_string();
+                let note = format!(
+                    "hook {point} exited {} in {}ms",
+                    out.status.code().unwrap_or(-1),
+                    start.elapsed().as_millis()
+                );
+                (code_ok, so, se, note)
+            }
+            Ok(Err(e)) => (
+                false,
+                String::new(),
+                String::new(),
+                format!("hook {point} spawn failed: {e}"),
+            ),
         };
 
         // Route UI notes and outputs via BackgroundEvent.
         match hook.route {
             HookRoute::Ui | HookRoute::Both => {
-                self.notify_background_event(sub_id, format!("{note}"))
-                    .await;
+                self.notify_background_event(sub_id, note).await;
                 if !stdout.trim().is_empty() {
                     let msg = format!("hook {point} stdout:\n{}", stdout.trim());
                     self.notify_background_event(sub_id, msg).await;
@@ -1545,12 +2353,22 @@ impl Session {
             _ => {}
         }
 
+        // A decision is meant for us rather than the model.
+        let decision = if ok {
+            HookDecision::from_stdout(&stdout)
+        } else {
+            None
+        };
+
         // Route to LLM by enqueuing a new user input turn with stdout content.
-        if matches!(hook.route, HookRoute::Llm | HookRoute::Both) && !stdout.trim().is_empty() {
+        if matches!(hook.route, HookRoute::Llm | HookRoute::Both)
+            && decision.is_none()
+            && !stdout.trim().is_empty()
+        {
             let _ = self.inject_input(vec![InputItem::Text { text: stdout }]);
         }
 
-        ok
+        HookOutcome { ok, decision }
     }
 }
 
@@ -1586,9 +2404,29 @@ async fn run_task(
         return;
     }
 
+    let budgets = sess.config.budgets;
+    let turn_started_at = Instant::now();
+    sess.state.lock_unchecked().turn_budget_usage = BudgetUsage::default();
+    if let Some(exceeded) = exhausted_budget(&sess, turn_started_at) {
+        abort_for_budget(&sess, sub_id, exceeded).await;
+        return;
+    }
+
+    sess.create_checkpoint(&sub_id).await;
+
+    // Compact before recording the new input so the summary does not swallow
+    // the request that is about to be answered.
+    if let Err(CodexErr::Interrupted) =
+        maybe_auto_compact(&sess, turn_context, &sub_id, Vec::new()).await
+    {
+        return;
+    }
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    sess.record_conversation_items(&[initial_input_for_turn.clone().into()])
-        .await;
+    // The user's input for this turn, kept across a mid-turn compaction so
+    // the model still sees what it is working on.
+    let mut turn_inputs: Vec<ResponseItem> = vec![initial_input_for_turn.clone().into()];
+    sess.record_conversation_items(&turn_inputs).await;
 
     let mut last_agent_message: Option<String> = None;
     // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
@@ -1605,6 +2443,7 @@ async fn run_task(
             .map(ResponseItem::from)
             .collect::<Vec<ResponseItem>>();
         sess.record_conversation_items(&pending_input).await;
+        turn_inputs.extend(pending_input.iter().cloned());
 
         // Construct the input that we will send to the model. When using the
         // Chat completions API (or ZDR clients), the model needs the full
@@ -1626,15 +2465,28 @@ async fn run_task(
                 })
             })
             .collect();
-        match run_turn(
+        let turn_fut = run_turn(
             &sess,
             turn_context,
             &mut turn_diff_tracker,
             sub_id.clone(),
             turn_input,
-        )
-        .await
-        {
+        );
+        // Bound the request (including any tool calls it makes) by the
+        // wall-clock budgets.
+        let turn_result = match wall_clock_deadline(&budgets, turn_started_at, sess.started_at) {
+            Some((deadline, exceeded)) => {
+                match tokio::time::timeout_at(deadline.into(), turn_fut).await {
+                    Ok(turn_result) => turn_result,
+                    Err(_) => {
+                        abort_for_budget(&sess, sub_id, exceeded).await;
+                        return;
+                    }
+                }
+            }
+            None => turn_fut.await,
+        };
+        match turn_result {
             Ok(turn_output) => {
                 let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
                 let mut responses = Vec::<ResponseInputItem>::new();
@@ -1747,6 +2599,27 @@ async fn run_task(
                     });
                     break;
                 }
+
+                // The model will be called again with the tool outputs; stop
+                // here if that would exceed a budget.
+                if let Some(exceeded) = exhausted_budget(&sess, turn_started_at) {
+                    abort_for_budget(&sess, sub_id, exceeded).await;
+                    return;
+                }
+
+                // Make room first if the context window is nearly full. The
+                // turn's input and the tool calls the model has not seen the
+                // output of yet are kept after the summary.
+                let keep = turn_inputs
+                    .iter()
+                    .chain(&items_to_record_in_conversation_history)
+                    .cloned()
+                    .collect();
+                if let Err(CodexErr::Interrupted) =
+                    maybe_auto_compact(&sess, turn_context, &sub_id, keep).await
+                {
+                    return;
+                }
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
@@ -1763,6 +2636,16 @@ async fn run_task(
         }
     }
     sess.remove_task(&sub_id);
+    if let Some(hooks) = &sess.hooks
+        && let Some(hook) = &hooks.turn_complete
+    {
+        let event = HookEvent::TurnComplete {
+            last_agent_message: last_agent_message.clone(),
+            diff: turn_diff_tracker.get_unified_diff().ok().flatten(),
+        };
+        sess.run_hook_and_route(&sub_id, hook, &turn_context.cwd, event)
+            .await;
+    }
     let event = Event {
         id: sub_id,
         msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
@@ -1770,6 +2653,82 @@ async fn run_task(
     sess.tx_event.send(event).await.ok();
 }
 
+/// Message recorded in the conversation after an undo so the model does not
+/// assume the reverted changes are still present.
+fn format_undo_note(turns: u32, work_tree: &Path, outcome: &UndoOutcome) -> String {
+    let mut note = format!(
+        "The user undid the last {turns} turn(s): files in {} were restored to their state before those turns. Changes made in those turns are no longer present.",
+        work_tree.display()
+    );
+    let sections = [
+        ("Restored files", &outcome.restored),
+        ("Removed files", &outcome.removed),
+    ];
+    for (title, paths) in sections {
+        if paths.is_empty() {
+            continue;
+        }
+        note.push_str(&format!("\n{title}:"));
+        for path in paths {
+            note.push_str(&format!("\n- {}", path.display()));
+        }
+    }
+    if outcome.restored.is_empty() && outcome.removed.is_empty() {
+        note.push_str("\nNo files needed to be changed.");
+    }
+    note
+}
+
+/// Returns the first turn or session budget that has been exhausted.
+fn exhausted_budget(sess: &Session, turn_started_at: Instant) -> Option<BudgetExceededEvent> {
+    let budgets = &sess.config.budgets;
+    let (turn_usage, session_usage) = {
+        let state = sess.state.lock_unchecked();
+        (state.turn_budget_usage, state.session_budget_usage)
+    };
+    check_budget(
+        BudgetScope::Turn,
+        &budgets.turn,
+        &turn_usage,
+        turn_started_at.elapsed(),
+    )
+    .or_else(|| {
+        check_budget(
+            BudgetScope::Session,
+            &budgets.session,
+            &session_usage,
+            sess.started_at.elapsed(),
+        )
+    })
+}
+
+/// Ends the current task because `exceeded` ran out, reporting which budget
+/// it was before the `TurnAborted` event.
+async fn abort_for_budget(sess: &Session, sub_id: String, exceeded: BudgetExceededEvent) {
+    warn!("{}", exceeded.message());
+    sess.remove_task(&sub_id);
+    {
+        let mut state = sess.state.lock_unchecked();
+        state.aborted_turns += 1;
+        // The wall-clock budget can run out while a call waits for the user;
+        // drop its request so it releases the approval slot.
+        state.pending_approvals.clear();
+    }
+    sess.persist_state().await;
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::BudgetExceeded(exceeded),
+    })
+    .await;
+    sess.send_event(Event {
+        id: sub_id,
+        msg: EventMsg::TurnAborted(TurnAbortedEvent {
+            reason: TurnAbortReason::BudgetExceeded,
+        }),
+    })
+    .await;
+}
+
 async fn run_turn(
     sess: &Session,
     turn_context: &TurnContext,
@@ -1905,6 +2864,9 @@ async fn try_run_turn(
     let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
     let mut output = Vec::new();
+    // Consecutive read-only tool calls, run together once an item that must
+    // be handled on its own arrives or the response completes.
+    let mut parallel_batch = Vec::new();
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
@@ -1932,6 +2894,20 @@ async fn try_run_turn(
         match event {
             ResponseEvent::Created => {}
             ResponseEvent::OutputItemDone(item) => {
+                if is_parallel_safe(&item, &sess.mcp_connection_manager) {
+                    parallel_batch.push(item);
+                    continue;
+                }
+                output.extend(
+                    handle_parallel_batch(
+                        sess,
+                        turn_context,
+                        turn_diff_tracker,
+                        sub_id,
+                        std::mem::take(&mut parallel_batch),
+                    )
+                    .await?,
+                );
                 let response = handle_response_item(
                     sess,
                     turn_context,
@@ -1955,17 +2931,30 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
+                output.extend(
+                    handle_parallel_batch(
+                        sess,
+                        turn_context,
+                        turn_diff_tracker,
+                        sub_id,
+                        std::mem::take(&mut parallel_batch),
+                    )
+                    .await?,
+                );
                 if let Some(token_usage) = token_usage {
-                    let info = codex_protocol::protocol::TokenUsageInfo {
-                        total_token_usage: token_usage.clone(),
-                        last_token_usage: token_usage,
-                        model_context_window: turn_context.client.get_model_context_window(),
-                    };
+                    sess.record_budget_tokens(&token_usage);
+                    let info = sess
+                        .add_token_usage(
+                            &turn_context.client.get_model(),
+                            &token_usage,
+                            turn_context.client.get_model_context_window(),
+                        )
+                        .await;
                     sess.tx_event
                         .send(Event {
                             id: sub_id.to_string(),
                             msg: EventMsg::TokenCount(codex_protocol::protocol::TokenCountEvent {
-                                info: Some(info),
+                                info,
                             }),
                         })
                         .await
@@ -2038,15 +3027,120 @@ async fn run_compact_task(
         return;
     }
 
+    match summarize_history(
+        &sess,
+        turn_context,
+        &sub_id,
+        input,
+        compact_instructions,
+        Vec::new(),
+    )
+    .await
+    {
+        Ok(()) => {}
+        Err(CodexErr::Interrupted) => return,
+        Err(e) => {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: e.to_string(),
+                }),
+            };
+            sess.send_event(event).await;
+            return;
+        }
+    }
+
+    sess.remove_task(&sub_id);
+
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::AgentMessage(AgentMessageEvent {
+            message: "Compact task completed".to_string(),
+        }),
+    };
+    sess.send_event(event).await;
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+        }),
+    };
+    sess.send_event(event).await;
+}
+
+/// Compacts the history inline when the last reported token usage has reached
+/// the auto-compaction limit, then emits [`EventMsg::ContextCompacted`]. The
+/// `keep` items are recorded again after the summary. A failed compaction is
+/// reported but does not abort the surrounding turn.
+async fn maybe_auto_compact(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    keep: Vec<ResponseItem>,
+) -> CodexResult<()> {
+    let Some(token_limit) = turn_context.client.get_auto_compact_token_limit() else {
+        return Ok(());
+    };
+    let tokens_in_context_window = match &sess.state.lock_unchecked().latest_token_usage {
+        Some(usage) => usage.tokens_in_context_window(),
+        None => return Ok(()),
+    };
+    if tokens_in_context_window < token_limit {
+        return Ok(());
+    }
+
+    info!("auto-compacting history: {tokens_in_context_window} tokens >= limit {token_limit}");
+    match summarize_history(
+        sess,
+        turn_context,
+        sub_id,
+        Vec::new(),
+        SUMMARIZATION_PROMPT.to_string(),
+        keep,
+    )
+    .await
+    {
+        Ok(()) => {
+            sess.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::ContextCompacted(ContextCompactedEvent {
+                    tokens_in_context_window,
+                    token_limit,
+                }),
+            })
+            .await;
+            Ok(())
+        }
+        Err(CodexErr::Interrupted) => Err(CodexErr::Interrupted),
+        Err(e) => {
+            warn!("auto-compaction failed: {e:#}");
+            sess.notify_background_event(sub_id, format!("auto-compaction failed: {e}"))
+                .await;
+            Ok(())
+        }
+    }
+}
+
+/// Asks the model to summarize the conversation so far and replaces the
+/// history with that summary followed by the `keep` items.
+async fn summarize_history(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    input: Vec<InputItem>,
+    compact_instructions: String,
+    keep: Vec<ResponseItem>,
+) -> CodexResult<()> {
     // Submit compact as a normal user turn instead of a system instruction override
     // so it works with ChatGPT/Pro accounts that reject custom `instructions`.
-    let mut injected_input = input.clone();
+    let mut injected_input = input;
     injected_input.push(InputItem::Text {
         text: compact_instructions,
     });
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(injected_input);
     let turn_input: Vec<ResponseItem> =
-        sess.turn_input_with_history(vec![initial_input_for_turn.clone().into()]);
+        sess.turn_input_with_history(vec![initial_input_for_turn.into()]);
 
     let prompt = Prompt {
         input: turn_input,
@@ -2058,17 +3152,15 @@ async fn run_compact_task(
     let mut retries = 0;
 
     loop {
-        let attempt_result = drain_to_completed(&sess, turn_context, &sub_id, &prompt).await;
-
-        match attempt_result {
+        match drain_to_completed(sess, turn_context, sub_id, &prompt).await {
             Ok(()) => break,
-            Err(CodexErr::Interrupted) => return,
+            Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(e) => {
                 if retries < max_retries {
                     retries += 1;
                     let delay = backoff(retries);
                     sess.notify_stream_error(
-                        &sub_id,
+                        sub_id,
                         format!(
                             "stream error: {e}; retrying {retries}/{max_retries} in {delay:?}…"
                         ),
@@ -2077,40 +3169,62 @@ async fn run_compact_task(
                     tokio::time::sleep(delay).await;
                     continue;
                 } else {
-                    let event = Event {
-                        id: sub_id.clone(),
-                        msg: EventMsg::Error(ErrorEvent {
-                            message: e.to_string(),
-                        }),
-                    };
-                    sess.send_event(event).await;
-                    return;
+                    return Err(e);
                 }
             }
         }
     }
 
-    sess.remove_task(&sub_id);
+    let mut state = sess.state.lock_unchecked();
+    state.history.keep_last_messages(1);
+    state.history.record_items(keep.iter());
+    state.latest_token_usage = None;
+    Ok(())
+}
 
-    {
-        let mut state = sess.state.lock_unchecked();
-        state.history.keep_last_messages(1);
+/// Handles a run of consecutive parallel-safe tool calls (see
+/// [`is_parallel_safe`]) concurrently. Results are returned, and the calls'
+/// begin/end events are sent, in the order the model issued the calls.
+async fn handle_parallel_batch(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    items: Vec<ResponseItem>,
+) -> CodexResult<Vec<ProcessedResponseItem>> {
+    if items.len() <= 1 {
+        let mut output = Vec::with_capacity(items.len());
+        for item in items {
+            let response =
+                handle_response_item(sess, turn_context, turn_diff_tracker, sub_id, item.clone())
+                    .await?;
+            output.push(ProcessedResponseItem { item, response });
+        }
+        return Ok(output);
     }
 
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::AgentMessage(AgentMessageEvent {
-            message: "Compact task completed".to_string(),
-        }),
-    };
-    sess.send_event(event).await;
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::TaskComplete(TaskCompleteEvent {
-            last_agent_message: None,
-        }),
-    };
-    sess.send_event(event).await;
+    let sequencer = Arc::new(CallSequencer::new(
+        items.iter().filter_map(tool_call_id).map(str::to_string),
+    ));
+    *sess.call_sequencer.lock_unchecked() = Some(Arc::clone(&sequencer));
+    let results = futures::future::join_all(items.into_iter().map(|item| {
+        let sequencer = Arc::clone(&sequencer);
+        async move {
+            // Parallel-safe calls only read the workspace, so they have no
+            // changes for the turn's diff and need no snapshot to find them.
+            let mut diff_tracker = TurnDiffTracker::without_exec_snapshots();
+            let response =
+                handle_response_item(sess, turn_context, &mut diff_tracker, sub_id, item.clone())
+                    .await;
+            if let Some(call_id) = tool_call_id(&item) {
+                sequencer.finish(call_id);
+            }
+            response.map(|response| ProcessedResponseItem { item, response })
+        }
+    }))
+    .await;
+    *sess.call_sequencer.lock_unchecked() = None;
+    results.into_iter().collect()
 }
 
 async fn handle_response_item(
@@ -2121,6 +3235,15 @@ async fn handle_response_item(
     item: ResponseItem,
 ) -> CodexResult<Option<ResponseInputItem>> {
     debug!(?item, "Output item");
+    let is_tool_call = matches!(
+        item,
+        ResponseItem::FunctionCall { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+    );
+    if is_tool_call && let Err(exceeded) = sess.take_tool_call() {
+        return Ok(skipped_tool_call_output(item, &exceeded));
+    }
     let output = match item {
         ResponseItem::FunctionCall {
             name,
@@ -2172,7 +3295,7 @@ async fn handle_response_item(
                 }
             };
 
-            let exec_params = to_exec_params(params, turn_context);
+            let exec_params = to_exec_params(params, sess, turn_context);
             Some(
                 handle_container_exec_with_params(
                     exec_params,
@@ -2229,6 +3352,40 @@ async fn handle_response_item(
     Ok(output)
 }
 
+/// Output for a tool call that was not run because `exceeded` has no call
+/// left. The task is aborted before the model sees it.
+fn skipped_tool_call_output(
+    item: ResponseItem,
+    exceeded: &BudgetExceededEvent,
+) -> Option<ResponseInputItem> {
+    let content = format!("tool call not run: {}", exceeded.message());
+    match item {
+        ResponseItem::FunctionCall { call_id, .. }
+        | ResponseItem::LocalShellCall {
+            call_id: Some(call_id),
+            ..
+        }
+        | ResponseItem::LocalShellCall {
+            call_id: None,
+            id: Some(call_id),
+            ..
+        } => Some(ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        }),
+        ResponseItem::CustomToolCall { call_id, .. } => {
+            Some(ResponseInputItem::CustomToolCallOutput {
+                call_id,
+                output: content,
+            })
+        }
+        _ => None,
+    }
+}
+
 async fn handle_function_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2240,12 +3397,13 @@ async fn handle_function_call(
 ) -> ResponseInputItem {
     match name.as_str() {
         "container.exec" | "shell" => {
-            let params = match parse_container_exec_arguments(arguments, turn_context, &call_id) {
-                Ok(params) => params,
-                Err(output) => {
-                    return *output;
-                }
-            };
+            let params =
+                match parse_container_exec_arguments(arguments, sess, turn_context, &call_id) {
+                    Ok(params) => params,
+                    Err(output) => {
+                        return *output;
+                    }
+                };
             handle_container_exec_with_params(
                 params,
                 sess,
@@ -2306,6 +3464,7 @@ async fn handle_function_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.sandbox_limits,
             };
             handle_container_exec_with_params(
                 exec_params,
@@ -2318,6 +3477,7 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        DELEGATE_TOOL_NAME => handle_delegate(sess, turn_context, sub_id, call_id, arguments).await,
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
@@ -2409,6 +3569,7 @@ async fn handle_custom_tool_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.sandbox_limits,
             };
             let resp = handle_container_exec_with_params(
                 exec_params,
@@ -2442,25 +3603,36 @@ async fn handle_custom_tool_call(
     }
 }
 
-fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> ExecParams {
+fn to_exec_params(
+    params: ShellToolCallParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+) -> ExecParams {
     ExecParams {
         command: params.command,
         cwd: turn_context.resolve_path(params.workdir.clone()),
         timeout_ms: params.timeout_ms.or(Some(turn_context.exec_timeout_ms)),
-        env: create_env(&turn_context.shell_environment_policy),
+        env: create_env(
+            &turn_context.shell_environment_policy,
+            sess.egress_proxy_socket(&turn_context.sandbox_policy),
+        ),
         with_escalated_permissions: params.with_escalated_permissions,
         justification: params.justification,
+        resource_limits: turn_context.sandbox_limits,
     }
 }
 
 fn parse_container_exec_arguments(
     arguments: String,
+    sess: &Session,
     turn_context: &TurnContext,
     call_id: &str,
 ) -> Result<ExecParams, Box<ResponseInputItem>> {
     // parse command
     match serde_json::from_str::<ShellToolCallParams>(&arguments) {
-        Ok(shell_tool_call_params) => Ok(to_exec_params(shell_tool_call_params, turn_context)),
+        Ok(shell_tool_call_params) => {
+            Ok(to_exec_params(shell_tool_call_params, sess, turn_context))
+        }
         Err(e) => {
             // allow model to re-sample
             let output = ResponseInputItem::FunctionCallOutput {
@@ -2509,6 +3681,40 @@ async fn handle_container_exec_with_params(
     sub_id: String,
     call_id: String,
 ) -> ResponseInputItem {
+    let sandbox_policy = sess.effective_sandbox_policy(&turn_context.sandbox_policy);
+    // The hook runs before the command is classified, so a rewrite into or
+    // out of an `apply_patch` call is handled as if the model had sent it.
+    let mut params = params;
+    let decision = sess
+        .run_pre_command_hook(&sub_id, &call_id, &params.cwd, &params.command)
+        .await;
+    match &decision {
+        Some(HookDecision::Rewrite {
+            command: Some(command),
+            ..
+        }) if !command.is_empty() => {
+            sess.notify_background_event(
+                &sub_id,
+                format!("pre_command hook rewrote command to: {}", command.join(" ")),
+            )
+            .await;
+            params.command = command.clone();
+        }
+        Some(HookDecision::Deny { reason }) => {
+            let reason = reason
+                .clone()
+                .unwrap_or_else(|| "denied by pre_command hook".to_string());
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("exec command rejected: {reason}"),
+                    success: None,
+                },
+            };
+        }
+        _ => {}
+    }
+
     // check if this was a patch, and apply it if so
     let apply_patch_exec = match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
         MaybeApplyPatchVerified::Body(changes) => {
@@ -2542,6 +3748,7 @@ async fn handle_container_exec_with_params(
         Some(ApplyPatchExec {
             action: ApplyPatchAction { patch, cwd, .. },
             user_explicitly_approved_this_action,
+            allowed_by_hook,
         }) => {
             let path_to_codex = std::env::current_exe()
                 .ok()
@@ -2567,17 +3774,24 @@ async fn handle_container_exec_with_params(
                 env: HashMap::new(),
                 with_escalated_permissions: params.with_escalated_permissions,
                 justification: params.justification.clone(),
+                resource_limits: params.resource_limits,
             };
             let safety = if *user_explicitly_approved_this_action {
                 SafetyCheck::AutoApprove {
                     sandbox_type: SandboxType::None,
                 }
             } else {
-                assess_safety_for_untrusted_command(
+                let assessed = assess_safety_for_untrusted_command(
                     turn_context.approval_policy,
-                    &turn_context.sandbox_policy,
+                    &sandbox_policy,
                     params.with_escalated_permissions.unwrap_or(false),
-                )
+                );
+                match decision {
+                    Some(HookDecision::Ask) => SafetyCheck::AskUser,
+                    Some(HookDecision::Allow) => allow_without_prompt(assessed, &sandbox_policy),
+                    _ if *allowed_by_hook => allow_without_prompt(assessed, &sandbox_policy),
+                    _ => assessed,
+                }
             };
             (
                 params,
@@ -2586,16 +3800,23 @@ async fn handle_container_exec_with_params(
             )
         }
         None => {
-            let safety = {
+            let assessed = {
                 let state = sess.state.lock_unchecked();
                 assess_command_safety(
                     &params.command,
                     turn_context.approval_policy,
-                    &turn_context.sandbox_policy,
+                    &sandbox_policy,
                     &state.approved_commands,
+                    &sess.exec_policy,
+                    &params.cwd,
                     params.with_escalated_permissions.unwrap_or(false),
                 )
             };
+            let safety = match decision {
+                Some(HookDecision::Allow) => allow_without_prompt(assessed, &sandbox_policy),
+                Some(HookDecision::Ask) => SafetyCheck::AskUser,
+                Some(HookDecision::Deny { .. } | HookDecision::Rewrite { .. }) | None => assessed,
+            };
             let command_for_display = params.command.clone();
             (params, safety, command_for_display)
         }
@@ -2611,14 +3832,18 @@ async fn handle_container_exec_with_params(
                     params.command.clone(),
                     params.cwd.clone(),
                     params.justification.clone(),
+                    None,
                 )
                 .await;
             match rx_approve.await.unwrap_or_default() {
                 ReviewDecision::Approved => (),
                 ReviewDecision::ApprovedForSession => {
-                    sess.add_approved_command(params.command.clone());
+                    sess.add_approved_command(params.command.clone()).await;
                 }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
+                // Nothing was offered to grant before the command ran.
+                ReviewDecision::ApprovedWritableRootForSession
+                | ReviewDecision::Denied
+                | ReviewDecision::Abort => {
                     return ResponseInputItem::FunctionCallOutput {
                         call_id,
                         output: FunctionCallOutputPayload {
@@ -2654,12 +3879,12 @@ async fn handle_container_exec_with_params(
             |ApplyPatchExec {
                  action,
                  user_explicitly_approved_this_action,
+                 ..
              }| ApplyPatchCommandContext {
                 user_explicitly_approved_this_action,
                 changes: convert_apply_patch_to_protocol(&action),
             },
         ),
-        timeout_ms: params.timeout_ms.unwrap_or(crate::exec::DEFAULT_TIMEOUT_MS),
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
@@ -2670,7 +3895,7 @@ async fn handle_container_exec_with_params(
             ExecInvokeArgs {
                 params: params.clone(),
                 sandbox_type,
-                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_policy: &sandbox_policy,
                 codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
                 stdout_stream: if exec_command_context.apply_patch.is_some() {
                     None
@@ -2686,7 +3911,10 @@ async fn handle_container_exec_with_params(
         .await;
 
     match output_result {
-        Ok(output) => {
+        Ok(mut output) => {
+            if let Some(note) = sess.take_denied_hosts_note() {
+                output.aggregated_output.text.push_str(&note);
+            }
             let ExecToolCallOutput { exit_code, .. } = &output;
 
             let is_success = *exit_code == 0;
@@ -2738,12 +3966,15 @@ async fn handle_sandbox_error(
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
         AskForApproval::Never | AskForApproval::OnRequest => {
+            let mut content =
+                format!("failed in sandbox {sandbox_type:?} with execution error: {error}");
+            if let Some(note) = sess.take_denied_hosts_note() {
+                content.push_str(&note);
+            }
             return ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: format!(
-                        "failed in sandbox {sandbox_type:?} with execution error: {error}"
-                    ),
+                    content,
                     success: Some(false),
                 },
             };
@@ -2777,85 +4008,133 @@ async fn handle_sandbox_error(
     sess.notify_background_event(&sub_id, format!("Execution failed: {error}"))
         .await;
 
+    // If the failure looks like a write outside the writable roots, also
+    // offer to make that directory writable and retry inside the sandbox.
+    let grant_root = match &error {
+        SandboxErr::Denied(_, _, stderr) => {
+            writable_root_to_grant(stderr, &turn_context.sandbox_policy, &turn_context.cwd)
+        }
+        _ => None,
+    };
+    // The sandbox refused to run a command it could not connect to the egress
+    // proxy; only running it without the sandbox gives it network access.
+    let proxy_unavailable = matches!(
+        &error,
+        SandboxErr::Denied(_, _, stderr) if stderr.contains(EGRESS_PROXY_UNAVAILABLE_MESSAGE)
+    );
+    let reason = match &grant_root {
+        _ if proxy_unavailable => {
+            "network access to allowed_domains is unavailable in the sandbox; retry without sandbox?"
+                .to_string()
+        }
+        Some(root) => format!(
+            "command failed; retry without sandbox, or with write access to {}?",
+            root.display()
+        ),
+        None => "command failed; retry without sandbox?".to_string(),
+    };
+
     let rx_approve = sess
         .request_command_approval(
             sub_id.clone(),
             call_id.clone(),
             params.command.clone(),
             cwd.clone(),
-            Some("command failed; retry without sandbox?".to_string()),
+            Some(reason),
+            grant_root.clone(),
         )
         .await;
 
-    match rx_approve.await.unwrap_or_default() {
-        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+    let retry_sandbox_type = match (rx_approve.await.unwrap_or_default(), grant_root) {
+        (ReviewDecision::Approved | ReviewDecision::ApprovedForSession, _) => {
             // Persist this command as pre‑approved for the
             // remainder of the session so future
             // executions skip the sandbox directly.
             // TODO(ragona): Isn't this a bug? It always saves the command in an | fork?
-            sess.add_approved_command(params.command.clone());
+            sess.add_approved_command(params.command.clone()).await;
             // Inform UI we are retrying without sandbox.
             sess.notify_background_event(&sub_id, "retrying command without sandbox")
                 .await;
 
             // This is an escalated retry; the policy will not be
             // examined and the sandbox has been set to `None`.
-            let retry_output_result = sess
-                .run_exec_with_events(
-                    turn_diff_tracker,
-                    exec_command_context.clone(),
-                    ExecInvokeArgs {
-                        params,
-                        sandbox_type: SandboxType::None,
-                        sandbox_policy: &turn_context.sandbox_policy,
-                        codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
-                        stdout_stream: if exec_command_context.apply_patch.is_some() {
-                            None
-                        } else {
-                            Some(StdoutStream {
-                                sub_id: sub_id.clone(),
-                                call_id: call_id.clone(),
-                                tx_event: sess.tx_event.clone(),
-                            })
-                        },
-                    },
-                )
-                .await;
-
-            match retry_output_result {
-                Ok(retry_output) => {
-                    let ExecToolCallOutput { exit_code, .. } = &retry_output;
-
-                    let is_success = *exit_code == 0;
-                    let content = format_exec_output(&retry_output);
-
-                    ResponseInputItem::FunctionCallOutput {
-                        call_id: call_id.clone(),
-                        output: FunctionCallOutputPayload {
-                            content,
-                            success: Some(is_success),
-                        },
-                    }
-                }
-                Err(e) => ResponseInputItem::FunctionCallOutput {
-                    call_id: call_id.clone(),
-                    output: FunctionCallOutputPayload {
-                        content: format!("retry failed: {e}"),
-                        success: None,
-                    },
-                },
-            }
+            SandboxType::None
         }
-        ReviewDecision::Denied | ReviewDecision::Abort => {
+        (ReviewDecision::ApprovedWritableRootForSession, Some(root)) => {
+            sess.notify_background_event(
+                &sub_id,
+                format!(
+                    "granted write access to {} for this session; retrying command in sandbox",
+                    root.display()
+                ),
+            )
+            .await;
+            sess.grant_writable_root(root).await;
+            sandbox_type
+        }
+        (ReviewDecision::ApprovedWritableRootForSession, None)
+        | (ReviewDecision::Denied | ReviewDecision::Abort, _) => {
             // Fall through to original failure handling.
-            ResponseInputItem::FunctionCallOutput {
+            let content = if proxy_unavailable {
+                "exec command rejected by user; network access to allowed_domains is unavailable in the sandbox"
+            } else {
+                "exec command rejected by user"
+            };
+            return ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: "exec command rejected by user".to_string(),
+                    content: content.to_string(),
                     success: None,
                 },
+            };
+        }
+    };
+
+    let sandbox_policy = sess.effective_sandbox_policy(&turn_context.sandbox_policy);
+    let retry_output_result = sess
+        .run_exec_with_events(
+            turn_diff_tracker,
+            exec_command_context.clone(),
+            ExecInvokeArgs {
+                params,
+                sandbox_type: retry_sandbox_type,
+                sandbox_policy: &sandbox_policy,
+                codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
+                stdout_stream: if exec_command_context.apply_patch.is_some() {
+                    None
+                } else {
+                    Some(StdoutStream {
+                        sub_id: sub_id.clone(),
+                        call_id: call_id.clone(),
+                        tx_event: sess.tx_event.clone(),
+                    })
+                },
+            },
+        )
+        .await;
+
+    match retry_output_result {
+        Ok(retry_output) => {
+            let ExecToolCallOutput { exit_code, .. } = &retry_output;
+
+            let is_success = *exit_code == 0;
+            let content = format_exec_output(&retry_output);
+
+            ResponseInputItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(is_success),
+                },
             }
         }
+        Err(e) => ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.clone(),
+            output: FunctionCallOutputPayload {
+                content: format!("retry failed: {e}"),
+                success: None,
+            },
+        },
     }
 }
 
@@ -3045,16 +4324,18 @@ async fn drain_to_completed(
                 // some providers don't return token usage, so we default
                 // TODO: consider approximate token usage
                 let token_usage = token_usage.unwrap_or_default();
-                let info = codex_protocol::protocol::TokenUsageInfo {
-                    total_token_usage: token_usage.clone(),
-                    last_token_usage: token_usage,
-                    model_context_window: turn_context.client.get_model_context_window(),
-                };
+                let info = sess
+                    .add_token_usage(
+                        &turn_context.client.get_model(),
+                        &token_usage,
+                        turn_context.client.get_model_context_window(),
+                    )
+                    .await;
                 sess.tx_event
                     .send(Event {
                         id: sub_id.to_string(),
                         msg: EventMsg::TokenCount(codex_protocol::protocol::TokenCountEvent {
-                            info: Some(info),
+                            info,
                         }),
                     })
                     .await
@@ -3171,7 +4452,9 @@ while True:
         send({'jsonrpc': '2.0', 'id': id_, 'error': {'code': -32601, 'message': 'unknown method'}})
 "#;
 
-    async fn setup_session(exec_timeout_ms: u64) -> (Arc<Session>, TurnContext, TempDir) {
+    async fn setup_session(
+        exec_timeout_ms: u64,
+    ) -> (Arc<Session>, TurnContext, Receiver<Event>, TempDir) {
         let tmp = TempDir::new().unwrap();
         let script_path = tmp.path().join("dummy_mcp_server.py");
         std::fs::write(&script_path, DUMMY_MCP_SERVER).unwrap();
@@ -3192,7 +4475,7 @@ while True:
             },
         );
 
-        let (tx_event, _rx_event) = async_channel::unbounded();
+        let (tx_event, rx_event) = async_channel::unbounded();
         let configure_session = ConfigureSession {
             provider: config.model_provider.clone(),
             model: config.model.clone(),
@@ -3205,6 +4488,7 @@ while True:
             disable_response_storage: config.disable_response_storage,
             notify: config.notify.clone(),
             cwd: config.cwd.clone(),
+            parent_conversation_id: None,
         };
         let auth_manager = AuthManager::shared(
             config.codex_home.clone(),
@@ -3221,12 +4505,12 @@ while True:
         .await
         .unwrap();
         turn_context.exec_timeout_ms = exec_timeout_ms;
-        (session, turn_context, tmp)
+        (session, turn_context, rx_event, tmp)
     }
 
     #[tokio::test]
     async fn mcp_tool_call_times_out() {
-        let (session, turn_context, _tmp) = setup_session(10).await;
+        let (session, turn_context, _rx_event, _tmp) = setup_session(10).await;
         let args = json!({ "duration_ms": 2000 }).to_string();
         let mut diff = TurnDiffTracker::default();
         let response = handle_function_call(
@@ -3253,11 +4537,70 @@ while True:
             other => panic!("unexpected response: {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn approvals_of_one_submission_are_requested_one_at_a_time() {
+        let (session, _turn_context, rx_event, tmp) = setup_session(1_000).await;
+        let next_approval_call_id = || async {
+            loop {
+                let event = rx_event.recv().await.unwrap();
+                if let EventMsg::ExecApprovalRequest(request) = event.msg {
+                    return request.call_id;
+                }
+            }
+        };
+
+        let first = session
+            .request_command_approval(
+                "sub".to_string(),
+                "first".to_string(),
+                vec!["true".to_string()],
+                tmp.path().to_path_buf(),
+                None,
+                None,
+            )
+            .await;
+        assert_eq!(next_approval_call_id().await, "first");
+        let second = tokio::spawn({
+            let session = Arc::clone(&session);
+            let cwd = tmp.path().to_path_buf();
+            async move {
+                let rx_approve = session
+                    .request_command_approval(
+                        "sub".to_string(),
+                        "second".to_string(),
+                        vec!["true".to_string()],
+                        cwd,
+                        None,
+                        None,
+                    )
+                    .await;
+                rx_approve.await.unwrap()
+            }
+        });
+        // The second request waits until the first one is answered.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        while let Ok(event) = rx_event.try_recv() {
+            assert!(
+                !matches!(event.msg, EventMsg::ExecApprovalRequest(_)),
+                "unexpected approval request: {event:?}"
+            );
+        }
+
+        session.notify_approval("sub", ReviewDecision::Denied);
+        assert_eq!(first.await.unwrap(), ReviewDecision::Denied);
+        assert_eq!(next_approval_call_id().await, "second");
+        session.notify_approval("sub", ReviewDecision::Approved);
+        assert_eq!(second.await.unwrap(), ReviewDecision::Approved);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use codex_protocol::config_types::SandboxMode;
     use mcp_types::ContentBlock;
     use mcp_types::TextContent;
     use pretty_assertions::assert_eq;
@@ -3309,6 +4652,7 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(full.clone()),
             duration: StdDuration::from_secs(1),
+            resource_limit_exceeded: None,
         };
 
         let out = format_exec_output_str(&exec);
@@ -3351,6 +4695,7 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(full.clone()),
             duration: StdDuration::from_secs(1),
+            resource_limit_exceeded: None,
         };
 
         let out = format_exec_output_str(&exec);
@@ -3424,4 +4769,220 @@ mod tests {
 
         assert_eq!(expected, got);
     }
+
+    #[test]
+    fn state_snapshot_survives_restore() {
+        let mut state = State::default();
+        state
+            .approved_commands
+            .insert(vec!["cargo".to_string(), "test".to_string()]);
+        state
+            .approved_commands
+            .insert(vec!["cargo".to_string(), "build".to_string()]);
+        state.granted_writable_roots.push(PathBuf::from("/tmp/out"));
+        state.plan = Some(UpdatePlanArgs {
+            explanation: None,
+            plan: Vec::new(),
+        });
+        let usage = TokenUsage {
+            input_tokens: 10,
+            total_tokens: 12,
+            ..Default::default()
+        };
+        state.token_info = TokenUsageInfo::new_or_append(&None, &Some(usage.clone()), None);
+        state.token_info =
+            TokenUsageInfo::new_or_append(&state.token_info, &Some(usage.clone()), None);
+
+        let snapshot = state.snapshot();
+        assert_eq!(
+            snapshot.approved_commands,
+            vec![
+                vec!["cargo".to_string(), "build".to_string()],
+                vec!["cargo".to_string(), "test".to_string()],
+            ]
+        );
+        assert_eq!(
+            snapshot.total_token_usage.as_ref().map(|u| u.total_tokens),
+            Some(24)
+        );
+
+        let mut restored = State::default();
+        restored.restore(&snapshot, Some(1000), true);
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(
+            restored
+                .token_info
+                .and_then(|info| info.model_context_window),
+            Some(1000)
+        );
+    }
+
+    fn recorded_turn(
+        approval_policy: AskForApproval,
+        sandbox_policy: SandboxPolicy,
+        cwd: PathBuf,
+    ) -> TurnSettings {
+        TurnSettings {
+            model: "recorded-model".to_string(),
+            reasoning_effort: ReasoningEffortConfig::High,
+            reasoning_summary: ReasoningSummaryConfig::Detailed,
+            approval_policy,
+            sandbox_policy,
+            cwd,
+        }
+    }
+
+    #[test]
+    fn resume_keeps_explicit_settings_and_never_widens_policies() {
+        let home = tempfile::TempDir::new().unwrap();
+        let recorded_cwd = tempfile::TempDir::new().unwrap();
+        let load = |overrides: ConfigOverrides| {
+            Config::load_from_base_config_with_overrides(
+                ConfigToml::default(),
+                overrides,
+                home.path().to_path_buf(),
+            )
+            .unwrap()
+        };
+
+        // Defaults give way to the recorded settings as long as those are
+        // no more permissive.
+        let mut config = load(ConfigOverrides::default());
+        restore_turn_settings(
+            &mut config,
+            &recorded_turn(
+                AskForApproval::UnlessTrusted,
+                SandboxPolicy::ReadOnly,
+                recorded_cwd.path().to_path_buf(),
+            ),
+        );
+        assert_eq!(config.model, "recorded-model");
+        assert_eq!(config.model_reasoning_effort, ReasoningEffortConfig::High);
+        assert_eq!(config.approval_policy, AskForApproval::UnlessTrusted);
+        assert_eq!(config.sandbox_policy, SandboxPolicy::ReadOnly);
+        assert_eq!(config.cwd, recorded_cwd.path());
+
+        // More permissive recorded policies are ignored.
+        let mut config = load(ConfigOverrides::default());
+        let configured = (config.approval_policy, config.sandbox_policy.clone());
+        restore_turn_settings(
+            &mut config,
+            &recorded_turn(
+                AskForApproval::Never,
+                SandboxPolicy::DangerFullAccess,
+                recorded_cwd.path().to_path_buf(),
+            ),
+        );
+        assert_eq!((config.approval_policy, config.sandbox_policy), configured);
+
+        // Settings given on the command line win.
+        let mut config = load(ConfigOverrides {
+            model: Some("chosen-model".to_string()),
+            approval_policy: Some(AskForApproval::OnFailure),
+            sandbox_mode: Some(SandboxMode::WorkspaceWrite),
+            cwd: Some(home.path().to_path_buf()),
+            ..Default::default()
+        });
+        restore_turn_settings(
+            &mut config,
+            &recorded_turn(
+                AskForApproval::UnlessTrusted,
+                SandboxPolicy::ReadOnly,
+                recorded_cwd.path().to_path_buf(),
+            ),
+        );
+        assert_eq!(config.model, "chosen-model");
+        assert_eq!(config.approval_policy, AskForApproval::OnFailure);
+        assert!(matches!(
+            config.sandbox_policy,
+            SandboxPolicy::WorkspaceWrite { .. }
+        ));
+        assert_eq!(config.cwd, home.path());
+    }
+
+    #[test]
+    fn resume_with_other_policies_drops_recorded_grants() {
+        let home = tempfile::TempDir::new().unwrap();
+        let recorded_cwd = tempfile::TempDir::new().unwrap();
+        let turn = recorded_turn(
+            AskForApproval::OnRequest,
+            SandboxPolicy::new_workspace_write_policy(),
+            recorded_cwd.path().to_path_buf(),
+        );
+        let snapshot = SessionStateSnapshot {
+            approved_commands: vec![vec!["cargo".to_string(), "test".to_string()]],
+            granted_writable_roots: vec![PathBuf::from("/tmp/out")],
+            turn: Some(turn.clone()),
+            ..Default::default()
+        };
+
+        // The user resumes with a stricter sandbox and approval policy: the
+        // old session's approvals would otherwise run unsandboxed.
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                approval_policy: Some(AskForApproval::UnlessTrusted),
+                sandbox_mode: Some(SandboxMode::ReadOnly),
+                ..Default::default()
+            },
+            home.path().to_path_buf(),
+        )
+        .unwrap();
+        restore_turn_settings(&mut config, &turn);
+        let keep_grants = recorded_grants_apply(&config, snapshot.turn.as_ref());
+        assert!(!keep_grants);
+        let mut restored = State::default();
+        restored.restore(&snapshot, None, keep_grants);
+        assert!(restored.approved_commands.is_empty());
+        assert!(restored.granted_writable_roots.is_empty());
+
+        // Resuming under the recorded policies keeps them.
+        config.approval_policy = turn.approval_policy;
+        config.sandbox_policy = turn.sandbox_policy.clone();
+        let keep_grants = recorded_grants_apply(&config, snapshot.turn.as_ref());
+        assert!(keep_grants);
+        let mut restored = State::default();
+        restored.restore(&snapshot, None, keep_grants);
+        assert_eq!(
+            restored.snapshot().approved_commands,
+            snapshot.approved_commands
+        );
+        assert_eq!(
+            restored.granted_writable_roots,
+            snapshot.granted_writable_roots
+        );
+
+        // Without recorded policies there is nothing to compare against.
+        assert!(!recorded_grants_apply(&config, None));
+    }
+
+    #[test]
+    fn recorded_sandbox_may_not_read_denied_paths_or_reach_more_hosts() {
+        let current = SandboxPolicy::new_workspace_write_policy();
+        let edited = |edit: fn(&mut Vec<PathBuf>, &mut Vec<String>)| {
+            let mut recorded = current.clone();
+            if let SandboxPolicy::WorkspaceWrite {
+                deny_read,
+                allowed_domains,
+                ..
+            } = &mut recorded
+            {
+                edit(deny_read, allowed_domains);
+            }
+            recorded
+        };
+        assert!(sandbox_is_no_wider(&current, &current));
+        assert!(sandbox_is_no_wider(
+            &SandboxPolicy::ReadOnly,
+            &SandboxPolicy::DangerFullAccess
+        ));
+        assert!(!sandbox_is_no_wider(
+            &edited(|deny_read, _| deny_read.clear()),
+            &current
+        ));
+        assert!(!sandbox_is_no_wider(
+            &edited(|_, domains| domains.push("example.com".to_string())),
+            &current
+        ));
+    }
 }
@@ -1,13 +1,19 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::Budgets;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
+use crate::config_types::ModelPrice;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::SandboxLimits;
 use crate::config_types::SandboxWorkspaceWrite;
+use crate::config_types::SessionRetention;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
 use crate::exec::DEFAULT_TIMEOUT_MS;
+use crate::exec_policy::discover_policy_files;
+use crate::git_info::get_git_repo_root;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::find_family_for_model;
@@ -57,6 +63,11 @@ pub struct Config {
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Token usage at which the conversation history is compacted
+    /// automatically before the next model request. When unset, defaults to
+    /// a fraction of `model_context_window`.
+    pub model_auto_compact_token_limit: Option<u64>,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
@@ -73,6 +84,9 @@ pub struct Config {
     /// Default timeout for `exec` commands (milliseconds) when a tool call does not specify one.
     pub exec_timeout_ms: u64,
 
+    /// Resource limits applied to `exec` commands.
+    pub sandbox_limits: SandboxLimits,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -188,6 +202,11 @@ pub struct Config {
 
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
+
+    /// Include the `delegate` tool that lets the agent hand a self-contained
+    /// sub-task to a child conversation.
+    pub include_delegate_tool: bool,
+
     /// When true, disables burst-paste detection for typed input entirely.
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
@@ -196,6 +215,40 @@ pub struct Config {
     /// Optional event hooks that run before/after lifecycle points.
     /// When unset, no hooks run.
     pub hooks: Option<crate::hooks::HooksConfig>,
+
+    /// Per-turn and per-session limits on tool calls, tokens and time.
+    pub budgets: Budgets,
+
+    /// Snapshot the git worktree before every turn so `Op::Undo` can restore
+    /// it. Snapshots live under `CODEX_HOME/checkpoints`.
+    pub checkpoints: bool,
+
+    /// Retention and compression of rollouts under `CODEX_HOME/sessions`.
+    pub sessions: SessionRetention,
+
+    /// Per-model prices, keyed by model slug, for estimating spend.
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// execpolicy files consulted when deciding whether to auto-approve or
+    /// reject a command: `*.policy` files in `CODEX_HOME` and, for trusted
+    /// projects, in the project root.
+    pub exec_policy_files: Vec<PathBuf>,
+
+    /// Which turn settings the user chose explicitly, so that resuming a
+    /// session does not replace them with the ones it was recorded with.
+    pub explicit_settings: ExplicitSettings,
+}
+
+/// Turn settings set on the command line, in the active profile or in
+/// `config.toml`, as opposed to left at their defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExplicitSettings {
+    pub model: bool,
+    pub model_reasoning_effort: bool,
+    pub model_reasoning_summary: bool,
+    pub approval_policy: bool,
+    pub sandbox_policy: bool,
+    pub cwd: bool,
 }
 
 impl Config {
@@ -413,6 +466,10 @@ pub struct ConfigToml {
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Token usage at which the conversation history is compacted
+    /// automatically.
+    pub model_auto_compact_token_limit: Option<u64>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
@@ -428,6 +485,9 @@ pub struct ConfigToml {
     /// Sandbox configuration to apply if `sandbox` is `WorkspaceWrite`.
     pub sandbox_workspace_write: Option<SandboxWorkspaceWrite>,
 
+    /// Resource limits applied to exec commands.
+    pub sandbox_limits: Option<SandboxLimits>,
+
     /// Disable server-side response storage (sends the full conversation
     /// context with every request). Currently necessary for OpenAI customers
     /// who have opted into Zero Data Retention (ZDR).
@@ -517,6 +577,18 @@ pub struct ConfigToml {
 
     /// Optional event hooks that run before/after key lifecycle points.
     pub hooks: Option<crate::hooks::HooksToml>,
+
+    /// Per-turn and per-session budgets.
+    pub budgets: Option<Budgets>,
+
+    /// Record workspace checkpoints before every turn. Defaults to false.
+    pub checkpoints: Option<bool>,
+
+    /// Retention and compression of recorded sessions.
+    pub sessions: Option<SessionRetention>,
+
+    /// Prices per million tokens, keyed by model slug.
+    pub model_prices: Option<HashMap<String, ModelPrice>>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -532,30 +604,51 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `delegate` tool that lets the agent spawn a sub-agent.
+    #[serde(default)]
+    pub delegate: Option<bool>,
 }
 
 impl ConfigToml {
     /// Derive the effective sandbox policy from the configuration.
-    fn derive_sandbox_policy(&self, sandbox_mode_override: Option<SandboxMode>) -> SandboxPolicy {
+    fn derive_sandbox_policy(
+        &self,
+        sandbox_mode_override: Option<SandboxMode>,
+        codex_home: &Path,
+    ) -> SandboxPolicy {
         let resolved_sandbox_mode = sandbox_mode_override
             .or(self.sandbox_mode)
             .unwrap_or_default();
         match resolved_sandbox_mode {
             SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
-            SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
-                Some(SandboxWorkspaceWrite {
+            SandboxMode::WorkspaceWrite => {
+                let SandboxWorkspaceWrite {
                     writable_roots,
                     network_access,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
-                }) => SandboxPolicy::WorkspaceWrite {
-                    writable_roots: writable_roots.clone(),
-                    network_access: *network_access,
-                    exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
-                    exclude_slash_tmp: *exclude_slash_tmp,
-                },
-                None => SandboxPolicy::new_workspace_write_policy(),
-            },
+                    deny_read,
+                    exclude_default_deny_read,
+                    allowed_domains,
+                    isolate_namespaces,
+                } = self.sandbox_workspace_write.clone().unwrap_or_default();
+                let mut denied = if exclude_default_deny_read {
+                    Vec::new()
+                } else {
+                    default_deny_read_paths(codex_home)
+                };
+                denied.extend(deny_read);
+                SandboxPolicy::WorkspaceWrite {
+                    writable_roots,
+                    network_access,
+                    exclude_tmpdir_env_var,
+                    exclude_slash_tmp,
+                    deny_read: denied,
+                    allowed_domains,
+                    isolate_namespaces,
+                }
+            }
             SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
         }
     }
@@ -669,7 +762,44 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
-        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
+        let explicit_settings = ExplicitSettings {
+            model: model.is_some() || config_profile.model.is_some() || cfg.model.is_some(),
+            model_reasoning_effort: config_profile.model_reasoning_effort.is_some()
+                || cfg.model_reasoning_effort.is_some(),
+            model_reasoning_summary: config_profile.model_reasoning_summary.is_some()
+                || cfg.model_reasoning_summary.is_some(),
+            approval_policy: approval_policy.is_some()
+                || config_profile.approval_policy.is_some()
+                || cfg.approval_policy.is_some(),
+            sandbox_policy: sandbox_mode.is_some() || cfg.sandbox_mode.is_some(),
+            cwd: cwd.is_some(),
+        };
+
+        let resolved_cwd = {
+            use std::env;
+
+            match cwd {
+                None => {
+                    tracing::info!("cwd not set, using current dir");
+                    env::current_dir()?
+                }
+                Some(p) if p.is_absolute() => p,
+                Some(p) => {
+                    // Resolve relative path against the current working directory.
+                    tracing::info!("cwd is relative, resolving against current dir");
+                    let mut current = env::current_dir()?;
+                    current.push(p);
+                    current
+                }
+            }
+        };
+
+        let project_root = cfg
+            .is_cwd_trusted(&resolved_cwd)
+            .then(|| get_git_repo_root(&resolved_cwd).unwrap_or_else(|| resolved_cwd.clone()));
+        let exec_policy_files = discover_policy_files(&codex_home, project_root.as_deref());
+
+        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode, &codex_home);
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -694,25 +824,6 @@ impl Config {
         let shell_environment_policy = cfg.shell_environment_policy.into();
         let exec_timeout_ms = cfg.exec_timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
 
-        let resolved_cwd = {
-            use std::env;
-
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
-            }
-        };
-
         let history = cfg.history.unwrap_or_default();
 
         let tools_web_search_request = override_tools_web_search_request
@@ -723,6 +834,8 @@ impl Config {
             .or(cfg.tools.as_ref().and_then(|t| t.view_image))
             .unwrap_or(true);
 
+        let include_delegate_tool = cfg.tools.as_ref().and_then(|t| t.delegate).unwrap_or(false);
+
         let model = model
             .or(config_profile.model)
             .or(cfg.model)
@@ -782,6 +895,7 @@ impl Config {
             model_family,
             model_context_window,
             model_max_output_tokens,
+            model_auto_compact_token_limit: cfg.model_auto_compact_token_limit,
             model_provider_id,
             model_provider,
             cwd: resolved_cwd,
@@ -792,6 +906,7 @@ impl Config {
             sandbox_policy,
             shell_environment_policy,
             exec_timeout_ms,
+            sandbox_limits: cfg.sandbox_limits.unwrap_or_default(),
             // Profiles do not carry disable_response_storage; prefer explicit
             // CLI override, then config.toml field, then computed default.
             disable_response_storage: disable_response_storage
@@ -838,8 +953,15 @@ impl Config {
                 .experimental_use_exec_command_tool
                 .unwrap_or(false),
             include_view_image_tool,
+            include_delegate_tool,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
             hooks: hooks_cfg,
+            budgets: cfg.budgets.unwrap_or_default(),
+            checkpoints: cfg.checkpoints.unwrap_or(false),
+            sessions: cfg.sessions.unwrap_or_default(),
+            model_prices: cfg.model_prices.unwrap_or_default(),
+            exec_policy_files,
+            explicit_settings,
         };
         Ok(config)
     }
@@ -935,6 +1057,32 @@ pub fn find_codex_home() -> std::io::Result<PathBuf> {
     Ok(p)
 }
 
+/// Credential locations that sandboxed commands may not read unless
+/// `sandbox_workspace_write.exclude_default_deny_read` is set.
+pub fn default_deny_read_paths(codex_home: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = home_dir()
+        .map(|home| {
+            [".ssh", ".aws", ".azure", ".gnupg", ".config/gcloud"]
+                .iter()
+                .map(|dir| home.join(dir))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.push(codex_home.join("auth.json"));
+    paths
+}
+
+/// [`SandboxPolicy::new_workspace_write_policy`] with the
+/// [`default_deny_read_paths`] denied, for switching to workspace-write
+/// outside of `config.toml`.
+pub fn default_workspace_write_policy(codex_home: &Path) -> SandboxPolicy {
+    let mut policy = SandboxPolicy::new_workspace_write_policy();
+    if let SandboxPolicy::WorkspaceWrite { deny_read, .. } = &mut policy {
+        *deny_read = default_deny_read_paths(codex_home);
+    }
+    policy
+}
+
 /// Returns the path to the folder where Codex logs are stored. Does not verify
 /// that the directory exists.
 pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
@@ -985,6 +1133,7 @@ persistence = "none"
 
     #[test]
     fn test_sandbox_config_parsing() {
+        let codex_home = Path::new("/home/user/.codex");
         let sandbox_full_access = r#"
 sandbox_mode = "danger-full-access"
 
@@ -996,7 +1145,7 @@ network_access = false  # This should be ignored.
         let sandbox_mode_override = None;
         assert_eq!(
             SandboxPolicy::DangerFullAccess,
-            sandbox_full_access_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_full_access_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
         );
 
         let sandbox_read_only = r#"
@@ -1011,7 +1160,7 @@ network_access = true  # This should be ignored.
         let sandbox_mode_override = None;
         assert_eq!(
             SandboxPolicy::ReadOnly,
-            sandbox_read_only_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_read_only_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
         );
 
         let sandbox_workspace_write = r#"
@@ -1023,6 +1172,10 @@ writable_roots = [
 ]
 exclude_tmpdir_env_var = true
 exclude_slash_tmp = true
+deny_read = ["/my/workspace/.env"]
+exclude_default_deny_read = true
+allowed_domains = ["crates.io"]
+isolate_namespaces = true
 "#;
 
         let sandbox_workspace_write_cfg = toml::from_str::<ConfigToml>(sandbox_workspace_write)
@@ -1034,9 +1187,26 @@ exclude_slash_tmp = true
                 network_access: false,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                deny_read: vec![PathBuf::from("/my/workspace/.env")],
+                allowed_domains: vec!["crates.io".to_string()],
+                isolate_namespaces: true,
             },
-            sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override, codex_home)
         );
+
+        let sandbox_workspace_write_defaults = toml::from_str::<ConfigToml>(
+            r#"
+sandbox_mode = "workspace-write"
+
+[sandbox_workspace_write]
+deny_read = ["/my/workspace/.env"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let policy = sandbox_workspace_write_defaults.derive_sandbox_policy(None, codex_home);
+        let deny_read = policy.get_deny_read_paths();
+        assert!(deny_read.contains(&codex_home.join("auth.json")));
+        assert_eq!(deny_read.last(), Some(&PathBuf::from("/my/workspace/.env")));
     }
 
     struct PrecedenceTestFixture {
@@ -1186,12 +1356,14 @@ model_verbosity = "high"
                 model_family: find_family_for_model("o3").expect("known model slug"),
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
+                model_auto_compact_token_limit: None,
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
                 exec_timeout_ms: DEFAULT_TIMEOUT_MS,
+                sandbox_limits: SandboxLimits::default(),
                 disable_response_storage: false,
                 user_instructions: None,
                 notify: None,
@@ -1219,7 +1391,22 @@ model_verbosity = "high"
                 preferred_auth_method: AuthMode::ChatGPT,
                 use_experimental_streamable_shell_tool: false,
                 include_view_image_tool: true,
+                include_delegate_tool: false,
                 disable_paste_burst: false,
+                hooks: None,
+                budgets: Budgets::default(),
+                checkpoints: false,
+                sessions: SessionRetention::default(),
+                model_prices: HashMap::new(),
+                exec_policy_files: Vec::new(),
+                explicit_settings: ExplicitSettings {
+                    model: true,
+                    model_reasoning_effort: true,
+                    model_reasoning_summary: true,
+                    approval_policy: true,
+                    sandbox_policy: false,
+                    cwd: true,
+                },
             },
             o3_profile_config
         );
@@ -1245,12 +1432,14 @@ model_verbosity = "high"
             model_family: find_family_for_model("gpt-3.5-turbo").expect("known model slug"),
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
+            model_auto_compact_token_limit: None,
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             exec_timeout_ms: DEFAULT_TIMEOUT_MS,
+            sandbox_limits: SandboxLimits::default(),
             disable_response_storage: false,
             user_instructions: None,
             notify: None,
@@ -1278,7 +1467,22 @@ model_verbosity = "high"
             preferred_auth_method: AuthMode::ChatGPT,
             use_experimental_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             disable_paste_burst: false,
+            hooks: None,
+            budgets: Budgets::default(),
+            checkpoints: false,
+            sessions: SessionRetention::default(),
+            model_prices: HashMap::new(),
+            exec_policy_files: Vec::new(),
+            explicit_settings: ExplicitSettings {
+                model: true,
+                model_reasoning_effort: false,
+                model_reasoning_summary: false,
+                approval_policy: true,
+                sandbox_policy: false,
+                cwd: true,
+            },
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -1323,12 +1527,14 @@ model_verbosity = "high"
             model_family: find_family_for_model("o3").expect("known model slug"),
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
+            model_auto_compact_token_limit: None,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             exec_timeout_ms: DEFAULT_TIMEOUT_MS,
+            sandbox_limits: SandboxLimits::default(),
             disable_response_storage: true,
             user_instructions: None,
             notify: None,
@@ -1356,7 +1562,22 @@ model_verbosity = "high"
             preferred_auth_method: AuthMode::ChatGPT,
             use_experimental_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             disable_paste_burst: false,
+            hooks: None,
+            budgets: Budgets::default(),
+            checkpoints: false,
+            sessions: SessionRetention::default(),
+            model_prices: HashMap::new(),
+            exec_policy_files: Vec::new(),
+            explicit_settings: ExplicitSettings {
+                model: true,
+                model_reasoning_effort: false,
+                model_reasoning_summary: false,
+                approval_policy: true,
+                sandbox_policy: false,
+                cwd: true,
+            },
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -1384,12 +1605,14 @@ model_verbosity = "high"
             // Upstream model metadata currently reports ~272k context for gpt‑5.
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
+            model_auto_compact_token_limit: None,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             exec_timeout_ms: DEFAULT_TIMEOUT_MS,
+            sandbox_limits: SandboxLimits::default(),
             disable_response_storage: false,
             user_instructions: None,
             notify: None,
@@ -1417,7 +1640,22 @@ model_verbosity = "high"
             preferred_auth_method: AuthMode::ChatGPT,
             use_experimental_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
             disable_paste_burst: false,
+            hooks: None,
+            budgets: Budgets::default(),
+            checkpoints: false,
+            sessions: SessionRetention::default(),
+            model_prices: HashMap::new(),
+            exec_policy_files: Vec::new(),
+            explicit_settings: ExplicitSettings {
+                model: true,
+                model_reasoning_effort: true,
+                model_reasoning_summary: true,
+                approval_policy: true,
+                sandbox_policy: false,
+                cwd: true,
+            },
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
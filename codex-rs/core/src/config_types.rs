@@ -78,10 +78,84 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Retention for rollouts under `~/.codex/sessions`. Unset fields disable
+/// the corresponding step; saved sessions are never touched.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub struct SessionRetention {
+    /// Delete rollouts not modified for this many days.
+    pub max_age_days: Option<u64>,
+
+    /// Delete the least recently modified rollouts while all rollouts
+    /// together take more than this many MiB.
+    pub max_total_size_mb: Option<u64>,
+
+    /// Gzip rollouts not modified for this many days.
+    pub compress_after_days: Option<u64>,
+}
+
+/// Price of a model in USD per million tokens, used by `codex stats` to
+/// estimate spend. Reasoning tokens are billed as output tokens.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+
+    /// Price of cached input tokens. Defaults to `input`.
+    pub cached_input: Option<f64>,
+
+    pub output: f64,
+}
+
 /// Collection of settings that are specific to the TUI.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Tui {}
 
+/// Resource limits for a turn or a session. Unset fields are unlimited.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub struct Budget {
+    /// Maximum number of tool calls.
+    pub max_tool_calls: Option<u64>,
+
+    /// Maximum number of tokens: the output of every model response plus the
+    /// input each request adds to the context window.
+    pub max_tokens: Option<u64>,
+
+    /// Maximum wall-clock time, in seconds.
+    pub max_duration_secs: Option<u64>,
+}
+
+/// Budgets enforced while running tasks. When a budget is exhausted the task
+/// is aborted with `TurnAbortReason::BudgetExceeded`.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub struct Budgets {
+    /// Limits for each user turn.
+    #[serde(default)]
+    pub turn: Budget,
+
+    /// Limits across every turn in the session.
+    #[serde(default)]
+    pub session: Budget,
+}
+
+/// Operating-system resource limits applied to every command Codex runs,
+/// sandboxed or not. Unset fields are unlimited.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+pub struct SandboxLimits {
+    /// Maximum CPU time, in seconds (`RLIMIT_CPU`).
+    pub cpu_time_secs: Option<u64>,
+
+    /// Maximum size of the address space, in MiB (`RLIMIT_AS`).
+    pub memory_mb: Option<u64>,
+
+    /// Maximum size of a file the command may write, in MiB
+    /// (`RLIMIT_FSIZE`).
+    pub max_file_size_mb: Option<u64>,
+
+    /// Maximum number of processes the command and everything it starts may
+    /// run at once (`pids.max` of a cgroup v2 subtree). Only enforced on Linux
+    /// when Codex runs in a cgroup delegated to the user.
+    pub max_processes: Option<u64>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
@@ -92,6 +166,20 @@ pub struct SandboxWorkspaceWrite {
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
     pub exclude_slash_tmp: bool,
+    /// Extra files and folders that sandboxed commands may not read.
+    #[serde(default)]
+    pub deny_read: Vec<PathBuf>,
+    /// When set to `true`, only `deny_read` is denied, not the built-in list
+    /// of credential locations.
+    #[serde(default)]
+    pub exclude_default_deny_read: bool,
+    /// Domains that commands may reach through the egress proxy when
+    /// `network_access` is `false`.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Run commands in isolated namespaces (Linux only).
+    #[serde(default)]
+    pub isolate_namespaces: bool,
 }
 
 impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettings {
@@ -18,12 +18,20 @@ use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::SessionConfiguredEvent;
 use crate::rollout::RolloutRecorder;
+use crate::rollout::SessionStateSnapshot;
 use codex_protocol::models::ResponseItem;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum InitialHistory {
     New,
-    Resumed(Vec<ResponseItem>),
+    Resumed(Box<ResumedHistory>),
+}
+
+/// Transcript and session state of a conversation that is being continued.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumedHistory {
+    pub items: Vec<ResponseItem>,
+    pub state: SessionStateSnapshot,
 }
 
 /// Represents a newly created Codex conversation, including the first event
@@ -60,6 +68,27 @@ impl ConversationManager {
             .await
     }
 
+    /// Spawn a child conversation on behalf of `parent`. The child's rollout
+    /// records `parent` so the two sessions can be linked later.
+    pub(crate) async fn new_delegate_conversation(
+        &self,
+        config: Config,
+        parent: ConversationId,
+    ) -> CodexResult<NewConversation> {
+        let CodexSpawnOk {
+            codex,
+            conversation_id,
+            ..
+        } = Codex::spawn_with_parent(
+            config,
+            self.auth_manager.clone(),
+            InitialHistory::New,
+            Some(parent),
+        )
+        .await?;
+        self.finalize_spawn(codex, conversation_id).await
+    }
+
     async fn spawn_conversation(
         &self,
         config: Config,
@@ -173,10 +202,14 @@ impl ConversationManager {
 }
 
 /// Return a prefix of `items` obtained by dropping the last `n` user messages
-/// and all items that follow them.
+/// and all items that follow them. The fork starts from a clean session
+/// state.
 fn truncate_after_dropping_last_messages(items: Vec<ResponseItem>, n: usize) -> InitialHistory {
     if n == 0 {
-        return InitialHistory::Resumed(items);
+        return InitialHistory::Resumed(Box::new(ResumedHistory {
+            items,
+            state: SessionStateSnapshot::default(),
+        }));
     }
 
     // Walk backwards counting only `user` Message items, find cut index.
@@ -198,7 +231,10 @@ fn truncate_after_dropping_last_messages(items: Vec<ResponseItem>, n: usize) ->
         // No prefix remains after dropping; start a new conversation.
         InitialHistory::New
     } else {
-        InitialHistory::Resumed(items.into_iter().take(cut_index).collect())
+        InitialHistory::Resumed(Box::new(ResumedHistory {
+            items: items.into_iter().take(cut_index).collect(),
+            state: SessionStateSnapshot::default(),
+        }))
     }
 }
 
@@ -256,7 +292,10 @@ mod tests {
         let truncated = truncate_after_dropping_last_messages(items.clone(), 1);
         assert_eq!(
             truncated,
-            InitialHistory::Resumed(vec![items[0].clone(), items[1].clone(), items[2].clone(),])
+            InitialHistory::Resumed(Box::new(ResumedHistory {
+                items: vec![items[0].clone(), items[1].clone(), items[2].clone()],
+                state: SessionStateSnapshot::default(),
+            }))
         );
 
         let truncated2 = truncate_after_dropping_last_messages(items, 2);
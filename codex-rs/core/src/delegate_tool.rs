@@ -0,0 +1,517 @@
+//! The `delegate` tool lets the model hand a self-contained sub-task to a
+//! child conversation. The child runs with the parent's cwd, sandbox and
+//! approval policy (or a narrower version of them), its events are streamed
+//! back to the parent wrapped in [`EventMsg::DelegateEvent`], and its last
+//! agent message becomes the output of the tool call.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+use serde::Deserialize;
+
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::codex_conversation::CodexConversation;
+use crate::config::default_workspace_write_policy;
+use crate::conversation_manager::ConversationManager;
+use crate::conversation_manager::NewConversation;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::AskForApproval;
+use crate::protocol::DelegateBeginEvent;
+use crate::protocol::DelegateEndEvent;
+use crate::protocol::DelegateEventEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::TaskCompleteEvent;
+use crate::rollout::DelegateRecord;
+
+pub(crate) const DELEGATE_TOOL_NAME: &str = "delegate";
+
+pub(crate) static DELEGATE_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "prompt".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Complete, self-contained instructions for the sub-agent. It does not see this conversation."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "cwd".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Working directory for the sub-agent. Must be inside the current working directory."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "sandbox_mode".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of: read-only, workspace-write, danger-full-access. May only narrow the current sandbox."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "approval_policy".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of: untrusted, on-request, on-failure, never. May only ask the user more often than the current policy."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: DELEGATE_TOOL_NAME.to_string(),
+        description: r#"Delegates a sub-task to a separate Codex agent and returns its final message.
+Use it for work that can be described completely up front, such as investigating a question or making an isolated change.
+The sub-agent starts with an empty conversation, so include every detail it needs in the prompt.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["prompt".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Deserialize)]
+struct DelegateToolArgs {
+    prompt: String,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    sandbox_mode: Option<SandboxMode>,
+    #[serde(default)]
+    approval_policy: Option<AskForApproval>,
+}
+
+/// Runs the sub-task described by `arguments` in a child conversation and
+/// returns the child's last agent message as the function call output.
+pub(crate) async fn handle_delegate(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<DelegateToolArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return function_call_output(
+                call_id,
+                format!("failed to parse function arguments: {e}"),
+                false,
+            );
+        }
+    };
+
+    let cwd = match narrow_cwd(&turn_context.cwd, args.cwd) {
+        Ok(cwd) => cwd,
+        Err(message) => return function_call_output(call_id, message, false),
+    };
+    let sandbox_policy = match narrow_sandbox_policy(
+        &turn_context.sandbox_policy,
+        args.sandbox_mode,
+        &sess.config.codex_home,
+    ) {
+        Ok(policy) => policy,
+        Err(message) => return function_call_output(call_id, message, false),
+    };
+    let approval_policy =
+        match narrow_approval_policy(turn_context.approval_policy, args.approval_policy) {
+            Ok(policy) => policy,
+            Err(message) => return function_call_output(call_id, message, false),
+        };
+
+    // The child inherits the parent's configuration, with the per-turn
+    // settings taken from the parent's current turn. Nested delegation is
+    // disabled so a sub-agent cannot fan out further.
+    let mut config = (*sess.config).clone();
+    config.model = turn_context.client.get_model();
+    config.model_family = turn_context.client.get_model_family();
+    config.model_reasoning_effort = turn_context.client.get_reasoning_effort();
+    config.model_reasoning_summary = turn_context.client.get_reasoning_summary();
+    config.cwd = cwd.clone();
+    config.sandbox_policy = sandbox_policy;
+    config.approval_policy = approval_policy;
+    config.include_delegate_tool = false;
+    config.experimental_resume = None;
+
+    let manager = ConversationManager::new(Arc::clone(&sess.auth_manager));
+    let NewConversation {
+        conversation_id,
+        conversation,
+        session_configured,
+    } = match manager
+        .new_delegate_conversation(config, ConversationId(sess.session_id))
+        .await
+    {
+        Ok(new_conversation) => new_conversation,
+        Err(e) => {
+            return function_call_output(call_id, format!("failed to start sub-agent: {e}"), false);
+        }
+    };
+
+    sess.record_delegate(DelegateRecord {
+        call_id: call_id.clone(),
+        conversation_id,
+        rollout_path: session_configured.rollout_path.clone(),
+    })
+    .await;
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::DelegateBegin(DelegateBeginEvent {
+            call_id: call_id.clone(),
+            conversation_id,
+            prompt: args.prompt.clone(),
+            cwd,
+            rollout_path: session_configured.rollout_path,
+        }),
+    })
+    .await;
+
+    // Shut the child down even if this future is dropped because the parent
+    // task was interrupted.
+    let guard = ChildGuard(Some(Arc::clone(&conversation)));
+    let outcome = drive_child(sess, &sub_id, &call_id, &conversation, args.prompt).await;
+    guard.shutdown().await;
+    manager.remove_conversation(conversation_id).await;
+
+    let (last_agent_message, success) = match outcome {
+        Ok(last_agent_message) => (last_agent_message, true),
+        Err(message) => (Some(message), false),
+    };
+    sess.send_event(Event {
+        id: sub_id,
+        msg: EventMsg::DelegateEnd(DelegateEndEvent {
+            call_id: call_id.clone(),
+            last_agent_message: last_agent_message.clone(),
+            success,
+        }),
+    })
+    .await;
+
+    let content = last_agent_message
+        .unwrap_or_else(|| "sub-agent finished without a final message".to_string());
+    function_call_output(call_id, content, success)
+}
+
+/// Submits `prompt` to the child and forwards its events until the task
+/// completes. Approval requests are re-issued from the parent session so the
+/// user answers them exactly as they would for the parent. Returns the last
+/// agent message, or a description of why the child did not finish.
+async fn drive_child(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    conversation: &CodexConversation,
+    prompt: String,
+) -> Result<Option<String>, String> {
+    conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: prompt }],
+        })
+        .await
+        .map_err(|e| format!("failed to submit prompt to sub-agent: {e}"))?;
+
+    let mut last_error = None;
+    loop {
+        let Event { id, msg } = conversation
+            .next_event()
+            .await
+            .map_err(|e| format!("sub-agent stopped unexpectedly: {e}"))?;
+        match msg {
+            EventMsg::ExecApprovalRequest(_) | EventMsg::ApplyPatchApprovalRequest(_) => {
+                let is_exec = matches!(msg, EventMsg::ExecApprovalRequest(_));
+                let rx = sess
+                    .request_delegated_approval(sub_id.to_string(), msg)
+                    .await;
+                let decision = rx.await.unwrap_or_default();
+                let op = if is_exec {
+                    Op::ExecApproval { id, decision }
+                } else {
+                    Op::PatchApproval { id, decision }
+                };
+                conversation
+                    .submit(op)
+                    .await
+                    .map_err(|e| format!("failed to forward approval to sub-agent: {e}"))?;
+            }
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                forward(
+                    sess,
+                    sub_id,
+                    call_id,
+                    EventMsg::TaskComplete(TaskCompleteEvent {
+                        last_agent_message: last_agent_message.clone(),
+                    }),
+                )
+                .await;
+                return match (last_agent_message, last_error) {
+                    (None, Some(error)) => Err(error),
+                    (last_agent_message, _) => Ok(last_agent_message),
+                };
+            }
+            EventMsg::TurnAborted(ev) => {
+                let reason = format!("sub-agent turn aborted: {:?}", ev.reason);
+                forward(sess, sub_id, call_id, EventMsg::TurnAborted(ev)).await;
+                return Err(reason);
+            }
+            EventMsg::ShutdownComplete => {
+                return Err("sub-agent shut down before completing its task".to_string());
+            }
+            EventMsg::Error(ev) => {
+                last_error = Some(ev.message.clone());
+                forward(sess, sub_id, call_id, EventMsg::Error(ev)).await;
+            }
+            msg => forward(sess, sub_id, call_id, msg).await,
+        }
+    }
+}
+
+async fn forward(sess: &Session, sub_id: &str, call_id: &str, msg: EventMsg) {
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::DelegateEvent(DelegateEventEvent {
+            call_id: call_id.to_string(),
+            msg: Box::new(msg),
+        }),
+    })
+    .await;
+}
+
+/// Owns the child conversation for the duration of a `delegate` call.
+struct ChildGuard(Option<Arc<CodexConversation>>);
+
+impl ChildGuard {
+    async fn shutdown(mut self) {
+        if let Some(conversation) = self.0.take() {
+            let _ = conversation.submit(Op::Shutdown).await;
+        }
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let Some(conversation) = self.0.take() {
+            tokio::spawn(async move {
+                let _ = conversation.submit(Op::Interrupt).await;
+                let _ = conversation.submit(Op::Shutdown).await;
+            });
+        }
+    }
+}
+
+/// Resolves the requested cwd against the parent's and rejects anything that
+/// escapes it. Both paths are canonicalized first so that neither `..` nor a
+/// symlink inside the parent's cwd can point the child elsewhere.
+fn narrow_cwd(parent: &Path, requested: Option<String>) -> Result<PathBuf, String> {
+    let Some(requested) = requested else {
+        return Ok(parent.to_path_buf());
+    };
+    let cwd = parent.join(requested);
+    let canonical_parent = parent
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve {}: {e}", parent.display()))?;
+    let canonical_cwd = cwd
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve sub-agent cwd {}: {e}", cwd.display()))?;
+    if !canonical_cwd.starts_with(&canonical_parent) {
+        return Err(format!(
+            "sub-agent cwd must be inside {}: {}",
+            parent.display(),
+            cwd.display()
+        ));
+    }
+    if !canonical_cwd.is_dir() {
+        return Err(format!(
+            "sub-agent cwd is not a directory: {}",
+            cwd.display()
+        ));
+    }
+    Ok(canonical_cwd)
+}
+
+/// Returns the sandbox policy for the child. The child may only be given a
+/// policy that is at least as restrictive as the parent's.
+fn narrow_sandbox_policy(
+    parent: &SandboxPolicy,
+    requested: Option<SandboxMode>,
+    codex_home: &Path,
+) -> Result<SandboxPolicy, String> {
+    match (requested, parent) {
+        (None, _) => Ok(parent.clone()),
+        (Some(SandboxMode::ReadOnly), _) => Ok(SandboxPolicy::ReadOnly),
+        (Some(SandboxMode::WorkspaceWrite), SandboxPolicy::WorkspaceWrite { .. }) => {
+            Ok(parent.clone())
+        }
+        (Some(SandboxMode::WorkspaceWrite), SandboxPolicy::DangerFullAccess) => {
+            Ok(default_workspace_write_policy(codex_home))
+        }
+        (Some(SandboxMode::DangerFullAccess), SandboxPolicy::DangerFullAccess) => {
+            Ok(SandboxPolicy::DangerFullAccess)
+        }
+        (Some(mode), _) => Err(format!(
+            "sub-agent sandbox `{mode}` is less restrictive than the current sandbox `{parent}`"
+        )),
+    }
+}
+
+/// Returns the approval policy for the child. The child may ask the user more
+/// often than the parent but never less. When the parent never asks, there may
+/// be nobody to answer, so the child must not ask either.
+fn narrow_approval_policy(
+    parent: AskForApproval,
+    requested: Option<AskForApproval>,
+) -> Result<AskForApproval, String> {
+    fn strictness(policy: AskForApproval) -> u8 {
+        match policy {
+            AskForApproval::Never => 0,
+            AskForApproval::OnFailure => 1,
+            AskForApproval::OnRequest => 2,
+            AskForApproval::UnlessTrusted => 3,
+        }
+    }
+
+    let Some(requested) = requested else {
+        return Ok(parent);
+    };
+    if parent == AskForApproval::Never && requested != AskForApproval::Never {
+        return Err("sub-agent cannot ask for approval when the current policy is `never`".into());
+    }
+    if strictness(requested) < strictness(parent) {
+        return Err(format!(
+            "sub-agent approval policy `{requested}` is less restrictive than the current policy `{parent}`"
+        ));
+    }
+    Ok(requested)
+}
+
+fn function_call_output(call_id: String, content: String, success: bool) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sandbox_defaults_to_parent() {
+        let parent = SandboxPolicy::new_workspace_write_policy();
+        assert_eq!(
+            Ok(parent.clone()),
+            narrow_sandbox_policy(&parent, None, Path::new("/codex-home"))
+        );
+    }
+
+    #[test]
+    fn sandbox_can_only_be_narrowed() {
+        let codex_home = Path::new("/codex-home");
+        let workspace_write = SandboxPolicy::new_workspace_write_policy();
+        assert_eq!(
+            Ok(SandboxPolicy::ReadOnly),
+            narrow_sandbox_policy(&workspace_write, Some(SandboxMode::ReadOnly), codex_home)
+        );
+        assert_eq!(
+            Ok(default_workspace_write_policy(codex_home)),
+            narrow_sandbox_policy(
+                &SandboxPolicy::DangerFullAccess,
+                Some(SandboxMode::WorkspaceWrite),
+                codex_home
+            )
+        );
+        assert!(
+            narrow_sandbox_policy(
+                &workspace_write,
+                Some(SandboxMode::DangerFullAccess),
+                codex_home
+            )
+            .is_err()
+        );
+        assert!(
+            narrow_sandbox_policy(
+                &SandboxPolicy::ReadOnly,
+                Some(SandboxMode::WorkspaceWrite),
+                codex_home
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn cwd_must_stay_inside_the_parent() {
+        let parent = tempfile::tempdir().unwrap();
+        let sub = parent.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        let canonical_sub = sub.canonicalize().unwrap();
+
+        assert_eq!(
+            Ok(canonical_sub.clone()),
+            narrow_cwd(parent.path(), Some("sub".to_string()))
+        );
+        assert_eq!(
+            Ok(canonical_sub),
+            narrow_cwd(parent.path(), Some("sub/../sub".to_string()))
+        );
+        assert!(narrow_cwd(parent.path(), Some("..".to_string())).is_err());
+        assert!(narrow_cwd(parent.path(), Some("missing".to_string())).is_err());
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), parent.path().join("link")).unwrap();
+            assert!(narrow_cwd(parent.path(), Some("link".to_string())).is_err());
+        }
+    }
+
+    #[test]
+    fn approval_can_only_be_narrowed() {
+        assert_eq!(
+            Ok(AskForApproval::UnlessTrusted),
+            narrow_approval_policy(
+                AskForApproval::OnRequest,
+                Some(AskForApproval::UnlessTrusted)
+            )
+        );
+        assert_eq!(
+            Ok(AskForApproval::OnFailure),
+            narrow_approval_policy(AskForApproval::OnFailure, None)
+        );
+        assert!(
+            narrow_approval_policy(
+                AskForApproval::UnlessTrusted,
+                Some(AskForApproval::OnRequest)
+            )
+            .is_err()
+        );
+        assert!(
+            narrow_approval_policy(AskForApproval::Never, Some(AskForApproval::OnRequest)).is_err()
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Local HTTP proxy that lets sandboxed commands reach an allow-list of
+//! domains.
+//!
+//! The proxy listens on a socket file that [`crate::exec_env::create_env`]
+//! hands to `codex-linux-sandbox`. The helper runs the command in a private
+//! network namespace that only has a loopback interface, forwards a port on it
+//! to the socket and advertises that port through the `HTTP_PROXY` family of
+//! variables, so any client that honors them works. `CONNECT host:port` requests
+//! are tunnelled and absolute-form `http://` requests are forwarded, in both
+//! cases only if the host is allowed. Hosts that were refused are remembered
+//! so they can be reported to the model.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::net::UnixListener;
+use tokio::net::UnixStream;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Requests whose head is larger than this are rejected.
+const MAX_REQUEST_HEAD_BYTES: usize = 64 * 1024;
+
+/// A running egress proxy. It stops when dropped.
+pub struct EgressProxy {
+    socket_path: PathBuf,
+    denied_hosts: Arc<Mutex<Vec<String>>>,
+    accept_task: JoinHandle<()>,
+    _socket_dir: TempDir,
+}
+
+impl EgressProxy {
+    /// Starts a proxy that allows `allowed_domains` and their subdomains.
+    /// Must be called from within a tokio runtime.
+    pub fn start(allowed_domains: Vec<String>) -> std::io::Result<Self> {
+        let socket_dir = tempfile::Builder::new().prefix("codex-egress-").tempdir()?;
+        let socket_path = socket_dir.path().join("proxy.sock");
+        let listener = UnixListener::bind(&socket_path)?;
+
+        let allowed_domains: Arc<[String]> = allowed_domains
+            .iter()
+            .map(|domain| normalize_host(domain))
+            .collect();
+        let denied_hosts = Arc::new(Mutex::new(Vec::new()));
+        let accept_task = tokio::spawn({
+            let denied_hosts = Arc::clone(&denied_hosts);
+            async move {
+                loop {
+                    let client = match listener.accept().await {
+                        Ok((client, _)) => client,
+                        Err(e) => {
+                            debug!("egress proxy failed to accept a connection: {e}");
+                            continue;
+                        }
+                    };
+                    let allowed_domains = Arc::clone(&allowed_domains);
+                    let denied_hosts = Arc::clone(&denied_hosts);
+                    tokio::spawn(async move {
+                        if let Err(e) =
+                            handle_connection(client, &allowed_domains, &denied_hosts).await
+                        {
+                            debug!("egress proxy connection failed: {e}");
+                        }
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            socket_path,
+            denied_hosts,
+            accept_task,
+            _socket_dir: socket_dir,
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Returns the hosts refused since the last call, in the order they were
+    /// first requested.
+    pub fn take_denied_hosts(&self) -> Vec<String> {
+        self.denied_hosts
+            .lock()
+            .map(|mut hosts| std::mem::take(&mut *hosts))
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for EgressProxy {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn handle_connection(
+    mut client: UnixStream,
+    allowed_domains: &[String],
+    denied_hosts: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let head_len = loop {
+        if let Some(pos) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_REQUEST_HEAD_BYTES {
+            return respond(&mut client, "431 Request Header Fields Too Large", "").await;
+        }
+        let mut chunk = [0u8; 4096];
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_len]).into_owned();
+    let body_prefix = &buf[head_len..];
+
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return respond(&mut client, "400 Bad Request", "malformed request line\n").await;
+    };
+
+    let is_connect = method.eq_ignore_ascii_case("CONNECT");
+    let (authority, path) = if is_connect {
+        (target, "")
+    } else if let Some(rest) = target.strip_prefix("http://") {
+        rest.find('/')
+            .map_or((rest, "/"), |slash| (&rest[..slash], &rest[slash..]))
+    } else {
+        return respond(
+            &mut client,
+            "400 Bad Request",
+            "only CONNECT and absolute http:// requests are supported\n",
+        )
+        .await;
+    };
+    let Some((host, port)) = split_host_port(authority, if is_connect { 443 } else { 80 }) else {
+        return respond(&mut client, "400 Bad Request", "malformed host\n").await;
+    };
+
+    if !is_host_allowed(allowed_domains, &host) {
+        if let Ok(mut hosts) = denied_hosts.lock()
+            && !hosts.contains(&host)
+        {
+            hosts.push(host.clone());
+        }
+        let body = format!("codex sandbox: {host} is not in allowed_domains\n");
+        return respond(&mut client, "403 Forbidden", &body).await;
+    }
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let body = format!("codex sandbox: failed to connect to {host}:{port}: {e}\n");
+            return respond(&mut client, "502 Bad Gateway", &body).await;
+        }
+    };
+
+    if is_connect {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+    } else {
+        // Forward the request in origin form. Each client connection is
+        // pinned to one upstream, so ask both sides not to reuse it.
+        let mut forwarded = format!("{method} {path} {version}\r\n");
+        for header in lines.filter(|line| !line.is_empty()) {
+            let name = header.split(':').next().unwrap_or_default().trim();
+            if name.eq_ignore_ascii_case("connection")
+                || name.to_ascii_lowercase().starts_with("proxy-")
+            {
+                continue;
+            }
+            forwarded.push_str(header);
+            forwarded.push_str("\r\n");
+        }
+        forwarded.push_str("Connection: close\r\n\r\n");
+        upstream.write_all(forwarded.as_bytes()).await?;
+    }
+    upstream.write_all(body_prefix).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+async fn respond(client: &mut UnixStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+/// Splits `host[:port]` or `[ipv6][:port]`, using `default_port` if none is
+/// given. The host is normalized with [`normalize_host`].
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        match rest.strip_prefix(':') {
+            Some(port) => (host, port.parse().ok()?),
+            None if rest.is_empty() => (host, default_port),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((normalize_host(host), port))
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// A host is allowed if it equals an allowed domain or is a subdomain of one.
+fn is_host_allowed(allowed_domains: &[String], host: &str) -> bool {
+    allowed_domains.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tokio::net::TcpListener;
+
+    async fn send(proxy: &EgressProxy, request: &str) -> String {
+        let mut stream = UnixStream::connect(proxy.socket_path()).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn allows_listed_domains_and_their_subdomains() {
+        let allowed = vec!["crates.io".to_string(), "registry.npmjs.org".to_string()];
+        assert!(is_host_allowed(&allowed, "crates.io"));
+        assert!(is_host_allowed(&allowed, "static.crates.io"));
+        assert!(is_host_allowed(&allowed, "registry.npmjs.org"));
+        assert!(!is_host_allowed(&allowed, "evilcrates.io"));
+        assert!(!is_host_allowed(&allowed, "npmjs.org"));
+        assert_eq!(
+            split_host_port("Static.Crates.IO.:8443", 443),
+            Some(("static.crates.io".to_string(), 8443))
+        );
+        assert_eq!(split_host_port("[::1]", 80), Some(("::1".to_string(), 80)));
+    }
+
+    #[tokio::test]
+    async fn denied_hosts_are_refused_and_recorded() {
+        let proxy = EgressProxy::start(vec!["crates.io".to_string()]).unwrap();
+        let response = send(
+            &proxy,
+            "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"), "{response}");
+        assert_eq!(proxy.take_denied_hosts(), vec!["example.com".to_string()]);
+        assert_eq!(proxy.take_denied_hosts(), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn allowed_http_requests_are_forwarded() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = upstream.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = upstream.accept().await.unwrap();
+            let mut request = vec![0u8; 1024];
+            let n = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        let proxy = EgressProxy::start(vec!["127.0.0.1".to_string()]).unwrap();
+        let response = send(
+            &proxy,
+            &format!(
+                "GET http://127.0.0.1:{port}/index.json HTTP/1.1\r\nHost: 127.0.0.1\r\nProxy-Connection: keep-alive\r\n\r\n"
+            ),
+        )
+        .await;
+        assert!(response.ends_with("\r\n\r\nok"), "{response}");
+        assert_eq!(
+            server.await.unwrap(),
+            "GET /index.json HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+        );
+        assert_eq!(proxy.take_denied_hosts(), Vec::<String>::new());
+    }
+}
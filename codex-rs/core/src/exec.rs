@@ -14,6 +14,7 @@ use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
 
+use crate::config_types::SandboxLimits;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
@@ -31,9 +32,14 @@ pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
 
 // Hardcode these since it does not seem worth including the libc crate just
 // for these.
+const SIGABRT_CODE: i32 = 6;
 const SIGKILL_CODE: i32 = 9;
+const SIGSEGV_CODE: i32 = 11;
+const SIGXCPU_CODE: i32 = 24;
+const SIGXFSZ_CODE: i32 = 25;
 const TIMEOUT_CODE: i32 = 64;
 const EXIT_CODE_SIGNAL_BASE: i32 = 128; // conventional shell: 128 + signal
+const ENOMEM_CODE: i32 = 12;
 
 // I/O buffer sizing
 const READ_CHUNK_SIZE: usize = 8192; // bytes per read
@@ -51,6 +57,7 @@ pub struct ExecParams {
     pub env: HashMap<String, String>,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    pub resource_limits: SandboxLimits,
 }
 
 impl ExecParams {
@@ -70,6 +77,24 @@ pub enum SandboxType {
     LinuxSeccomp,
 }
 
+/// A resource limit that a command was killed for exceeding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceLimit {
+    CpuTime,
+    Memory,
+    FileSize,
+}
+
+impl std::fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceLimit::CpuTime => f.write_str("cpu time"),
+            ResourceLimit::Memory => f.write_str("memory"),
+            ResourceLimit::FileSize => f.write_str("file size"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct StdoutStream {
     pub sub_id: String,
@@ -85,6 +110,7 @@ pub async fn process_exec_tool_call(
     stdout_stream: Option<StdoutStream>,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
+    let resource_limits = params.resource_limits;
 
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
     {
@@ -92,11 +118,16 @@ pub async fn process_exec_tool_call(
         SandboxType::MacosSeatbelt => {
             let timeout = params.timeout_duration();
             let ExecParams {
-                command, cwd, env, ..
+                command,
+                cwd,
+                env,
+                resource_limits,
+                ..
             } = params;
             let child = spawn_command_under_seatbelt(
                 command,
                 sandbox_policy,
+                &resource_limits,
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
@@ -107,7 +138,11 @@ pub async fn process_exec_tool_call(
         SandboxType::LinuxSeccomp => {
             let timeout = params.timeout_duration();
             let ExecParams {
-                command, cwd, env, ..
+                command,
+                cwd,
+                env,
+                resource_limits,
+                ..
             } = params;
 
             let codex_linux_sandbox_exe = codex_linux_sandbox_exe
@@ -117,6 +152,7 @@ pub async fn process_exec_tool_call(
                 codex_linux_sandbox_exe,
                 command,
                 sandbox_policy,
+                &resource_limits,
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
@@ -130,10 +166,47 @@ pub async fn process_exec_tool_call(
     match raw_output_result {
         Ok(raw_output) => {
             let stdout = raw_output.stdout.from_utf8_lossy();
-            let stderr = raw_output.stderr.from_utf8_lossy();
+            let mut stderr = raw_output.stderr.from_utf8_lossy();
+            let mut aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
 
             #[cfg(target_family = "unix")]
-            match raw_output.exit_status.signal() {
+            let signal = raw_output.exit_status.signal();
+            #[cfg(not(target_family = "unix"))]
+            let signal: Option<i32> = None;
+
+            // A shell that runs the command as a child reports the signal in
+            // its exit code instead of dying from it.
+            let exit_code = match signal {
+                Some(signal) => EXIT_CODE_SIGNAL_BASE + signal,
+                None => raw_output.exit_status.code().unwrap_or(-1),
+            };
+            let killed_by = exit_code
+                .checked_sub(EXIT_CODE_SIGNAL_BASE)
+                .filter(|signal| *signal > 0);
+            let resource_limit_exceeded = if exit_code == 0 {
+                None
+            } else {
+                classify_resource_limit(&resource_limits, exit_code, killed_by, &stderr.text)
+            };
+
+            if let Some(limit) = resource_limit_exceeded {
+                let note = match killed_by {
+                    Some(_) => format!("\nkilled: {limit} limit exceeded\n"),
+                    None => format!("\n{limit} limit exceeded\n"),
+                };
+                stderr.text.push_str(&note);
+                aggregated_output.text.push_str(&note);
+                return Ok(ExecToolCallOutput {
+                    exit_code,
+                    stdout,
+                    stderr,
+                    aggregated_output,
+                    duration,
+                    resource_limit_exceeded,
+                });
+            }
+
+            match signal {
                 Some(TIMEOUT_CODE) => return Err(CodexErr::Sandbox(SandboxErr::Timeout)),
                 Some(signal) => {
                     return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
@@ -141,8 +214,6 @@ pub async fn process_exec_tool_call(
                 None => {}
             }
 
-            let exit_code = raw_output.exit_status.code().unwrap_or(-1);
-
             if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
                 return Err(CodexErr::Sandbox(SandboxErr::Denied(
                     exit_code,
@@ -155,8 +226,9 @@ pub async fn process_exec_tool_call(
                 exit_code,
                 stdout,
                 stderr,
-                aggregated_output: raw_output.aggregated_output.from_utf8_lossy(),
+                aggregated_output,
                 duration,
+                resource_limit_exceeded: None,
             })
         }
         Err(err) => {
@@ -166,6 +238,49 @@ pub async fn process_exec_tool_call(
     }
 }
 
+/// Attributes a failed command to one of the configured `limits`.
+///
+/// Exceeding `RLIMIT_CPU` and `RLIMIT_FSIZE` raises a dedicated signal.
+/// Exceeding `RLIMIT_AS` only makes allocations fail with `ENOMEM`, and
+/// neither a crash nor an error message alone says why, so the memory limit
+/// is only blamed when the command reported an allocation failure on stderr
+/// and then aborted, crashed or exited with `ENOMEM` as its status.
+fn classify_resource_limit(
+    limits: &SandboxLimits,
+    exit_code: i32,
+    signal: Option<i32>,
+    stderr: &str,
+) -> Option<ResourceLimit> {
+    match signal {
+        Some(SIGXCPU_CODE) if limits.cpu_time_secs.is_some() => Some(ResourceLimit::CpuTime),
+        Some(SIGXFSZ_CODE) if limits.max_file_size_mb.is_some() => Some(ResourceLimit::FileSize),
+        Some(SIGABRT_CODE) | Some(SIGSEGV_CODE) | None
+            if limits.memory_mb.is_some()
+                && (signal.is_some() || exit_code == ENOMEM_CODE)
+                && reports_allocation_failure(stderr) =>
+        {
+            Some(ResourceLimit::Memory)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `stderr` contains one of the messages common runtimes print when
+/// an allocation fails.
+fn reports_allocation_failure(stderr: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "cannot allocate memory", // strerror(ENOMEM)
+        "out of memory",
+        "memory exhausted",
+        "memoryerror",
+        "bad_alloc",
+        "memory allocation of",
+        "allocation failed",
+    ];
+    let stderr = stderr.to_ascii_lowercase();
+    MARKERS.iter().any(|marker| stderr.contains(marker))
+}
+
 /// We don't have a fully deterministic way to tell if our command failed
 /// because of the sandbox - a command in the user's zshrc file might hit an
 /// error, but the command itself might fail or succeed for other reasons.
@@ -229,6 +344,9 @@ pub struct ExecToolCallOutput {
     pub stderr: StreamOutput<String>,
     pub aggregated_output: StreamOutput<String>,
     pub duration: Duration,
+    /// Set when the command was killed for exceeding one of the
+    /// [`SandboxLimits`].
+    pub resource_limit_exceeded: Option<ResourceLimit>,
 }
 
 async fn exec(
@@ -238,7 +356,11 @@ async fn exec(
 ) -> Result<RawExecToolCallOutput> {
     let timeout = params.timeout_duration();
     let ExecParams {
-        command, cwd, env, ..
+        command,
+        cwd,
+        env,
+        resource_limits,
+        ..
     } = params;
 
     let (program, args) = command.split_first().ok_or_else(|| {
@@ -254,6 +376,7 @@ async fn exec(
         arg0,
         cwd,
         sandbox_policy,
+        &resource_limits,
         StdioPolicy::RedirectForShellTool,
         env,
     )
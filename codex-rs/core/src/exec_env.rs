@@ -3,6 +3,29 @@ use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyInherit;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
+
+/// Tells the Linux sandbox helper where the egress proxy listens. The helper
+/// forwards a loopback port of the command's private network to it and points
+/// [`PROXY_ENV_VARS`] at that port.
+pub const CODEX_EGRESS_PROXY_SOCKET_ENV_VAR: &str = "CODEX_EGRESS_PROXY_SOCKET";
+
+/// Printed by the Linux sandbox helper when it cannot connect the command to
+/// the egress proxy (for instance because unprivileged user namespaces are
+/// disabled). The helper then exits without running the command, which Codex
+/// reports as a sandbox denial.
+pub const EGRESS_PROXY_UNAVAILABLE_MESSAGE: &str =
+    "codex-linux-sandbox: network access to allowed_domains is unavailable";
+
+/// Variables pointed at the egress proxy, in both spellings tools look for.
+pub const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
 
 /// Construct an environment map based on the rules in the specified policy. The
 /// resulting map can be passed directly to `Command::envs()` after calling
@@ -10,9 +33,21 @@ use std::collections::HashSet;
 /// process.
 ///
 /// The derivation follows the algorithm documented in the struct-level comment
-/// for [`ShellEnvironmentPolicy`].
-pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
-    populate_env(std::env::vars(), policy)
+/// for [`ShellEnvironmentPolicy`]. When `egress_proxy` is the socket of the
+/// sandbox's egress proxy, it is passed on to the sandbox helper in
+/// [`CODEX_EGRESS_PROXY_SOCKET_ENV_VAR`].
+pub fn create_env(
+    policy: &ShellEnvironmentPolicy,
+    egress_proxy: Option<&Path>,
+) -> HashMap<String, String> {
+    let mut env_map = populate_env(std::env::vars(), policy);
+    if let Some(socket) = egress_proxy {
+        env_map.insert(
+            CODEX_EGRESS_PROXY_SOCKET_ENV_VAR.to_string(),
+            socket.to_string_lossy().into_owned(),
+        );
+    }
+    env_map
 }
 
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
@@ -191,4 +226,19 @@ mod tests {
         };
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_egress_proxy_socket_is_passed_on() {
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::None,
+            ..Default::default()
+        };
+
+        let result = create_env(&policy, Some(Path::new("/tmp/codex-egress/proxy.sock")));
+        let expected: HashMap<String, String> = hashmap! {
+            CODEX_EGRESS_PROXY_SOCKET_ENV_VAR.to_string() => "/tmp/codex-egress/proxy.sock".to_string(),
+        };
+        assert_eq!(result, expected);
+        assert_eq!(create_env(&policy, None), HashMap::new());
+    }
 }
@@ -0,0 +1,539 @@
+//! User-supplied execpolicy files that auto-approve or forbid commands.
+//!
+//! Policies are `*.policy` files written in the Starlark dialect understood by
+//! [`codex_execpolicy::PolicyParser`]. They are read from `CODEX_HOME` and,
+//! for trusted projects, from the project root.
+//!
+//! A `bash -lc` script is split into its simple commands, each of which is
+//! checked on its own.
+
+use std::ffi::OsString;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::ExecvChecker;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::ValidExec;
+use tracing::debug;
+
+use crate::bash::SUBSTITUTED_WORD;
+use crate::bash::ShellScript;
+use crate::bash::try_parse_bash;
+use crate::bash::try_parse_shell_script;
+use crate::protocol::SandboxPolicy;
+
+const POLICY_FILE_EXTENSION: &str = "policy";
+
+/// Redirection targets that are always safe to write to.
+const DEVICE_FILES: &[&str] = &["/dev/null", "/dev/stdout", "/dev/stderr"];
+
+/// Result of checking a command against the loaded policies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyVerdict {
+    /// A policy matched the command and every file it reads or writes is
+    /// accessible under the sandbox policy.
+    Allowed,
+    /// A policy forbids the command.
+    Forbidden { reason: String },
+    /// No policy vouches for the command.
+    Unmatched,
+}
+
+/// The set of policies loaded for a session.
+#[derive(Default)]
+pub struct ExecPolicy {
+    checkers: Vec<ExecvChecker>,
+    allow_subshells: bool,
+    allow_command_substitution: bool,
+}
+
+impl ExecPolicy {
+    /// Parses every file in `paths`. Files that cannot be read or parsed are
+    /// skipped and reported in the returned list of errors.
+    pub fn load(paths: &[PathBuf]) -> (Self, Vec<String>) {
+        let mut exec_policy = Self::default();
+        let mut errors = Vec::new();
+        for path in paths {
+            let source = path.to_string_lossy();
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|unparsed| {
+                    PolicyParser::new(&source, &unparsed)
+                        .parse()
+                        .map_err(|e| e.to_string())
+                });
+            match parsed {
+                Ok(policy) => {
+                    exec_policy.allow_subshells |= policy.allows_subshells();
+                    exec_policy.allow_command_substitution |= policy.allows_command_substitution();
+                    exec_policy.checkers.push(ExecvChecker::new(policy));
+                }
+                Err(e) => errors.push(format!("failed to load exec policy {source}: {e}")),
+            }
+        }
+        (exec_policy, errors)
+    }
+
+    /// Checks `command` against every policy. A command forbidden by any
+    /// policy is forbidden; otherwise it is allowed if any policy matches it
+    /// and the files it names fall within what `sandbox_policy` permits.
+    ///
+    /// For `bash -lc <script>`, the verdict combines those of the simple
+    /// commands in the script: any forbidden command forbids the script, and
+    /// it is allowed only if every command is allowed.
+    pub fn check(
+        &self,
+        command: &[String],
+        cwd: &Path,
+        sandbox_policy: &SandboxPolicy,
+    ) -> PolicyVerdict {
+        let verdict = self.check_exec(command, cwd, sandbox_policy);
+        if matches!(verdict, PolicyVerdict::Forbidden { .. }) {
+            return verdict;
+        }
+        match parse_bash_lc_script(command) {
+            Some(script) => self.check_script(&script, cwd, sandbox_policy),
+            None => verdict,
+        }
+    }
+
+    /// A script is allowed only if every command in it is allowed, it writes
+    /// only to files in writable roots, and it uses subshells or command
+    /// substitution only if a policy permits them. Scripts that change
+    /// directory are never allowed because later relative paths would be
+    /// resolved against the wrong directory.
+    fn check_script(
+        &self,
+        script: &ShellScript,
+        cwd: &Path,
+        sandbox_policy: &SandboxPolicy,
+    ) -> PolicyVerdict {
+        let verdicts: Vec<PolicyVerdict> = script
+            .commands
+            .iter()
+            .map(|command| self.check_exec(command, cwd, sandbox_policy))
+            .collect();
+        if let Some(forbidden) = verdicts
+            .iter()
+            .find(|verdict| matches!(verdict, PolicyVerdict::Forbidden { .. }))
+        {
+            return forbidden.clone();
+        }
+
+        let allowed = verdicts
+            .iter()
+            .all(|verdict| *verdict == PolicyVerdict::Allowed)
+            && (!script.has_subshell || self.allow_subshells)
+            && (!script.has_command_substitution || self.allow_command_substitution)
+            && !script.commands.iter().any(|command| {
+                command
+                    .first()
+                    .is_some_and(|program| matches!(program.as_str(), "cd" | "pushd" | "popd"))
+            })
+            && script.write_redirects.iter().all(|target| {
+                DEVICE_FILES.contains(&target.as_str())
+                    || is_path_writable(target, cwd, sandbox_policy)
+            });
+        if allowed {
+            PolicyVerdict::Allowed
+        } else {
+            PolicyVerdict::Unmatched
+        }
+    }
+
+    fn check_exec(
+        &self,
+        command: &[String],
+        cwd: &Path,
+        sandbox_policy: &SandboxPolicy,
+    ) -> PolicyVerdict {
+        let Some((program, args)) = command.split_first() else {
+            return PolicyVerdict::Unmatched;
+        };
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+
+        let mut matches = Vec::new();
+        for checker in &self.checkers {
+            match checker.r#match(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return PolicyVerdict::Forbidden { reason };
+                }
+                Ok(MatchedExec::Match { exec }) => matches.push((checker, exec)),
+                Err(e) => debug!("exec policy did not match {program}: {e:?}"),
+            }
+        }
+
+        let writable_folders: Vec<PathBuf> = if sandbox_policy.has_full_disk_write_access() {
+            vec![PathBuf::from("/")]
+        } else {
+            sandbox_policy
+                .get_writable_roots_with_cwd(cwd)
+                .into_iter()
+                .map(|root| root.root)
+                .collect()
+        };
+        let readable_folders = vec![PathBuf::from("/")];
+        let checker_cwd = Some(OsString::from(cwd.as_os_str()));
+
+        let allowed = matches.into_iter().any(|(checker, exec)| {
+            let writes = writable_files(&exec);
+            // The output of a command substitution is unknown, so it may only
+            // be used where the policy does not expect a file.
+            substitutions_are_opaque(&exec)
+                && checker
+                    .check(exec, &checker_cwd, &readable_folders, &writable_folders)
+                    .is_ok()
+                // ExecvChecker only knows about whole folders; also honour the
+                // read-only subpaths (such as `.git`) inside writable roots.
+                && writes
+                    .iter()
+                    .all(|file| is_path_writable(file, cwd, sandbox_policy))
+        });
+        if allowed {
+            PolicyVerdict::Allowed
+        } else {
+            PolicyVerdict::Unmatched
+        }
+    }
+}
+
+/// Lists the policy files that apply to a session: `*.policy` files directly
+/// inside `codex_home`, followed by those in `project_root` if given. Files
+/// in each directory are sorted by name.
+pub fn discover_policy_files(codex_home: &Path, project_root: Option<&Path>) -> Vec<PathBuf> {
+    std::iter::once(codex_home)
+        .chain(project_root)
+        .flat_map(policy_files_in)
+        .collect()
+}
+
+fn policy_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == POLICY_FILE_EXTENSION)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse_bash_lc_script(command: &[String]) -> Option<ShellScript> {
+    let [bash, flag, script] = command else {
+        return None;
+    };
+    if bash != "bash" || !matches!(flag.as_str(), "-lc" | "-c") {
+        return None;
+    }
+    let tree = try_parse_bash(script)?;
+    try_parse_shell_script(&tree, script)
+}
+
+fn is_path_writable(file: &str, cwd: &Path, sandbox_policy: &SandboxPolicy) -> bool {
+    sandbox_policy.has_full_disk_write_access()
+        || sandbox_policy
+            .get_writable_roots_with_cwd(cwd)
+            .iter()
+            .any(|root| root.is_path_writable(&normalize(&cwd.join(file))))
+}
+
+fn substitutions_are_opaque(exec: &ValidExec) -> bool {
+    exec.args
+        .iter()
+        .map(|arg| (&arg.r#type, &arg.value))
+        .chain(exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)))
+        .all(|(arg_type, value)| {
+            !value.contains(SUBSTITUTED_WORD) || matches!(arg_type, ArgType::OpaqueNonFile)
+        })
+}
+
+fn writable_files(exec: &ValidExec) -> Vec<String> {
+    exec.args
+        .iter()
+        .map(|arg| (&arg.r#type, &arg.value))
+        .chain(exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)))
+        .filter(|(arg_type, _)| matches!(arg_type, ArgType::WriteableFile))
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+/// Resolves `.` and `..` components lexically so that prefix checks cannot be
+/// sidestepped with paths such as `src/../.git/config`.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const POLICY: &str = r#"
+define_program(
+    program="cp",
+    options=[
+        flag("-r"),
+    ],
+    args=[ARG_RFILES, ARG_WFILE],
+)
+
+define_program(
+    program="rg",
+    args=[ARG_OPAQUE_VALUE, ARG_RFILES_OR_CWD],
+)
+
+define_program(
+    program="head",
+    options=[
+        opt("-n", ARG_POS_INT),
+    ],
+    args=[ARG_RFILES_OR_CWD],
+)
+
+forbid_program_regex(
+    regex="^shutdown$",
+    reason="shutting down the machine is not allowed",
+)
+"#;
+
+    fn load_policy(dir: &TempDir) -> ExecPolicy {
+        let path = dir.path().join("team.policy");
+        std::fs::write(&path, POLICY).unwrap();
+        let (policy, errors) = ExecPolicy::load(&[path]);
+        assert_eq!(errors, Vec::<String>::new());
+        policy
+    }
+
+    fn workspace_write() -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
+        }
+    }
+
+    fn cmd(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn matched_command_writing_inside_workspace_is_allowed() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            policy.check(&cmd(&["cp", "a.txt", "b.txt"]), &cwd, &workspace_write()),
+            PolicyVerdict::Allowed
+        );
+    }
+
+    #[test]
+    fn writes_outside_writable_roots_are_not_allowed() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            policy.check(
+                &cmd(&["cp", "a.txt", "/etc/passwd"]),
+                &cwd,
+                &workspace_write()
+            ),
+            PolicyVerdict::Unmatched
+        );
+        assert_eq!(
+            policy.check(
+                &cmd(&["cp", "a.txt", "b.txt"]),
+                &cwd,
+                &SandboxPolicy::ReadOnly
+            ),
+            PolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn writes_into_read_only_subpaths_are_not_allowed() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(cwd.join(".git")).unwrap();
+        assert_eq!(
+            policy.check(
+                &cmd(&["cp", "hook.sh", ".git/hooks/pre-commit"]),
+                &cwd,
+                &workspace_write()
+            ),
+            PolicyVerdict::Unmatched
+        );
+        assert_eq!(
+            policy.check(
+                &cmd(&["cp", "hook.sh", "src/../.git/config"]),
+                &cwd,
+                &workspace_write()
+            ),
+            PolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn forbidden_program_is_rejected_with_reason() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        assert_eq!(
+            policy.check(&cmd(&["shutdown"]), dir.path(), &workspace_write()),
+            PolicyVerdict::Forbidden {
+                reason: "shutting down the machine is not allowed".to_string()
+            }
+        );
+    }
+
+    fn bash_lc(script: &str) -> Vec<String> {
+        cmd(&["bash", "-lc", script])
+    }
+
+    #[test]
+    fn script_is_allowed_when_every_command_is_allowed() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            policy.check(
+                &bash_lc("rg foo | head -n 20 && cp a.txt b.txt > out.log 2>/dev/null"),
+                &cwd,
+                &workspace_write()
+            ),
+            PolicyVerdict::Allowed
+        );
+        assert_eq!(
+            policy.check(&bash_lc("rg foo; git push"), &cwd, &workspace_write()),
+            PolicyVerdict::Unmatched
+        );
+        assert_eq!(
+            policy.check(&bash_lc("rg foo || shutdown"), &cwd, &workspace_write()),
+            PolicyVerdict::Forbidden {
+                reason: "shutting down the machine is not allowed".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn script_redirecting_outside_writable_roots_is_not_allowed() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        for script in ["rg foo > /etc/motd", "rg foo 2>> ../out.txt"] {
+            assert_eq!(
+                policy.check(&bash_lc(script), &cwd, &workspace_write()),
+                PolicyVerdict::Unmatched,
+                "{script}"
+            );
+        }
+        assert_eq!(
+            policy.check(
+                &bash_lc("rg foo &> out.txt"),
+                &cwd,
+                &SandboxPolicy::ReadOnly
+            ),
+            PolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn subshells_and_substitutions_require_opt_in() {
+        let dir = TempDir::new().unwrap();
+        let policy = load_policy(&dir);
+        let cwd = dir.path().canonicalize().unwrap();
+        let subshell = bash_lc("(rg foo) | head -n 1");
+        let substitution = bash_lc("rg \"$(head -n 1 pattern.txt)\" src");
+        assert_eq!(
+            policy.check(&subshell, &cwd, &workspace_write()),
+            PolicyVerdict::Unmatched
+        );
+        assert_eq!(
+            policy.check(&substitution, &cwd, &workspace_write()),
+            PolicyVerdict::Unmatched
+        );
+
+        let opt_in = dir.path().join("shell.policy");
+        std::fs::write(
+            &opt_in,
+            "allow_shell_constructs(subshells=True, command_substitution=True)",
+        )
+        .unwrap();
+        let (policy, errors) = ExecPolicy::load(&[dir.path().join("team.policy"), opt_in]);
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(
+            policy.check(&subshell, &cwd, &workspace_write()),
+            PolicyVerdict::Allowed
+        );
+        assert_eq!(
+            policy.check(&substitution, &cwd, &workspace_write()),
+            PolicyVerdict::Allowed
+        );
+        // The substituted value is unknown, so it cannot stand in for a file.
+        assert_eq!(
+            policy.check(
+                &bash_lc("cp a.txt \"$(head -n 1 target.txt)\""),
+                &cwd,
+                &workspace_write()
+            ),
+            PolicyVerdict::Unmatched
+        );
+    }
+
+    #[test]
+    fn discovers_policy_files_in_codex_home_then_project_root() {
+        let codex_home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        for name in ["b.policy", "a.policy", "notes.txt"] {
+            std::fs::write(codex_home.path().join(name), "").unwrap();
+        }
+        std::fs::write(project.path().join("repo.policy"), "").unwrap();
+
+        assert_eq!(
+            discover_policy_files(codex_home.path(), Some(project.path())),
+            vec![
+                codex_home.path().join("a.policy"),
+                codex_home.path().join("b.policy"),
+                project.path().join("repo.policy"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unparseable_policy_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.policy");
+        std::fs::write(&path, "define_program(").unwrap();
+        let (policy, errors) = ExecPolicy::load(&[path]);
+        assert!(policy.checkers.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+}
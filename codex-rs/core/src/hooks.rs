@@ -1,4 +1,11 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::protocol::FileChange;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HooksConfig {
@@ -6,6 +13,10 @@ pub struct HooksConfig {
     pub session_end: Option<HookSpec>,
     pub pre_command: Option<HookSpec>,
     pub post_command: Option<HookSpec>,
+    pub pre_apply_patch: Option<HookSpec>,
+    pub post_apply_patch: Option<HookSpec>,
+    pub user_prompt_submit: Option<HookSpec>,
+    pub turn_complete: Option<HookSpec>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -14,6 +25,10 @@ pub struct HooksToml {
     pub session_end: Option<HookToml>,
     pub pre_command: Option<HookToml>,
     pub post_command: Option<HookToml>,
+    pub pre_apply_patch: Option<HookToml>,
+    pub post_apply_patch: Option<HookToml>,
+    pub user_prompt_submit: Option<HookToml>,
+    pub turn_complete: Option<HookToml>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -72,10 +87,222 @@ impl HooksConfig {
         };
 
         Ok(Self {
-            session_start: t.session_start.as_ref().map(&map).transpose()?,
-            session_end: t.session_end.as_ref().map(&map).transpose()?,
-            pre_command: t.pre_command.as_ref().map(&map).transpose()?,
-            post_command: t.post_command.as_ref().map(&map).transpose()?,
+            session_start: t.session_start.as_ref().map(map).transpose()?,
+            session_end: t.session_end.as_ref().map(map).transpose()?,
+            pre_command: t.pre_command.as_ref().map(map).transpose()?,
+            post_command: t.post_command.as_ref().map(map).transpose()?,
+            pre_apply_patch: t.pre_apply_patch.as_ref().map(map).transpose()?,
+            post_apply_patch: t.post_apply_patch.as_ref().map(map).transpose()?,
+            user_prompt_submit: t.user_prompt_submit.as_ref().map(map).transpose()?,
+            turn_complete: t.turn_complete.as_ref().map(map).transpose()?,
         })
     }
 }
+
+/// JSON document written to a hook's stdin.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HookPayload {
+    pub session_id: Uuid,
+    pub cwd: PathBuf,
+    #[serde(flatten)]
+    pub event: HookEvent,
+}
+
+/// The lifecycle point a hook runs at, with the data specific to it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HookEvent {
+    SessionStart,
+    SessionEnd,
+    PreCommand {
+        call_id: String,
+        command: Vec<String>,
+    },
+    PostCommand {
+        call_id: String,
+        command: Vec<String>,
+        exit_code: i32,
+        duration_ms: u128,
+        output: String,
+    },
+    PreApplyPatch {
+        call_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    },
+    PostApplyPatch {
+        call_id: String,
+        success: bool,
+        output: String,
+        /// Unified diff of every change made during the turn so far.
+        diff: Option<String>,
+    },
+    UserPromptSubmit {
+        prompt: String,
+    },
+    TurnComplete {
+        last_agent_message: Option<String>,
+        /// Unified diff of every change made during the turn.
+        diff: Option<String>,
+    },
+}
+
+impl HookEvent {
+    /// Name of the hook point, as used in the `[hooks]` config table.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::SessionStart => "session_start",
+            HookEvent::SessionEnd => "session_end",
+            HookEvent::PreCommand { .. } => "pre_command",
+            HookEvent::PostCommand { .. } => "post_command",
+            HookEvent::PreApplyPatch { .. } => "pre_apply_patch",
+            HookEvent::PostApplyPatch { .. } => "post_apply_patch",
+            HookEvent::UserPromptSubmit { .. } => "user_prompt_submit",
+            HookEvent::TurnComplete { .. } => "turn_complete",
+        }
+    }
+}
+
+/// Decision a `pre_command`, `pre_apply_patch` or `user_prompt_submit` hook
+/// may print to stdout as a JSON object, e.g. `{"decision": "deny", "reason":
+/// "..."}`. Output that is not a decision leaves the call to the usual safety
+/// checks.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum HookDecision {
+    /// Run without asking the user. Unlike the user's approval this does not
+    /// lift the sandbox, and a call the usual checks reject stays rejected.
+    Allow,
+    /// Refuse the call; the reason is reported back to the model.
+    Deny {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// Ask the user for approval even if the call would be auto-approved.
+    Ask,
+    /// Replace the command (for `pre_command`), the patch (for
+    /// `pre_apply_patch`) or the prompt (for `user_prompt_submit`) and
+    /// continue.
+    Rewrite {
+        #[serde(default)]
+        command: Option<Vec<String>>,
+        #[serde(default)]
+        patch: Option<String>,
+        #[serde(default)]
+        prompt: Option<String>,
+    },
+}
+
+impl HookDecision {
+    /// Parses a decision from a hook's stdout, if it contains one.
+    pub fn from_stdout(stdout: &str) -> Option<Self> {
+        let stdout = stdout.trim();
+        if !stdout.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str(stdout).ok()
+    }
+}
+
+/// Result of running a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutcome {
+    /// Whether the hook exited with code 0 within its timeout.
+    pub ok: bool,
+    /// The decision printed by a successful hook, if any.
+    pub decision: Option<HookDecision>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn payload_is_flattened_and_tagged_with_the_hook_point() {
+        let payload = HookPayload {
+            session_id: Uuid::nil(),
+            cwd: PathBuf::from("/work"),
+            event: HookEvent::PostCommand {
+                call_id: "call-1".to_string(),
+                command: vec!["cargo".to_string(), "test".to_string()],
+                exit_code: 101,
+                duration_ms: 1500,
+                output: "test failed".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "session_id": "00000000-0000-0000-0000-000000000000",
+                "cwd": "/work",
+                "event": "post_command",
+                "call_id": "call-1",
+                "command": ["cargo", "test"],
+                "exit_code": 101,
+                "duration_ms": 1500,
+                "output": "test failed",
+            })
+        );
+    }
+
+    #[test]
+    fn new_hook_points_are_parsed_from_toml() {
+        let toml: HooksToml = toml::from_str(
+            r#"
+[user_prompt_submit]
+command = ["check-prompt"]
+on_error = "fail-closed"
+
+[turn_complete]
+command = ["notify"]
+route = "both"
+"#,
+        )
+        .unwrap();
+        let hooks = HooksConfig::from_toml(&toml).unwrap();
+        assert_eq!(
+            hooks.user_prompt_submit,
+            Some(HookSpec {
+                command: vec!["check-prompt".to_string()],
+                route: HookRoute::Ui,
+                timeout_ms: 2000,
+                on_error: HookOnError::FailClosed,
+            })
+        );
+        assert_eq!(hooks.turn_complete.map(|h| h.route), Some(HookRoute::Both));
+        assert_eq!(hooks.pre_apply_patch, None);
+    }
+
+    #[test]
+    fn decisions_are_parsed_from_stdout() {
+        assert_eq!(
+            HookDecision::from_stdout("{\"decision\": \"allow\"}\n"),
+            Some(HookDecision::Allow)
+        );
+        assert_eq!(
+            HookDecision::from_stdout(r#"{"decision": "deny", "reason": "no network"}"#),
+            Some(HookDecision::Deny {
+                reason: Some("no network".to_string())
+            })
+        );
+        assert_eq!(
+            HookDecision::from_stdout(r#"{"decision": "rewrite", "command": ["rg", "foo"]}"#),
+            Some(HookDecision::Rewrite {
+                command: Some(vec!["rg".to_string(), "foo".to_string()]),
+                patch: None,
+                prompt: None,
+            })
+        );
+        assert_eq!(
+            HookDecision::from_stdout(r#"{"decision": "rewrite", "patch": "*** Begin Patch"}"#),
+            Some(HookDecision::Rewrite {
+                command: None,
+                patch: Some("*** Begin Patch".to_string()),
+                prompt: None,
+            })
+        );
+        assert_eq!(HookDecision::from_stdout("lint passed"), None);
+        assert_eq!(HookDecision::from_stdout(r#"{"decision": "maybe"}"#), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config_types::SandboxLimits;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -17,6 +18,7 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    resource_limits: &SandboxLimits,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
@@ -32,6 +34,7 @@ where
         arg0,
         cwd,
         sandbox_policy,
+        resource_limits,
         stdio_policy,
         env,
     )
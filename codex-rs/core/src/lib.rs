@@ -8,7 +8,11 @@
 mod apply_patch;
 pub mod auth;
 pub mod bash;
+mod budget;
+#[cfg(target_os = "linux")]
+mod cgroup;
 mod chat_completions;
+mod checkpoints;
 mod client;
 mod client_common;
 pub mod codex;
@@ -20,11 +24,14 @@ pub mod config_profile;
 pub mod config_types;
 mod conversation_history;
 pub mod custom_prompts;
+mod delegate_tool;
+pub mod egress_proxy;
 mod environment_context;
 pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod exec_policy;
 mod flags;
 pub mod git_info;
 pub mod hooks;
@@ -52,6 +59,7 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+mod parallel_tools;
 pub mod plan_tool;
 pub mod project_doc;
 mod rollout;
@@ -62,10 +70,24 @@ pub mod spawn;
 pub mod terminal;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
+pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
+pub use rollout::export::ExportFormat;
+pub use rollout::export::ExportOptions;
+pub use rollout::export::export_rollout;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::search::SearchField;
+pub use rollout::search::SearchMatch;
+pub use rollout::search::SessionIndex;
+pub use rollout::search::SessionSearchHit;
+pub use rollout::search::SessionSearchQuery;
+pub use rollout::search::parse_search_date;
+pub use rollout::stats::ModelDayUsage;
+pub use rollout::stats::SessionUsage;
+pub use rollout::stats::UsageStats;
+pub use rollout::stats::UsageTotals;
 /// Public constants exposing the absolute paths of built-in prompts at build time.
 pub mod prompt_paths;
 mod user_notification;
@@ -127,22 +127,20 @@ impl McpConnectionManager {
             }
 
             // Skip entries without a valid command instead of failing startup.
-            if cfg
+            let Some(command_str) = cfg
                 .command
-                .as_deref()
-                .map(|s| s.trim().is_empty())
-                .unwrap_or(true)
-            {
+                .clone()
+                .filter(|command| !command.trim().is_empty())
+            else {
                 let error = anyhow::anyhow!(
                     "mcp_servers.{} is missing a 'command' – skipping",
                     server_name
                 );
                 errors.insert(server_name, error);
                 continue;
-            }
+            };
 
             // Move values into the task explicitly to avoid borrowing issues.
-            let command_str = cfg.command.clone().unwrap();
             let args_vec = cfg.args.clone();
             let env_map = cfg.env.clone();
             let server_name_cloned = server_name.clone();
@@ -243,6 +241,16 @@ impl McpConnectionManager {
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Returns `true` when the server annotated the fully-qualified tool
+    /// `tool_name` with `readOnlyHint: true`.
+    pub fn is_read_only_tool(&self, tool_name: &str) -> bool {
+        self.tools
+            .get(tool_name)
+            .and_then(|tool| tool.tool.annotations.as_ref())
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
 }
 
 /// Query every server for its available tools and return a single map that
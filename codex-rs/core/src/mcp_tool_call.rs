@@ -4,6 +4,7 @@ use std::time::Instant;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::parallel_tools::CallPhase;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
@@ -53,7 +54,14 @@ pub(crate) async fn handle_mcp_tool_call(
         call_id: call_id.clone(),
         invocation: invocation.clone(),
     });
-    notify_mcp_tool_call_event(sess, sub_id, tool_call_begin_event).await;
+    notify_mcp_tool_call_event(
+        sess,
+        sub_id,
+        &call_id,
+        CallPhase::Begin,
+        tool_call_begin_event,
+    )
+    .await;
 
     let start = Instant::now();
     // Perform the tool call.
@@ -68,15 +76,32 @@ pub(crate) async fn handle_mcp_tool_call(
         result: result.clone(),
     });
 
-    notify_mcp_tool_call_event(sess, sub_id, tool_call_end_event.clone()).await;
+    notify_mcp_tool_call_event(
+        sess,
+        sub_id,
+        &call_id,
+        CallPhase::End,
+        tool_call_end_event.clone(),
+    )
+    .await;
 
     ResponseInputItem::McpToolCallOutput { call_id, result }
 }
 
-async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMsg) {
-    sess.send_event(Event {
-        id: sub_id.to_string(),
-        msg: event,
-    })
+async fn notify_mcp_tool_call_event(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    phase: CallPhase,
+    event: EventMsg,
+) {
+    sess.send_tool_call_event(
+        call_id,
+        phase,
+        Event {
+            id: sub_id.to_string(),
+            msg: event,
+        },
+    )
     .await;
 }
@@ -0,0 +1,200 @@
+//! Support for running independent read-only tool calls from a single model
+//! response concurrently.
+//!
+//! Only calls that do not modify the workspace are batched: `shell`
+//! invocations accepted by [`is_known_safe_command`] and MCP tools annotated
+//! with `readOnlyHint`. Such calls may still prompt, e.g. to retry outside the
+//! sandbox after a denial; the session lets one approval request wait for the
+//! user at a time, so concurrent prompts are asked one after the other.
+//! Outputs are collected in the order the model issued the calls, and
+//! [`CallSequencer`] keeps the begin/end events of a batch in that same order.
+
+use std::collections::HashMap;
+
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use tokio::sync::watch;
+
+use crate::is_safe_command::is_known_safe_command;
+use crate::mcp_connection_manager::McpConnectionManager;
+
+/// Which of the two lifecycle events of a tool call is being emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CallPhase {
+    Begin,
+    End,
+}
+
+/// Orders the begin/end events of a batch of concurrently running tool
+/// calls: a call's event is held back until every earlier call in the batch
+/// has emitted (or given up on emitting) the same event.
+pub(crate) struct CallSequencer {
+    slots: HashMap<String, usize>,
+    begun: watch::Sender<Vec<bool>>,
+    ended: watch::Sender<Vec<bool>>,
+}
+
+impl CallSequencer {
+    pub(crate) fn new(call_ids: impl IntoIterator<Item = String>) -> Self {
+        let call_ids: Vec<String> = call_ids.into_iter().collect();
+        let len = call_ids.len();
+        let slots = call_ids
+            .into_iter()
+            .enumerate()
+            .map(|(idx, call_id)| (call_id, idx))
+            .collect();
+        Self {
+            slots,
+            begun: watch::Sender::new(vec![false; len]),
+            ended: watch::Sender::new(vec![false; len]),
+        }
+    }
+
+    /// Waits until every call preceding `call_id` has passed `phase`. Calls
+    /// that are not part of the batch never wait.
+    pub(crate) async fn wait(&self, call_id: &str, phase: CallPhase) {
+        let Some(&idx) = self.slots.get(call_id) else {
+            return;
+        };
+        let mut rx = self.channel(phase).subscribe();
+        // The sender lives as long as `self`, so this cannot fail.
+        let _ = rx.wait_for(|done| done[..idx].iter().all(|d| *d)).await;
+    }
+
+    /// Records that `call_id` has emitted its `phase` event.
+    pub(crate) fn mark(&self, call_id: &str, phase: CallPhase) {
+        if let Some(&idx) = self.slots.get(call_id) {
+            self.channel(phase).send_modify(|done| done[idx] = true);
+        }
+    }
+
+    /// Releases both phases for `call_id`, so that a call which failed before
+    /// emitting its events does not hold back the rest of the batch.
+    pub(crate) fn finish(&self, call_id: &str) {
+        self.mark(call_id, CallPhase::Begin);
+        self.mark(call_id, CallPhase::End);
+    }
+
+    fn channel(&self, phase: CallPhase) -> &watch::Sender<Vec<bool>> {
+        match phase {
+            CallPhase::Begin => &self.begun,
+            CallPhase::End => &self.ended,
+        }
+    }
+}
+
+/// Returns the call id the response to `item` will carry, if `item` is a
+/// tool call.
+pub(crate) fn tool_call_id(item: &ResponseItem) -> Option<&str> {
+    match item {
+        ResponseItem::FunctionCall { call_id, .. }
+        | ResponseItem::CustomToolCall { call_id, .. } => Some(call_id),
+        ResponseItem::LocalShellCall { call_id, id, .. } => call_id.as_deref().or(id.as_deref()),
+        _ => None,
+    }
+}
+
+/// Returns `true` when `item` is a tool call that can safely run alongside
+/// other such calls: it only reads the workspace.
+pub(crate) fn is_parallel_safe(item: &ResponseItem, mcp: &McpConnectionManager) -> bool {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments)
+                .is_ok_and(|params| is_known_safe_command(&params.command)),
+            _ => mcp.is_read_only_tool(name),
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => tool_call_id(item).is_some() && is_known_safe_command(&action.command),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn shell_call(call_id: &str, command: &[&str]) -> ResponseItem {
+        ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: serde_json::json!({ "command": command }).to_string(),
+            call_id: call_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn only_known_safe_shell_calls_are_parallel_safe() {
+        let mcp = McpConnectionManager::default();
+        assert!(is_parallel_safe(
+            &shell_call("a", &["rg", "-n", "foo"]),
+            &mcp
+        ));
+        assert!(is_parallel_safe(
+            &shell_call("b", &["bash", "-lc", "cat README.md"]),
+            &mcp
+        ));
+        assert!(!is_parallel_safe(
+            &shell_call("c", &["rm", "-rf", "target"]),
+            &mcp
+        ));
+        assert!(!is_parallel_safe(
+            &shell_call("d", &["apply_patch", "*** Begin Patch"]),
+            &mcp
+        ));
+
+        let unknown_tool = ResponseItem::FunctionCall {
+            id: None,
+            name: "server__tool".to_string(),
+            arguments: "{}".to_string(),
+            call_id: "e".to_string(),
+        };
+        assert!(!is_parallel_safe(&unknown_tool, &mcp));
+    }
+
+    #[tokio::test]
+    async fn sequencer_releases_events_in_batch_order() {
+        let sequencer = Arc::new(CallSequencer::new([
+            "first".to_string(),
+            "second".to_string(),
+        ]));
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+
+        let emit = |call_id: &'static str, delay_ms: u64| {
+            let sequencer = Arc::clone(&sequencer);
+            let emitted = Arc::clone(&emitted);
+            async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                sequencer.wait(call_id, CallPhase::End).await;
+                emitted.lock().unwrap().push(call_id);
+                sequencer.mark(call_id, CallPhase::End);
+            }
+        };
+
+        // The second call finishes first but must wait for the first one.
+        tokio::join!(emit("first", 50), emit("second", 0));
+        assert_eq!(*emitted.lock().unwrap(), vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn finished_calls_do_not_block_later_ones() {
+        let sequencer = CallSequencer::new(["first".to_string(), "second".to_string()]);
+        sequencer.finish("first");
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            sequencer.wait("second", CallPhase::Begin),
+        )
+        .await
+        .unwrap();
+        // Calls outside the batch never wait.
+        sequencer.wait("other", CallPhase::End).await;
+    }
+}
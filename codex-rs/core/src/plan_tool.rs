@@ -78,6 +78,7 @@ pub(crate) async fn handle_update_plan(
                     success: Some(true),
                 },
             };
+            session.set_plan(args.clone()).await;
             session
                 .send_event(Event {
                     id: sub_id.to_string(),
@@ -0,0 +1,830 @@
+//! Render a recorded rollout as a transcript that can be shared outside
+//! Codex: Markdown, a self-contained HTML page, or normalized JSON.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self};
+use std::path::Path;
+
+use codex_apply_patch::Hunk;
+use codex_apply_patch::MaybeApplyPatch;
+use codex_apply_patch::UpdateFileChunk;
+use codex_apply_patch::maybe_parse_apply_patch;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::plan_tool::StepStatus;
+use codex_protocol::plan_tool::UpdatePlanArgs;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::USER_MESSAGE_BEGIN;
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::CowStr;
+use pulldown_cmark::Event;
+use pulldown_cmark::Options;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+
+use super::SessionStateSnapshot;
+
+const DEV_NULL: &str = "/dev/null";
+
+/// Command output lines kept from the start and from the end of each output.
+const OUTPUT_HEAD_LINES: usize = 10;
+const OUTPUT_TAIL_LINES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Leave out the environment-context and user-instructions blocks that
+    /// Codex injects as user messages.
+    pub redact_context: bool,
+}
+
+/// Reads the rollout at `path` (plain or compressed) and renders it according to `options`.
+pub fn export_rollout(path: &Path, options: &ExportOptions) -> io::Result<String> {
+    let text = super::read_rollout(path)?;
+    let rollout = Rollout::parse(&text, options.redact_context)?;
+    Ok(match options.format {
+        ExportFormat::Markdown => rollout.to_markdown(),
+        ExportFormat::Html => markdown_to_html(&rollout.title(), &rollout.to_markdown()),
+        ExportFormat::Json => {
+            let doc = json!({
+                "session": rollout.meta,
+                "state": rollout.state,
+                "items": rollout.items,
+            });
+            serde_json::to_string_pretty(&doc)?
+        }
+    })
+}
+
+struct Rollout {
+    meta: Value,
+    items: Vec<ResponseItem>,
+    state: Option<SessionStateSnapshot>,
+}
+
+/// How a call was rendered, so its output can be rendered to match.
+enum CallKind {
+    Command,
+    Patch,
+    Plan,
+    Tool,
+}
+
+impl Rollout {
+    fn parse(text: &str, redact_context: bool) -> io::Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let meta = lines
+            .next()
+            .ok_or_else(|| io::Error::other("empty session file"))?;
+        let meta: Value = serde_json::from_str(meta)?;
+        let mut items = Vec::new();
+        let mut state = None;
+        for line in lines {
+            let Ok(value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if value.get("record_type").and_then(Value::as_str) == Some("state") {
+                // The last state line describes the session as it ended.
+                state = serde_json::from_value::<SessionStateSnapshot>(value)
+                    .ok()
+                    .or(state);
+                continue;
+            }
+            // Unknown records simply fail to parse.
+            let Ok(item) = serde_json::from_value::<ResponseItem>(value) else {
+                continue;
+            };
+            let item = if redact_context {
+                redact(item)
+            } else {
+                Some(item)
+            };
+            items.extend(item);
+        }
+        Ok(Self { meta, items, state })
+    }
+
+    fn title(&self) -> String {
+        match self.meta.get("id").and_then(Value::as_str) {
+            Some(id) => format!("Codex session {id}"),
+            None => "Codex session".to_string(),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.title());
+        let meta_str = |key: &str| self.meta.get(key).and_then(Value::as_str);
+        if let Some(timestamp) = meta_str("timestamp") {
+            let _ = writeln!(out, "- Started: {timestamp}");
+        }
+        if let Some(cwd) = meta_str("cwd") {
+            let _ = writeln!(out, "- Directory: `{cwd}`");
+        }
+        let git = self.meta.get("git");
+        let git_str = |key: &str| git.and_then(|g| g.get(key)).and_then(Value::as_str);
+        match (git_str("branch"), git_str("commit_hash")) {
+            (Some(branch), Some(commit)) => {
+                let short = commit.get(..7).unwrap_or(commit);
+                let _ = writeln!(out, "- Branch: `{branch}` at `{short}`");
+            }
+            (Some(branch), None) => {
+                let _ = writeln!(out, "- Branch: `{branch}`");
+            }
+            (None, Some(commit)) => {
+                let _ = writeln!(out, "- Commit: `{commit}`");
+            }
+            (None, None) => {}
+        }
+        if let Some(url) = git_str("repository_url") {
+            let _ = writeln!(out, "- Repository: {url}");
+        }
+        let state = self.state.as_ref();
+        if let Some(turn) = state.and_then(|s| s.turn.as_ref()) {
+            let _ = writeln!(out, "- Model: `{}`", turn.model);
+        }
+        if let Some(usage) = state.and_then(|s| s.total_token_usage.as_ref()) {
+            let _ = writeln!(
+                out,
+                "- Tokens: {} input ({} cached), {} output ({} reasoning)",
+                usage.input_tokens,
+                usage.cached_input_tokens,
+                usage.output_tokens,
+                usage.reasoning_output_tokens
+            );
+        }
+        out.push_str("\n---\n");
+
+        let mut calls: HashMap<String, CallKind> = HashMap::new();
+        for item in &self.items {
+            render_item(item, &mut calls, &mut out);
+        }
+        out
+    }
+}
+
+/// Drops the context blocks Codex injects as user messages, and the message
+/// itself when nothing else is left in it.
+fn redact(item: ResponseItem) -> Option<ResponseItem> {
+    match item {
+        ResponseItem::Message { id, role, content } if role == "user" => {
+            let content: Vec<ContentItem> = content
+                .into_iter()
+                .filter(|c| match c {
+                    ContentItem::InputText { text } => matches!(
+                        InputMessageKind::from(("user", text.as_str())),
+                        InputMessageKind::Plain
+                    ),
+                    _ => true,
+                })
+                .collect();
+            if content.is_empty() {
+                None
+            } else {
+                Some(ResponseItem::Message { id, role, content })
+            }
+        }
+        item => Some(item),
+    }
+}
+
+fn render_item(item: &ResponseItem, calls: &mut HashMap<String, CallKind>, out: &mut String) {
+    match item {
+        ResponseItem::Message { role, content, .. } => render_message(role, content, out),
+        ResponseItem::Reasoning { summary, .. } => {
+            for ReasoningItemReasoningSummary::SummaryText { text } in summary {
+                let quoted = text
+                    .lines()
+                    .map(|line| format!("> {line}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let _ = writeln!(out, "\n> _Reasoning_\n>\n{quoted}");
+            }
+        }
+        ResponseItem::LocalShellCall {
+            call_id,
+            id,
+            action: LocalShellAction::Exec(exec),
+            ..
+        } => {
+            let kind = render_command(&exec.command, out);
+            if let Some(call_id) = call_id.as_ref().or(id.as_ref()) {
+                calls.insert(call_id.clone(), kind);
+            }
+        }
+        ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        } => {
+            let kind = match name.as_str() {
+                "container.exec" | "shell" => {
+                    match serde_json::from_str::<ShellToolCallParams>(arguments) {
+                        Ok(params) => render_command(&params.command, out),
+                        Err(_) => render_tool_call(name, arguments, out),
+                    }
+                }
+                "apply_patch" => {
+                    #[derive(Deserialize)]
+                    struct ApplyPatchArgs {
+                        input: String,
+                    }
+                    match serde_json::from_str::<ApplyPatchArgs>(arguments) {
+                        Ok(args) => render_command(&["apply_patch".to_string(), args.input], out),
+                        Err(_) => render_tool_call(name, arguments, out),
+                    }
+                }
+                "update_plan" => match serde_json::from_str::<UpdatePlanArgs>(arguments) {
+                    Ok(args) => render_plan(&args, out),
+                    Err(_) => render_tool_call(name, arguments, out),
+                },
+                _ => render_tool_call(name, arguments, out),
+            };
+            calls.insert(call_id.clone(), kind);
+        }
+        ResponseItem::CustomToolCall {
+            name,
+            input,
+            call_id,
+            ..
+        } => {
+            let kind = if name == "apply_patch" {
+                render_command(&["apply_patch".to_string(), input.clone()], out)
+            } else {
+                render_tool_call(name, input, out)
+            };
+            calls.insert(call_id.clone(), kind);
+        }
+        ResponseItem::FunctionCallOutput { call_id, output } => {
+            render_output(calls.get(call_id), &output.content, out);
+        }
+        ResponseItem::CustomToolCallOutput { call_id, output } => {
+            render_output(calls.get(call_id), output, out);
+        }
+        _ => {}
+    }
+}
+
+fn render_message(role: &str, content: &[ContentItem], out: &mut String) {
+    let heading = if role == "user" { "User" } else { "Codex" };
+    let mut body = String::new();
+    for content in content {
+        match content {
+            ContentItem::InputText { text } => {
+                match InputMessageKind::from(("user", text.as_str())) {
+                    InputMessageKind::Plain => {
+                        // Strip ide context.
+                        let text = match text.find(USER_MESSAGE_BEGIN) {
+                            Some(idx) => text[idx + USER_MESSAGE_BEGIN.len()..].trim(),
+                            None => text.trim(),
+                        };
+                        let _ = writeln!(body, "\n{text}");
+                    }
+                    InputMessageKind::UserInstructions => {
+                        render_details("User instructions", text, &mut body);
+                    }
+                    InputMessageKind::EnvironmentContext => {
+                        render_details("Environment context", text, &mut body);
+                    }
+                }
+            }
+            ContentItem::OutputText { text } => {
+                let _ = writeln!(body, "\n{}", text.trim());
+            }
+            ContentItem::InputImage { .. } => body.push_str("\n_[image]_\n"),
+        }
+    }
+    if !body.trim().is_empty() {
+        let _ = write!(out, "\n## {heading}\n{body}");
+    }
+}
+
+fn render_details(summary: &str, text: &str, out: &mut String) {
+    let fence = fence_for(text);
+    let _ = writeln!(
+        out,
+        "\n<details>\n<summary>{summary}</summary>\n\n{fence}text\n{}\n{fence}\n\n</details>",
+        text.trim()
+    );
+}
+
+/// Renders a shell command, or the patch it carries when it is `apply_patch`.
+fn render_command(command: &[String], out: &mut String) -> CallKind {
+    if let MaybeApplyPatch::Body(args) = maybe_parse_apply_patch(command) {
+        out.push_str("\n**Patch**\n\n");
+        for hunk in &args.hunks {
+            let line = match hunk {
+                Hunk::AddFile { path, .. } => format!("- A `{}`", path.display()),
+                Hunk::DeleteFile { path } => format!("- D `{}`", path.display()),
+                Hunk::UpdateFile {
+                    path,
+                    move_path: Some(dest),
+                    ..
+                } => format!("- R `{}` → `{}`", path.display(), dest.display()),
+                Hunk::UpdateFile { path, .. } => format!("- M `{}`", path.display()),
+                Hunk::CopyFile {
+                    path, copy_path, ..
+                } => format!("- C `{}` → `{}`", path.display(), copy_path.display()),
+            };
+            let _ = writeln!(out, "{line}");
+        }
+        let diff = hunks_to_unified_diff(&args.hunks);
+        let diff = diff.trim_end();
+        let fence = fence_for(diff);
+        let _ = writeln!(out, "\n{fence}diff\n{diff}\n{fence}");
+        return CallKind::Patch;
+    }
+    let text = match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => script.clone(),
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    };
+    let fence = fence_for(&text);
+    let _ = writeln!(out, "\n**Command**\n\n{fence}shell\n$ {text}\n{fence}");
+    CallKind::Command
+}
+
+/// Renders the hunks of a patch as a unified diff, with `a/` and `b/` paths
+/// as in the turn diff. The patch does not say where in a file an update
+/// applies, so the line numbers of its `@@` headers count from the first line
+/// of each change, and the header carries the change's context line if it
+/// has one.
+fn hunks_to_unified_diff(hunks: &[Hunk]) -> String {
+    let mut out = String::new();
+    for hunk in hunks {
+        match hunk {
+            Hunk::AddFile { path, contents, .. } => {
+                let _ = writeln!(out, "--- {DEV_NULL}\n+++ b/{}", path.display());
+                push_diff_hunks("", contents, None, &mut out);
+            }
+            Hunk::DeleteFile { path } => {
+                let _ = writeln!(out, "--- a/{}\n+++ {DEV_NULL}", path.display());
+            }
+            Hunk::UpdateFile {
+                path,
+                move_path,
+                chunks,
+                ..
+            } => {
+                let dest = move_path.as_deref().unwrap_or(path);
+                let _ = writeln!(out, "--- a/{}\n+++ b/{}", path.display(), dest.display());
+                for chunk in chunks {
+                    push_chunk(chunk, &mut out);
+                }
+            }
+            Hunk::CopyFile {
+                path, copy_path, ..
+            } => {
+                let _ = writeln!(
+                    out,
+                    "--- a/{}\n+++ b/{}",
+                    path.display(),
+                    copy_path.display()
+                );
+            }
+        }
+    }
+    out
+}
+
+fn push_chunk(chunk: &UpdateFileChunk, out: &mut String) {
+    let join = |lines: &[String]| {
+        lines
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>()
+    };
+    push_diff_hunks(
+        &join(&chunk.old_lines),
+        &join(&chunk.new_lines),
+        chunk.change_context.as_deref(),
+        out,
+    );
+}
+
+/// Appends the `@@` hunks that turn `old` into `new`, keeping every
+/// unchanged line as context.
+fn push_diff_hunks(old: &str, new: &str, context: Option<&str>, out: &mut String) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let radius = old.lines().count().max(new.lines().count());
+    for hunk in diff.unified_diff().context_radius(radius).iter_hunks() {
+        let hunk = hunk.to_string();
+        match (context, hunk.split_once('\n')) {
+            (Some(context), Some((header, lines))) => {
+                let _ = write!(out, "{header} {context}\n{lines}");
+            }
+            _ => out.push_str(&hunk),
+        }
+    }
+}
+
+fn render_plan(args: &UpdatePlanArgs, out: &mut String) -> CallKind {
+    out.push_str("\n**Plan**\n\n");
+    if let Some(explanation) = &args.explanation {
+        let _ = writeln!(out, "{}\n", explanation.trim());
+    }
+    for item in &args.plan {
+        let line = match item.status {
+            StepStatus::Completed => format!("- [x] {}", item.step),
+            StepStatus::InProgress => format!("- [ ] **{}**", item.step),
+            StepStatus::Pending => format!("- [ ] {}", item.step),
+        };
+        let _ = writeln!(out, "{line}");
+    }
+    CallKind::Plan
+}
+
+fn render_tool_call(name: &str, arguments: &str, out: &mut String) -> CallKind {
+    let arguments = serde_json::from_str::<Value>(arguments)
+        .and_then(|v| serde_json::to_string_pretty(&v))
+        .unwrap_or_else(|_| arguments.to_string());
+    let fence = fence_for(&arguments);
+    let _ = writeln!(
+        out,
+        "\n**Tool call** `{name}`\n\n{fence}json\n{arguments}\n{fence}"
+    );
+    CallKind::Tool
+}
+
+fn render_output(kind: Option<&CallKind>, content: &str, out: &mut String) {
+    #[derive(Deserialize)]
+    struct ExecOutput {
+        output: String,
+        metadata: ExecMetadata,
+    }
+    #[derive(Deserialize)]
+    struct ExecMetadata {
+        exit_code: i32,
+    }
+
+    match kind {
+        // "Plan updated" carries nothing the plan itself does not show.
+        Some(CallKind::Plan) => {}
+        Some(CallKind::Command) | Some(CallKind::Patch) => {
+            let (label, output) = match serde_json::from_str::<ExecOutput>(content) {
+                Ok(exec) => (
+                    format!("Output (exit {})", exec.metadata.exit_code),
+                    exec.output,
+                ),
+                Err(_) => ("Output".to_string(), content.to_string()),
+            };
+            render_truncated(&label, &output, out);
+        }
+        Some(CallKind::Tool) | None => render_truncated("Result", content, out),
+    }
+}
+
+fn render_truncated(label: &str, text: &str, out: &mut String) {
+    let text = text.trim_end();
+    if text.is_empty() {
+        let _ = writeln!(out, "\n_{label}: (no output)_");
+        return;
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let shown = if lines.len() > OUTPUT_HEAD_LINES + OUTPUT_TAIL_LINES {
+        let omitted = lines.len() - OUTPUT_HEAD_LINES - OUTPUT_TAIL_LINES;
+        let mut shown = lines[..OUTPUT_HEAD_LINES].join("\n");
+        let _ = write!(shown, "\n[... {omitted} lines omitted ...]\n");
+        shown.push_str(&lines[lines.len() - OUTPUT_TAIL_LINES..].join("\n"));
+        shown
+    } else {
+        text.to_string()
+    };
+    let fence = fence_for(&shown);
+    let _ = writeln!(out, "\n_{label}_\n\n{fence}text\n{shown}\n{fence}");
+}
+
+/// A backtick fence longer than any backtick run inside `text`.
+fn fence_for(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Wraps the rendered Markdown in a standalone HTML page. Lines of `diff`
+/// code blocks are wrapped so additions and removals can be coloured.
+fn markdown_to_html(title: &str, markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+
+    let mut in_diff = false;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang)))
+            if lang.as_ref() == "diff" =>
+        {
+            in_diff = true;
+            event
+        }
+        Event::End(TagEnd::CodeBlock) => {
+            in_diff = false;
+            event
+        }
+        Event::Text(text) if in_diff => {
+            let mut html = String::new();
+            for line in text.split_inclusive('\n') {
+                let class = match line.chars().next() {
+                    _ if line.starts_with("+++ ") || line.starts_with("--- ") => "meta",
+                    Some('+') => "add",
+                    Some('-') => "del",
+                    Some('@') => "meta",
+                    _ => "ctx",
+                };
+                let _ = write!(html, "<span class=\"{class}\">{}</span>", escape_html(line));
+            }
+            Event::Html(html.into())
+        }
+        // Messages are untrusted; show any HTML in them as text, except the
+        // markup `render_details` writes around the context blocks.
+        Event::Html(html) if is_details_markup(&html) => Event::Html(html),
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, events);
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{HTML_STYLE}</style>\n</head>\n<body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+/// Whether every line of an HTML block is one the exporter writes itself in
+/// `render_details`. None of it carries attributes or script, so a message
+/// that spells it out can at worst add a collapsible section.
+fn is_details_markup(html: &str) -> bool {
+    html.lines().all(|line| {
+        matches!(
+            line.trim(),
+            "" | "<details>"
+                | "</details>"
+                | "<summary>User instructions</summary>"
+                | "<summary>Environment context</summary>"
+        )
+    })
+}
+
+/// Whether a link or image URL may be kept in the HTML export: relative
+/// URLs and `http`, `https` and `mailto` ones. Any other scheme, such as
+/// `javascript:`, could run script when the link is followed.
+fn is_safe_url(url: &str) -> bool {
+    if url.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return false;
+    }
+    match url.find([':', '/', '?', '#']) {
+        Some(idx) if url[idx..].starts_with(':') => matches!(
+            url[..idx].to_ascii_lowercase().as_str(),
+            "http" | "https" | "mailto"
+        ),
+        _ => true,
+    }
+}
+
+const HTML_STYLE: &str = "body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", sans-serif; line-height: 1.5; margin: 0; background: #fafafa; color: #1f2328; }
+main { max-width: 880px; margin: 0 auto; padding: 2rem 1.5rem; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.25rem; margin-top: 2rem; }
+pre { background: #f0f2f4; padding: 0.75rem; overflow-x: auto; border-radius: 6px; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9em; }
+blockquote { color: #59636e; border-left: 3px solid #d0d7de; margin-left: 0; padding-left: 1rem; }
+details { margin: 0.5rem 0; }
+.add { color: #1a7f37; }
+.del { color: #cf222e; }
+.meta { color: #8250df; }
+";
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn rollout_file() -> NamedTempFile {
+        let lines = [
+            json!({
+                "id": "00000000-0000-0000-0000-000000000001",
+                "timestamp": "2025-01-02T10:00:00.000Z",
+                "instructions": null,
+                "cwd": "/work/repo",
+                "git": { "branch": "main", "commit_hash": "0123456789abcdef" },
+            }),
+            json!({
+                "type": "message",
+                "role": "user",
+                "content": [
+                    { "type": "input_text", "text": "<environment_context>\n  <cwd>/work/repo</cwd>\n</environment_context>" },
+                ],
+            }),
+            json!({
+                "type": "message",
+                "role": "user",
+                "content": [{ "type": "input_text", "text": "Run the tests" }],
+            }),
+            json!({
+                "type": "function_call",
+                "name": "shell",
+                "arguments": "{\"command\":[\"bash\",\"-lc\",\"cargo test\"]}",
+                "call_id": "c1",
+            }),
+            json!({
+                "type": "function_call_output",
+                "call_id": "c1",
+                "output": serde_json::to_string(&json!({
+                    "output": (1..=30).map(|i| format!("line {i}")).collect::<Vec<_>>().join("\n"),
+                    "metadata": { "exit_code": 0, "duration_seconds": 1.0 },
+                })).unwrap(),
+            }),
+            json!({
+                "type": "custom_tool_call",
+                "name": "apply_patch",
+                "input": "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n-old\n+new\n*** End Patch",
+                "call_id": "c2",
+            }),
+            json!({
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "output_text", "text": "All tests pass." }],
+            }),
+            json!({ "record_type": "state" }),
+            json!({
+                "record_type": "state",
+                "total_token_usage": {
+                    "input_tokens": 1200,
+                    "cached_input_tokens": 200,
+                    "output_tokens": 300,
+                    "reasoning_output_tokens": 100,
+                    "total_tokens": 1500,
+                },
+            }),
+        ];
+        let mut file = NamedTempFile::new().unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+        file
+    }
+
+    fn export(format: ExportFormat, redact_context: bool) -> String {
+        let file = rollout_file();
+        export_rollout(
+            file.path(),
+            &ExportOptions {
+                format,
+                redact_context,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn markdown_renders_messages_commands_and_patches() {
+        let md = export(ExportFormat::Markdown, false);
+        assert!(md.starts_with("# Codex session 00000000-0000-0000-0000-000000000001\n"));
+        assert!(md.contains("- Branch: `main` at `0123456`"));
+        assert!(md.contains("- Tokens: 1200 input (200 cached), 300 output (100 reasoning)"));
+        assert!(md.contains("<summary>Environment context</summary>"));
+        assert!(md.contains("## User\n\nRun the tests\n"));
+        assert!(md.contains("```shell\n$ cargo test\n```"));
+        assert!(md.contains("_Output (exit 0)_"));
+        assert!(md.contains("line 10\n[... 10 lines omitted ...]\nline 21"));
+        assert!(md.contains("- M `src/lib.rs`"));
+        assert!(
+            md.contains(
+                "```diff\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-old\n+new\n```"
+            )
+        );
+        assert!(!md.contains("*** Begin Patch"));
+        assert!(md.contains("## Codex\n\nAll tests pass.\n"));
+    }
+
+    #[test]
+    fn patches_become_unified_diffs() {
+        let args = codex_apply_patch::parse_patch(
+            "*** Begin Patch\n*** Add File: new.txt\n+one\n+two\n*** Update File: src/lib.rs\n*** Move to: src/main.rs\n@@ fn main() {\n     keep\n-    old\n+    new\n*** Delete File: gone.txt\n*** End Patch",
+        )
+        .unwrap();
+        assert_eq!(
+            hunks_to_unified_diff(&args.hunks),
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n\
+             --- a/src/lib.rs\n+++ b/src/main.rs\n@@ -1,2 +1,2 @@ fn main() {\n     keep\n-    old\n+    new\n\
+             --- a/gone.txt\n+++ /dev/null\n"
+        );
+    }
+
+    #[test]
+    fn redaction_drops_context_blocks() {
+        let md = export(ExportFormat::Markdown, true);
+        assert!(!md.contains("environment_context"));
+        assert!(md.contains("Run the tests"));
+
+        let doc: Value = serde_json::from_str(&export(ExportFormat::Json, true)).unwrap();
+        assert_eq!(doc["session"]["cwd"], json!("/work/repo"));
+        assert_eq!(doc["items"].as_array().map(Vec::len), Some(5));
+        assert_eq!(
+            doc["state"]["total_token_usage"]["output_tokens"],
+            json!(300)
+        );
+    }
+
+    #[test]
+    fn html_is_standalone_and_colours_diffs() {
+        let html = export(ExportFormat::Html, false);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(
+            html.contains("<span class=\"del\">-old\n</span><span class=\"add\">+new\n</span>")
+        );
+        assert!(html.contains("<span class=\"meta\">--- a/src/lib.rs\n</span>"));
+        assert!(html.contains("&lt;environment_context&gt;"));
+        assert!(html.contains("<details>\n<summary>Environment context</summary>"));
+        assert!(html.contains("</details>"));
+        assert!(!html.contains("&lt;details&gt;"));
+        assert!(!html.contains("&lt;/details&gt;"));
+    }
+
+    #[test]
+    fn html_in_messages_is_escaped() {
+        let html = markdown_to_html(
+            "t",
+            "<script>alert(1)</script>\n\nsee <img src=x onerror=alert(2)> here\n",
+        );
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("&lt;img src=x onerror=alert(2)&gt;"));
+    }
+
+    #[test]
+    fn html_drops_links_with_unsafe_schemes() {
+        let html = markdown_to_html(
+            "t",
+            "[a](javascript:alert(1)) [b](JavaScript:alert(2)) ![c](data:text/html,x)\n\n\
+             [d](https://example.com) [e](mailto:me@example.com) [f](docs/readme.md)\n",
+        );
+        assert!(!html.to_ascii_lowercase().contains("javascript:"));
+        assert!(!html.contains("data:"));
+        assert!(html.contains("<a href=\"\">a</a>"));
+        assert!(html.contains("<img src=\"\" alt=\"c\""));
+        assert!(html.contains("<a href=\"https://example.com\">d</a>"));
+        assert!(html.contains("<a href=\"mailto:me@example.com\">e</a>"));
+        assert!(html.contains("<a href=\"docs/readme.md\">f</a>"));
+    }
+
+    #[test]
+    fn fences_outgrow_embedded_backticks() {
+        assert_eq!(fence_for("plain"), "```");
+        assert_eq!(fence_for("has ``` inside"), "````");
+    }
+}
@@ -9,7 +9,11 @@ use time::format_description::FormatItem;
 use time::macros::format_description;
 use uuid::Uuid;
 
+use super::COMPRESSED_EXTENSION;
 use super::SESSIONS_SUBDIR;
+use super::decode_rollout;
+use super::is_compressed;
+use super::is_rollout_file_name;
 
 /// Returned page of conversation summaries.
 #[derive(Debug, Default, PartialEq)]
@@ -102,15 +106,16 @@ pub(crate) async fn get_conversations(
 }
 
 /// Load the full contents of a single conversation session file at `path`.
-/// Returns the entire file contents as a String.
+/// Returns the entire file contents as a String, decompressed if needed.
 #[allow(dead_code)]
 pub(crate) async fn get_conversation(path: &Path) -> io::Result<String> {
-    tokio::fs::read_to_string(path).await
+    decode_rollout(path, tokio::fs::read(path).await?)
 }
 
 /// Load conversation file paths from disk using directory traversal.
 ///
-/// Directory layout: `~/.codex/sessions/YYYY/MM/DD/rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl`
+/// Directory layout: `~/.codex/sessions/YYYY/MM/DD/rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl`,
+/// with `.jsonl.gz` for rollouts compressed by the retention pass.
 /// Returned newest (latest) first.
 async fn traverse_directories_for_paths(
     root: PathBuf,
@@ -142,7 +147,7 @@ async fn traverse_directories_for_paths(
                     break 'outer;
                 }
                 let mut day_files = collect_files(day_path, |name_str, path| {
-                    if !name_str.starts_with("rollout-") || !name_str.ends_with(".jsonl") {
+                    if !is_rollout_file_name(name_str) {
                         return None;
                     }
 
@@ -257,8 +262,11 @@ where
 }
 
 fn parse_timestamp_uuid_from_filename(name: &str) -> Option<(OffsetDateTime, Uuid)> {
-    // Expected: rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl
-    let core = name.strip_prefix("rollout-")?.strip_suffix(".jsonl")?;
+    // Expected: rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl (or .jsonl.gz)
+    let name = name.strip_prefix("rollout-")?;
+    let core = name
+        .strip_suffix(COMPRESSED_EXTENSION)
+        .or_else(|| name.strip_suffix(".jsonl"))?;
 
     // Scan from the right for a '-' such that the suffix parses as a UUID.
     let (sep_idx, uuid) = core
@@ -279,6 +287,16 @@ async fn read_first_jsonl_records(
 ) -> io::Result<Vec<serde_json::Value>> {
     use tokio::io::AsyncBufReadExt;
 
+    if is_compressed(path) {
+        // Compressed rollouts are old and small enough to decode whole.
+        let text = decode_rollout(path, tokio::fs::read(path).await?)?;
+        return Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line.trim()).ok())
+            .take(max_records)
+            .collect());
+    }
+
     let file = tokio::fs::File::open(path).await?;
     let reader = tokio::io::BufReader::new(file);
     let mut lines = reader.lines();
@@ -1,13 +1,83 @@
 //! Rollout module: persistence and discovery of session rollout files.
 
+use std::io::Read;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+
+use flate2::read::GzDecoder;
+
 pub(crate) const SESSIONS_SUBDIR: &str = "sessions";
+pub const ARCHIVED_SESSIONS_SUBDIR: &str = "archived_sessions";
 
+/// Extension of a rollout that the retention pass has gzipped.
+pub(crate) const COMPRESSED_EXTENSION: &str = ".jsonl.gz";
+
+pub mod export;
 pub mod list;
 pub(crate) mod policy;
 pub mod recorder;
+pub(crate) mod retention;
+pub mod search;
+pub mod stats;
 
+pub(crate) use recorder::DelegateRecord;
+pub(crate) use recorder::ResumeRecord;
 pub use recorder::RolloutRecorder;
 pub use recorder::SessionStateSnapshot;
+pub use recorder::TurnSettings;
+
+/// Whether `name` is a rollout file, plain or compressed.
+pub(crate) fn is_rollout_file_name(name: &str) -> bool {
+    name.starts_with("rollout-")
+        && (name.ends_with(".jsonl") || name.ends_with(COMPRESSED_EXTENSION))
+}
+
+pub(crate) fn is_compressed(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|p| p.ends_with(COMPRESSED_EXTENSION))
+}
+
+/// Decodes the raw bytes of the rollout at `path`, gunzipping compressed
+/// rollouts.
+pub(crate) fn decode_rollout(path: &Path, bytes: Vec<u8>) -> io::Result<String> {
+    if is_compressed(path) {
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice()).read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Reads a rollout, plain or compressed, as text.
+pub(crate) fn read_rollout(path: &Path) -> io::Result<String> {
+    decode_rollout(path, std::fs::read(path)?)
+}
+
+/// Recursively collects plain and compressed rollout files under `dir`. A
+/// missing directory yields nothing.
+pub(crate) fn collect_rollout_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_rollout_files(&path, files)?;
+        } else if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_rollout_file_name)
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 pub mod tests;
@@ -4,8 +4,15 @@ use std::fs::File;
 use std::fs::{self};
 use std::io::Error as IoError;
 use std::path::Path;
+use std::path::PathBuf;
 
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::plan_tool::UpdatePlanArgs;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::SandboxPolicy;
+use codex_protocol::protocol::TokenUsage;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -20,12 +27,15 @@ use tracing::info;
 use tracing::warn;
 
 use super::SESSIONS_SUBDIR;
+use super::decode_rollout;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
 use super::policy::is_persisted_response_item;
+use super::retention::spawn_maintenance;
 use crate::config::Config;
 use crate::conversation_manager::InitialHistory;
+use crate::conversation_manager::ResumedHistory;
 use crate::git_info::GitInfo;
 use crate::git_info::collect_git_info;
 use codex_protocol::models::ResponseItem;
@@ -35,6 +45,16 @@ pub struct SessionMeta {
     pub id: ConversationId,
     pub timestamp: String,
     pub instructions: Option<String>,
+    /// Conversation that delegated this session, when it was spawned by the
+    /// `delegate` tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<ConversationId>,
+    /// Working directory the session was started in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<std::path::PathBuf>,
+    /// Id of the model provider the session talks to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_provider: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -45,8 +65,87 @@ struct SessionMetaWithGit {
     git: Option<GitInfo>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct SessionStateSnapshot {}
+/// Session state that is not part of the transcript. It is written as a
+/// `{"record_type":"state", ...}` line whenever it changes and restored when
+/// the rollout is resumed. Every field defaults to empty so that the bare
+/// state lines of older rollouts still parse.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct SessionStateSnapshot {
+    /// Commands the user approved for the rest of the session, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approved_commands: Vec<Vec<String>>,
+    /// Directories the user made writable for the rest of the session.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub granted_writable_roots: Vec<PathBuf>,
+    /// Latest plan sent through the `update_plan` tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<UpdatePlanArgs>,
+    /// Model and policies of the current turn context, including changes
+    /// made with `Op::OverrideTurnContext`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn: Option<TurnSettings>,
+    /// Tokens used by every model response of the session so far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_token_usage: Option<TokenUsage>,
+    /// Approval requests shown to the user.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub approval_requests: u64,
+    /// Turns that were interrupted, replaced or stopped by a budget.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub aborted_turns: u64,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TurnSettings {
+    pub model: String,
+    pub reasoning_effort: ReasoningEffortConfig,
+    pub reasoning_summary: ReasoningSummaryConfig,
+    pub approval_policy: AskForApproval,
+    pub sandbox_policy: SandboxPolicy,
+    pub cwd: PathBuf,
+}
+
+/// Tokens used by one model response. Written as a
+/// `{"record_type":"usage", ...}` line so that usage can be attributed to the
+/// model that produced it and the time it was used.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UsageRecord {
+    pub timestamp: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+/// A child conversation started by the `delegate` tool. Written to the
+/// parent's rollout as a `{"record_type":"delegate", ...}` line, so that a
+/// parent links to its children as `SessionMeta::parent_id` links back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DelegateRecord {
+    pub call_id: String,
+    pub conversation_id: ConversationId,
+    pub rollout_path: PathBuf,
+}
+
+/// Marks the end of the history a resumed session replays from the rollout
+/// it was resumed from. Written as a `{"record_type":"resumed", ...}` line
+/// with the counters the session carried over, so that usage statistics only
+/// count what happened after the resume.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ResumeRecord {
+    /// Tokens used before the resume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_token_usage: Option<TokenUsage>,
+    /// Approval requests shown before the resume.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub approval_requests: u64,
+    /// Turns aborted before the resume.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub aborted_turns: u64,
+}
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct SavedSession {
@@ -75,7 +174,10 @@ pub struct RolloutRecorder {
 
 enum RolloutCmd {
     AddItems(Vec<ResponseItem>),
-    UpdateState(SessionStateSnapshot),
+    UpdateState(Box<SessionStateSnapshot>),
+    AddDelegate(DelegateRecord),
+    AddUsage(UsageRecord),
+    AddResume(ResumeRecord),
     Shutdown { ack: oneshot::Sender<()> },
 }
 
@@ -97,6 +199,7 @@ impl RolloutRecorder {
         config: &Config,
         conversation_id: ConversationId,
         instructions: Option<String>,
+        parent_id: Option<ConversationId>,
     ) -> std::io::Result<Self> {
         let LogFileInfo {
             file,
@@ -105,13 +208,10 @@ impl RolloutRecorder {
             path,
         } = create_log_file(config, conversation_id)?;
 
-        let timestamp_format: &[FormatItem] = format_description!(
-            "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
-        );
-        let timestamp = timestamp
-            .to_offset(time::UtcOffset::UTC)
-            .format(timestamp_format)
-            .map_err(|e| IoError::other(format!("failed to format timestamp: {e}")))?;
+        let timestamp = format_timestamp(timestamp)?;
+
+        // Starting a session is a good moment to tidy up older ones.
+        spawn_maintenance(config.codex_home.clone(), config.sessions);
 
         // Clone the cwd for the spawned task to collect git info asynchronously
         let cwd = config.cwd.clone();
@@ -131,6 +231,9 @@ impl RolloutRecorder {
                 timestamp,
                 id: session_id,
                 instructions,
+                parent_id,
+                cwd: Some(cwd.clone()),
+                model_provider: Some(config.model_provider_id.clone()),
             }),
             cwd,
         ));
@@ -163,19 +266,54 @@ impl RolloutRecorder {
 
     pub(crate) async fn record_state(&self, state: SessionStateSnapshot) -> std::io::Result<()> {
         self.tx
-            .send(RolloutCmd::UpdateState(state))
+            .send(RolloutCmd::UpdateState(Box::new(state)))
             .await
             .map_err(|e| IoError::other(format!("failed to queue rollout state: {e}")))
     }
 
+    pub(crate) async fn record_delegate(&self, delegate: DelegateRecord) -> std::io::Result<()> {
+        self.tx
+            .send(RolloutCmd::AddDelegate(delegate))
+            .await
+            .map_err(|e| IoError::other(format!("failed to queue rollout delegate: {e}")))
+    }
+
+    /// Records the tokens `model` used for one response, stamped with the
+    /// current time.
+    pub(crate) async fn record_usage(
+        &self,
+        model: &str,
+        usage: &TokenUsage,
+    ) -> std::io::Result<()> {
+        let record = UsageRecord {
+            timestamp: format_timestamp(OffsetDateTime::now_utc())?,
+            model: model.to_string(),
+            usage: usage.clone(),
+        };
+        self.tx
+            .send(RolloutCmd::AddUsage(record))
+            .await
+            .map_err(|e| IoError::other(format!("failed to queue rollout usage: {e}")))
+    }
+
+    /// Marks the end of the history replayed from the rollout this session
+    /// was resumed from.
+    pub(crate) async fn record_resume(&self, resume: ResumeRecord) -> std::io::Result<()> {
+        self.tx
+            .send(RolloutCmd::AddResume(resume))
+            .await
+            .map_err(|e| IoError::other(format!("failed to queue rollout resume: {e}")))
+    }
+
     pub async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
-        let text = tokio::fs::read_to_string(path).await?;
+        let text = decode_rollout(path, tokio::fs::read(path).await?)?;
         let mut lines = text.lines();
         let _ = lines
             .next()
             .ok_or_else(|| IoError::other("empty session file"))?;
         let mut items = Vec::new();
+        let mut state = SessionStateSnapshot::default();
 
         for line in lines {
             if line.trim().is_empty() {
@@ -185,12 +323,18 @@ impl RolloutRecorder {
                 Ok(v) => v,
                 Err(_) => continue,
             };
-            if v.get("record_type")
-                .and_then(|rt| rt.as_str())
-                .map(|s| s == "state")
-                .unwrap_or(false)
-            {
-                continue;
+            match v.get("record_type").and_then(|rt| rt.as_str()) {
+                Some("state") => {
+                    // The last state line describes the session as it ended.
+                    match serde_json::from_value::<SessionStateSnapshot>(v) {
+                        Ok(snapshot) => state = snapshot,
+                        Err(e) => warn!("failed to parse state line: {e}"),
+                    }
+                    continue;
+                }
+                // Delegate links are not part of the conversation history.
+                Some("delegate") | Some("usage") | Some("resumed") => continue,
+                _ => {}
             }
             match serde_json::from_value::<ResponseItem>(v.clone()) {
                 Ok(item) => {
@@ -204,11 +348,20 @@ impl RolloutRecorder {
             }
         }
 
+        // Rollouts written before the plan was part of the state still have
+        // the `update_plan` calls themselves.
+        if state.plan.is_none() {
+            state.plan = last_plan_in(&items);
+        }
+
         info!("Resumed rollout successfully from {path:?}");
         if items.is_empty() {
             Ok(InitialHistory::New)
         } else {
-            Ok(InitialHistory::Resumed(items))
+            Ok(InitialHistory::Resumed(Box::new(ResumedHistory {
+                items,
+                state,
+            })))
         }
     }
 
@@ -228,6 +381,16 @@ impl RolloutRecorder {
     }
 }
 
+/// Arguments of the last well-formed `update_plan` call in `items`.
+fn last_plan_in(items: &[ResponseItem]) -> Option<UpdatePlanArgs> {
+    items.iter().rev().find_map(|item| match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } if name == "update_plan" => serde_json::from_str(arguments).ok(),
+        _ => None,
+    })
+}
+
 struct LogFileInfo {
     /// Opened file handle to the rollout file.
     file: File,
@@ -241,6 +404,17 @@ struct LogFileInfo {
     path: std::path::PathBuf,
 }
 
+/// Formats `timestamp` in UTC with millisecond precision, as in
+/// "2025-01-02T10:00:00.000Z".
+fn format_timestamp(timestamp: OffsetDateTime) -> std::io::Result<String> {
+    let timestamp_format: &[FormatItem] =
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z");
+    timestamp
+        .to_offset(time::UtcOffset::UTC)
+        .format(timestamp_format)
+        .map_err(|e| IoError::other(format!("failed to format timestamp: {e}")))
+}
+
 fn create_log_file(
     config: &Config,
     conversation_id: ConversationId,
@@ -270,6 +444,9 @@ fn create_log_file(
         .append(true)
         .create(true)
         .open(&path)?;
+    // Held until the writer closes the file, so that session maintenance in
+    // any Codex process leaves the rollout of a running session alone.
+    file.lock_shared()?;
 
     Ok(LogFileInfo {
         file,
@@ -323,6 +500,48 @@ async fn rollout_writer(
                     })
                     .await?;
             }
+            RolloutCmd::AddDelegate(delegate) => {
+                #[derive(Serialize)]
+                struct DelegateLine<'a> {
+                    record_type: &'static str,
+                    #[serde(flatten)]
+                    delegate: &'a DelegateRecord,
+                }
+                writer
+                    .write_line(&DelegateLine {
+                        record_type: "delegate",
+                        delegate: &delegate,
+                    })
+                    .await?;
+            }
+            RolloutCmd::AddUsage(usage) => {
+                #[derive(Serialize)]
+                struct UsageLine<'a> {
+                    record_type: &'static str,
+                    #[serde(flatten)]
+                    usage: &'a UsageRecord,
+                }
+                writer
+                    .write_line(&UsageLine {
+                        record_type: "usage",
+                        usage: &usage,
+                    })
+                    .await?;
+            }
+            RolloutCmd::AddResume(resume) => {
+                #[derive(Serialize)]
+                struct ResumeLine<'a> {
+                    record_type: &'static str,
+                    #[serde(flatten)]
+                    resume: &'a ResumeRecord,
+                }
+                writer
+                    .write_line(&ResumeLine {
+                        record_type: "resumed",
+                        resume: &resume,
+                    })
+                    .await?;
+            }
             RolloutCmd::Shutdown { ack } => {
                 let _ = ack.send(());
             }
@@ -345,3 +564,118 @@ impl JsonlWriter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn rollout_file(lines: &[Value]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        let meta = json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "timestamp": "2025-01-02T10:00:00.000Z",
+        });
+        writeln!(file, "{meta}").unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+        file
+    }
+
+    fn user_message(text: &str) -> Value {
+        json!({
+            "type": "message",
+            "role": "user",
+            "content": [{ "type": "input_text", "text": text }],
+        })
+    }
+
+    async fn resumed_state(file: &NamedTempFile) -> SessionStateSnapshot {
+        match RolloutRecorder::get_rollout_history(file.path())
+            .await
+            .unwrap()
+        {
+            InitialHistory::Resumed(resumed) => resumed.state,
+            InitialHistory::New => panic!("expected a resumed history"),
+        }
+    }
+
+    #[tokio::test]
+    async fn resume_uses_the_last_state_line() {
+        let file = rollout_file(&[
+            json!({ "record_type": "state", "approved_commands": [["ls"]] }),
+            user_message("hi"),
+            json!({
+                "record_type": "state",
+                "approved_commands": [["ls"], ["pwd"]],
+                "turn": {
+                    "model": "o3",
+                    "reasoning_effort": "high",
+                    "reasoning_summary": "auto",
+                    "approval_policy": "on-request",
+                    "sandbox_policy": { "mode": "read-only" },
+                    "cwd": "/work",
+                },
+            }),
+        ]);
+        let state = resumed_state(&file).await;
+        assert_eq!(
+            state.approved_commands,
+            vec![vec!["ls".to_string()], vec!["pwd".to_string()]]
+        );
+        let turn = state.turn.unwrap();
+        assert_eq!(turn.model, "o3");
+        assert_eq!(turn.approval_policy, AskForApproval::OnRequest);
+        assert_eq!(turn.sandbox_policy, SandboxPolicy::ReadOnly);
+    }
+
+    #[tokio::test]
+    async fn resume_skips_delegate_lines() {
+        let file = rollout_file(&[
+            user_message("hi"),
+            json!({
+                "record_type": "delegate",
+                "call_id": "c1",
+                "conversation_id": "00000000-0000-0000-0000-000000000002",
+                "rollout_path": "/sessions/child.jsonl",
+            }),
+        ]);
+        match RolloutRecorder::get_rollout_history(file.path())
+            .await
+            .unwrap()
+        {
+            InitialHistory::Resumed(resumed) => assert_eq!(resumed.items.len(), 1),
+            InitialHistory::New => panic!("expected a resumed history"),
+        }
+    }
+
+    #[tokio::test]
+    async fn old_rollouts_recover_the_plan_from_update_plan_calls() {
+        let plan = json!({
+            "plan": [{ "step": "write tests", "status": "in_progress" }],
+        });
+        let file = rollout_file(&[
+            json!({ "record_type": "state" }),
+            user_message("hi"),
+            json!({
+                "type": "function_call",
+                "name": "update_plan",
+                "arguments": plan.to_string(),
+                "call_id": "c1",
+            }),
+            json!({ "record_type": "state" }),
+        ]);
+        let state = resumed_state(&file).await;
+        assert_eq!(
+            state,
+            SessionStateSnapshot {
+                plan: Some(serde_json::from_value(plan).unwrap()),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Background maintenance of `CODEX_HOME/sessions`: gzips old rollouts and
+//! prunes the oldest ones according to [`SessionRetention`].
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use tracing::info;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use super::collect_rollout_files;
+use super::is_compressed;
+use crate::config_types::SessionRetention;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct MaintenanceReport {
+    pub compressed: usize,
+    pub deleted: usize,
+}
+
+struct RolloutFile {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    /// Referenced from `saves.json` or still being recorded.
+    protected: bool,
+}
+
+/// Runs [`run_maintenance`] on a blocking thread unless `retention` is empty.
+pub(crate) fn spawn_maintenance(codex_home: PathBuf, retention: SessionRetention) {
+    if retention == SessionRetention::default() {
+        return;
+    }
+    tokio::task::spawn_blocking(move || {
+        match run_maintenance(&codex_home, &retention, SystemTime::now()) {
+            Ok(report) if report != MaintenanceReport::default() => info!(
+                "session maintenance compressed {} and deleted {} rollouts",
+                report.compressed, report.deleted
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("session maintenance failed: {e}"),
+        }
+    });
+}
+
+/// Applies `retention` to every rollout under `codex_home`: deletes those
+/// older than `max_age_days`, gzips those older than `compress_after_days`,
+/// then deletes the oldest remaining ones while the total exceeds
+/// `max_total_size_mb`. Sessions referenced from `saves.json` and rollouts
+/// of running sessions are never compressed or deleted.
+pub(crate) fn run_maintenance(
+    codex_home: &Path,
+    retention: &SessionRetention,
+    now: SystemTime,
+) -> io::Result<MaintenanceReport> {
+    let saved = saved_session_paths(codex_home);
+    let mut paths = Vec::new();
+    collect_rollout_files(&codex_home.join(SESSIONS_SUBDIR), &mut paths)?;
+    let mut files: Vec<RolloutFile> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let modified = metadata.modified().ok()?;
+            Some(RolloutFile {
+                protected: saved.contains(&path) || is_being_recorded(&path),
+                path,
+                modified,
+                len: metadata.len(),
+            })
+        })
+        .collect();
+    // Oldest first, so size pruning removes the least recently used.
+    files.sort_by_key(|f| f.modified);
+
+    let older_than = |file: &RolloutFile, days: Option<u64>| {
+        days.is_some_and(|days| {
+            now.duration_since(file.modified).unwrap_or_default()
+                > Duration::from_secs(days * SECS_PER_DAY)
+        })
+    };
+
+    let mut report = MaintenanceReport::default();
+    let mut kept = Vec::with_capacity(files.len());
+    for mut file in files {
+        if file.protected {
+            kept.push(file);
+            continue;
+        }
+        if older_than(&file, retention.max_age_days) {
+            match fs::remove_file(&file.path) {
+                Ok(()) => report.deleted += 1,
+                Err(e) => {
+                    warn!("failed to delete {}: {e}", file.path.display());
+                    kept.push(file);
+                }
+            }
+            continue;
+        }
+        if !is_compressed(&file.path) && older_than(&file, retention.compress_after_days) {
+            match compress(&file.path, file.modified) {
+                Ok((path, len)) => {
+                    file.path = path;
+                    file.len = len;
+                    report.compressed += 1;
+                }
+                Err(e) => warn!("failed to compress {}: {e}", file.path.display()),
+            }
+        }
+        kept.push(file);
+    }
+
+    if let Some(max_mb) = retention.max_total_size_mb {
+        let max_bytes = max_mb.saturating_mul(1024 * 1024);
+        let mut total: u64 = kept.iter().map(|f| f.len).sum();
+        for file in kept.iter().filter(|f| !f.protected) {
+            if total <= max_bytes {
+                break;
+            }
+            match fs::remove_file(&file.path) {
+                Ok(()) => {
+                    total = total.saturating_sub(file.len);
+                    report.deleted += 1;
+                }
+                Err(e) => warn!("failed to delete {}: {e}", file.path.display()),
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Gzips `path` next to itself as `.jsonl.gz`, keeping its modification
+/// time, and removes the original. Returns the new path and size.
+fn compress(path: &Path, modified: SystemTime) -> io::Result<(PathBuf, u64)> {
+    let mut target = path.as_os_str().to_owned();
+    target.push(".gz");
+    let target = PathBuf::from(target);
+    let tmp = target.with_extension("gz.tmp");
+
+    let mut encoder = GzEncoder::new(fs::File::create(&tmp)?, Compression::default());
+    encoder.write_all(&fs::read(path)?)?;
+    let file = encoder.finish()?;
+    file.sync_all()?;
+    // Age-based rules keep working on the compressed file.
+    file.set_modified(modified)?;
+    let len = file.metadata()?.len();
+    drop(file);
+
+    fs::rename(&tmp, &target)?;
+    fs::remove_file(path)?;
+    Ok((target, len))
+}
+
+/// Whether a running session, possibly in another Codex process, holds the
+/// lock that [`super::recorder::RolloutRecorder`] takes on its rollout.
+fn is_being_recorded(path: &Path) -> bool {
+    if is_compressed(path) {
+        return false;
+    }
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(fs::TryLockError::WouldBlock))
+}
+
+/// Rollout paths referenced from `CODEX_HOME/saves.json`.
+fn saved_session_paths(codex_home: &Path) -> HashSet<PathBuf> {
+    let Ok(text) = fs::read_to_string(codex_home.join("saves.json")) else {
+        return HashSet::new();
+    };
+    let Ok(root) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return HashSet::new();
+    };
+    ["by_name", "by_id"]
+        .iter()
+        .filter_map(|key| root.get(*key).and_then(|v| v.as_object()))
+        .flat_map(|map| map.values())
+        .filter_map(|v| v.as_str())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rollout::COMPRESSED_EXTENSION;
+    use crate::rollout::list::get_conversations;
+    use crate::rollout::read_rollout;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    const DAY: Duration = Duration::from_secs(SECS_PER_DAY);
+
+    fn write_rollout(codex_home: &Path, name: &str, age: Duration, len: usize) -> PathBuf {
+        let dir = codex_home.join(SESSIONS_SUBDIR).join("2025/01/02");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let meta = r#"{"id":"00000000-0000-0000-0000-000000000001","timestamp":"2025-01-02T10:00:00.000Z"}"#;
+        let padding = "x".repeat(len);
+        fs::write(&path, format!("{meta}\n{{\"padding\":\"{padding}\"}}\n")).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+        path
+    }
+
+    fn name(n: u8) -> String {
+        format!("rollout-2025-01-02T10-00-0{n}-00000000-0000-0000-0000-00000000000{n}.jsonl")
+    }
+
+    #[test]
+    fn compresses_old_rollouts_and_deletes_expired_ones() {
+        let home = TempDir::new().unwrap();
+        let expired = write_rollout(home.path(), &name(1), DAY * 40, 10);
+        let old = write_rollout(home.path(), &name(2), DAY * 10, 10);
+        let fresh = write_rollout(home.path(), &name(3), Duration::ZERO, 10);
+        let saved = write_rollout(home.path(), &name(4), DAY * 50, 10);
+        fs::write(
+            home.path().join("saves.json"),
+            serde_json::json!({ "by_name": { "keep": saved }, "by_id": {} }).to_string(),
+        )
+        .unwrap();
+
+        let retention = SessionRetention {
+            max_age_days: Some(30),
+            max_total_size_mb: None,
+            compress_after_days: Some(7),
+        };
+        let report = run_maintenance(home.path(), &retention, SystemTime::now()).unwrap();
+        assert_eq!(
+            report,
+            MaintenanceReport {
+                compressed: 1,
+                deleted: 1
+            }
+        );
+        assert!(!expired.exists());
+        assert!(!old.exists());
+        assert!(fresh.exists());
+        assert!(saved.exists());
+
+        let mut gz = old.into_os_string();
+        gz.push(".gz");
+        let gz = PathBuf::from(gz);
+        assert!(read_rollout(&gz).unwrap().contains("\"padding\""));
+        let age = SystemTime::now()
+            .duration_since(fs::metadata(&gz).unwrap().modified().unwrap())
+            .unwrap();
+        assert!(age > DAY * 9);
+    }
+
+    #[test]
+    fn prunes_oldest_unsaved_rollouts_over_the_size_limit() {
+        let home = TempDir::new().unwrap();
+        let mib = 1024 * 1024;
+        let oldest = write_rollout(home.path(), &name(1), DAY * 3, mib);
+        let middle = write_rollout(home.path(), &name(2), DAY * 2, mib);
+        let newest = write_rollout(home.path(), &name(3), DAY, mib);
+
+        let retention = SessionRetention {
+            max_total_size_mb: Some(2),
+            ..Default::default()
+        };
+        let report = run_maintenance(home.path(), &retention, SystemTime::now()).unwrap();
+        assert_eq!(report.deleted, 2);
+        assert!(!oldest.exists());
+        assert!(!middle.exists());
+        assert!(newest.exists());
+    }
+
+    #[test]
+    fn rollouts_of_running_sessions_are_kept() {
+        let home = TempDir::new().unwrap();
+        let live = write_rollout(home.path(), &name(1), DAY * 40, 10);
+        let ended = write_rollout(home.path(), &name(2), DAY * 40, 10);
+        let recording = fs::File::options().append(true).open(&live).unwrap();
+        recording.lock_shared().unwrap();
+
+        let retention = SessionRetention {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        let report = run_maintenance(home.path(), &retention, SystemTime::now()).unwrap();
+        assert_eq!(report.deleted, 1);
+        assert!(live.exists());
+        assert!(!ended.exists());
+    }
+
+    #[tokio::test]
+    async fn compressed_rollouts_are_listed_and_resumable() {
+        let home = TempDir::new().unwrap();
+        write_rollout(home.path(), &name(1), DAY * 10, 10);
+        let retention = SessionRetention {
+            compress_after_days: Some(1),
+            ..Default::default()
+        };
+        run_maintenance(home.path(), &retention, SystemTime::now()).unwrap();
+
+        let page = get_conversations(home.path(), 10, None).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        let item = &page.items[0];
+        assert!(item.path.to_string_lossy().ends_with(COMPRESSED_EXTENSION));
+        assert_eq!(
+            item.head[0]["id"],
+            serde_json::json!("00000000-0000-0000-0000-000000000001")
+        );
+    }
+}
@@ -0,0 +1,699 @@
+//! Full-text index over recorded session rollouts.
+//!
+//! The index is stored in `CODEX_HOME/session_index.json` and is refreshed
+//! incrementally: rollout files are append-only, so every entry remembers how
+//! many bytes of its file it has consumed and only the new tail is parsed on
+//! the next update.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::Hunk;
+use codex_apply_patch::MaybeApplyPatch;
+use codex_apply_patch::maybe_parse_apply_patch;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::USER_MESSAGE_BEGIN;
+use serde::Deserialize;
+use serde::Serialize;
+use time::Date;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use super::collect_rollout_files;
+use super::is_compressed;
+use super::read_rollout;
+
+/// Name of the index file inside `CODEX_HOME`.
+pub const SESSION_INDEX_FILE: &str = "session_index.json";
+
+/// Bumped whenever the on-disk layout changes; older indexes are rebuilt.
+const INDEX_VERSION: u32 = 1;
+
+/// Longest message or command kept per index entry.
+const MAX_INDEXED_TEXT_BYTES: usize = 4 * 1024;
+
+/// Bytes of context shown before a match in a snippet.
+const SNIPPET_BEFORE: usize = 30;
+/// Bytes of context shown from the start of a match in a snippet.
+const SNIPPET_AFTER: usize = 60;
+
+/// Searchable summary of every rollout under `CODEX_HOME/sessions`, newest
+/// session first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionIndex {
+    version: u32,
+    sessions: Vec<IndexedSession>,
+}
+
+/// What the index remembers about one rollout file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexedSession {
+    /// Absolute path to the rollout file.
+    pub path: PathBuf,
+    pub id: Option<String>,
+    /// Session start as recorded in the meta line (RFC 3339, UTC).
+    pub timestamp: Option<String>,
+    /// Working directory the session was started in.
+    pub cwd: Option<PathBuf>,
+    /// Git branch checked out when the session started.
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub user_messages: Vec<String>,
+    #[serde(default)]
+    pub agent_messages: Vec<String>,
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Paths touched through `apply_patch`, as written in the patch.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Bytes of the rollout file already folded into this entry.
+    #[serde(default)]
+    indexed_len: u64,
+}
+
+/// Filters and words for [`SessionIndex::search`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionSearchQuery {
+    /// Words that must all appear somewhere in the session (case-insensitive).
+    pub terms: Vec<String>,
+    /// Only sessions started in this directory or below it.
+    pub cwd: Option<PathBuf>,
+    /// Only sessions started on this git branch.
+    pub branch: Option<String>,
+    /// Only sessions started on or after this date (UTC).
+    pub since: Option<Date>,
+    /// Only sessions started on or before this date (UTC).
+    pub until: Option<Date>,
+}
+
+/// Part of a session that a search term matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    UserMessage,
+    AgentMessage,
+    Command,
+    File,
+}
+
+impl SearchField {
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchField::UserMessage => "user",
+            SearchField::AgentMessage => "agent",
+            SearchField::Command => "command",
+            SearchField::File => "file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub field: SearchField,
+    /// Single-line excerpt around the match.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSearchHit {
+    pub path: PathBuf,
+    pub timestamp: Option<String>,
+    pub cwd: Option<PathBuf>,
+    pub branch: Option<String>,
+    /// First user message of the session.
+    pub preview: Option<String>,
+    /// One excerpt per search term, in term order.
+    pub matches: Vec<SearchMatch>,
+}
+
+impl SessionIndex {
+    /// Reads the index stored in `codex_home`. A missing, unreadable or
+    /// outdated index yields an empty one that the next update rebuilds.
+    pub fn load(codex_home: &Path) -> io::Result<Self> {
+        let text = match fs::read_to_string(codex_home.join(SESSION_INDEX_FILE)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        match serde_json::from_str::<Self>(&text) {
+            Ok(index) if index.version == INDEX_VERSION => Ok(index),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the index, folds in everything recorded since it was last
+    /// written, and saves it back.
+    pub fn update(codex_home: &Path) -> io::Result<Self> {
+        let mut index = Self::load(codex_home)?;
+        index.refresh(&codex_home.join(SESSIONS_SUBDIR))?;
+        index.save(codex_home)?;
+        Ok(index)
+    }
+
+    /// Async wrapper around [`SessionIndex::update`] that keeps the file I/O
+    /// off the runtime threads.
+    pub async fn update_in_background(codex_home: &Path) -> io::Result<Self> {
+        let codex_home = codex_home.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::update(&codex_home))
+            .await
+            .map_err(|e| io::Error::other(format!("session index task failed: {e}")))?
+    }
+
+    pub fn sessions(&self) -> &[IndexedSession] {
+        &self.sessions
+    }
+
+    /// Returns up to `limit` sessions, newest first, that pass every filter
+    /// in `query` and contain every one of its terms.
+    pub fn search(&self, query: &SessionSearchQuery, limit: usize) -> Vec<SessionSearchHit> {
+        let terms: Vec<String> = query.terms.iter().map(|t| t.to_lowercase()).collect();
+        self.sessions
+            .iter()
+            .filter(|session| session.matches_filters(query))
+            .filter_map(|session| {
+                let matches = terms
+                    .iter()
+                    .map(|term| session.find(term))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(SessionSearchHit {
+                    path: session.path.clone(),
+                    timestamp: session.timestamp.clone(),
+                    cwd: session.cwd.clone(),
+                    branch: session.branch.clone(),
+                    preview: session.user_messages.first().cloned(),
+                    matches,
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    fn refresh(&mut self, sessions_dir: &Path) -> io::Result<()> {
+        let mut previous: HashMap<PathBuf, IndexedSession> = self
+            .sessions
+            .drain(..)
+            .map(|session| (session.path.clone(), session))
+            .collect();
+        let mut files = Vec::new();
+        collect_rollout_files(sessions_dir, &mut files)?;
+        for path in files {
+            let entry = previous.remove(&path).unwrap_or_else(|| IndexedSession {
+                path: path.clone(),
+                ..Default::default()
+            });
+            match index_rollout(entry) {
+                Ok(entry) => self.sessions.push(entry),
+                Err(e) => warn!("failed to index {}: {e}", path.display()),
+            }
+        }
+        self.sessions.sort_by(|a, b| {
+            b.timestamp
+                .cmp(&a.timestamp)
+                .then_with(|| b.path.cmp(&a.path))
+        });
+        self.version = INDEX_VERSION;
+        Ok(())
+    }
+
+    fn save(&self, codex_home: &Path) -> io::Result<()> {
+        let path = codex_home.join(SESSION_INDEX_FILE);
+        let tmp = codex_home.join(format!("{SESSION_INDEX_FILE}.tmp"));
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)
+    }
+}
+
+impl SessionSearchQuery {
+    /// Parses search-box input. Plain words become terms; `cwd:`, `branch:`,
+    /// `since:` and `until:` prefixes set the filters, with dates written as
+    /// `YYYY-MM-DD`.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut query = Self::default();
+        for word in input.split_whitespace() {
+            if let Some(cwd) = word.strip_prefix("cwd:") {
+                query.cwd = Some(PathBuf::from(cwd));
+            } else if let Some(branch) = word.strip_prefix("branch:") {
+                query.branch = Some(branch.to_string());
+            } else if let Some(date) = word.strip_prefix("since:") {
+                query.since = Some(parse_search_date(date)?);
+            } else if let Some(date) = word.strip_prefix("until:") {
+                query.until = Some(parse_search_date(date)?);
+            } else {
+                query.terms.push(word.to_string());
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.cwd.is_none()
+            && self.branch.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date used by the `since`/`until` filters.
+pub fn parse_search_date(date: &str) -> Result<Date, String> {
+    Date::parse(date, format_description!("[year]-[month]-[day]"))
+        .map_err(|_| format!("invalid date `{date}`, expected YYYY-MM-DD"))
+}
+
+impl IndexedSession {
+    fn matches_filters(&self, query: &SessionSearchQuery) -> bool {
+        if let Some(cwd) = &query.cwd
+            && !self.cwd.as_ref().is_some_and(|c| c.starts_with(cwd))
+        {
+            return false;
+        }
+        if let Some(branch) = &query.branch
+            && self.branch.as_ref() != Some(branch)
+        {
+            return false;
+        }
+        if query.since.is_none() && query.until.is_none() {
+            return true;
+        }
+        let Some(date) = self.start_date() else {
+            return false;
+        };
+        query.since.is_none_or(|since| date >= since)
+            && query.until.is_none_or(|until| date <= until)
+    }
+
+    fn start_date(&self) -> Option<Date> {
+        let timestamp = OffsetDateTime::parse(self.timestamp.as_deref()?, &Rfc3339).ok()?;
+        Some(timestamp.to_offset(time::UtcOffset::UTC).date())
+    }
+
+    /// First place `term` (already lowercased) occurs, searching messages
+    /// before commands and file paths.
+    fn find(&self, term: &str) -> Option<SearchMatch> {
+        let fields = [
+            (SearchField::UserMessage, &self.user_messages),
+            (SearchField::AgentMessage, &self.agent_messages),
+            (SearchField::Command, &self.commands),
+            (SearchField::File, &self.files),
+        ];
+        fields.into_iter().find_map(|(field, texts)| {
+            texts
+                .iter()
+                .find_map(|text| snippet(text, term).map(|snippet| SearchMatch { field, snippet }))
+        })
+    }
+
+    /// Folds complete rollout lines into the entry; `at_meta` says whether
+    /// the first of them is the session meta line.
+    fn absorb_lines(&mut self, text: &str, mut at_meta: bool) {
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if at_meta {
+                at_meta = false;
+                self.absorb_meta(line);
+            } else {
+                self.absorb_item(line);
+            }
+        }
+    }
+
+    fn absorb_meta(&mut self, line: &str) {
+        #[derive(Deserialize)]
+        struct MetaLine {
+            id: Option<String>,
+            timestamp: Option<String>,
+            cwd: Option<PathBuf>,
+            git: Option<GitLine>,
+        }
+        #[derive(Deserialize)]
+        struct GitLine {
+            branch: Option<String>,
+        }
+
+        if let Ok(meta) = serde_json::from_str::<MetaLine>(line) {
+            self.id = meta.id;
+            self.timestamp = meta.timestamp;
+            self.cwd = meta.cwd;
+            self.branch = meta.git.and_then(|git| git.branch);
+        }
+    }
+
+    fn absorb_item(&mut self, line: &str) {
+        // State lines and unknown records simply fail to parse.
+        let Ok(item) = serde_json::from_str::<ResponseItem>(line) else {
+            return;
+        };
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                for content in content {
+                    match content {
+                        ContentItem::InputText { text }
+                            if role == "user"
+                                && matches!(
+                                    InputMessageKind::from(("user", text.as_str())),
+                                    InputMessageKind::Plain
+                                ) =>
+                        {
+                            // Strip ide context.
+                            let text = match text.find(USER_MESSAGE_BEGIN) {
+                                Some(idx) => text[idx + USER_MESSAGE_BEGIN.len()..].trim(),
+                                None => text.trim(),
+                            };
+                            if !text.is_empty() {
+                                self.user_messages.push(truncate(text));
+                            }
+                        }
+                        ContentItem::OutputText { text } if role == "assistant" => {
+                            self.agent_messages.push(truncate(text.trim()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ResponseItem::LocalShellCall {
+                action: LocalShellAction::Exec(exec),
+                ..
+            } => self.absorb_command(&exec.command),
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => match name.as_str() {
+                "container.exec" | "shell" => {
+                    if let Ok(params) = serde_json::from_str::<ShellToolCallParams>(&arguments) {
+                        self.absorb_command(&params.command);
+                    }
+                }
+                "apply_patch" => {
+                    #[derive(Deserialize)]
+                    struct ApplyPatchArgs {
+                        input: String,
+                    }
+                    if let Ok(args) = serde_json::from_str::<ApplyPatchArgs>(&arguments) {
+                        self.absorb_command(&["apply_patch".to_string(), args.input]);
+                    }
+                }
+                _ => {}
+            },
+            ResponseItem::CustomToolCall { name, input, .. } if name == "apply_patch" => {
+                self.absorb_command(&["apply_patch".to_string(), input]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Records a command, or the files it touches when it is `apply_patch`.
+    fn absorb_command(&mut self, command: &[String]) {
+        if let MaybeApplyPatch::Body(args) = maybe_parse_apply_patch(command) {
+            for hunk in args.hunks {
+                let paths = match hunk {
+                    Hunk::AddFile { path, .. } | Hunk::DeleteFile { path } => vec![path],
+                    Hunk::UpdateFile {
+                        path, move_path, ..
+                    } => std::iter::once(path).chain(move_path).collect(),
+                    Hunk::CopyFile {
+                        path, copy_path, ..
+                    } => vec![path, copy_path],
+                };
+                for path in paths {
+                    let path = path.display().to_string();
+                    if !self.files.contains(&path) {
+                        self.files.push(path);
+                    }
+                }
+            }
+            return;
+        }
+        let text = match command {
+            [bash, flag, script] if bash == "bash" && flag == "-lc" => script.clone(),
+            _ => shlex::try_join(command.iter().map(String::as_str))
+                .unwrap_or_else(|_| command.join(" ")),
+        };
+        self.commands.push(truncate(&text));
+    }
+}
+
+/// Folds the unread tail of `entry`'s rollout file into it.
+fn index_rollout(mut entry: IndexedSession) -> io::Result<IndexedSession> {
+    let mut file = fs::File::open(&entry.path)?;
+    let len = file.metadata()?.len();
+    if len < entry.indexed_len {
+        // The file was rewritten rather than appended to; start over.
+        entry = IndexedSession {
+            path: entry.path,
+            ..Default::default()
+        };
+    }
+    if len == entry.indexed_len {
+        return Ok(entry);
+    }
+    if is_compressed(&entry.path) {
+        // Compressed rollouts are complete; index them in one go and
+        // remember the compressed length to detect changes.
+        let text = read_rollout(&entry.path)?;
+        entry = IndexedSession {
+            path: entry.path,
+            ..Default::default()
+        };
+        entry.absorb_lines(&text, true);
+        entry.indexed_len = len;
+        return Ok(entry);
+    }
+    file.seek(SeekFrom::Start(entry.indexed_len))?;
+    let mut buf = Vec::new();
+    file.take(len - entry.indexed_len).read_to_end(&mut buf)?;
+    // Only consume complete lines; a trailing partial line is still being
+    // written and is picked up next time.
+    let Some(end) = buf.iter().rposition(|b| *b == b'\n') else {
+        return Ok(entry);
+    };
+    let at_meta = entry.indexed_len == 0;
+    entry.absorb_lines(&String::from_utf8_lossy(&buf[..=end]), at_meta);
+    entry.indexed_len += end as u64 + 1;
+    Ok(entry)
+}
+
+fn truncate(text: &str) -> String {
+    let mut end = text.len().min(MAX_INDEXED_TEXT_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
+/// Single-line excerpt of `text` around the first occurrence of `term`
+/// (already lowercased), or `None` when it does not occur.
+fn snippet(text: &str, term: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let found = lower.find(term)?;
+    // Lowercasing can change byte lengths outside ASCII; fall back to the
+    // start of the text when the offsets no longer line up.
+    let idx = if lower.len() == text.len() && text.is_char_boundary(found) {
+        found
+    } else {
+        0
+    };
+    let mut start = idx.saturating_sub(SNIPPET_BEFORE);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (idx + SNIPPET_AFTER).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    let mut snippet = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < text.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_rollout(codex_home: &Path, name: &str, lines: &[serde_json::Value]) -> PathBuf {
+        let dir = codex_home.join(SESSIONS_SUBDIR).join("2025/01/02");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let mut file = fs::File::create(&path).unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+        path
+    }
+
+    fn meta(timestamp: &str, cwd: &str, branch: &str) -> serde_json::Value {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "timestamp": timestamp,
+            "instructions": null,
+            "cwd": cwd,
+            "git": { "branch": branch },
+        })
+    }
+
+    fn user(text: &str) -> serde_json::Value {
+        json!({
+            "type": "message",
+            "role": "user",
+            "content": [{ "type": "input_text", "text": text }],
+        })
+    }
+
+    fn terms(query: &str) -> SessionSearchQuery {
+        SessionSearchQuery::parse(query).unwrap()
+    }
+
+    #[test]
+    fn indexes_messages_commands_and_patched_files() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            "rollout-2025-01-02T10-00-00-a.jsonl",
+            &[
+                meta("2025-01-02T10:00:00.000Z", "/work/repo", "main"),
+                user("<user_instructions>ignore me</user_instructions>"),
+                user("Fix the flaky login test"),
+                json!({
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": "The retry loop is racy." }],
+                }),
+                json!({
+                    "type": "function_call",
+                    "name": "shell",
+                    "arguments": "{\"command\":[\"bash\",\"-lc\",\"cargo test -p login\"]}",
+                    "call_id": "c1",
+                }),
+                json!({
+                    "type": "custom_tool_call",
+                    "name": "apply_patch",
+                    "input": "*** Begin Patch\n*** Add File: src/retry.rs\n+fn retry() {}\n*** End Patch",
+                    "call_id": "c2",
+                }),
+                json!({ "record_type": "state" }),
+            ],
+        );
+
+        let index = SessionIndex::update(home.path()).unwrap();
+        let session = &index.sessions()[0];
+        assert_eq!(session.cwd, Some(PathBuf::from("/work/repo")));
+        assert_eq!(session.branch.as_deref(), Some("main"));
+        assert_eq!(session.user_messages, vec!["Fix the flaky login test"]);
+        assert_eq!(session.agent_messages, vec!["The retry loop is racy."]);
+        assert_eq!(session.commands, vec!["cargo test -p login"]);
+        assert_eq!(session.files, vec!["src/retry.rs"]);
+
+        let hits = index.search(&terms("LOGIN retry.rs"), 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].matches,
+            vec![
+                SearchMatch {
+                    field: SearchField::UserMessage,
+                    snippet: "Fix the flaky login test".to_string(),
+                },
+                SearchMatch {
+                    field: SearchField::File,
+                    snippet: "src/retry.rs".to_string(),
+                },
+            ]
+        );
+        assert!(index.search(&terms("login missing"), 10).is_empty());
+    }
+
+    #[test]
+    fn update_only_reads_appended_lines() {
+        let home = TempDir::new().unwrap();
+        let path = write_rollout(
+            home.path(),
+            "rollout-2025-01-02T10-00-00-a.jsonl",
+            &[
+                meta("2025-01-02T10:00:00.000Z", "/work", "main"),
+                user("first"),
+            ],
+        );
+        SessionIndex::update(home.path()).unwrap();
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}", user("second")).unwrap();
+        // A partial line is left for the next update.
+        write!(file, "{{\"type\":\"mess").unwrap();
+
+        let index = SessionIndex::update(home.path()).unwrap();
+        assert_eq!(index.sessions()[0].user_messages, vec!["first", "second"]);
+        assert_eq!(index.sessions()[0].branch.as_deref(), Some("main"));
+
+        // Reloading from disk gives the same result without rereading.
+        let reloaded = SessionIndex::load(home.path()).unwrap();
+        assert_eq!(
+            reloaded.sessions()[0].user_messages,
+            vec!["first", "second"]
+        );
+    }
+
+    #[test]
+    fn filters_by_cwd_branch_and_date() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            "rollout-2025-01-01T10-00-00-a.jsonl",
+            &[
+                meta("2025-01-01T10:00:00.000Z", "/work/api", "main"),
+                user("deploy"),
+            ],
+        );
+        write_rollout(
+            home.path(),
+            "rollout-2025-03-01T10-00-00-b.jsonl",
+            &[
+                meta("2025-03-01T10:00:00.000Z", "/work/web", "feature"),
+                user("deploy"),
+            ],
+        );
+        let index = SessionIndex::update(home.path()).unwrap();
+        let found = |query: &str| -> Vec<PathBuf> {
+            index
+                .search(&terms(query), 10)
+                .into_iter()
+                .map(|hit| hit.cwd.unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            found("deploy"),
+            vec![PathBuf::from("/work/web"), PathBuf::from("/work/api")]
+        );
+        assert_eq!(
+            found("deploy cwd:/work/api"),
+            vec![PathBuf::from("/work/api")]
+        );
+        assert_eq!(found("branch:feature"), vec![PathBuf::from("/work/web")]);
+        assert_eq!(found("since:2025-02-01"), vec![PathBuf::from("/work/web")]);
+        assert_eq!(found("until:2025-01-01"), vec![PathBuf::from("/work/api")]);
+        assert!(SessionSearchQuery::parse("since:yesterday").is_err());
+    }
+}
@@ -0,0 +1,664 @@
+//! Usage statistics over recorded rollouts: tokens, estimated cost and
+//! activity per day, model, provider and repository.
+//!
+//! Tokens come from the usage line recorded for every model response (see
+//! [`UsageRecord`]) and are attributed to that response's model and day.
+//! Rollouts recorded before those lines existed fall back to the totals of
+//! their last state line (see [`SessionStateSnapshot`]), attributed to the
+//! session's last model and start day. Sessions and activity are attributed
+//! to the day the session started and its last model.
+//!
+//! A resumed session replays the history of the rollout it was resumed from
+//! into a new rollout and carries over its counters. Only what follows the
+//! resume line (see [`ResumeRecord`]) is counted for the new rollout, so
+//! nothing is counted twice.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::{self};
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::MaybeApplyPatch;
+use codex_apply_patch::maybe_parse_apply_patch;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::TokenUsage;
+use serde::Serialize;
+use serde_json::Value;
+use time::Date;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use super::SessionStateSnapshot;
+use super::collect_rollout_files;
+use super::read_rollout;
+use super::recorder::ResumeRecord;
+use super::recorder::UsageRecord;
+use crate::config_types::ModelPrice;
+use crate::exec_command::EXEC_COMMAND_TOOL_NAME;
+
+/// Grouping key for sessions whose model, provider or repository is unknown.
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub sessions: u64,
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: u64,
+    pub commands: u64,
+    pub patches: u64,
+    pub approval_requests: u64,
+    pub aborted_turns: u64,
+    /// Estimated cost in USD of the sessions whose model has a price.
+    pub cost: Option<f64>,
+}
+
+impl UsageTotals {
+    /// These totals without the tokens and their cost.
+    fn activity(&self) -> UsageTotals {
+        UsageTotals {
+            input_tokens: 0,
+            cached_input_tokens: 0,
+            output_tokens: 0,
+            reasoning_output_tokens: 0,
+            cost: None,
+            ..self.clone()
+        }
+    }
+
+    fn add(&mut self, other: &UsageTotals) {
+        self.sessions += other.sessions;
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_output_tokens += other.reasoning_output_tokens;
+        self.commands += other.commands;
+        self.patches += other.patches;
+        self.approval_requests += other.approval_requests;
+        self.aborted_turns += other.aborted_turns;
+        self.cost = match (self.cost, other.cost) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Tokens one model used on one day of a session.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelDayUsage {
+    /// `YYYY-MM-DD` (UTC).
+    pub day: Option<String>,
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionUsage {
+    pub path: PathBuf,
+    pub id: Option<String>,
+    pub timestamp: Option<String>,
+    /// Model of the session's last turn.
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub repository: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    /// The tokens in `totals`, per model and day.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub token_usage: Vec<ModelDayUsage>,
+}
+
+impl SessionUsage {
+    /// UTC day the session started on.
+    fn day(&self) -> Option<Date> {
+        utc_day(self.timestamp.as_deref()?)
+    }
+
+    /// Drops the token usage from before `since` and adds the rest to the
+    /// totals. Returns whether anything of the session is left.
+    fn keep_since(&mut self, since: Option<Date>) -> bool {
+        if let Some(since) = since {
+            self.token_usage.retain(|usage| {
+                let day = usage.day.as_deref();
+                day.and_then(|day| Date::parse(day, DAY_FORMAT).ok()) >= Some(since)
+            });
+        }
+        for usage in &self.token_usage {
+            self.totals.add(&usage.totals);
+        }
+        since.is_none_or(|since| self.day() >= Some(since)) || !self.token_usage.is_empty()
+    }
+}
+
+const DAY_FORMAT: &[time::format_description::FormatItem<'static>] =
+    time::macros::format_description!("[year]-[month]-[day]");
+
+/// UTC day of an RFC 3339 timestamp.
+fn utc_day(timestamp: &str) -> Option<Date> {
+    let timestamp = OffsetDateTime::parse(timestamp, &Rfc3339).ok()?;
+    Some(timestamp.to_offset(time::UtcOffset::UTC).date())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageStats {
+    pub total: UsageTotals,
+    /// Keyed by the `YYYY-MM-DD` (UTC) day each session started on.
+    pub by_day: BTreeMap<String, UsageTotals>,
+    pub by_model: BTreeMap<String, UsageTotals>,
+    pub by_provider: BTreeMap<String, UsageTotals>,
+    pub by_repository: BTreeMap<String, UsageTotals>,
+    /// Every session counted, oldest first.
+    pub sessions: Vec<SessionUsage>,
+}
+
+impl UsageStats {
+    /// Aggregates every rollout under `codex_home`, counting only tokens used
+    /// on or after `since` and sessions started on or after it or using
+    /// tokens since, pricing tokens with `prices` (keyed by model slug).
+    pub fn collect(
+        codex_home: &Path,
+        since: Option<Date>,
+        prices: &HashMap<String, ModelPrice>,
+    ) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        collect_rollout_files(&codex_home.join(SESSIONS_SUBDIR), &mut paths)?;
+
+        let mut sessions: Vec<SessionUsage> = paths
+            .into_iter()
+            .filter_map(|path| match read_session(&path, prices) {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    warn!("skipping {}: {e}", path.display());
+                    None
+                }
+            })
+            .filter_map(|mut session| session.keep_since(since).then_some(session))
+            .collect();
+        sessions.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        let mut stats = UsageStats::default();
+        let key = |value: Option<&String>| value.map_or(UNKNOWN, String::as_str).to_string();
+        for session in &sessions {
+            let day = session.day().map(|day| day.to_string());
+            let activity = session.totals.activity();
+            stats.total.add(&session.totals);
+            stats
+                .by_provider
+                .entry(key(session.provider.as_ref()))
+                .or_default()
+                .add(&session.totals);
+            stats
+                .by_repository
+                .entry(key(session.repository.as_ref()))
+                .or_default()
+                .add(&session.totals);
+            stats
+                .by_day
+                .entry(key(day.as_ref()))
+                .or_default()
+                .add(&activity);
+            stats
+                .by_model
+                .entry(key(session.model.as_ref()))
+                .or_default()
+                .add(&activity);
+            for usage in &session.token_usage {
+                stats
+                    .by_day
+                    .entry(key(usage.day.as_ref()))
+                    .or_default()
+                    .add(&usage.totals);
+                stats
+                    .by_model
+                    .entry(key(usage.model.as_ref()))
+                    .or_default()
+                    .add(&usage.totals);
+            }
+        }
+        stats.sessions = sessions;
+        Ok(stats)
+    }
+}
+
+/// Reads one rollout: its metadata, usage lines, last state line and tool
+/// calls. Tokens are left in [`SessionUsage::token_usage`], not yet added to
+/// the totals.
+fn read_session(path: &Path, prices: &HashMap<String, ModelPrice>) -> io::Result<SessionUsage> {
+    let text = read_rollout(path)?;
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let meta: Value = serde_json::from_str(
+        lines
+            .next()
+            .ok_or_else(|| io::Error::other("empty session file"))?,
+    )?;
+    let meta_str = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+    let timestamp = meta_str(meta.get("timestamp"));
+
+    let mut totals = UsageTotals {
+        sessions: 1,
+        ..Default::default()
+    };
+    let mut state = SessionStateSnapshot::default();
+    let mut usage_records = Vec::new();
+    let mut resume = ResumeRecord::default();
+    for line in lines {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        match value.get("record_type").and_then(Value::as_str) {
+            Some("state") => {
+                if let Ok(snapshot) = serde_json::from_value(value) {
+                    state = snapshot;
+                }
+                continue;
+            }
+            Some("usage") => {
+                if let Ok(record) = serde_json::from_value::<UsageRecord>(value) {
+                    usage_records.push(record);
+                }
+                continue;
+            }
+            Some("resumed") => {
+                // Everything so far was replayed from the earlier rollout,
+                // which counts it itself.
+                if let Ok(record) = serde_json::from_value(value) {
+                    resume = record;
+                }
+                totals.commands = 0;
+                totals.patches = 0;
+                continue;
+            }
+            _ => {}
+        }
+        if let Ok(item) = serde_json::from_value::<ResponseItem>(value) {
+            count_tool_call(&item, &mut totals);
+        }
+    }
+    totals.approval_requests = state
+        .approval_requests
+        .saturating_sub(resume.approval_requests);
+    totals.aborted_turns = state.aborted_turns.saturating_sub(resume.aborted_turns);
+
+    let model = state
+        .turn
+        .map(|turn| turn.model)
+        .or_else(|| usage_records.last().map(|record| record.model.clone()));
+    let start_day = timestamp.as_deref().and_then(utc_day);
+    let usages = if usage_records.is_empty() {
+        // Older rollouts only have the session totals, which include the
+        // tokens of the rollout this one was resumed from.
+        state
+            .total_token_usage
+            .map(|usage| match &resume.total_token_usage {
+                Some(before) => usage_since(&usage, before),
+                None => usage,
+            })
+            .map(|usage| (start_day, model.clone(), usage))
+            .into_iter()
+            .collect()
+    } else {
+        usage_records
+            .into_iter()
+            .map(|record| {
+                let day = utc_day(&record.timestamp).or(start_day);
+                (day, Some(record.model), record.usage)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut token_usage: Vec<ModelDayUsage> = Vec::new();
+    for (day, model, usage) in usages {
+        let day = day.map(|day| day.to_string());
+        let index = match token_usage
+            .iter()
+            .position(|entry| entry.day == day && entry.model == model)
+        {
+            Some(index) => index,
+            None => {
+                token_usage.push(ModelDayUsage {
+                    day,
+                    model,
+                    totals: UsageTotals::default(),
+                });
+                token_usage.len() - 1
+            }
+        };
+        let totals = &mut token_usage[index].totals;
+        totals.input_tokens += usage.input_tokens;
+        totals.cached_input_tokens += usage.cached_input_tokens;
+        totals.output_tokens += usage.output_tokens;
+        totals.reasoning_output_tokens += usage.reasoning_output_tokens;
+    }
+    for entry in &mut token_usage {
+        entry.totals.cost = entry
+            .model
+            .as_ref()
+            .and_then(|model| prices.get(model))
+            .map(|price| estimate_cost(price, &entry.totals));
+    }
+
+    let repository = meta_str(meta.get("git").and_then(|git| git.get("repository_url")))
+        .or_else(|| meta_str(meta.get("cwd")));
+    Ok(SessionUsage {
+        path: path.to_path_buf(),
+        id: meta_str(meta.get("id")),
+        timestamp,
+        model,
+        provider: meta_str(meta.get("model_provider")),
+        repository,
+        totals,
+        token_usage,
+    })
+}
+
+/// The tokens in `total` that were not yet used at `before`.
+fn usage_since(total: &TokenUsage, before: &TokenUsage) -> TokenUsage {
+    TokenUsage {
+        input_tokens: total.input_tokens.saturating_sub(before.input_tokens),
+        cached_input_tokens: total
+            .cached_input_tokens
+            .saturating_sub(before.cached_input_tokens),
+        output_tokens: total.output_tokens.saturating_sub(before.output_tokens),
+        reasoning_output_tokens: total
+            .reasoning_output_tokens
+            .saturating_sub(before.reasoning_output_tokens),
+        total_tokens: total.total_tokens.saturating_sub(before.total_tokens),
+    }
+}
+
+/// Counts `item` as a command or a patch when it is one.
+fn count_tool_call(item: &ResponseItem, totals: &mut UsageTotals) {
+    let command = match item {
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(exec),
+            ..
+        } => exec.command.clone(),
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "container.exec" | "shell" => {
+                match serde_json::from_str::<ShellToolCallParams>(arguments) {
+                    Ok(params) => params.command,
+                    Err(_) => return,
+                }
+            }
+            "apply_patch" => {
+                totals.patches += 1;
+                return;
+            }
+            name if name == EXEC_COMMAND_TOOL_NAME => {
+                totals.commands += 1;
+                return;
+            }
+            _ => return,
+        },
+        ResponseItem::CustomToolCall { name, .. } if name == "apply_patch" => {
+            totals.patches += 1;
+            return;
+        }
+        _ => return,
+    };
+    match maybe_parse_apply_patch(&command) {
+        MaybeApplyPatch::Body(_) => totals.patches += 1,
+        _ => totals.commands += 1,
+    }
+}
+
+/// Cost in USD of the tokens in `totals`. Output tokens include reasoning.
+fn estimate_cost(price: &ModelPrice, totals: &UsageTotals) -> f64 {
+    let cached = totals.cached_input_tokens as f64;
+    let uncached = totals
+        .input_tokens
+        .saturating_sub(totals.cached_input_tokens) as f64;
+    let output = totals.output_tokens as f64;
+    (uncached * price.input
+        + cached * price.cached_input.unwrap_or(price.input)
+        + output * price.output)
+        / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_rollout(codex_home: &Path, n: u8, timestamp: &str, lines: &[Value]) {
+        let dir = codex_home.join(SESSIONS_SUBDIR).join("2025/01/02");
+        fs::create_dir_all(&dir).unwrap();
+        let meta = json!({
+            "id": format!("00000000-0000-0000-0000-00000000000{n}"),
+            "timestamp": timestamp,
+            "cwd": "/work/repo",
+            "model_provider": "openai",
+            "git": { "repository_url": "https://example.com/repo.git" },
+        });
+        let text = std::iter::once(&meta)
+            .chain(lines)
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        let name =
+            format!("rollout-2025-01-02T10-00-0{n}-00000000-0000-0000-0000-00000000000{n}.jsonl");
+        fs::write(dir.join(name), text).unwrap();
+    }
+
+    fn state(model: &str, input: u64, cached: u64, output: u64) -> Value {
+        json!({
+            "record_type": "state",
+            "turn": {
+                "model": model,
+                "reasoning_effort": "medium",
+                "reasoning_summary": "auto",
+                "approval_policy": "on-request",
+                "sandbox_policy": { "mode": "read-only" },
+                "cwd": "/work/repo",
+            },
+            "total_token_usage": {
+                "input_tokens": input,
+                "cached_input_tokens": cached,
+                "output_tokens": output,
+                "reasoning_output_tokens": 0,
+                "total_tokens": input + output,
+            },
+            "approval_requests": 2,
+            "aborted_turns": 1,
+        })
+    }
+
+    fn shell_call(command: &str) -> Value {
+        json!({
+            "type": "function_call",
+            "name": "shell",
+            "arguments": json!({ "command": ["bash", "-lc", command] }).to_string(),
+            "call_id": "c1",
+        })
+    }
+
+    #[test]
+    fn aggregates_tokens_cost_and_activity() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            1,
+            "2025-01-02T10:00:00.000Z",
+            &[
+                shell_call("cargo test"),
+                json!({
+                    "type": "custom_tool_call",
+                    "name": "apply_patch",
+                    "input": "*** Begin Patch\n*** End Patch",
+                    "call_id": "c2",
+                }),
+                state("o3", 1_000_000, 500_000, 100_000),
+            ],
+        );
+        write_rollout(
+            home.path(),
+            2,
+            "2025-01-03T10:00:00.000Z",
+            &[shell_call("ls"), state("gpt-5", 10, 0, 10)],
+        );
+        let prices = HashMap::from([(
+            "o3".to_string(),
+            ModelPrice {
+                input: 2.0,
+                cached_input: Some(0.5),
+                output: 8.0,
+            },
+        )]);
+
+        let stats = UsageStats::collect(home.path(), None, &prices).unwrap();
+        assert_eq!(stats.total.sessions, 2);
+        assert_eq!(stats.total.input_tokens, 1_000_010);
+        assert_eq!(stats.total.commands, 2);
+        assert_eq!(stats.total.patches, 1);
+        assert_eq!(stats.total.approval_requests, 4);
+        assert_eq!(stats.total.aborted_turns, 2);
+        // 0.5M uncached * $2 + 0.5M cached * $0.5 + 0.1M output * $8
+        assert_eq!(stats.total.cost, Some(2.05));
+        assert_eq!(stats.by_model["gpt-5"].cost, None);
+        assert_eq!(
+            stats.by_day.keys().collect::<Vec<_>>(),
+            vec!["2025-01-02", "2025-01-03"]
+        );
+        assert_eq!(stats.by_provider["openai"].sessions, 2);
+        assert_eq!(
+            stats.by_repository["https://example.com/repo.git"].sessions,
+            2
+        );
+
+        let since = Date::from_calendar_date(2025, time::Month::January, 3).unwrap();
+        let stats = UsageStats::collect(home.path(), Some(since), &prices).unwrap();
+        assert_eq!(stats.sessions.len(), 1);
+        assert_eq!(stats.sessions[0].model.as_deref(), Some("gpt-5"));
+    }
+
+    fn usage(timestamp: &str, model: &str, input: u64, output: u64) -> Value {
+        json!({
+            "record_type": "usage",
+            "timestamp": timestamp,
+            "model": model,
+            "input_tokens": input,
+            "cached_input_tokens": 0,
+            "output_tokens": output,
+            "reasoning_output_tokens": 0,
+            "total_tokens": input + output,
+        })
+    }
+
+    #[test]
+    fn tokens_are_attributed_to_the_model_and_day_of_each_response() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            1,
+            "2025-01-02T23:00:00.000Z",
+            &[
+                usage("2025-01-02T23:30:00.000Z", "o3", 1_000_000, 0),
+                usage("2025-01-03T00:30:00.000Z", "gpt-5", 10, 5),
+                usage("2025-01-03T01:00:00.000Z", "gpt-5", 10, 5),
+            ],
+        );
+        let prices = HashMap::from([(
+            "o3".to_string(),
+            ModelPrice {
+                input: 2.0,
+                cached_input: None,
+                output: 8.0,
+            },
+        )]);
+
+        let stats = UsageStats::collect(home.path(), None, &prices).unwrap();
+        assert_eq!(stats.total.input_tokens, 1_000_020);
+        assert_eq!(stats.total.cost, Some(2.0));
+        assert_eq!(stats.by_model["o3"].input_tokens, 1_000_000);
+        assert_eq!(stats.by_model["gpt-5"].output_tokens, 10);
+        assert_eq!(stats.by_model["gpt-5"].sessions, 1);
+        assert_eq!(stats.by_day["2025-01-02"].input_tokens, 1_000_000);
+        assert_eq!(stats.by_day["2025-01-02"].sessions, 1);
+        assert_eq!(stats.by_day["2025-01-03"].input_tokens, 20);
+        assert_eq!(stats.by_day["2025-01-03"].sessions, 0);
+
+        // The session started before `since` but used tokens after it.
+        let since = Date::from_calendar_date(2025, time::Month::January, 3).unwrap();
+        let stats = UsageStats::collect(home.path(), Some(since), &prices).unwrap();
+        assert_eq!(stats.sessions.len(), 1);
+        assert_eq!(stats.total.input_tokens, 20);
+        assert_eq!(stats.total.cost, None);
+        assert!(!stats.by_model.contains_key("o3"));
+    }
+
+    #[test]
+    fn rollouts_without_state_count_as_unknown() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            1,
+            "2025-01-02T10:00:00.000Z",
+            &[json!({ "record_type": "state" }), shell_call("ls")],
+        );
+        let stats = UsageStats::collect(home.path(), None, &HashMap::new()).unwrap();
+        assert_eq!(stats.by_model[UNKNOWN].sessions, 1);
+        assert_eq!(stats.total.commands, 1);
+        assert_eq!(stats.total.input_tokens, 0);
+        assert_eq!(stats.total.cost, None);
+    }
+
+    #[test]
+    fn resumed_rollouts_count_only_what_follows_the_resume() {
+        let home = TempDir::new().unwrap();
+        write_rollout(
+            home.path(),
+            1,
+            "2025-01-02T10:00:00.000Z",
+            &[shell_call("cargo test"), state("o3", 100, 0, 10)],
+        );
+        // Resumed from the first rollout: its history and counters are
+        // replayed before the resume line, followed by one new command.
+        let mut resumed_state = state("o3", 150, 0, 15);
+        resumed_state["approval_requests"] = json!(3);
+        resumed_state["aborted_turns"] = json!(1);
+        write_rollout(
+            home.path(),
+            2,
+            "2025-01-03T10:00:00.000Z",
+            &[
+                state("o3", 100, 0, 10),
+                shell_call("cargo test"),
+                json!({
+                    "record_type": "resumed",
+                    "total_token_usage": {
+                        "input_tokens": 100,
+                        "cached_input_tokens": 0,
+                        "output_tokens": 10,
+                        "reasoning_output_tokens": 0,
+                        "total_tokens": 110,
+                    },
+                    "approval_requests": 2,
+                    "aborted_turns": 1,
+                }),
+                shell_call("ls"),
+                resumed_state,
+            ],
+        );
+
+        let stats = UsageStats::collect(home.path(), None, &HashMap::new()).unwrap();
+        assert_eq!(stats.total.sessions, 2);
+        assert_eq!(stats.total.commands, 2);
+        assert_eq!(stats.total.approval_requests, 3);
+        assert_eq!(stats.total.aborted_turns, 1);
+        assert_eq!(stats.total.input_tokens, 150);
+        assert_eq!(stats.total.output_tokens, 15);
+        assert_eq!(stats.by_day["2025-01-03"].commands, 1);
+        assert_eq!(stats.by_day["2025-01-03"].input_tokens, 50);
+    }
+}
@@ -7,6 +7,8 @@ use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_policy::PolicyVerdict;
 use crate::is_safe_command::is_known_safe_command;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
@@ -76,15 +78,27 @@ pub fn assess_patch_safety(
 /// true:
 ///
 /// - the user has explicitly approved the command
-/// - the command is on the "known safe" list
+/// - the command is on the "known safe" list and the sandbox policy denies
+///   no reads
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
+///
+/// A command that matches a user-supplied exec policy and only touches files
+/// the sandbox policy allows is approved without asking, but still runs in
+/// the sandbox. A command forbidden by an exec policy is always rejected.
 pub fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
+    exec_policy: &ExecPolicy,
+    cwd: &Path,
     with_escalated_permissions: bool,
 ) -> SafetyCheck {
+    let verdict = exec_policy.check(command, cwd, sandbox_policy);
+    if let PolicyVerdict::Forbidden { reason } = verdict {
+        return SafetyCheck::Reject { reason };
+    }
+
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
     // - the user has explicitly approved the command for this session
@@ -98,11 +112,31 @@ pub fn assess_command_safety(
     // would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
-    if is_known_safe_command(command) || approved.contains(command) {
+    if approved.contains(command) {
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         };
     }
+    if is_known_safe_command(command) {
+        // Known-safe commands only read, but they must not read the paths
+        // the policy denies.
+        let sandbox_type = if sandbox_policy.get_deny_read_paths().is_empty() {
+            None
+        } else {
+            get_platform_sandbox()
+        };
+        return SafetyCheck::AutoApprove {
+            sandbox_type: sandbox_type.unwrap_or(SandboxType::None),
+        };
+    }
+
+    // A policy match only vouches for the paths the command declares, so the
+    // sandbox stays in place to hold it to them.
+    if verdict == PolicyVerdict::Allowed
+        && let Some(sandbox_type) = get_platform_sandbox()
+    {
+        return SafetyCheck::AutoApprove { sandbox_type };
+    }
 
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
@@ -166,6 +200,83 @@ pub(crate) fn assess_safety_for_untrusted_command(
     }
 }
 
+/// Picks a directory to offer as an extra writable root after a sandboxed
+/// command failed with `stderr`: the nearest existing directory around the
+/// first absolute path that a permission error mentions and that the sandbox
+/// does not already allow writing to. Never suggests `/`, the home directory
+/// or anything above it, nor a path the policy denies reading.
+pub(crate) fn writable_root_to_grant(
+    stderr: &str,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Option<PathBuf> {
+    if !matches!(sandbox_policy, SandboxPolicy::WorkspaceWrite { .. }) {
+        return None;
+    }
+    let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+    let is_writable = |path: &Path| {
+        writable_roots
+            .iter()
+            .any(|writable_root| writable_root.is_path_writable(path))
+    };
+    let home = dirs::home_dir();
+
+    stderr
+        .lines()
+        .filter(|line| {
+            let line = line.to_ascii_lowercase();
+            line.contains("permission denied")
+                || line.contains("read-only file system")
+                || line.contains("operation not permitted")
+        })
+        .flat_map(|line| {
+            line.split(|c: char| {
+                c.is_whitespace()
+                    || matches!(c, '\'' | '"' | '`' | ':' | ',' | '(' | ')' | '[' | ']')
+            })
+        })
+        .filter(|token| token.starts_with('/'))
+        .filter_map(|token| normalize(Path::new(token)))
+        .filter(|path| !is_writable(path))
+        .filter(|path| {
+            !sandbox_policy
+                .get_deny_read_paths()
+                .iter()
+                .any(|denied| path.starts_with(denied))
+        })
+        .filter_map(|path| {
+            let dir = path.ancestors().find(|ancestor| ancestor.is_dir())?;
+            dir.canonicalize().ok()
+        })
+        .find(|dir| {
+            dir.parent().is_some()
+                && !home.as_ref().is_some_and(|home| home.starts_with(dir))
+                && !is_writable(dir)
+        })
+}
+
+/// Applies a hook's `allow` decision to the result of the usual checks: the
+/// call no longer needs the user's approval, but it keeps the sandbox a
+/// trusted call would get, and a rejection stands. Without a sandbox to
+/// enforce the user is still asked, unless they opted into full access.
+pub(crate) fn allow_without_prompt(
+    assessed: SafetyCheck,
+    sandbox_policy: &SandboxPolicy,
+) -> SafetyCheck {
+    match assessed {
+        SafetyCheck::AskUser => match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None if sandbox_policy == &SandboxPolicy::DangerFullAccess => {
+                SafetyCheck::AutoApprove {
+                    sandbox_type: SandboxType::None,
+                }
+            }
+            None => SafetyCheck::AskUser,
+        },
+        SafetyCheck::AutoApprove { .. } | SafetyCheck::Reject { .. } => assessed,
+    }
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -192,22 +303,6 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
     };
 
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
-    }
-
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
     // prefix check.
@@ -229,7 +324,10 @@ fn is_write_patch_constrained_to_writable_paths(
 
     for (path, change) in action.changes() {
         match change {
-            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => {
+            // A copy is keyed by its destination; the source is only read.
+            ApplyPatchFileChange::Add { .. }
+            | ApplyPatchFileChange::Delete { .. }
+            | ApplyPatchFileChange::Copy { .. } => {
                 if !is_path_writable(path) {
                     return false;
                 }
@@ -250,11 +348,53 @@ fn is_write_patch_constrained_to_writable_paths(
     true
 }
 
+/// Normalizes a path by removing `.` and resolving `..` without touching the
+/// filesystem (works even if the file does not exist).
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn hook_allow_skips_the_prompt_but_keeps_the_sandbox() {
+        let policy = SandboxPolicy::new_read_only_policy();
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(
+            allow_without_prompt(SafetyCheck::AskUser, &policy),
+            expected
+        );
+
+        let reject = SafetyCheck::Reject {
+            reason: "forbidden".to_string(),
+        };
+        assert_eq!(
+            allow_without_prompt(
+                SafetyCheck::Reject {
+                    reason: "forbidden".to_string(),
+                },
+                &policy
+            ),
+            reject
+        );
+    }
+
     #[test]
     fn test_writable_roots_constraint() {
         // Use a temporary directory as our workspace to avoid touching
@@ -276,6 +416,9 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
         };
 
         assert!(is_write_patch_constrained_to_writable_paths(
@@ -297,6 +440,9 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
         };
         assert!(is_write_patch_constrained_to_writable_paths(
             &add_outside,
@@ -319,6 +465,8 @@ mod tests {
             approval_policy,
             &sandbox_policy,
             &approved,
+            &ExecPolicy::default(),
+            Path::new("/"),
             request_escalated_privileges,
         );
 
@@ -338,6 +486,8 @@ mod tests {
             approval_policy,
             &sandbox_policy,
             &approved,
+            &ExecPolicy::default(),
+            Path::new("/"),
             request_escalated_privileges,
         );
 
@@ -347,4 +497,136 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn exec_policy_forbids_and_auto_approves_commands() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().canonicalize().unwrap();
+        let policy_path = cwd.join("team.policy");
+        std::fs::write(
+            &policy_path,
+            r#"
+define_program(program="touch", args=[ARG_WFILE])
+forbid_program_regex(regex="^curl$", reason="use the package registry mirror")
+"#,
+        )
+        .unwrap();
+        let (exec_policy, errors) = ExecPolicy::load(&[policy_path]);
+        assert!(errors.is_empty(), "{errors:?}");
+        let sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |command: &[&str]| {
+            let command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+            assess_command_safety(
+                &command,
+                AskForApproval::UnlessTrusted,
+                &sandbox_policy,
+                &approved,
+                &exec_policy,
+                &cwd,
+                false,
+            )
+        };
+
+        // Matched commands are approved but stay sandboxed.
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(assess(&["touch", "notes.txt"]), expected);
+        assert_eq!(assess(&["touch", "/etc/motd"]), SafetyCheck::AskUser);
+        assert_eq!(
+            assess(&["curl", "https://example.com"]),
+            SafetyCheck::Reject {
+                reason: "use the package registry mirror".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn known_safe_commands_are_sandboxed_when_reads_are_denied() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let exec_policy = ExecPolicy::default();
+        let command = vec!["cat".to_string(), "id_ed25519".to_string()];
+        let assess = |sandbox_policy: &SandboxPolicy| {
+            assess_command_safety(
+                &command,
+                AskForApproval::OnRequest,
+                sandbox_policy,
+                &approved,
+                &exec_policy,
+                &cwd,
+                false,
+            )
+        };
+
+        let policy = SandboxPolicy::new_workspace_write_policy();
+        assert!(!policy.get_deny_read_paths().is_empty());
+        assert_eq!(
+            assess(&policy),
+            SafetyCheck::AutoApprove {
+                sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None)
+            }
+        );
+        assert_eq!(
+            assess(&SandboxPolicy::DangerFullAccess),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+    }
+
+    #[test]
+    fn writable_root_to_grant_suggests_directory_from_permission_error() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let cwd = root.join("workspace");
+        let cache = root.join("cache").join("go-build");
+        let secrets = root.join("secrets");
+        std::fs::create_dir_all(&cwd).unwrap();
+        std::fs::create_dir_all(&cache).unwrap();
+        std::fs::create_dir_all(&secrets).unwrap();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            deny_read: vec![secrets.clone()],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
+        };
+
+        // The missing subdirectory falls back to its nearest existing ancestor.
+        let stderr = format!(
+            "go: open {}/ab/ab12-d: permission denied\n",
+            cache.display()
+        );
+        assert_eq!(
+            writable_root_to_grant(&stderr, &policy, &cwd),
+            Some(cache.clone())
+        );
+
+        // Paths already writable, denied for reading or on lines that are not
+        // permission errors are not offered.
+        let stderr = format!(
+            "touch: cannot touch '{cwd}/a': Permission denied\n\
+             touch: cannot touch '{secrets}/b': Permission denied\n\
+             cat: {cache}/c: No such file or directory\n",
+            cwd = cwd.display(),
+            secrets = secrets.display(),
+            cache = cache.display(),
+        );
+        assert_eq!(writable_root_to_grant(&stderr, &policy, &cwd), None);
+
+        // Neither `/` nor a read-only policy can be extended this way.
+        let stderr = "mkdir: cannot create directory '/x': Read-only file system";
+        assert_eq!(writable_root_to_grant(stderr, &policy, &cwd), None);
+        let stderr = format!("open {}/d: permission denied", cache.display());
+        assert_eq!(
+            writable_root_to_grant(&stderr, &SandboxPolicy::ReadOnly, &cwd),
+            None
+        );
+    }
 }
@@ -3,6 +3,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::config_types::SandboxLimits;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
@@ -19,6 +20,7 @@ const MACOS_PATH_TO_SEATBELT_EXECUTABLE: &str = "/usr/bin/sandbox-exec";
 pub async fn spawn_command_under_seatbelt(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    resource_limits: &SandboxLimits,
     cwd: PathBuf,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
@@ -32,6 +34,7 @@ pub async fn spawn_command_under_seatbelt(
         arg0,
         cwd,
         sandbox_policy,
+        resource_limits,
         stdio_policy,
         env,
     )
@@ -96,11 +99,28 @@ fn create_seatbelt_command_args(
         }
     };
 
-    let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
+    let mut file_read_policy = if sandbox_policy.has_full_disk_read_access() {
+        "; allow read-only file operations\n(allow file-read*)".to_string()
     } else {
-        ""
+        String::new()
     };
+    let mut extra_cli_args = extra_cli_args;
+    let deny_read = sandbox_policy.get_deny_read_paths();
+    if !deny_read.is_empty() {
+        // Later rules take precedence, so this carves the denied paths out of
+        // the read access granted above.
+        let mut denied_paths: Vec<String> = Vec::new();
+        for (index, path) in deny_read.iter().enumerate() {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            let param = format!("DENY_READ_{index}");
+            extra_cli_args.push(format!("-D{param}={}", canonical.to_string_lossy()));
+            denied_paths.push(format!("(subpath (param \"{param}\"))"));
+        }
+        file_read_policy.push_str(&format!(
+            "\n(deny file-read*\n{}\n)",
+            denied_paths.join(" ")
+        ));
+    }
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
     let network_policy = if sandbox_policy.has_full_network_access() {
@@ -157,6 +177,9 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
         };
 
         let args = create_seatbelt_command_args(
@@ -232,6 +255,9 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
         };
 
         let args = create_seatbelt_command_args(
@@ -300,6 +326,66 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn create_seatbelt_args_denies_reading_deny_read_paths() {
+        if cfg!(target_os = "windows") {
+            // /tmp does not exist on Windows, so skip this test.
+            return;
+        }
+
+        let tmp = TempDir::new().expect("tempdir");
+        let secret = tmp.path().join("auth.json");
+        fs::write(&secret, "{}").expect("write auth.json");
+        let missing = tmp.path().join("missing");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            deny_read: vec![secret.clone(), missing.clone()],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
+        };
+        let cwd = tmp.path().canonicalize().expect("canonicalize tmp");
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/cat".to_string(), secret.to_string_lossy().to_string()],
+            &policy,
+            &cwd,
+        );
+
+        let expected_policy = format!(
+            r#"{MACOS_SEATBELT_BASE_POLICY}
+; allow read-only file operations
+(allow file-read*)
+(deny file-read*
+(subpath (param "DENY_READ_0")) (subpath (param "DENY_READ_1"))
+)
+(allow file-write*
+(subpath (param "WRITABLE_ROOT_0"))
+)
+"#,
+        );
+        let expected_args = vec![
+            "-p".to_string(),
+            expected_policy,
+            format!("-DWRITABLE_ROOT_0={}", cwd.to_string_lossy()),
+            format!(
+                "-DDENY_READ_0={}",
+                secret
+                    .canonicalize()
+                    .expect("canonicalize")
+                    .to_string_lossy()
+            ),
+            format!("-DDENY_READ_1={}", missing.to_string_lossy()),
+            "--".to_string(),
+            "/bin/cat".to_string(),
+            secret.to_string_lossy().to_string(),
+        ];
+
+        assert_eq!(expected_args, args);
+    }
+
     struct PopulatedTmp {
         root_with_git: PathBuf,
         root_without_git: PathBuf,
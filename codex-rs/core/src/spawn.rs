@@ -5,7 +5,18 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
 
+#[cfg(target_os = "linux")]
+use crate::cgroup::CommandCgroup;
+#[cfg(target_os = "linux")]
+use crate::cgroup::join_cgroup;
+use crate::config_types::SandboxLimits;
 use crate::protocol::SandboxPolicy;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::AtomicBool;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::Ordering;
+#[cfg(target_os = "linux")]
+use tracing::warn;
 
 /// Experimental environment variable that will be set to some non-empty value
 /// if both of the following are true:
@@ -22,6 +33,11 @@ pub const CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR: &str = "CODEX_SANDBOX_NETWORK_
 /// accommodate sandboxing configuration and other sandboxing mechanisms.
 pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 
+/// Set once the user has been warned that `sandbox_limits.max_processes`
+/// cannot be enforced, so the warning is not repeated for every command.
+#[cfg(target_os = "linux")]
+static PIDS_LIMIT_WARNED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
@@ -35,17 +51,23 @@ pub enum StdioPolicy {
 /// For now, we take `SandboxPolicy` as a parameter to spawn_child() because
 /// we need to determine whether to set the
 /// `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
+///
+/// `resource_limits` are applied to the child with setrlimit(2) before it
+/// executes `program`, so they are inherited by anything it runs. On Linux
+/// the process limit moves the child into a cgroup of its own instead.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spawn_child_async(
     program: PathBuf,
     args: Vec<String>,
     #[cfg_attr(not(unix), allow(unused_variables))] arg0: Option<&str>,
     cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
+    #[cfg_attr(not(unix), allow(unused_variables))] resource_limits: &SandboxLimits,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
     trace!(
-        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
+        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {resource_limits:?} {stdio_policy:?} {env:?}"
     );
 
     let mut cmd = Command::new(&program);
@@ -85,6 +107,34 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    #[cfg(unix)]
+    if *resource_limits != SandboxLimits::default() {
+        let resource_limits = *resource_limits;
+        unsafe {
+            cmd.pre_exec(move || apply_resource_limits(&resource_limits));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    let pids_cgroup = resource_limits.max_processes.and_then(|max_processes| {
+        match CommandCgroup::create(max_processes) {
+            Ok(cgroup) => Some(cgroup),
+            Err(err) => {
+                if !PIDS_LIMIT_WARNED.swap(true, Ordering::Relaxed) {
+                    warn!("sandbox_limits.max_processes is not enforced: {err}");
+                }
+                None
+            }
+        }
+    });
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = &pids_cgroup {
+        let procs = cgroup.procs_path().to_owned();
+        unsafe {
+            cmd.pre_exec(move || join_cgroup(&procs));
+        }
+    }
+
     match stdio_policy {
         StdioPolicy::RedirectForShellTool => {
             // Do not create a file descriptor for stdin because otherwise some
@@ -103,5 +153,63 @@ pub(crate) async fn spawn_child_async(
         }
     }
 
-    cmd.kill_on_drop(true).spawn()
+    let child = cmd.kill_on_drop(true).spawn();
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = pids_cgroup {
+        cgroup.finish_spawn(child.is_ok());
+    }
+    child
+}
+
+/// Lowers the limits of the current process to `limits`. Runs between fork
+/// and exec, so it only calls async-signal-safe functions.
+#[cfg(unix)]
+fn apply_resource_limits(limits: &SandboxLimits) -> std::io::Result<()> {
+    const MIB: u64 = 1024 * 1024;
+    let SandboxLimits {
+        cpu_time_secs,
+        memory_mb,
+        max_file_size_mb,
+        // Enforced through a cgroup, not an rlimit.
+        max_processes: _,
+    } = *limits;
+
+    for (resource, limit) in [
+        (libc::RLIMIT_CPU, cpu_time_secs),
+        (libc::RLIMIT_AS, memory_mb.map(|mb| mb.saturating_mul(MIB))),
+        (
+            libc::RLIMIT_FSIZE,
+            max_file_size_mb.map(|mb| mb.saturating_mul(MIB)),
+        ),
+    ] {
+        let Some(limit) = limit else {
+            continue;
+        };
+        let limit = limit as libc::rlim_t;
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(resource, &mut current) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Give the command a second of grace after SIGXCPU before the kernel
+        // sends SIGKILL, so that running out of CPU time can be told apart
+        // from other kills. An unprivileged process cannot raise its hard
+        // limit, so never ask for more than the current one.
+        let hard = if resource == libc::RLIMIT_CPU {
+            limit.saturating_add(1)
+        } else {
+            limit
+        }
+        .min(current.rlim_max);
+        let new = libc::rlimit {
+            rlim_cur: limit.min(hard),
+            rlim_max: hard,
+        };
+        if unsafe { libc::setrlimit(resource, &new) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
 }
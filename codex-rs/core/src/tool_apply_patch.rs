@@ -57,13 +57,15 @@ Your patch language is a stripped‑down, file‑oriented diff format designed t
 
 Within that envelope, you get a sequence of file operations.
 You MUST include a header to specify the action you are taking.
-Each operation starts with one of three headers:
+Each operation starts with one of four headers:
 
 *** Add File: <path> - create a new file. Every following line is a + line (the initial contents).
 *** Delete File: <path> - remove an existing file. Nothing follows.
 *** Update File: <path> - patch an existing file in place (optionally with a rename).
+*** Copy File: <path> - copy an existing file, byte for byte, to the path given by a following *** Copy to: <new path> line.
 
 May be immediately followed by *** Move to: <new path> if you want to rename the file.
+Add, Update and Copy may also set the file's permission bits with *** Set Mode: <octal mode>, e.g. *** Set Mode: 755 to make a script executable. It goes right after the header (and after Move to / Copy to).
 Then one or more “hunks”, each introduced by @@ (optionally followed by a hunk header).
 Within a hunk each line starts with:
 
@@ -89,11 +91,13 @@ The full grammar definition is below:
 Patch := Begin { FileOp } End
 Begin := "*** Begin Patch" NEWLINE
 End := "*** End Patch" NEWLINE
-FileOp := AddFile | DeleteFile | UpdateFile
-AddFile := "*** Add File: " path NEWLINE { "+" line NEWLINE }
+FileOp := AddFile | DeleteFile | UpdateFile | CopyFile
+AddFile := "*** Add File: " path NEWLINE [ SetMode ] { "+" line NEWLINE }
 DeleteFile := "*** Delete File: " path NEWLINE
-UpdateFile := "*** Update File: " path NEWLINE [ MoveTo ] { Hunk }
+UpdateFile := "*** Update File: " path NEWLINE [ MoveTo ] [ SetMode ] { Hunk }
+CopyFile := "*** Copy File: " path NEWLINE "*** Copy to: " newPath NEWLINE [ SetMode ]
 MoveTo := "*** Move to: " newPath NEWLINE
+SetMode := "*** Set Mode: " octalMode NEWLINE
 Hunk := "@@" [ header ] NEWLINE { HunkLine } [ "*** End of File" NEWLINE ]
 HunkLine := (" " | "-" | "+") text NEWLINE
 
@@ -112,7 +116,7 @@ A full patch can combine several operations:
 
 It is important to remember:
 
-- You must include a header with your intended action (Add/Delete/Update)
+- You must include a header with your intended action (Add/Delete/Update/Copy)
 - You must prefix new lines with `+` even when creating a new file
 - File references can only be relative, NEVER ABSOLUTE.
 "#
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::SystemTime;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -15,6 +17,15 @@ use crate::protocol::FileChange;
 const ZERO_OID: &str = "0000000000000000000000000000000000000000";
 const DEV_NULL: &str = "/dev/null";
 
+/// Worktrees with more files than this are not snapshotted around shell
+/// commands, to keep every command cheap.
+const MAX_SNAPSHOT_FILES: usize = 20_000;
+/// Files larger than this are not kept in the snapshot; changes to them are
+/// not reported.
+const MAX_SNAPSHOT_FILE_BYTES: u64 = 1024 * 1024;
+/// Upper bound on the contents kept in the snapshot across all files.
+const MAX_SNAPSHOT_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+
 struct BaselineFileInfo {
     path: PathBuf,
     content: Vec<u8>,
@@ -40,6 +51,31 @@ pub struct TurnDiffTracker {
     temp_name_to_current_path: HashMap<String, PathBuf>,
     /// Cache of known git worktree roots to avoid repeated filesystem walks.
     git_root_cache: Vec<PathBuf>,
+    /// Files of the worktree as they were before the current shell command.
+    exec_snapshot: Option<ExecSnapshot>,
+    /// Set for calls that only read the workspace, which need no snapshot.
+    skip_exec_snapshots: bool,
+}
+
+/// The files of a git worktree (tracked or not ignored) before a shell
+/// command runs, so that the changes it makes can be detected afterwards.
+struct ExecSnapshot {
+    root: PathBuf,
+    files: HashMap<PathBuf, SnapshotFile>,
+}
+
+struct SnapshotFile {
+    stamp: FileStamp,
+    /// `None` when the file is already tracked or too large to keep.
+    content: Option<Vec<u8>>,
+}
+
+/// Cheap summary of a file used to tell whether it changed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    mode: FileMode,
 }
 
 impl TurnDiffTracker {
@@ -47,6 +83,15 @@ impl TurnDiffTracker {
         Self::default()
     }
 
+    /// A tracker that does not snapshot the worktree around shell commands,
+    /// for calls that cannot change files.
+    pub fn without_exec_snapshots() -> Self {
+        Self {
+            skip_exec_snapshots: true,
+            ..Self::default()
+        }
+    }
+
     /// Front-run apply patch calls to track the starting contents of any modified files.
     /// - Creates an in-memory baseline snapshot for files that already exist on disk when first seen.
     /// - For additions, we intentionally do not create a baseline snapshot so that diffs are proper additions.
@@ -127,6 +172,101 @@ impl TurnDiffTracker {
         }
     }
 
+    /// Snapshot the worktree containing `cwd` before a shell command runs, so
+    /// that [`Self::on_exec_end`] can fold in the files it changes. Contents
+    /// read for an earlier command are reused for files unchanged since. The
+    /// worktree is read on the blocking thread pool.
+    pub async fn on_exec_begin(&mut self, cwd: &Path) {
+        let previous = self.exec_snapshot.take();
+        if self.skip_exec_snapshots {
+            return;
+        }
+        let Some(root) = self.find_git_root_cached(cwd) else {
+            return;
+        };
+        let previous = previous
+            .filter(|snapshot| snapshot.root == root)
+            .map(|snapshot| snapshot.files)
+            .unwrap_or_default();
+        let tracked: HashSet<PathBuf> = self.external_to_temp_name.keys().cloned().collect();
+        self.exec_snapshot =
+            tokio::task::spawn_blocking(move || snapshot_worktree(root, previous, &tracked))
+                .await
+                .ok()
+                .flatten();
+    }
+
+    /// Compare the worktree against the snapshot taken by
+    /// [`Self::on_exec_begin`] and start tracking every file the command
+    /// added, modified or deleted. Returns whether any file changed.
+    pub async fn on_exec_end(&mut self) -> bool {
+        let Some(snapshot) = self.exec_snapshot.take() else {
+            return false;
+        };
+        let root = snapshot.root.clone();
+        let Ok(Some(stamps)) =
+            tokio::task::spawn_blocking(move || stamp_worktree_files(&root)).await
+        else {
+            return false;
+        };
+
+        let mut changed = false;
+        let mut present = HashSet::with_capacity(stamps.len());
+        for (path, stamp) in stamps {
+            match snapshot.files.get(&path) {
+                Some(before) if before.stamp == stamp => {}
+                before => {
+                    changed = true;
+                    self.track_exec_change(&path, before);
+                }
+            }
+            present.insert(path);
+        }
+        for (path, before) in &snapshot.files {
+            if !present.contains(path) {
+                changed = true;
+                self.track_exec_change(path, Some(before));
+            }
+        }
+
+        self.exec_snapshot = Some(snapshot);
+        changed
+    }
+
+    /// Start tracking a file changed by a shell command, with its snapshot
+    /// (`None` if the command created it) as the baseline.
+    fn track_exec_change(&mut self, path: &Path, before: Option<&SnapshotFile>) {
+        if self.external_to_temp_name.contains_key(path) {
+            return;
+        }
+        let baseline_file_info = match before {
+            None => BaselineFileInfo {
+                path: path.to_path_buf(),
+                content: vec![],
+                mode: FileMode::Regular,
+                oid: ZERO_OID.to_string(),
+            },
+            Some(SnapshotFile {
+                stamp,
+                content: Some(content),
+            }) => BaselineFileInfo {
+                path: path.to_path_buf(),
+                content: content.clone(),
+                mode: stamp.mode,
+                oid: format!("{:x}", git_blob_sha1_hex_bytes(content)),
+            },
+            // Too large to have been kept, so the change cannot be shown.
+            Some(SnapshotFile { content: None, .. }) => return,
+        };
+
+        let internal = Uuid::new_v4().to_string();
+        self.external_to_temp_name
+            .insert(path.to_path_buf(), internal.clone());
+        self.temp_name_to_current_path
+            .insert(internal.clone(), path.to_path_buf());
+        self.baseline_file_info.insert(internal, baseline_file_info);
+    }
+
     fn get_path_for_internal(&self, internal: &str) -> Option<PathBuf> {
         self.temp_name_to_current_path
             .get(internal)
@@ -294,8 +434,9 @@ impl TurnDiffTracker {
             None
         };
 
-        // Fast path: identical bytes or both missing.
-        if left_bytes == right_bytes.as_deref() {
+        // Fast path: identical bytes and mode, or both missing.
+        let same_bytes = left_bytes == right_bytes.as_deref();
+        if same_bytes && (!left_present || baseline_mode == current_mode) {
             return aggregated;
         }
 
@@ -312,6 +453,10 @@ impl TurnDiffTracker {
             aggregated.push_str(&format!("old mode {baseline_mode}\n"));
             aggregated.push_str(&format!("new mode {current_mode}\n"));
         }
+        if same_bytes {
+            // Only the mode changed.
+            return aggregated;
+        }
 
         let left_text = left_bytes.and_then(|b| std::str::from_utf8(b).ok());
         let right_text = right_bytes
@@ -379,6 +524,98 @@ fn git_blob_sha1_hex_bytes(data: &[u8]) -> Output<sha1::Sha1> {
     hasher.finalize()
 }
 
+/// Reads the worktree at `root` into a snapshot, reusing the contents in
+/// `previous` for files unchanged since and skipping the contents of the
+/// `tracked` files, whose baseline is already known.
+fn snapshot_worktree(
+    root: PathBuf,
+    mut previous: HashMap<PathBuf, SnapshotFile>,
+    tracked: &HashSet<PathBuf>,
+) -> Option<ExecSnapshot> {
+    let paths = list_worktree_files(&root)?;
+    if paths.len() > MAX_SNAPSHOT_FILES {
+        return None;
+    }
+
+    let mut files = HashMap::with_capacity(paths.len());
+    let mut total_bytes = 0;
+    for path in paths {
+        let Some(stamp) = file_stamp(&path) else {
+            continue;
+        };
+        let unchanged = previous.remove(&path).filter(|file| file.stamp == stamp);
+        let content = if tracked.contains(&path) {
+            None
+        } else if let Some(file) = unchanged {
+            file.content
+        } else if stamp.len <= MAX_SNAPSHOT_FILE_BYTES
+            && total_bytes + stamp.len <= MAX_SNAPSHOT_TOTAL_BYTES
+        {
+            blob_bytes(&path, &stamp.mode)
+        } else {
+            None
+        };
+        total_bytes += content.as_ref().map_or(0, |c| c.len() as u64);
+        files.insert(path, SnapshotFile { stamp, content });
+    }
+    Some(ExecSnapshot { root, files })
+}
+
+/// The files of the worktree at `root` with their current stamps.
+fn stamp_worktree_files(root: &Path) -> Option<Vec<(PathBuf, FileStamp)>> {
+    let paths = list_worktree_files(root)?;
+    Some(
+        paths
+            .into_iter()
+            .filter_map(|path| file_stamp(&path).map(|stamp| (path, stamp)))
+            .collect(),
+    )
+}
+
+/// Lists the files of the worktree at `root` that git tracks or does not
+/// ignore, as absolute paths.
+fn list_worktree_files(root: &Path) -> Option<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut seen = HashSet::new();
+    Some(
+        output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|rel| !rel.is_empty())
+            .map(|rel| root.join(String::from_utf8_lossy(rel).as_ref()))
+            // Unmerged files are listed once per stage.
+            .filter(|path| seen.insert(path.clone()))
+            .collect(),
+    )
+}
+
+/// Summary of the regular file or symlink at `path`, if there is one.
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    if meta.is_dir() {
+        return None;
+    }
+    Some(FileStamp {
+        len: meta.len(),
+        modified: meta.modified().ok(),
+        mode: file_mode_for_path(path)?,
+    })
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum FileMode {
     Regular,
@@ -515,6 +752,7 @@ mod tests {
             file.clone(),
             FileChange::Add {
                 content: "foo\n".to_string(),
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&add_changes);
@@ -545,6 +783,7 @@ index {ZERO_OID}..{right_oid}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: None,
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&update_changes);
@@ -620,6 +859,7 @@ index {left_oid}..{ZERO_OID}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: Some(dest.clone()),
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&mv_changes);
@@ -660,6 +900,7 @@ index {left_oid}..{right_oid}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: Some(dest.clone()),
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&mv_changes);
@@ -682,6 +923,7 @@ index {left_oid}..{right_oid}
             FileChange::Update {
                 unified_diff: "".into(),
                 move_path: Some(dest.clone()),
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&mv);
@@ -722,6 +964,7 @@ index {ZERO_OID}..{right_oid}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: None,
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&update_a);
@@ -802,6 +1045,7 @@ index {left_oid_b}..{ZERO_OID}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: None,
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&update_changes);
@@ -826,6 +1070,107 @@ Binary files differ
         assert_eq!(diff, expected);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn mode_change_without_content_change() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("run.sh");
+        fs::write(&file, "echo hi\n").unwrap();
+
+        let mut acc = TurnDiffTracker::new();
+        let update_changes = HashMap::from([(
+            file.clone(),
+            FileChange::Update {
+                unified_diff: "".to_owned(),
+                move_path: None,
+                mode: Some(0o755),
+            },
+        )]);
+        acc.on_patch_begin(&update_changes);
+
+        // Apply the mode change on disk
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let diff = acc.get_unified_diff().unwrap().unwrap();
+        let diff = normalize_diff_for_test(&diff, dir.path());
+        let expected = r#"diff --git a/<TMP>/run.sh b/<TMP>/run.sh
+old mode 100644
+new mode 100755
+"#;
+        assert_eq!(diff, expected);
+    }
+
+    #[tokio::test]
+    async fn shell_command_changes_are_tracked() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q"]);
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("edit.txt"), "before\n").unwrap();
+        fs::write(root.join("gone.txt"), "bye\n").unwrap();
+        git(&["add", "."]);
+
+        let mut acc = TurnDiffTracker::new();
+        acc.on_exec_begin(root).await;
+        assert!(!acc.on_exec_end().await);
+        assert_eq!(acc.get_unified_diff().unwrap(), None);
+
+        // Simulate a command such as `sed -i`, a generator and `rm`.
+        acc.on_exec_begin(root).await;
+        fs::write(root.join("edit.txt"), "after\n").unwrap();
+        fs::write(root.join("new.txt"), "fresh\n").unwrap();
+        fs::write(root.join("build.log"), "ignored\n").unwrap();
+        fs::remove_file(root.join("gone.txt")).unwrap();
+        assert!(acc.on_exec_end().await);
+
+        let diff = acc.get_unified_diff().unwrap().unwrap();
+        let diff = normalize_diff_for_test(&diff, root);
+        let expected = {
+            let edit_left = git_blob_sha1_hex("before\n");
+            let edit_right = git_blob_sha1_hex("after\n");
+            let gone_left = git_blob_sha1_hex("bye\n");
+            let new_right = git_blob_sha1_hex("fresh\n");
+            let mode = FileMode::Regular;
+            format!(
+                r#"diff --git a/edit.txt b/edit.txt
+index {edit_left}..{edit_right}
+--- a/edit.txt
++++ b/edit.txt
+@@ -1 +1 @@
+-before
++after
+diff --git a/gone.txt b/gone.txt
+deleted file mode {mode}
+index {gone_left}..{ZERO_OID}
+--- a/gone.txt
++++ {DEV_NULL}
+@@ -1 +0,0 @@
+-bye
+diff --git a/new.txt b/new.txt
+new file mode {mode}
+index {ZERO_OID}..{new_right}
+--- {DEV_NULL}
++++ b/new.txt
+@@ -0,0 +1 @@
++fresh
+"#
+            )
+        };
+        assert_eq!(diff, expected);
+    }
+
     #[test]
     fn filenames_with_spaces_add_and_update() {
         let mut acc = TurnDiffTracker::new();
@@ -838,6 +1183,7 @@ Binary files differ
             file.clone(),
             FileChange::Add {
                 content: "foo\n".to_string(),
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&add_changes);
@@ -868,6 +1214,7 @@ index {ZERO_OID}..{right_oid}
             FileChange::Update {
                 unified_diff: "".to_owned(),
                 move_path: None,
+                mode: None,
             },
         )]);
         acc.on_patch_begin(&update_changes);
@@ -92,6 +92,90 @@ pub fn load_sse_fixture_with_id(path: impl AsRef<std::path::Path>, id: &str) ->
         .collect()
 }
 
+/// Builds an SSE stream body from a list of JSON events. An event with only a
+/// `type` field results in an event with no `data:` section.
+pub fn sse(events: Vec<serde_json::Value>) -> String {
+    use std::fmt::Write as _;
+    let mut out = String::new();
+    for ev in events {
+        let kind = ev
+            .get("type")
+            .and_then(|v| v.as_str())
+            .expect("SSE event missing type");
+        let _ = writeln!(out, "event: {kind}");
+        if !ev.as_object().map(|o| o.len() == 1).unwrap_or(false) {
+            let _ = write!(out, "data: {ev}\n\n");
+        } else {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// SSE event for a completed response with a specific id.
+pub fn ev_completed(id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}
+        }
+    })
+}
+
+/// SSE event for a single assistant message output item.
+pub fn ev_assistant_message(id: &str, text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "message",
+            "role": "assistant",
+            "id": id,
+            "content": [{"type": "output_text", "text": text}]
+        }
+    })
+}
+
+/// SSE event for a call of the function tool `name` with JSON `arguments`.
+pub fn ev_function_call(
+    call_id: &str,
+    name: &str,
+    arguments: &serde_json::Value,
+) -> serde_json::Value {
+    serde_json::json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "function_call",
+            "name": name,
+            "arguments": arguments.to_string(),
+            "call_id": call_id
+        }
+    })
+}
+
+pub fn sse_response(body: String) -> wiremock::ResponseTemplate {
+    wiremock::ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(body, "text/event-stream")
+}
+
+/// Serves `body` exactly once for a request to `/v1/responses` that matches
+/// `matcher`.
+pub async fn mount_sse_once<M>(server: &wiremock::MockServer, matcher: M, body: String)
+where
+    M: wiremock::Match + Send + Sync + 'static,
+{
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+    wiremock::Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(matcher)
+        .respond_with(sse_response(body))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
 pub async fn wait_for_event<F>(
     codex: &CodexConversation,
     predicate: F,
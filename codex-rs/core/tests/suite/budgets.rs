@@ -0,0 +1,241 @@
+#![expect(clippy::unwrap_used)]
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::config_types::Budget;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::BudgetScope;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::TurnAbortReason;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::ev_completed;
+use core_test_support::ev_function_call;
+use core_test_support::load_default_config_for_test;
+use core_test_support::sse;
+use core_test_support::sse_response;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// SSE body for a response that makes `calls` `update_plan` tool calls.
+fn sse_update_plan_calls(calls: usize) -> String {
+    let arguments = json!({"plan": [{"step": "inspect", "status": "pending"}]});
+    let mut events: Vec<_> = (1..=calls)
+        .map(|n| ev_function_call(&format!("call-{n}"), "update_plan", &arguments))
+        .collect();
+    events.push(ev_completed("r1"));
+    sse(events)
+}
+
+/// SSE body for a response that runs a shell command needing approval.
+fn sse_shell_call_needing_approval() -> String {
+    sse(vec![
+        ev_function_call(
+            "call-1",
+            "shell",
+            &json!({"command": ["touch", "budget.txt"]}),
+        ),
+        ev_completed("r1"),
+    ])
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn turn_aborts_when_tool_call_budget_is_exhausted() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    // Only one request is expected: the budget is exhausted by the first tool
+    // call, so the tool output is never sent back to the model.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse_update_plan_calls(1)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.budgets.turn = Budget {
+        max_tool_calls: Some(1),
+        ..Default::default()
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "make a plan".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::BudgetExceeded(exceeded) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::BudgetExceeded(_))).await
+    else {
+        unreachable!("wait_for_event returned a different event");
+    };
+    assert_eq!(
+        exceeded,
+        BudgetExceededEvent {
+            scope: BudgetScope::Turn,
+            kind: BudgetKind::ToolCalls,
+            limit: 1,
+            used: 1,
+        }
+    );
+
+    let EventMsg::TurnAborted(aborted) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TurnAborted(_))).await
+    else {
+        unreachable!("wait_for_event returned a different event");
+    };
+    assert_eq!(aborted.reason, TurnAbortReason::BudgetExceeded);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tool_calls_beyond_the_budget_in_one_response_are_not_run() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse_update_plan_calls(3)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.budgets.turn = Budget {
+        max_tool_calls: Some(1),
+        ..Default::default()
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "make three plans".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut plan_updates = 0;
+    loop {
+        match codex.next_event().await.unwrap().msg {
+            EventMsg::PlanUpdate(_) => plan_updates += 1,
+            EventMsg::TurnAborted(aborted) => {
+                assert_eq!(aborted.reason, TurnAbortReason::BudgetExceeded);
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(plan_updates, 1, "only the first call fits in the budget");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wall_clock_budget_during_approval_releases_the_approval_slot() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse_shell_call_needing_approval()))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.approval_policy = AskForApproval::UnlessTrusted;
+    config.budgets.turn = Budget {
+        max_duration_secs: Some(1),
+        ..Default::default()
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    // The first approval request is never answered, so the turn runs out of
+    // wall-clock time while it waits.
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "touch a file".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ExecApprovalRequest(_))).await;
+    let EventMsg::TurnAborted(aborted) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TurnAborted(_))).await
+    else {
+        unreachable!("wait_for_event returned a different event");
+    };
+    assert_eq!(aborted.reason, TurnAbortReason::BudgetExceeded);
+
+    // The next turn must still be able to ask the user.
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "touch it again".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ExecApprovalRequest(_))).await;
+}
@@ -8,83 +8,24 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::ev_assistant_message;
+use core_test_support::ev_completed;
+use core_test_support::ev_function_call;
 use core_test_support::load_default_config_for_test;
+use core_test_support::mount_sse_once;
+use core_test_support::sse;
 use core_test_support::wait_for_event;
 use serde_json::Value;
 use tempfile::TempDir;
-use wiremock::Mock;
 use wiremock::MockServer;
-use wiremock::ResponseTemplate;
-use wiremock::matchers::method;
-use wiremock::matchers::path;
 
 use pretty_assertions::assert_eq;
 
-// --- Test helpers -----------------------------------------------------------
-
-/// Build an SSE stream body from a list of JSON events.
-fn sse(events: Vec<Value>) -> String {
-    use std::fmt::Write as _;
-    let mut out = String::new();
-    for ev in events {
-        let kind = ev.get("type").and_then(|v| v.as_str()).unwrap();
-        writeln!(&mut out, "event: {kind}").unwrap();
-        if !ev.as_object().map(|o| o.len() == 1).unwrap_or(false) {
-            write!(&mut out, "data: {ev}\n\n").unwrap();
-        } else {
-            out.push('\n');
-        }
-    }
-    out
-}
-
-/// Convenience: SSE event for a completed response with a specific id.
-fn ev_completed(id: &str) -> Value {
-    serde_json::json!({
-        "type": "response.completed",
-        "response": {
-            "id": id,
-            "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}
-        }
-    })
-}
-
-/// Convenience: SSE event for a single assistant message output item.
-fn ev_assistant_message(id: &str, text: &str) -> Value {
-    serde_json::json!({
-        "type": "response.output_item.done",
-        "item": {
-            "type": "message",
-            "role": "assistant",
-            "id": id,
-            "content": [{"type": "output_text", "text": text}]
-        }
-    })
-}
-
-fn sse_response(body: String) -> ResponseTemplate {
-    ResponseTemplate::new(200)
-        .insert_header("content-type", "text/event-stream")
-        .set_body_raw(body, "text/event-stream")
-}
-
-async fn mount_sse_once<M>(server: &MockServer, matcher: M, body: String)
-where
-    M: wiremock::Match + Send + Sync + 'static,
-{
-    Mock::given(method("POST"))
-        .and(path("/v1/responses"))
-        .and(matcher)
-        .respond_with(sse_response(body))
-        .expect(1)
-        .mount(server)
-        .await;
-}
-
 const FIRST_REPLY: &str = "FIRST_REPLY";
 const SUMMARY_TEXT: &str = "SUMMARY_ONLY_CONTEXT";
 const SUMMARIZE_TRIGGER: &str = "Start Summarization";
 const THIRD_USER_MSG: &str = "next turn";
+const SUMMARIZATION_INSTRUCTIONS: &str = "You are a summarization assistant";
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn summarize_context_three_requests_and_instructions() {
@@ -186,16 +127,15 @@ async fn summarize_context_three_requests_and_instructions() {
     let body2 = req2.body_json::<serde_json::Value>().unwrap();
     let body3 = req3.body_json::<serde_json::Value>().unwrap();
 
-    // System instructions should change for the summarization turn.
+    // Manual /compact submits its prompt as a normal user turn rather than
+    // overriding the system instructions, so that it works with ChatGPT
+    // accounts that reject custom `instructions`. The instructions therefore
+    // stay the same and the summarization prompt follows the user input.
     let instr1 = body1.get("instructions").and_then(|v| v.as_str()).unwrap();
     let instr2 = body2.get("instructions").and_then(|v| v.as_str()).unwrap();
-    assert_ne!(
+    assert_eq!(
         instr1, instr2,
-        "summarization should override base instructions"
-    );
-    assert!(
-        instr2.contains("You are a summarization assistant"),
-        "summarization instructions not applied"
+        "summarization should not override base instructions"
     );
 
     // The summarization request should include the injected user input marker.
@@ -206,6 +146,11 @@ async fn summarize_context_three_requests_and_instructions() {
     assert_eq!(last2.get("role").unwrap().as_str().unwrap(), "user");
     let text2 = last2["content"][0]["text"].as_str().unwrap();
     assert!(text2.contains(SUMMARIZE_TRIGGER));
+    let instructions2 = last2["content"][1]["text"].as_str().unwrap();
+    assert!(
+        instructions2.contains(SUMMARIZATION_INSTRUCTIONS),
+        "summarization instructions not applied"
+    );
 
     // Third request must contain only the summary from step 2 as prior history plus new user msg.
     let input3 = body3.get("input").and_then(|v| v.as_array()).unwrap();
@@ -249,3 +194,217 @@ async fn summarize_context_three_requests_and_instructions() {
         "third request should not include the summarize trigger"
     );
 }
+
+/// SSE event for a completed response that reports `total_tokens` of usage.
+fn ev_completed_with_tokens(id: &str, total_tokens: u64) -> Value {
+    serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {
+                "input_tokens": total_tokens,
+                "input_tokens_details": null,
+                "output_tokens": 0,
+                "output_tokens_details": null,
+                "total_tokens": total_tokens
+            }
+        }
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn auto_compact_runs_when_token_limit_reached() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // SSE 1: normal reply whose usage exceeds the configured limit.
+    let sse1 = sse(vec![
+        ev_assistant_message("m1", FIRST_REPLY),
+        ev_completed_with_tokens("r1", 500),
+    ]);
+    // SSE 2: automatic summarization before the next turn.
+    let sse2 = sse(vec![
+        ev_assistant_message("m2", SUMMARY_TEXT),
+        ev_completed("r2"),
+    ]);
+    // SSE 3: the user's next turn, sent with the summary as history.
+    let sse3 = sse(vec![ev_completed("r3")]);
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("\"text\":\"hello world\"") && !body.contains(SUMMARIZATION_INSTRUCTIONS)
+    };
+    mount_sse_once(&server, first_matcher, sse1).await;
+
+    let second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(SUMMARIZATION_INSTRUCTIONS)
+    };
+    mount_sse_once(&server, second_matcher, sse2).await;
+
+    let third_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(&format!("\"text\":\"{THIRD_USER_MSG}\""))
+            && !body.contains(SUMMARIZATION_INSTRUCTIONS)
+    };
+    mount_sse_once(&server, third_matcher, sse3).await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_auto_compact_token_limit = Some(100);
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: THIRD_USER_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let compacted = wait_for_event(&codex, |ev| matches!(ev, EventMsg::ContextCompacted(_))).await;
+    let EventMsg::ContextCompacted(compacted) = compacted else {
+        unreachable!("wait_for_event returned a different event");
+    };
+    assert_eq!(compacted.tokens_in_context_window, 500);
+    assert_eq!(compacted.token_limit, 100);
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected exactly three requests");
+
+    let body3 = requests[2].body_json::<serde_json::Value>().unwrap();
+    let body3 = body3["input"].to_string();
+    assert!(
+        body3.contains(SUMMARY_TEXT),
+        "third request should carry the summary"
+    );
+    assert!(
+        !body3.contains("hello world"),
+        "third request should not include the compacted user input"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn mid_turn_auto_compact_keeps_the_request_and_pending_tool_output() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // SSE 1: a tool call whose usage exceeds the configured limit.
+    let plan = serde_json::json!({
+        "plan": [{ "step": "inspect", "status": "in_progress" }],
+    });
+    let sse1 = sse(vec![
+        ev_function_call("plan-call", "update_plan", &plan),
+        ev_completed_with_tokens("r1", 500),
+    ]);
+    // SSE 2: automatic summarization before the tool output is sent back.
+    let sse2 = sse(vec![
+        ev_assistant_message("m2", SUMMARY_TEXT),
+        ev_completed("r2"),
+    ]);
+    // SSE 3: the turn continues after the summary.
+    let sse3 = sse(vec![
+        ev_assistant_message("m3", FIRST_REPLY),
+        ev_completed("r3"),
+    ]);
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        !body.contains("plan-call")
+    };
+    mount_sse_once(&server, first_matcher, sse1).await;
+
+    let second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(SUMMARIZATION_INSTRUCTIONS)
+    };
+    mount_sse_once(&server, second_matcher, sse2).await;
+
+    let third_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("plan-call") && !body.contains(SUMMARIZATION_INSTRUCTIONS)
+    };
+    mount_sse_once(&server, third_matcher, sse3).await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_auto_compact_token_limit = Some(100);
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ContextCompacted(_))).await;
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected exactly three requests");
+
+    let body3 = requests[2].body_json::<serde_json::Value>().unwrap();
+    let input = body3["input"].as_array().unwrap();
+    let summary_index = input
+        .iter()
+        .position(|item| item.to_string().contains(SUMMARY_TEXT))
+        .expect("third request should carry the summary");
+    let after_summary: Vec<&Value> = input[summary_index + 1..].iter().collect();
+    assert!(
+        after_summary
+            .iter()
+            .any(|item| item.to_string().contains("hello world")),
+        "the user request should follow the summary"
+    );
+    let call_types: Vec<&str> = after_summary
+        .iter()
+        .filter(|item| item["call_id"] == "plan-call")
+        .filter_map(|item| item["type"].as_str())
+        .collect();
+    assert_eq!(call_types, vec!["function_call", "function_call_output"]);
+}
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 
+use codex_core::config_types::SandboxLimits;
 use codex_core::exec::ExecParams;
 use codex_core::exec::ExecToolCallOutput;
 use codex_core::exec::SandboxType;
@@ -35,6 +36,7 @@ async fn run_test_cmd(tmp: TempDir, cmd: Vec<&str>) -> Result<ExecToolCallOutput
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: SandboxLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
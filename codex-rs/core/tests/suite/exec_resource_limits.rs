@@ -0,0 +1,170 @@
+#![cfg(unix)]
+
+use std::collections::HashMap;
+
+use codex_core::config_types::SandboxLimits;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::ResourceLimit;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::protocol::SandboxPolicy;
+use tempfile::TempDir;
+
+#[expect(clippy::expect_used)]
+async fn run_with_limits(
+    script: &str,
+    cwd: &TempDir,
+    resource_limits: SandboxLimits,
+) -> ExecToolCallOutput {
+    let params = ExecParams {
+        command: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+        cwd: cwd.path().to_path_buf(),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits,
+    };
+    let policy = SandboxPolicy::new_read_only_policy();
+    process_exec_tool_call(params, SandboxType::None, &policy, &None, None)
+        .await
+        .expect("exec should report the limit instead of failing")
+}
+
+#[tokio::test]
+async fn cpu_time_limit_is_reported() {
+    let cwd = TempDir::new().unwrap();
+    let output = run_with_limits(
+        "while :; do :; done",
+        &cwd,
+        SandboxLimits {
+            cpu_time_secs: Some(1),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(output.resource_limit_exceeded, Some(ResourceLimit::CpuTime));
+    assert_ne!(output.exit_code, 0);
+    assert!(
+        output
+            .aggregated_output
+            .text
+            .contains("killed: cpu time limit exceeded"),
+        "{:?}",
+        output.aggregated_output.text
+    );
+}
+
+#[tokio::test]
+async fn file_size_limit_is_reported_through_the_shell() {
+    let cwd = TempDir::new().unwrap();
+    // `head` runs as a child of the shell, which reports the signal that
+    // killed it as an exit code of 128 + SIGXFSZ.
+    let output = run_with_limits(
+        "head -c 2097152 /dev/zero > big; exit $?",
+        &cwd,
+        SandboxLimits {
+            max_file_size_mb: Some(1),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(
+        output.resource_limit_exceeded,
+        Some(ResourceLimit::FileSize)
+    );
+    assert_eq!(
+        std::fs::metadata(cwd.path().join("big")).unwrap().len(),
+        1024 * 1024
+    );
+    assert!(
+        output
+            .aggregated_output
+            .text
+            .contains("killed: file size limit exceeded"),
+        "{:?}",
+        output.aggregated_output.text
+    );
+}
+
+#[tokio::test]
+async fn allocation_failure_is_reported_as_memory_limit() {
+    let cwd = TempDir::new().unwrap();
+    // Rust and C++ programs abort after reporting a failed allocation.
+    let output = run_with_limits(
+        "sh -c 'echo \"memory allocation of 1073741824 bytes failed\" >&2; kill -ABRT $$'; exit $?",
+        &cwd,
+        SandboxLimits {
+            memory_mb: Some(64),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(output.resource_limit_exceeded, Some(ResourceLimit::Memory));
+    assert!(
+        output
+            .aggregated_output
+            .text
+            .contains("memory limit exceeded"),
+        "{:?}",
+        output.aggregated_output.text
+    );
+}
+
+#[tokio::test]
+async fn crash_without_allocation_failure_is_not_blamed_on_memory() {
+    let cwd = TempDir::new().unwrap();
+    let output = run_with_limits(
+        "sh -c 'kill -SEGV $$'; exit $?",
+        &cwd,
+        SandboxLimits {
+            memory_mb: Some(1024),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(output.resource_limit_exceeded, None);
+    assert_eq!(output.exit_code, 128 + 11);
+}
+
+#[tokio::test]
+async fn allocation_message_without_abort_is_not_blamed_on_memory() {
+    let cwd = TempDir::new().unwrap();
+    let output = run_with_limits(
+        "echo 'grep: out of memory: no such file' >&2; exit 1",
+        &cwd,
+        SandboxLimits {
+            memory_mb: Some(1024),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(output.resource_limit_exceeded, None);
+    assert_eq!(output.exit_code, 1);
+}
+
+#[tokio::test]
+async fn process_limit_leaves_small_commands_alone() {
+    let cwd = TempDir::new().unwrap();
+    // Whether or not a delegated cgroup is available to enforce it, a command
+    // that stays below the limit runs normally.
+    let output = run_with_limits(
+        "echo started & wait",
+        &cwd,
+        SandboxLimits {
+            max_processes: Some(16),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(output.resource_limit_exceeded, None);
+    assert_eq!(output.exit_code, 0);
+    assert_eq!(output.stdout.text, "started\n");
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use async_channel::Receiver;
+use codex_core::config_types::SandboxLimits;
 use codex_core::exec::ExecParams;
 use codex_core::exec::SandboxType;
 use codex_core::exec::StdoutStream;
@@ -53,6 +54,7 @@ async fn test_exec_stdout_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: SandboxLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -103,6 +105,7 @@ async fn test_exec_stderr_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: SandboxLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -156,6 +159,7 @@ async fn test_aggregated_output_interleaves_in_order() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: SandboxLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -0,0 +1,122 @@
+#![expect(clippy::unwrap_used)]
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::hooks::HookOnError;
+use codex_core::hooks::HookRoute;
+use codex_core::hooks::HookSpec;
+use codex_core::hooks::HooksConfig;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::ev_completed;
+use core_test_support::ev_function_call;
+use core_test_support::load_default_config_for_test;
+use core_test_support::mount_sse_once;
+use core_test_support::sse;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::MockServer;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_command_rewrite_into_apply_patch_applies_the_patch() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![
+            ev_function_call("call-1", "shell", &json!({"command": ["echo", "hi"]})),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![ev_completed("r2")]),
+    )
+    .await;
+
+    // The hook replaces every command with an `apply_patch` call.
+    let decision = json!({
+        "decision": "rewrite",
+        "command": [
+            "apply_patch",
+            "*** Begin Patch\n*** Add File: hooked.txt\n+from hook\n*** End Patch\n"
+        ],
+    });
+    let workspace = TempDir::new().unwrap();
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.cwd = workspace.path().to_path_buf();
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    config.hooks = Some(HooksConfig {
+        session_start: None,
+        session_end: None,
+        pre_command: Some(HookSpec {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "cat > /dev/null; printf '%s' \"$0\"".to_string(),
+                decision.to_string(),
+            ],
+            route: HookRoute::Ui,
+            timeout_ms: 5000,
+            on_error: HookOnError::FailOpen,
+        }),
+        post_command: None,
+        pre_apply_patch: None,
+        post_apply_patch: None,
+        user_prompt_submit: None,
+        turn_complete: None,
+    });
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "say hi".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::PatchApplyBegin(_))).await;
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    assert_eq!(
+        std::fs::read_to_string(workspace.path().join("hooked.txt")).unwrap(),
+        "from hook\n"
+    );
+}
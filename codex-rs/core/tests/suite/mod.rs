@@ -1,14 +1,19 @@
 // Aggregates all former standalone integration tests as modules.
 
+mod budgets;
 mod cli_stream;
 mod client;
 mod compact;
 mod exec;
+mod exec_resource_limits;
 mod exec_stream_events;
 mod filter_reasoning_chatgpt;
 mod fork_conversation;
+mod hooks;
 mod live_cli;
+mod parallel_tool_calls;
 mod prompt_caching;
 mod seatbelt;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
+mod undo;
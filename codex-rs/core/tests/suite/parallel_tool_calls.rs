@@ -0,0 +1,127 @@
+#![expect(clippy::unwrap_used)]
+
+use std::time::Duration;
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::ev_completed;
+use core_test_support::ev_function_call;
+use core_test_support::load_default_config_for_test;
+use core_test_support::mount_sse_once;
+use core_test_support::sse;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::MockServer;
+
+fn ev_shell_call(call_id: &str, command: &[&str]) -> Value {
+    ev_function_call(call_id, "shell", &json!({"command": command}))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn read_only_tool_calls_keep_their_order() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![
+            ev_shell_call("call-1", &["echo", "first"]),
+            ev_shell_call("call-2", &["echo", "second"]),
+            ev_shell_call("call-3", &["pwd"]),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![ev_completed("r2")]),
+    )
+    .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "look around".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut lifecycle = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), codex.next_event())
+            .await
+            .unwrap()
+            .unwrap();
+        match event.msg {
+            EventMsg::ExecCommandBegin(ev) => lifecycle.push(format!("begin {}", ev.call_id)),
+            EventMsg::ExecCommandEnd(ev) => lifecycle.push(format!("end {}", ev.call_id)),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    let begins: Vec<&String> = lifecycle
+        .iter()
+        .filter(|e| e.starts_with("begin"))
+        .collect();
+    let ends: Vec<&String> = lifecycle.iter().filter(|e| e.starts_with("end")).collect();
+    assert_eq!(begins, ["begin call-1", "begin call-2", "begin call-3"]);
+    assert_eq!(ends, ["end call-1", "end call-2", "end call-3"]);
+
+    // Tool outputs are sent back to the model in the order of the calls.
+    let requests = server.received_requests().await.unwrap();
+    let body = requests[1].body_json::<Value>().unwrap();
+    let outputs: Vec<(String, String)> = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"] == "function_call_output")
+        .map(|item| {
+            (
+                item["call_id"].as_str().unwrap().to_string(),
+                item["output"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let call_ids: Vec<&str> = outputs.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(call_ids, ["call-1", "call-2", "call-3"]);
+    assert!(outputs[0].1.contains("first"));
+    assert!(outputs[1].1.contains("second"));
+}
@@ -385,6 +385,9 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
                 network_access: true,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                deny_read: vec![],
+                allowed_domains: vec![],
+                isolate_namespaces: false,
             }),
             model: Some("o3".to_string()),
             effort: Some(ReasoningEffort::High),
@@ -511,6 +514,9 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
                 network_access: true,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                deny_read: vec![],
+                allowed_domains: vec![],
+                isolate_namespaces: false,
             },
             model: "o3".to_string(),
             effort: ReasoningEffort::High,
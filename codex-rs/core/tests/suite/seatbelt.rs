@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::config_types::SandboxLimits;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::CODEX_SANDBOX_ENV_VAR;
@@ -80,6 +81,9 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
         network_access: false,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        deny_read: vec![],
+        allowed_domains: vec![],
+        isolate_namespaces: false,
     };
 
     test_scenario
@@ -106,6 +110,9 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
         network_access: false,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        deny_read: vec![],
+        allowed_domains: vec![],
+        isolate_namespaces: false,
     };
 
     test_scenario
@@ -187,6 +194,7 @@ async fn touch(path: &Path, policy: &SandboxPolicy) -> bool {
             path.to_string_lossy().to_string(),
         ],
         policy,
+        &SandboxLimits::default(),
         std::env::current_dir().expect("should be able to get current dir"),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
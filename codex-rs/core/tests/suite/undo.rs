@@ -0,0 +1,119 @@
+#![expect(clippy::unwrap_used)]
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::ev_completed;
+use core_test_support::ev_function_call;
+use core_test_support::load_default_config_for_test;
+use core_test_support::mount_sse_once;
+use core_test_support::sse;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use wiremock::MockServer;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn undo_reverts_changes_made_by_shell_commands() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let workspace = TempDir::new().unwrap();
+    let status = std::process::Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(workspace.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::write(workspace.path().join("notes.txt"), "original\n").unwrap();
+
+    let server = MockServer::start().await;
+    // The turn overwrites notes.txt and creates new.txt with a shell command.
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            !std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![
+            ev_function_call(
+                "call-1",
+                "shell",
+                &json!({
+                    "command": ["sh", "-c", "echo changed > notes.txt && echo new > new.txt"],
+                }),
+            ),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            std::str::from_utf8(&req.body)
+                .unwrap_or("")
+                .contains("function_call_output")
+        },
+        sse(vec![ev_completed("r2")]),
+    )
+    .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.cwd = workspace.path().to_path_buf();
+    config.checkpoints = true;
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "edit the notes".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    assert_eq!(
+        std::fs::read_to_string(workspace.path().join("notes.txt")).unwrap(),
+        "changed\n"
+    );
+
+    codex.submit(Op::Undo { turns: 1 }).await.unwrap();
+    let EventMsg::UndoCompleted(undone) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::UndoCompleted(_))).await
+    else {
+        unreachable!("wait_for_event returned a different event");
+    };
+    assert_eq!(undone.restored, vec![PathBuf::from("notes.txt")]);
+    assert_eq!(undone.removed, vec![PathBuf::from("new.txt")]);
+    assert_eq!(
+        std::fs::read_to_string(workspace.path().join("notes.txt")).unwrap(),
+        "original\n"
+    );
+    assert!(!workspace.path().join("new.txt").exists());
+}
@@ -1,6 +1,8 @@
+use clap::Args;
 use clap::Parser;
 use clap::ValueEnum;
 use codex_common::CliConfigOverrides;
+use codex_core::config_types::Budgets;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -79,12 +81,67 @@ pub struct Cli {
     #[arg(long = "from-summarize")]
     pub from_summarize: Option<String>,
 
+    #[clap(flatten)]
+    pub budgets: BudgetArgs,
+
     /// Initial instructions for the agent. If not provided as an argument (or
     /// if `-` is used), instructions are read from stdin.
     #[arg(value_name = "PROMPT")]
     pub prompt: Option<String>,
 }
 
+/// Hard limits for unattended runs. These override `[budgets]` in
+/// config.toml; when a limit is reached the task is aborted.
+#[derive(Args, Debug, Default)]
+pub struct BudgetArgs {
+    /// Maximum number of tool calls in a single turn.
+    #[arg(long = "max-turn-tool-calls", value_name = "N")]
+    pub max_turn_tool_calls: Option<u64>,
+
+    /// Maximum number of tokens used by a single turn.
+    #[arg(long = "max-turn-tokens", value_name = "N")]
+    pub max_turn_tokens: Option<u64>,
+
+    /// Maximum wall-clock seconds for a single turn.
+    #[arg(long = "max-turn-seconds", value_name = "SECS")]
+    pub max_turn_seconds: Option<u64>,
+
+    /// Maximum number of tool calls across the whole session.
+    #[arg(long = "max-session-tool-calls", value_name = "N")]
+    pub max_session_tool_calls: Option<u64>,
+
+    /// Maximum number of tokens used across the whole session.
+    #[arg(long = "max-session-tokens", value_name = "N")]
+    pub max_session_tokens: Option<u64>,
+
+    /// Maximum wall-clock seconds for the whole session.
+    #[arg(long = "max-session-seconds", value_name = "SECS")]
+    pub max_session_seconds: Option<u64>,
+}
+
+impl BudgetArgs {
+    /// Overlay the limits given on the command line onto `budgets`.
+    pub fn apply(&self, budgets: &mut Budgets) {
+        let overlay = |flag: Option<u64>, field: &mut Option<u64>| {
+            if flag.is_some() {
+                *field = flag;
+            }
+        };
+        overlay(self.max_turn_tool_calls, &mut budgets.turn.max_tool_calls);
+        overlay(self.max_turn_tokens, &mut budgets.turn.max_tokens);
+        overlay(self.max_turn_seconds, &mut budgets.turn.max_duration_secs);
+        overlay(
+            self.max_session_tool_calls,
+            &mut budgets.session.max_tool_calls,
+        );
+        overlay(self.max_session_tokens, &mut budgets.session.max_tokens);
+        overlay(
+            self.max_session_seconds,
+            &mut budgets.session.max_duration_secs,
+        );
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum Color {
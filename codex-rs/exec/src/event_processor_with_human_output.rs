@@ -8,6 +8,10 @@ use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::ContextCompactedEvent;
+use codex_core::protocol::DelegateBeginEvent;
+use codex_core::protocol::DelegateEndEvent;
+use codex_core::protocol::DelegateEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -24,6 +28,7 @@ use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::UndoCompletedEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
 use owo_colors::OwoColorize;
@@ -399,11 +404,12 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 // it's easy to scan in the terminal output.
                 for (path, change) in changes.iter() {
                     match change {
-                        FileChange::Add { content } => {
+                        FileChange::Add { content, mode } => {
                             let header = format!(
-                                "{} {}",
+                                "{} {}{}",
                                 format_file_change(change),
-                                path.to_string_lossy()
+                                path.to_string_lossy(),
+                                format_file_mode(*mode)
                             );
                             println!("{}", header.style(self.magenta));
                             for line in content.lines() {
@@ -424,16 +430,23 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                         FileChange::Update {
                             unified_diff,
                             move_path,
+                            mode,
                         } => {
                             let header = if let Some(dest) = move_path {
                                 format!(
-                                    "{} {} -> {}",
+                                    "{} {} -> {}{}",
                                     format_file_change(change),
                                     path.to_string_lossy(),
-                                    dest.to_string_lossy()
+                                    dest.to_string_lossy(),
+                                    format_file_mode(*mode)
                                 )
                             } else {
-                                format!("{} {}", format_file_change(change), path.to_string_lossy())
+                                format!(
+                                    "{} {}{}",
+                                    format_file_change(change),
+                                    path.to_string_lossy(),
+                                    format_file_mode(*mode)
+                                )
                             };
                             println!("{}", header.style(self.magenta));
 
@@ -452,6 +465,16 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                                 }
                             }
                         }
+                        FileChange::Copy { source, mode } => {
+                            let header = format!(
+                                "{} {} -> {}{}",
+                                format_file_change(change),
+                                source.to_string_lossy(),
+                                path.to_string_lossy(),
+                                format_file_mode(*mode)
+                            );
+                            println!("{}", header.style(self.magenta));
+                        }
                     }
                 }
             }
@@ -549,6 +572,63 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::DelegateBegin(DelegateBeginEvent { prompt, cwd, .. }) => {
+                ts_println!(
+                    self,
+                    "{} {} in {}",
+                    "delegate".style(self.magenta),
+                    prompt.style(self.bold),
+                    cwd.to_string_lossy(),
+                );
+            }
+            EventMsg::DelegateEvent(DelegateEventEvent { msg, .. }) => match *msg {
+                EventMsg::ExecCommandBegin(ExecCommandBeginEvent { command, .. }) => {
+                    ts_println!(
+                        self,
+                        "{} {}",
+                        "delegate exec".style(self.dimmed),
+                        escape_command(&command).style(self.dimmed),
+                    );
+                }
+                EventMsg::Error(ErrorEvent { message }) => {
+                    let prefix = "delegate ERROR:".style(self.red);
+                    ts_println!(self, "{prefix} {message}");
+                }
+                _ => {
+                    // Other nested events are summarized by `DelegateEnd`.
+                }
+            },
+            EventMsg::DelegateEnd(DelegateEndEvent {
+                last_agent_message,
+                success,
+                ..
+            }) => {
+                let (status, title_style) = if success {
+                    ("succeeded", self.green)
+                } else {
+                    ("failed", self.red)
+                };
+                let title = format!("delegate {status}:");
+                ts_println!(self, "{}", title.style(title_style));
+                if let Some(message) = last_agent_message {
+                    for line in message.lines() {
+                        println!("{}", line.style(self.dimmed));
+                    }
+                }
+            }
+            EventMsg::ContextCompacted(ContextCompactedEvent {
+                tokens_in_context_window,
+                token_limit,
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!(
+                        "context compacted: {tokens_in_context_window} tokens reached limit {token_limit}"
+                    )
+                    .style(self.dimmed)
+                );
+            }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
                 TurnAbortReason::Interrupted => {
                     ts_println!(self, "task interrupted");
@@ -556,7 +636,32 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 TurnAbortReason::Replaced => {
                     ts_println!(self, "task aborted: replaced by a new task");
                 }
+                TurnAbortReason::BudgetExceeded => {
+                    ts_println!(self, "task aborted: budget exhausted");
+                    return CodexStatus::InitiateShutdown;
+                }
             },
+            EventMsg::UndoCompleted(UndoCompletedEvent {
+                turns,
+                restored,
+                removed,
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!("undid {turns} turn(s)").style(self.magenta)
+                );
+                for path in restored {
+                    println!("{}", format!("M {}", path.display()).style(self.dimmed));
+                }
+                for path in removed {
+                    println!("{}", format!("D {}", path.display()).style(self.dimmed));
+                }
+            }
+            EventMsg::BudgetExceeded(ev) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(self, "{prefix} {}", ev.message());
+            }
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationHistory(_) => {}
             EventMsg::UserMessage(_) => {}
@@ -579,6 +684,14 @@ fn format_file_change(change: &FileChange) -> &'static str {
         FileChange::Update {
             move_path: None, ..
         } => "M",
+        FileChange::Copy { .. } => "C",
+    }
+}
+
+fn format_file_mode(mode: Option<u32>) -> String {
+    match mode {
+        Some(mode) => format!(" (mode {mode:o})"),
+        None => String::new(),
     }
 }
 
@@ -50,8 +50,9 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         prompt,
         config_overrides,
         auto_summary,
-        summarize_name,
-        from_summarize,
+        summarize_name: _,
+        from_summarize: _,
+        budgets,
     } = cli;
 
     // Determine the prompt based on CLI arg and/or stdin.
@@ -165,7 +166,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    budgets.apply(&mut config.budgets);
 
     // Load exec-specific overrides from config.toml (e.g., ~/.qoo/config.toml under qoo).
     let exec_overrides = exec_config::load_exec_overrides(&config.codex_home);
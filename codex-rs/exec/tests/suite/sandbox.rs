@@ -1,4 +1,5 @@
 #![cfg(unix)]
+use codex_core::config_types::SandboxLimits;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::spawn::StdioPolicy;
 use std::collections::HashMap;
@@ -17,7 +18,15 @@ async fn spawn_command_under_sandbox(
     env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
     use codex_core::seatbelt::spawn_command_under_seatbelt;
-    spawn_command_under_seatbelt(command, sandbox_policy, cwd, stdio_policy, env).await
+    spawn_command_under_seatbelt(
+        command,
+        sandbox_policy,
+        &SandboxLimits::default(),
+        cwd,
+        stdio_policy,
+        env,
+    )
+    .await
 }
 
 #[cfg(target_os = "linux")]
@@ -34,6 +43,7 @@ async fn spawn_command_under_sandbox(
         codex_linux_sandbox_exe,
         command,
         sandbox_policy,
+        &SandboxLimits::default(),
         cwd,
         stdio_policy,
         env,
@@ -58,6 +68,9 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
         network_access: false,
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
+        deny_read: vec![],
+        allowed_domains: vec![],
+        isolate_namespaces: false,
     };
 
     let python_code = r#"import multiprocessing
@@ -16,6 +16,8 @@ pub struct Policy {
     programs: MultiMap<String, ProgramSpec>,
     forbidden_program_regexes: Vec<ForbiddenProgramRegex>,
     forbidden_substrings_pattern: Option<Regex>,
+    allow_subshells: bool,
+    allow_command_substitution: bool,
 }
 
 impl Policy {
@@ -23,6 +25,8 @@ impl Policy {
         programs: MultiMap<String, ProgramSpec>,
         forbidden_program_regexes: Vec<ForbiddenProgramRegex>,
         forbidden_substrings: Vec<String>,
+        allow_subshells: bool,
+        allow_command_substitution: bool,
     ) -> std::result::Result<Self, RegexError> {
         let forbidden_substrings_pattern = if forbidden_substrings.is_empty() {
             None
@@ -38,9 +42,24 @@ impl Policy {
             programs,
             forbidden_program_regexes,
             forbidden_substrings_pattern,
+            allow_subshells,
+            allow_command_substitution,
         })
     }
 
+    /// Whether scripts checked against this policy may use `( ... )`
+    /// subshells, as enabled with `allow_shell_constructs(subshells=True)`.
+    pub fn allows_subshells(&self) -> bool {
+        self.allow_subshells
+    }
+
+    /// Whether scripts checked against this policy may use `$( ... )` command
+    /// substitution, as enabled with
+    /// `allow_shell_constructs(command_substitution=True)`.
+    pub fn allows_command_substitution(&self) -> bool {
+        self.allow_command_substitution
+    }
+
     pub fn check(&self, exec_call: &ExecCall) -> Result<MatchedExec> {
         let ExecCall { program, args } = &exec_call;
         for ForbiddenProgramRegex { regex, reason } in &self.forbidden_program_regexes {
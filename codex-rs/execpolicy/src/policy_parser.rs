@@ -18,6 +18,7 @@ use starlark::syntax::Dialect;
 use starlark::values::Heap;
 use starlark::values::list::UnpackList;
 use starlark::values::none::NoneType;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
@@ -82,6 +83,8 @@ struct PolicyBuilder {
     programs: RefCell<MultiMap<String, ProgramSpec>>,
     forbidden_program_regexes: RefCell<Vec<ForbiddenProgramRegex>>,
     forbidden_substrings: RefCell<Vec<String>>,
+    allow_subshells: Cell<bool>,
+    allow_command_substitution: Cell<bool>,
 }
 
 impl PolicyBuilder {
@@ -90,6 +93,8 @@ impl PolicyBuilder {
             programs: RefCell::new(MultiMap::new()),
             forbidden_program_regexes: RefCell::new(Vec::new()),
             forbidden_substrings: RefCell::new(Vec::new()),
+            allow_subshells: Cell::new(false),
+            allow_command_substitution: Cell::new(false),
         }
     }
 
@@ -97,7 +102,13 @@ impl PolicyBuilder {
         let programs = self.programs.into_inner();
         let forbidden_program_regexes = self.forbidden_program_regexes.into_inner();
         let forbidden_substrings = self.forbidden_substrings.into_inner();
-        Policy::new(programs, forbidden_program_regexes, forbidden_substrings)
+        Policy::new(
+            programs,
+            forbidden_program_regexes,
+            forbidden_substrings,
+            self.allow_subshells.get(),
+            self.allow_command_substitution.get(),
+        )
     }
 
     fn add_program_spec(&self, program_spec: ProgramSpec) {
@@ -212,6 +223,27 @@ fn policy_builtins(builder: &mut GlobalsBuilder) {
         Ok(NoneType)
     }
 
+    fn allow_shell_constructs(
+        subshells: Option<bool>,
+        command_substitution: Option<bool>,
+        eval: &mut Evaluator,
+    ) -> anyhow::Result<NoneType> {
+        #[expect(clippy::unwrap_used)]
+        let policy_builder = eval
+            .extra
+            .as_ref()
+            .unwrap()
+            .downcast_ref::<PolicyBuilder>()
+            .unwrap();
+        if subshells.unwrap_or(false) {
+            policy_builder.allow_subshells.set(true);
+        }
+        if command_substitution.unwrap_or(false) {
+            policy_builder.allow_command_substitution.set(true);
+        }
+        Ok(NoneType)
+    }
+
     fn opt(name: String, r#type: ArgMatcher, required: Option<bool>) -> anyhow::Result<Opt> {
         Ok(Opt::new(
             name,
@@ -118,29 +118,18 @@ impl ProgramSpec {
                     program: self.program.clone(),
                 });
             } else if arg.starts_with("-") {
-                match self.allowed_options.get(arg) {
-                    Some(opt) => {
-                        match &opt.meta {
-                            OptMeta::Flag => {
-                                matched_flags.push(MatchedFlag { name: arg.clone() });
-                                // A flag does not expect an argument: continue.
-                                continue;
-                            }
-                            OptMeta::Value(arg_type) => {
-                                expecting_option_value = Some((arg.clone(), arg_type.clone()));
-                                continue;
-                            }
+                for (name, meta, inline_value) in self.resolve_option(arg)? {
+                    match (meta, inline_value) {
+                        (OptMeta::Flag, _) => matched_flags.push(MatchedFlag { name }),
+                        (OptMeta::Value(arg_type), Some(value)) => {
+                            matched_opts.push(MatchedOpt::new(&name, &value, arg_type.clone())?);
+                        }
+                        (OptMeta::Value(arg_type), None) => {
+                            // The value is the next argument.
+                            expecting_option_value = Some((name, arg_type.clone()));
                         }
-                    }
-                    None => {
-                        // It could be an --option=value style flag...
                     }
                 }
-
-                return Err(Error::UnknownOption {
-                    program: self.program.clone(),
-                    option: arg.clone(),
-                });
             } else {
                 args.push(PositionalArg {
                     index,
@@ -194,6 +183,58 @@ impl ProgramSpec {
         }
     }
 
+    /// Resolves a command-line argument that starts with `-` into the options
+    /// it names. Besides options spelled exactly as declared, this accepts
+    /// `--name=value` when `combined_format` is set and bundled short options
+    /// such as `-al` (or `-n5`, where the value is attached to the last
+    /// option) when `option_bundling` is set.
+    ///
+    /// Each entry is the option's name, its definition and its inline value,
+    /// if any. Only the last entry may be a value option without a value.
+    fn resolve_option(&self, arg: &str) -> Result<Vec<(String, &OptMeta, Option<String>)>> {
+        if let Some(opt) = self.allowed_options.get(arg) {
+            return Ok(vec![(arg.to_string(), &opt.meta, None)]);
+        }
+
+        if self.combined_format
+            && arg.starts_with("--")
+            && let Some((name, value)) = arg.split_once('=')
+            && let Some(opt) = self.allowed_options.get(name)
+            && matches!(opt.meta, OptMeta::Value(_))
+        {
+            return Ok(vec![(name.to_string(), &opt.meta, Some(value.to_string()))]);
+        }
+
+        if self.option_bundling && !arg.starts_with("--") && arg.len() > 2 {
+            let mut resolved = Vec::new();
+            for (index, c) in arg.char_indices().skip(1) {
+                let name = format!("-{c}");
+                let Some(opt) = self.allowed_options.get(&name) else {
+                    return Err(Error::UnknownOption {
+                        program: self.program.clone(),
+                        option: name,
+                    });
+                };
+                match opt.meta {
+                    OptMeta::Flag => resolved.push((name, &opt.meta, None)),
+                    OptMeta::Value(_) => {
+                        // Whatever follows the option is its value.
+                        let rest = &arg[index + c.len_utf8()..];
+                        let value = (!rest.is_empty()).then(|| rest.to_string());
+                        resolved.push((name, &opt.meta, value));
+                        break;
+                    }
+                }
+            }
+            return Ok(resolved);
+        }
+
+        Err(Error::UnknownOption {
+            program: self.program.clone(),
+            option: arg.to_string(),
+        })
+    }
+
     pub fn verify_should_match_list(&self) -> Vec<PositiveExampleFailedCheck> {
         let mut violations = Vec::new();
         for good in &self.should_match {
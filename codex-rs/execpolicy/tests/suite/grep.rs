@@ -0,0 +1,68 @@
+extern crate codex_execpolicy;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::Error;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedArg;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::MatchedFlag;
+use codex_execpolicy::MatchedOpt;
+use codex_execpolicy::Policy;
+use codex_execpolicy::Result;
+use codex_execpolicy::ValidExec;
+use codex_execpolicy::get_default_policy;
+
+#[expect(clippy::expect_used)]
+fn setup() -> Policy {
+    get_default_policy().expect("failed to load default policy")
+}
+
+#[test]
+fn test_grep_combined_format_option() -> Result<()> {
+    let policy = setup();
+    let grep = ExecCall::new("grep", &["-rn", "--max-count=5", "init", "src"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "grep".to_string(),
+                flags: vec![MatchedFlag::new("-r"), MatchedFlag::new("-n")],
+                opts: vec![MatchedOpt::new(
+                    "--max-count",
+                    "5",
+                    ArgType::PositiveInteger
+                )?],
+                args: vec![
+                    MatchedArg::new(2, ArgType::OpaqueNonFile, "init")?,
+                    MatchedArg::new(3, ArgType::ReadableFile, "src")?,
+                ],
+                system_path: vec!["/bin/grep".to_string(), "/usr/bin/grep".to_string()],
+            }
+        }),
+        policy.check(&grep)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_grep_combined_format_requires_valid_value() {
+    let policy = setup();
+    let grep = ExecCall::new("grep", &["--max-count=0", "init", "main.rs"]);
+    assert_eq!(
+        Err(Error::InvalidPositiveInteger {
+            value: "0".to_string(),
+        }),
+        policy.check(&grep)
+    );
+}
+
+#[test]
+fn test_bundled_option_expecting_value_must_be_last() {
+    let policy = setup();
+    let grep = ExecCall::new("grep", &["-mn", "init", "main.rs"]);
+    assert_eq!(
+        Err(Error::InvalidPositiveInteger {
+            value: "n".to_string(),
+        }),
+        policy.check(&grep)
+    );
+}
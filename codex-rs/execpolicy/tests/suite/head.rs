@@ -134,3 +134,29 @@ fn test_head_invalid_n_as_negative_int() {
         policy.check(&head)
     )
 }
+
+#[test]
+fn test_head_value_attached_to_option() -> Result<()> {
+    let policy = setup();
+    let head = ExecCall::new("head", &["-n100", "src/extension.ts"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "head".to_string(),
+                flags: vec![],
+                opts: vec![
+                    MatchedOpt::new("-n", "100", ArgType::PositiveInteger)
+                        .expect("should validate")
+                ],
+                args: vec![MatchedArg::new(
+                    1,
+                    ArgType::ReadableFile,
+                    "src/extension.ts"
+                )?],
+                system_path: vec!["/bin/head".to_string(), "/usr/bin/head".to_string()],
+            }
+        }),
+        policy.check(&head)
+    );
+    Ok(())
+}
@@ -2,6 +2,7 @@
 mod bad;
 mod cp;
 mod good;
+mod grep;
 mod head;
 mod literal;
 mod ls;
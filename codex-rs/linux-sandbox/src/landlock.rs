@@ -27,21 +27,33 @@ use seccompiler::apply_filter;
 
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
+///
+/// `private_network` says the command runs in a network namespace of its own,
+/// where the egress proxy is forwarded to loopback.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    private_network: bool,
 ) -> Result<()> {
     if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+        install_network_seccomp_filter_on_current_thread(private_network)?;
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy
+        let mut writable_roots: Vec<PathBuf> = sandbox_policy
             .get_writable_roots_with_cwd(cwd)
             .into_iter()
             .map(|writable_root| writable_root.root)
             .collect();
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+        // With isolated namespaces /tmp is a private tmpfs, so there is no
+        // reason to keep it read-only.
+        if sandbox_policy.isolates_namespaces() {
+            writable_roots.push(PathBuf::from("/tmp"));
+        }
+        install_filesystem_landlock_rules_on_current_thread(
+            writable_roots,
+            sandbox_policy.get_deny_read_paths(),
+        )?;
     }
 
     // TODO(ragona): Add appropriate restrictions if
@@ -51,26 +63,58 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
 }
 
 /// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// access to the entire file-system except `deny_read`, while restricting
+/// write access to `/dev/null` and the provided list of `writable_roots`.
+///
+/// Landlock can only grant access, so the file-system is covered by an
+/// allow-list of subtrees that leaves out the denied paths. Directories stay
+/// listable everywhere so that the parents of a denied path remain usable;
+/// the names of denied files can therefore be seen, but not their contents.
+///
+/// A writable root that contains a denied path is split the same way. Its
+/// split directories (the root itself and those between it and the denied
+/// path) keep every write right, but not `READ_FILE` or `EXECUTE`, since the
+/// rule also covers the denied path beneath them. Files can therefore still
+/// be created, renamed and removed directly in those directories, but a file
+/// created there after the command started cannot be read until the next
+/// command.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(
+    writable_roots: Vec<PathBuf>,
+    deny_read: &[PathBuf],
+) -> Result<()> {
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
 
+    // Paths that do not exist cannot be read, so there is nothing to exclude.
+    let deny_read: Vec<PathBuf> = deny_read
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    let (readable_subtrees, _) = subtrees_excluding(vec![PathBuf::from("/")], &deny_read);
+    let (writable_subtrees, split_writable_dirs) = subtrees_excluding(writable_roots, &deny_read);
+    let access_write_only = access_rw & !(AccessFs::ReadFile | AccessFs::Execute);
+
     let mut ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
         .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
+        .add_rules(landlock::path_beneath_rules(&readable_subtrees, access_ro))?
+        .add_rules(landlock::path_beneath_rules(&["/"], AccessFs::ReadDir))?
         .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
         .set_no_new_privs(true);
 
-    if !writable_roots.is_empty() {
-        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
+    if !writable_subtrees.is_empty() {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_subtrees, access_rw))?;
+    }
+    if !split_writable_dirs.is_empty() {
+        ruleset = ruleset.add_rules(landlock::path_beneath_rules(
+            &split_writable_dirs,
+            access_write_only,
+        ))?;
     }
 
     let status = ruleset.restrict_self()?;
@@ -82,9 +126,56 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
+/// Splits `roots` into subtrees that together cover everything beneath them
+/// except the (canonical) `denied` paths, and also returns the directories
+/// that were split. Only directories that contain a denied path are split,
+/// and symlinks are never followed into one, so the result stays small.
+fn subtrees_excluding(roots: Vec<PathBuf>, denied: &[PathBuf]) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    if denied.is_empty() {
+        return (roots, Vec::new());
+    }
+
+    let mut subtrees = Vec::new();
+    let mut split = Vec::new();
+    let mut pending: Vec<(PathBuf, bool)> = roots.into_iter().map(|root| (root, false)).collect();
+    while let Some((path, is_symlink)) = pending.pop() {
+        // Landlock resolves symlinks when the rule is added, so decide based on
+        // where the path really points.
+        let Ok(resolved) = path.canonicalize() else {
+            continue;
+        };
+        if denied.iter().any(|denied| resolved.starts_with(denied)) {
+            continue;
+        }
+        if !denied.iter().any(|denied| denied.starts_with(&resolved)) {
+            subtrees.push(resolved);
+            continue;
+        }
+        if is_symlink {
+            continue;
+        }
+        if let Ok(entries) = std::fs::read_dir(&resolved) {
+            pending.extend(entries.flatten().map(|entry| {
+                let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+                (entry.path(), is_symlink)
+            }));
+        }
+        split.push(resolved);
+    }
+    (subtrees, split)
+}
+
 /// Installs a seccomp filter that blocks outbound network access except for
 /// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+///
+/// In a `private_network` the only interface is loopback, so TCP and UDP
+/// sockets are allowed there to reach the forwarded egress proxy. Unix
+/// sockets are then refused instead (apart from `socketpair`), since they
+/// could connect to services outside the namespace such as the Docker daemon
+/// or an SSH agent.
+fn install_network_seccomp_filter_on_current_thread(
+    private_network: bool,
+) -> std::result::Result<(), SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -93,25 +184,27 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules.insert(nr, vec![]); // empty rule vec = unconditional match
     };
 
-    deny_syscall(libc::SYS_connect);
     deny_syscall(libc::SYS_accept);
     deny_syscall(libc::SYS_accept4);
     deny_syscall(libc::SYS_bind);
     deny_syscall(libc::SYS_listen);
-    deny_syscall(libc::SYS_getpeername);
-    deny_syscall(libc::SYS_getsockname);
-    deny_syscall(libc::SYS_shutdown);
-    deny_syscall(libc::SYS_sendto);
-    deny_syscall(libc::SYS_sendmsg);
-    deny_syscall(libc::SYS_sendmmsg);
-    // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
-    // with their socketpair + child processes for sub-proc management
-    // deny_syscall(libc::SYS_recvfrom);
-    deny_syscall(libc::SYS_recvmsg);
-    deny_syscall(libc::SYS_recvmmsg);
-    deny_syscall(libc::SYS_getsockopt);
-    deny_syscall(libc::SYS_setsockopt);
     deny_syscall(libc::SYS_ptrace);
+    if !private_network {
+        deny_syscall(libc::SYS_connect);
+        deny_syscall(libc::SYS_getpeername);
+        deny_syscall(libc::SYS_getsockname);
+        deny_syscall(libc::SYS_shutdown);
+        deny_syscall(libc::SYS_sendto);
+        deny_syscall(libc::SYS_sendmsg);
+        deny_syscall(libc::SYS_sendmmsg);
+        // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
+        // with their socketpair + child processes for sub-proc management
+        // deny_syscall(libc::SYS_recvfrom);
+        deny_syscall(libc::SYS_recvmsg);
+        deny_syscall(libc::SYS_recvmmsg);
+        deny_syscall(libc::SYS_getsockopt);
+        deny_syscall(libc::SYS_setsockopt);
+    }
 
     // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
     let unix_only_rule = SeccompRule::new(vec![SeccompCondition::new(
@@ -121,8 +214,19 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         libc::AF_UNIX as u64,
     )?])?;
 
-    rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
-    rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
+    if private_network {
+        let domain_is_not = |domain: libc::c_int| {
+            SeccompCondition::new(0, SeccompCmpArgLen::Dword, SeccompCmpOp::Ne, domain as u64)
+        };
+        let inet_only_rule = SeccompRule::new(vec![
+            domain_is_not(libc::AF_INET)?,
+            domain_is_not(libc::AF_INET6)?,
+        ])?;
+        rules.insert(libc::SYS_socket, vec![inet_only_rule]);
+    } else {
+        rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
+    }
+    rules.insert(libc::SYS_socketpair, vec![unix_only_rule]);
 
     let filter = SeccompFilter::new(
         rules,
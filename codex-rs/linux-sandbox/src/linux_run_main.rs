@@ -1,8 +1,12 @@
 use clap::Parser;
+use codex_core::exec_env::CODEX_EGRESS_PROXY_SOCKET_ENV_VAR;
+use codex_core::exec_env::EGRESS_PROXY_UNAVAILABLE_MESSAGE;
 use std::ffi::CString;
 use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::namespaces::enter_isolated_namespaces;
+use crate::proxy_shim;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
@@ -24,7 +28,40 @@ pub fn run_main() -> ! {
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    let egress_proxy_socket = std::env::var_os(CODEX_EGRESS_PROXY_SOCKET_ENV_VAR)
+        .map(PathBuf::from)
+        .filter(|_| {
+            !sandbox_policy.has_full_network_access()
+                && !sandbox_policy.get_allowed_domains().is_empty()
+        });
+    // The helper is still single-threaded.
+    unsafe { std::env::remove_var(CODEX_EGRESS_PROXY_SOCKET_ENV_VAR) };
+
+    if sandbox_policy.isolates_namespaces()
+        && let Err(e) = enter_isolated_namespaces(
+            &sandbox_policy,
+            &sandbox_policy_cwd,
+            egress_proxy_socket.as_deref(),
+        )
+    {
+        panic!("error isolating namespaces: {e:?}");
+    }
+
+    // Without the forwarder the command would get no network at all, so refuse
+    // to run it rather than let it fail in ways that hide the reason.
+    if let Some(socket) = &egress_proxy_socket
+        && let Err(e) = proxy_shim::start(socket, sandbox_policy.isolates_namespaces())
+    {
+        eprintln!("{EGRESS_PROXY_UNAVAILABLE_MESSAGE}: {e}");
+        std::process::exit(1);
+    }
+    let private_network = egress_proxy_socket.is_some();
+
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &sandbox_policy_cwd,
+        private_network,
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
@@ -0,0 +1,325 @@
+//! Optional namespace isolation, enabled by `isolate_namespaces` on
+//! [`SandboxPolicy::WorkspaceWrite`].
+//!
+//! The helper moves into new user, mount, PID and IPC namespaces (plus a
+//! network namespace when the egress proxy is used) and forks.
+//! The child becomes PID 1 of the new PID namespace, rearranges its mounts and
+//! forks once more; the grandchild carries on with the Landlock and seccomp
+//! setup before exec'ing the command.
+//!
+//! The command is deliberately not PID 1: the kernel drops signals that PID 1
+//! has no handler for, which would make it immune to `kill` and to resource
+//! limits. Instead PID 1 reaps orphans until the command exits and passes the
+//! command's wait status to the original process, which mirrors it.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::SandboxPolicy;
+
+/// Isolates the rest of the sandbox setup in new namespaces, including a
+/// network namespace if the command reaches the network through the egress
+/// proxy listening on `egress_proxy_socket`, which stays reachable. Only
+/// returns in the process that goes on to exec the command; the others exit
+/// with its status once it finishes.
+///
+/// Must be called while the process is still single-threaded.
+pub(crate) fn enter_isolated_namespaces(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    egress_proxy_socket: Option<&Path>,
+) -> io::Result<()> {
+    let mut flags = libc::CLONE_NEWNS | libc::CLONE_NEWPID | libc::CLONE_NEWIPC;
+    if egress_proxy_socket.is_some() {
+        flags |= libc::CLONE_NEWNET;
+    }
+    enter_user_namespace(flags)?;
+
+    let mut fds = [0; 2];
+    check(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) })?;
+    let (status_reader, status_writer) =
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            drop(status_reader);
+            // Take the whole PID namespace down with the parent, which is
+            // what Codex kills on timeout.
+            check(unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) })?;
+            // The parent may have exited before the death signal was set up.
+            // `getppid` cannot tell, since the parent is outside the new PID
+            // namespace, but the status pipe loses its only reader.
+            if reader_has_exited(&status_writer) {
+                unsafe { libc::raise(libc::SIGKILL) };
+            }
+            set_up_mounts(sandbox_policy, sandbox_policy_cwd, egress_proxy_socket)?;
+            run_init(status_writer)
+        }
+        init => {
+            drop(status_writer);
+            wait_and_mirror_exit_status(init, status_reader)
+        }
+    }
+}
+
+/// Forks the process that runs the command and, as PID 1, reaps every
+/// process of the namespace until the command exits. Its wait status is then
+/// written to `status_writer`.
+fn run_init(status_writer: File) -> io::Result<()> {
+    let command = match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => {
+            drop(status_writer);
+            return Ok(());
+        }
+        command => command,
+    };
+
+    let mut status = 0;
+    loop {
+        let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+        if pid == command {
+            break;
+        }
+        if pid == -1 && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            std::process::exit(1);
+        }
+    }
+    let _ = (&status_writer).write_all(&status.to_ne_bytes());
+    // Exiting tears down the namespace, along with anything the command left
+    // running in the background.
+    std::process::exit(1);
+}
+
+/// Makes the file-system read-only except for the writable roots (whose
+/// read-only subpaths stay read-only), and gives the child a private `/tmp`
+/// and a `/proc` for its own PID namespace. The directory of
+/// `egress_proxy_socket` is kept visible even if it lives under `/tmp`.
+fn set_up_mounts(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    egress_proxy_socket: Option<&Path>,
+) -> io::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let tmp = Path::new("/tmp");
+
+    // Keep our mount changes from propagating back to the host.
+    mount(
+        None,
+        Path::new("/"),
+        None,
+        libc::MS_REC | libc::MS_PRIVATE,
+        None,
+    )?;
+    set_mount_attr(Path::new("/"), true, libc::MOUNT_ATTR_RDONLY, 0)?;
+
+    // Writable roots and the proxy socket under /tmp would be hidden by the
+    // private /tmp, so keep a handle on them to bind them back afterwards.
+    let mut roots_under_tmp: Vec<(PathBuf, File)> = Vec::new();
+    if let Some(socket_dir) = egress_proxy_socket.and_then(Path::parent)
+        && let Ok(socket_dir) = socket_dir.canonicalize()
+        && socket_dir != tmp
+        && socket_dir.starts_with(tmp)
+    {
+        let handle = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH)
+            .open(&socket_dir)?;
+        roots_under_tmp.push((socket_dir, handle));
+    }
+    for writable_root in sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd) {
+        let Ok(root) = writable_root.root.canonicalize() else {
+            continue;
+        };
+        bind(&root, &root)?;
+        set_mount_attr(&root, false, 0, libc::MOUNT_ATTR_RDONLY)?;
+        for subpath in writable_root.read_only_subpaths {
+            if subpath.exists() {
+                bind(&subpath, &subpath)?;
+                set_mount_attr(&subpath, true, libc::MOUNT_ATTR_RDONLY, 0)?;
+            }
+        }
+        if root != tmp && root.starts_with(tmp) {
+            let handle = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH)
+                .open(&root)?;
+            roots_under_tmp.push((root, handle));
+        }
+    }
+
+    if tmp.is_dir() {
+        mount(
+            Some(Path::new("tmpfs")),
+            tmp,
+            Some("tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some("mode=1777"),
+        )?;
+        for (root, handle) in roots_under_tmp {
+            std::fs::create_dir_all(&root)?;
+            let source = PathBuf::from(format!("/proc/self/fd/{}", handle.as_raw_fd()));
+            bind(&source, &root)?;
+        }
+    }
+
+    // Mounting a fresh /proc is refused when the current one is partially
+    // masked, as in many containers. The host's /proc still works then, it
+    // just shows processes outside the PID namespace.
+    if let Err(e) = mount(
+        Some(Path::new("proc")),
+        Path::new("/proc"),
+        Some("proc"),
+        libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+        None,
+    ) {
+        eprintln!("codex-linux-sandbox: /proc shows processes outside the sandbox: {e}");
+    }
+
+    // Re-resolve the working directory in case it now lives on a new mount.
+    std::env::set_current_dir(cwd)
+}
+
+/// Moves into a new user namespace, along with the other namespaces in
+/// `flags`, in which the caller keeps its own uid and gid.
+pub(crate) fn enter_user_namespace(flags: libc::c_int) -> io::Result<()> {
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    check(unsafe { libc::unshare(libc::CLONE_NEWUSER | flags) })?;
+    // Map the caller to itself so that files keep their owner.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+    Ok(())
+}
+
+/// Whether every process holding the read end of the pipe `writer` writes to
+/// has exited.
+fn reader_has_exited(writer: &File) -> bool {
+    let mut poll_fd = libc::pollfd {
+        fd: writer.as_raw_fd(),
+        events: libc::POLLOUT,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut poll_fd, 1, 0) };
+    ready == 1 && poll_fd.revents & libc::POLLERR != 0
+}
+
+/// Waits for PID 1 of the namespace and exits the way the command did, or
+/// the way PID 1 did if it never reported the command's status.
+fn wait_and_mirror_exit_status(init: libc::pid_t, mut status_reader: File) -> ! {
+    let mut status = wait_for(init);
+    let mut command_status = [0u8; 4];
+    if status_reader.read_exact(&mut command_status).is_ok() {
+        status = i32::from_ne_bytes(command_status);
+    }
+    exit_with_wait_status(status)
+}
+
+/// Waits for `pid` to exit and returns its wait status. Exits if it cannot
+/// be waited for.
+pub(crate) fn wait_for(pid: libc::pid_t) -> i32 {
+    let mut status = 0;
+    while unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            std::process::exit(1);
+        }
+    }
+    status
+}
+
+/// Exits the way a process with wait status `status` did.
+pub(crate) fn exit_with_wait_status(status: i32) -> ! {
+    if libc::WIFSIGNALED(status) {
+        let signal = libc::WTERMSIG(status);
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+        std::process::exit(128 + signal);
+    }
+    std::process::exit(libc::WEXITSTATUS(status));
+}
+
+fn bind(source: &Path, target: &Path) -> io::Result<()> {
+    mount(
+        Some(source),
+        target,
+        None,
+        libc::MS_BIND | libc::MS_REC,
+        None,
+    )
+}
+
+fn mount(
+    source: Option<&Path>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+    data: Option<&str>,
+) -> io::Result<()> {
+    let source = source.map(c_path).transpose()?;
+    let target = c_path(target)?;
+    let fstype = fstype.map(c_string).transpose()?;
+    let data = data.map(c_string).transpose()?;
+    check(unsafe {
+        libc::mount(
+            source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            target.as_ptr(),
+            fstype.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            flags,
+            data.as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr().cast::<libc::c_void>()),
+        )
+    })
+}
+
+/// Sets and clears `MOUNT_ATTR_*` flags on the mount at `path`, and on every
+/// mount beneath it if `recursive` is set.
+fn set_mount_attr(path: &Path, recursive: bool, set: u64, clear: u64) -> io::Result<()> {
+    let path = c_path(path)?;
+    let attr = libc::mount_attr {
+        attr_set: set,
+        attr_clr: clear,
+        propagation: 0,
+        userns_fd: 0,
+    };
+    let flags = if recursive { libc::AT_RECURSIVE } else { 0 };
+    check(unsafe {
+        libc::syscall(
+            libc::SYS_mount_setattr,
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            flags,
+            &attr as *const libc::mount_attr,
+            std::mem::size_of::<libc::mount_attr>(),
+        )
+    })
+}
+
+fn c_string(s: &str) -> io::Result<CString> {
+    CString::new(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+pub(crate) fn check(ret: impl Into<i64>) -> io::Result<()> {
+    if ret.into() == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! Gives commands a way to reach the egress proxy without any other network
+//! access.
+//!
+//! The command runs in a private network namespace whose only interface is
+//! loopback, so it may use TCP freely without reaching anything outside. This
+//! process binds a loopback port in that namespace and forks: the child goes
+//! on to run the command with the `HTTP_PROXY` family of variables pointed at
+//! the port, while the parent stays outside the seccomp and Landlock rules and
+//! forwards each connection to the proxy's Unix socket until the command
+//! exits.
+
+use std::io;
+use std::net::Shutdown;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::exec_env::PROXY_ENV_VARS;
+
+use crate::namespaces::check;
+use crate::namespaces::enter_user_namespace;
+use crate::namespaces::exit_with_wait_status;
+use crate::namespaces::wait_for;
+
+/// Starts forwarding a loopback port to the proxy at `socket` and returns in
+/// the process that goes on to exec the command, with the proxy variables
+/// set. Unless `in_private_network` is set, a network namespace is created
+/// first.
+///
+/// Must be called while the process is still single-threaded.
+pub(crate) fn start(socket: &Path, in_private_network: bool) -> io::Result<()> {
+    if !in_private_network {
+        enter_user_namespace(libc::CLONE_NEWNET)?;
+    }
+    bring_up_loopback()?;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let proxy_url = format!("http://{}", listener.local_addr()?);
+
+    let forwarder = unsafe { libc::getpid() };
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            drop(listener);
+            // Take the command down with the forwarder, which is what Codex
+            // kills on timeout.
+            check(unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) })?;
+            // The forwarder may have exited before the death signal was set up.
+            if unsafe { libc::getppid() } != forwarder {
+                unsafe { libc::raise(libc::SIGKILL) };
+            }
+            for key in PROXY_ENV_VARS {
+                // The process is single-threaded, so nothing reads the
+                // environment concurrently.
+                unsafe { std::env::set_var(key, &proxy_url) };
+            }
+            Ok(())
+        }
+        command => {
+            let socket = socket.to_path_buf();
+            std::thread::spawn(move || forward_connections(listener, socket));
+            exit_with_wait_status(wait_for(command))
+        }
+    }
+}
+
+fn forward_connections(listener: TcpListener, socket: PathBuf) {
+    for client in listener.incoming().flatten() {
+        let socket = socket.clone();
+        std::thread::spawn(move || {
+            if let Ok(upstream) = UnixStream::connect(&socket) {
+                let _ = forward(client, upstream);
+            }
+        });
+    }
+}
+
+/// Copies data both ways until each side has finished sending.
+fn forward(client: TcpStream, upstream: UnixStream) -> io::Result<()> {
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let to_upstream = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    let (mut upstream_reader, mut client_writer) = (upstream, client);
+    let _ = io::copy(&mut upstream_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Write);
+    let _ = to_upstream.join();
+    Ok(())
+}
+
+/// The loopback interface of a new network namespace starts out down.
+fn bring_up_loopback() -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    check(fd)?;
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in request.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as libc::c_char;
+    }
+    request.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+    let result = check(unsafe { libc::ioctl(fd, libc::SIOCSIFFLAGS, &request) });
+    unsafe { libc::close(fd) };
+    result
+}
@@ -1,5 +1,8 @@
 #![cfg(target_os = "linux")]
+use codex_core::config_types::SandboxLimits;
 use codex_core::config_types::ShellEnvironmentPolicy;
+use codex_core::config_types::ShellEnvironmentPolicyInherit;
+use codex_core::egress_proxy::EgressProxy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
 use codex_core::exec::ExecParams;
@@ -10,6 +13,8 @@ use codex_core::protocol::SandboxPolicy;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
 
 // At least on GitHub CI, the arm64 tests appear to need longer timeouts.
 
@@ -30,20 +35,19 @@ const NETWORK_TIMEOUT_MS: u64 = 10_000;
 
 fn create_env_from_core_vars() -> HashMap<String, String> {
     let policy = ShellEnvironmentPolicy::default();
-    create_env(&policy)
+    create_env(&policy, None)
 }
 
-#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
 async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
-    let params = ExecParams {
-        command: cmd.iter().map(|elm| elm.to_string()).collect(),
-        cwd: std::env::current_dir().expect("cwd should exist"),
-        timeout_ms: Some(timeout_ms),
-        env: create_env_from_core_vars(),
-        with_escalated_permissions: None,
-        justification: None,
-    };
+    run_cmd_with_deny_read(cmd, writable_roots, &[], timeout_ms).await;
+}
 
+async fn run_cmd_with_deny_read(
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    deny_read: &[PathBuf],
+    timeout_ms: u64,
+) {
     let sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: writable_roots.to_vec(),
         network_access: false,
@@ -52,13 +56,44 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
         // writing to in the sandbox.
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        deny_read: deny_read.to_vec(),
+        allowed_domains: vec![],
+        isolate_namespaces: false,
+    };
+    run_cmd_with_policy(cmd, &sandbox_policy, timeout_ms).await;
+}
+
+async fn run_cmd_isolated(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: writable_roots.to_vec(),
+        network_access: false,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        deny_read: vec![],
+        allowed_domains: vec![],
+        isolate_namespaces: true,
     };
+    run_cmd_with_policy(cmd, &sandbox_policy, timeout_ms).await;
+}
+
+#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
+async fn run_cmd_with_policy(cmd: &[&str], sandbox_policy: &SandboxPolicy, timeout_ms: u64) {
+    let params = ExecParams {
+        command: cmd.iter().map(|elm| elm.to_string()).collect(),
+        cwd: std::env::current_dir().expect("cwd should exist"),
+        timeout_ms: Some(timeout_ms),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: SandboxLimits::default(),
+    };
+
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
     let res = process_exec_tool_call(
         params,
         SandboxType::LinuxSeccomp,
-        &sandbox_policy,
+        sandbox_policy,
         &codex_linux_sandbox_exe,
         None,
     )
@@ -120,6 +155,165 @@ async fn test_writable_root() {
     .await;
 }
 
+#[tokio::test]
+async fn test_deny_read_leaves_other_files_readable() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ssh_dir = tmpdir.path().join(".ssh");
+    std::fs::create_dir(&ssh_dir).unwrap();
+    let notes = tmpdir.path().join("notes.txt");
+    std::fs::write(&notes, "hello").unwrap();
+    run_cmd_with_deny_read(
+        &["cat", &notes.to_string_lossy()],
+        &[],
+        &[ssh_dir],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_deny_read_file_in_writable_root() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let secret = tmpdir.path().join("auth.json");
+    std::fs::write(&secret, "{}").unwrap();
+    run_cmd_with_deny_read(
+        &["cat", &secret.to_string_lossy()],
+        &[tmpdir.path().to_path_buf()],
+        std::slice::from_ref(&secret),
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_deny_read_dir_through_symlink() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ssh_dir = tmpdir.path().join(".ssh");
+    std::fs::create_dir(&ssh_dir).unwrap();
+    std::fs::write(ssh_dir.join("id_ed25519"), "key").unwrap();
+    let link = tmpdir.path().join("keys");
+    std::os::unix::fs::symlink(&ssh_dir, &link).unwrap();
+    run_cmd_with_deny_read(
+        &["cat", &link.join("id_ed25519").to_string_lossy()],
+        &[],
+        &[ssh_dir],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_deny_read_keeps_writable_root_writable() {
+    // Like running in $HOME with ~/.ssh denied: the root is split around the
+    // denied directory, but files directly in it can still be created, moved
+    // and removed.
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ssh_dir = tmpdir.path().join(".ssh");
+    std::fs::create_dir(&ssh_dir).unwrap();
+    let existing = tmpdir.path().join("existing.txt");
+    std::fs::write(&existing, "hello").unwrap();
+    let root = tmpdir.path().to_string_lossy();
+    run_cmd_with_deny_read(
+        &[
+            "bash",
+            "-lc",
+            &format!(
+                "touch {root}/new && echo data > {root}/new && mv {root}/new {root}/moved \
+                 && rm {root}/moved && cat {root}/existing.txt && mkdir {root}/dir \
+                 && rmdir {root}/dir && mv {root}/existing.txt {root}/renamed.txt"
+            ),
+        ],
+        &[tmpdir.path().to_path_buf()],
+        &[ssh_dir],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_deny_read_dir_in_writable_root_stays_unreadable() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ssh_dir = tmpdir.path().join(".ssh");
+    std::fs::create_dir(&ssh_dir).unwrap();
+    std::fs::write(ssh_dir.join("id_ed25519"), "key").unwrap();
+    run_cmd_with_deny_read(
+        &["cat", &ssh_dir.join("id_ed25519").to_string_lossy()],
+        &[tmpdir.path().to_path_buf()],
+        &[ssh_dir],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_isolated_command_runs_in_own_pid_namespace() {
+    // The sandbox helper is PID 1 of the namespace and the command its child.
+    run_cmd_isolated(&["sh", "-c", "test $PPID -eq 1"], &[], LONG_TIMEOUT_MS).await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "Sandbox(Signal(15))")]
+async fn test_isolated_command_can_be_signalled() {
+    run_cmd_isolated(&["sh", "-c", "kill -TERM $$"], &[], LONG_TIMEOUT_MS).await;
+}
+
+#[tokio::test]
+async fn test_isolated_tmp_is_private() {
+    let host_file = NamedTempFile::new_in("/tmp").unwrap();
+    run_cmd_isolated(
+        &[
+            "sh",
+            "-c",
+            &format!(
+                "test ! -e {} && echo blah > /tmp/scratch",
+                host_file.path().to_string_lossy()
+            ),
+        ],
+        &[],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+    assert!(!std::path::Path::new("/tmp/scratch").exists());
+}
+
+#[tokio::test]
+async fn test_isolated_writable_root_under_tmp() {
+    let tmpdir = tempfile::tempdir_in("/tmp").unwrap();
+    let file_path = tmpdir.path().join("test");
+    run_cmd_isolated(
+        &[
+            "sh",
+            "-c",
+            &format!("echo blah > {}", file_path.to_string_lossy()),
+        ],
+        &[tmpdir.path().to_path_buf()],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "blah\n");
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_isolated_git_dir_is_read_only() {
+    let tmpdir = tempfile::tempdir_in("/tmp").unwrap();
+    let git_dir = tmpdir.path().join(".git");
+    std::fs::create_dir(&git_dir).unwrap();
+    run_cmd_isolated(
+        &[
+            "sh",
+            "-c",
+            &format!("echo blah > {}", git_dir.join("config").to_string_lossy()),
+        ],
+        &[tmpdir.path().to_path_buf()],
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
 #[tokio::test]
 #[should_panic(expected = "Sandbox(Timeout)")]
 async fn test_timeout() {
@@ -130,8 +324,12 @@ async fn test_timeout() {
 /// does NOT succeed (i.e. returns a non‑zero exit code) **unless** the binary
 /// is missing in which case we silently treat it as an accepted skip so the
 /// suite remains green on leaner CI images.
-#[expect(clippy::expect_used)]
 async fn assert_network_blocked(cmd: &[&str]) {
+    assert_network_blocked_with_policy(cmd, &SandboxPolicy::new_read_only_policy()).await;
+}
+
+#[expect(clippy::expect_used)]
+async fn assert_network_blocked_with_policy(cmd: &[&str], sandbox_policy: &SandboxPolicy) {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let params = ExecParams {
         command: cmd.iter().map(|s| s.to_string()).collect(),
@@ -142,15 +340,15 @@ async fn assert_network_blocked(cmd: &[&str]) {
         env: create_env_from_core_vars(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: SandboxLimits::default(),
     };
 
-    let sandbox_policy = SandboxPolicy::new_read_only_policy();
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
     let result = process_exec_tool_call(
         params,
         SandboxType::LinuxSeccomp,
-        &sandbox_policy,
+        sandbox_policy,
         &codex_linux_sandbox_exe,
         None,
     )
@@ -221,6 +419,138 @@ async fn sandbox_blocks_getent() {
     assert_network_blocked(&["getent", "ahosts", "openai.com"]).await;
 }
 
+#[tokio::test]
+async fn sandbox_with_allowed_domains_blocks_direct_connections() {
+    // Allowed domains are only reachable through the egress proxy, so a
+    // direct TCP connection must still fail.
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: false,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        deny_read: vec![],
+        allowed_domains: vec!["openai.com".to_string()],
+        isolate_namespaces: false,
+    };
+    assert_network_blocked_with_policy(
+        &["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"],
+        &sandbox_policy,
+    )
+    .await;
+}
+
+/// Serves one canned HTTP response per connection on a host loopback port.
+#[expect(clippy::unwrap_used)]
+async fn start_http_server() -> u16 {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = vec![0u8; 4096];
+            let _ = stream.read(&mut request).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello")
+                .await;
+        }
+    });
+    port
+}
+
+/// Runs `cmd` in a sandbox whose network goes through `proxy`, returning the
+/// exit code and stdout.
+#[expect(clippy::unwrap_used)]
+async fn run_with_egress_proxy(
+    cmd: &[&str],
+    proxy: &EgressProxy,
+    isolate_namespaces: bool,
+) -> (i32, String) {
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: false,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        deny_read: vec![],
+        allowed_domains: vec!["127.0.0.1".to_string()],
+        isolate_namespaces,
+    };
+    let env_policy = ShellEnvironmentPolicy {
+        inherit: ShellEnvironmentPolicyInherit::Core,
+        ..Default::default()
+    };
+    let params = ExecParams {
+        command: cmd.iter().map(|s| s.to_string()).collect(),
+        cwd: std::env::current_dir().unwrap(),
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env: create_env(&env_policy, Some(proxy.socket_path())),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: SandboxLimits::default(),
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &Some(PathBuf::from(sandbox_program)),
+        None,
+    )
+    .await;
+    match result {
+        Ok(output) => (output.exit_code, output.stdout.text),
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, stdout, _))) => (exit_code, stdout),
+        Err(e) => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[tokio::test]
+async fn allowed_domains_are_reachable_through_the_egress_proxy() {
+    let port = start_http_server().await;
+    let proxy = EgressProxy::start(vec!["127.0.0.1".to_string()]).unwrap();
+    let url = format!("http://127.0.0.1:{port}/");
+
+    let (exit_code, stdout) = run_with_egress_proxy(&["curl", "-sf", &url], &proxy, false).await;
+    if exit_code == 127 {
+        return; // curl is not installed
+    }
+    assert_eq!((exit_code, stdout.as_str()), (0, "hello"));
+
+    let (exit_code, _) =
+        run_with_egress_proxy(&["curl", "-sf", "http://example.com/"], &proxy, false).await;
+    assert_ne!(exit_code, 0);
+    assert_eq!(proxy.take_denied_hosts(), vec!["example.com".to_string()]);
+}
+
+#[tokio::test]
+async fn egress_proxy_sandbox_blocks_other_connections() {
+    let port = start_http_server().await;
+    let proxy = EgressProxy::start(vec!["127.0.0.1".to_string()]).unwrap();
+    let url = format!("http://127.0.0.1:{port}/");
+    let socket = proxy.socket_path().to_string_lossy().into_owned();
+
+    // The host's loopback is out of reach, and so are Unix sockets, even the
+    // proxy's own.
+    for cmd in [
+        vec!["curl", "-sf", "--noproxy", "*", &url],
+        vec!["curl", "-sf", "--unix-socket", &socket, &url],
+    ] {
+        let (exit_code, stdout) = run_with_egress_proxy(&cmd, &proxy, false).await;
+        assert_ne!(exit_code, 0, "{cmd:?} succeeded: {stdout}");
+    }
+}
+
+#[tokio::test]
+async fn egress_proxy_is_reachable_with_a_private_tmp() {
+    let port = start_http_server().await;
+    let proxy = EgressProxy::start(vec!["127.0.0.1".to_string()]).unwrap();
+    let url = format!("http://127.0.0.1:{port}/");
+
+    let (exit_code, stdout) = run_with_egress_proxy(&["curl", "-sf", &url], &proxy, true).await;
+    if exit_code == 127 {
+        return; // curl is not installed
+    }
+    assert_eq!((exit_code, stdout.as_str()), (0, "hello"));
+}
+
 #[tokio::test]
 async fn sandbox_blocks_dev_tcp_redirection() {
     // This syntax is only supported by bash and zsh. We try bash first.
@@ -98,6 +98,7 @@ async fn end_to_end_login_flow_persists_auth_json() {
     let opts = ServerOptions {
         codex_home: server_home,
         client_id: codex_login::CLIENT_ID.to_string(),
+        originator: "codex_cli_rs".to_string(),
         issuer,
         port: 0,
         open_browser: false,
@@ -156,6 +157,7 @@ async fn creates_missing_codex_home_dir() {
     let opts = ServerOptions {
         codex_home: server_home,
         client_id: codex_login::CLIENT_ID.to_string(),
+        originator: "codex_cli_rs".to_string(),
         issuer,
         port: 0,
         open_browser: false,
@@ -196,6 +198,7 @@ async fn cancels_previous_login_server_when_port_is_in_use() {
     let first_opts = ServerOptions {
         codex_home: first_codex_home,
         client_id: codex_login::CLIENT_ID.to_string(),
+        originator: "codex_cli_rs".to_string(),
         issuer: issuer.clone(),
         port: 0,
         open_browser: false,
@@ -214,6 +217,7 @@ async fn cancels_previous_login_server_when_port_is_in_use() {
     let second_opts = ServerOptions {
         codex_home: second_codex_home,
         client_id: codex_login::CLIENT_ID.to_string(),
+        originator: "codex_cli_rs".to_string(),
         issuer,
         port: login_port,
         open_browser: false,
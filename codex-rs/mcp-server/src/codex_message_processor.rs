@@ -3,6 +3,7 @@ use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::json_to_toml::json_to_toml;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotification;
+use codex_core::ARCHIVED_SESSIONS_SUBDIR;
 use codex_core::AuthManager;
 use codex_core::CodexConversation;
 use codex_core::ConversationManager;
@@ -80,12 +81,10 @@ use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::select;
 use tokio::sync::Mutex;
 use tokio::sync::oneshot;
 use tracing::error;
 use tracing::info;
-use tracing::warn;
 use uuid::Uuid;
 
 // Duration before a ChatGPT login attempt is abandoned.
@@ -486,7 +485,7 @@ impl CodexMessageProcessor {
         }
 
         let cwd = params.cwd.unwrap_or_else(|| self.config.cwd.clone());
-        let env = create_env(&self.config.shell_environment_policy);
+        let env = create_env(&self.config.shell_environment_policy, None);
         let timeout_ms = params.timeout_ms;
         let exec_params = ExecParams {
             command: params.command,
@@ -495,6 +494,7 @@ impl CodexMessageProcessor {
             env,
             with_escalated_permissions: None,
             justification: None,
+            resource_limits: self.config.sandbox_limits,
         };
 
         let effective_policy = params
@@ -773,7 +773,7 @@ impl CodexMessageProcessor {
 
         // Move the .jsonl file to the archived sessions subdir.
         let result: std::io::Result<()> = async {
-            let archive_folder = self.config.codex_home.join("archived_sessions");
+            let archive_folder = self.config.codex_home.join(ARCHIVED_SESSIONS_SUBDIR);
             tokio::fs::create_dir_all(&archive_folder).await?;
             tokio::fs::rename(&canonical_rollout_path, &archive_folder.join(&file_name)).await?;
             Ok(())
@@ -1076,6 +1076,7 @@ async fn apply_bespoke_event_handling(
             command,
             cwd,
             reason,
+            grant_root,
         }) => {
             let params = ExecCommandApprovalParams {
                 conversation_id,
@@ -1083,6 +1084,7 @@ async fn apply_bespoke_event_handling(
                 command,
                 cwd,
                 reason,
+                grant_root,
             };
             let value = serde_json::to_value(&params).unwrap_or_default();
             let rx = outgoing
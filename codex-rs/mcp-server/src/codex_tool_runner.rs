@@ -178,10 +178,12 @@ async fn run_codex_tool_session_inner(
                         cwd,
                         call_id,
                         reason: _,
+                        grant_root,
                     }) => {
                         handle_exec_approval_request(
                             command,
                             cwd,
+                            grant_root,
                             outgoing.clone(),
                             codex.clone(),
                             request_id.clone(),
@@ -276,6 +278,12 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::WebSearchEnd(_)
                     | EventMsg::GetHistoryEntryResponse(_)
                     | EventMsg::PlanUpdate(_)
+                    | EventMsg::DelegateBegin(_)
+                    | EventMsg::DelegateEvent(_)
+                    | EventMsg::DelegateEnd(_)
+                    | EventMsg::ContextCompacted(_)
+                    | EventMsg::BudgetExceeded(_)
+                    | EventMsg::UndoCompleted(_)
                     | EventMsg::TurnAborted(_)
                     | EventMsg::ConversationHistory(_)
                     | EventMsg::UserMessage(_)
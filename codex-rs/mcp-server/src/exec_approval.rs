@@ -35,6 +35,10 @@ pub struct ExecApprovalElicitRequestParams {
     pub codex_call_id: String,
     pub codex_command: Vec<String>,
     pub codex_cwd: PathBuf,
+    /// Directory the client may make writable for the rest of the session by
+    /// answering with `approved_writable_root_for_session`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codex_grant_root: Option<PathBuf>,
 }
 
 // TODO(mbolin): ExecApprovalResponse does not conform to ElicitResult. See:
@@ -50,6 +54,7 @@ pub struct ExecApprovalResponse {
 pub(crate) async fn handle_exec_approval_request(
     command: Vec<String>,
     cwd: PathBuf,
+    grant_root: Option<PathBuf>,
     outgoing: Arc<crate::outgoing_message::OutgoingMessageSender>,
     codex: Arc<CodexConversation>,
     request_id: RequestId,
@@ -59,10 +64,16 @@ pub(crate) async fn handle_exec_approval_request(
 ) {
     let escaped_command =
         shlex::try_join(command.iter().map(|s| s.as_str())).unwrap_or_else(|_| command.join(" "));
-    let message = format!(
+    let mut message = format!(
         "Allow Codex to run `{escaped_command}` in `{cwd}`?",
         cwd = cwd.to_string_lossy()
     );
+    if let Some(root) = &grant_root {
+        message.push_str(&format!(
+            "\nWrite access to `{}` can be granted for the rest of the session instead.",
+            root.to_string_lossy()
+        ));
+    }
 
     let params = ExecApprovalElicitRequestParams {
         message,
@@ -77,6 +88,7 @@ pub(crate) async fn handle_exec_approval_request(
         codex_call_id: call_id,
         codex_command: command,
         codex_cwd: cwd,
+        codex_grant_root: grant_root,
     };
     let params_json = match serde_json::to_value(&params) {
         Ok(value) => value,
@@ -162,6 +162,12 @@ pub enum Op {
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
     Compact,
+
+    /// Restore the workspace to the checkpoint taken before the `turns`-th
+    /// most recent turn and tell the model which files were reverted.
+    /// Reply is delivered via `EventMsg::UndoCompleted`.
+    Undo { turns: u32 },
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -231,6 +237,24 @@ pub enum SandboxPolicy {
         /// writable roots on UNIX. Defaults to `false`.
         #[serde(default)]
         exclude_slash_tmp: bool,
+
+        /// Files and folders (such as `~/.ssh`) that may not be read from
+        /// within the sandbox, even though the rest of the file-system is
+        /// readable.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        deny_read: Vec<PathBuf>,
+
+        /// Domains (and their subdomains) that commands may reach through
+        /// the local egress proxy when `network_access` is `false`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_domains: Vec<String>,
+
+        /// When set to `true`, commands run in their own user, PID, IPC and
+        /// mount namespaces with a private `/tmp` and a read-only view of the
+        /// file-system outside the writable roots. Linux only. Defaults to
+        /// `false`.
+        #[serde(default)]
+        isolate_namespaces: bool,
     },
 }
 
@@ -288,6 +312,9 @@ impl SandboxPolicy {
             network_access: false,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            deny_read: vec![],
+            allowed_domains: vec![],
+            isolate_namespaces: false,
         }
     }
 
@@ -304,6 +331,38 @@ impl SandboxPolicy {
         }
     }
 
+    /// Returns the paths that may not be read from within the sandbox.
+    pub fn get_deny_read_paths(&self) -> &[PathBuf] {
+        match self {
+            SandboxPolicy::DangerFullAccess => &[],
+            SandboxPolicy::ReadOnly => &[],
+            SandboxPolicy::WorkspaceWrite { deny_read, .. } => deny_read,
+        }
+    }
+
+    /// Returns the domains reachable through the egress proxy. Only
+    /// meaningful without full network access.
+    pub fn get_allowed_domains(&self) -> &[String] {
+        match self {
+            SandboxPolicy::DangerFullAccess => &[],
+            SandboxPolicy::ReadOnly => &[],
+            SandboxPolicy::WorkspaceWrite {
+                allowed_domains, ..
+            } => allowed_domains,
+        }
+    }
+
+    /// Whether commands should run in isolated namespaces.
+    pub fn isolates_namespaces(&self) -> bool {
+        match self {
+            SandboxPolicy::DangerFullAccess => false,
+            SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::WorkspaceWrite {
+                isolate_namespaces, ..
+            } => *isolate_namespaces,
+        }
+    }
+
     pub fn has_full_network_access(&self) -> bool {
         match self {
             SandboxPolicy::DangerFullAccess => true,
@@ -324,6 +383,9 @@ impl SandboxPolicy {
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
                 network_access: _,
+                deny_read: _,
+                allowed_domains: _,
+                isolate_namespaces: _,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -494,6 +556,30 @@ pub enum EventMsg {
 
     PlanUpdate(UpdatePlanArgs),
 
+    /// Notification that the agent handed a sub-task to a child conversation
+    /// via the `delegate` tool.
+    DelegateBegin(DelegateBeginEvent),
+
+    /// An event emitted by a delegated child conversation, wrapped so clients
+    /// can render it nested under the `delegate` call that produced it.
+    DelegateEvent(DelegateEventEvent),
+
+    /// Notification that a delegated child conversation has finished.
+    DelegateEnd(DelegateEndEvent),
+
+    /// Notification that the conversation history was summarized
+    /// automatically because it approached the model's context window.
+    ContextCompacted(ContextCompactedEvent),
+
+    /// Notification that a configured turn or session budget was exhausted.
+    /// Followed by [`EventMsg::TurnAborted`] with
+    /// [`TurnAbortReason::BudgetExceeded`].
+    BudgetExceeded(BudgetExceededEvent),
+
+    /// Response to [`Op::Undo`]: the workspace was restored to an earlier
+    /// checkpoint.
+    UndoCompleted(UndoCompletedEvent),
+
     TurnAborted(TurnAbortedEvent),
 
     /// Notification that the agent is shutting down.
@@ -519,7 +605,7 @@ pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default, TS)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Default, TS)]
 pub struct TokenUsage {
     pub input_tokens: u64,
     pub cached_input_tokens: u64,
@@ -982,6 +1068,11 @@ pub struct ExecApprovalRequestEvent {
     /// Optional human-readable reason for the approval (e.g. retry without sandbox).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// When set, the user may answer with
+    /// [`ReviewDecision::ApprovedWritableRootForSession`] to allow writes under
+    /// this directory for the remainder of the session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grant_root: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1029,6 +1120,106 @@ pub struct PatchApplyEndEvent {
     pub success: bool,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateBeginEvent {
+    /// Identifier so this can be paired with the DelegateEnd event.
+    pub call_id: String,
+    /// Id of the child conversation running the sub-task.
+    pub conversation_id: ConversationId,
+    /// The prompt handed to the child conversation.
+    pub prompt: String,
+    /// Working directory of the child conversation.
+    pub cwd: PathBuf,
+    /// Rollout file of the child conversation.
+    pub rollout_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateEventEvent {
+    /// Identifier for the DelegateBegin this event belongs to.
+    pub call_id: String,
+    /// The child conversation's event.
+    pub msg: Box<EventMsg>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct DelegateEndEvent {
+    /// Identifier for the DelegateBegin that finished.
+    pub call_id: String,
+    /// The last message the child agent produced, returned to the model as the
+    /// result of the `delegate` call.
+    pub last_agent_message: Option<String>,
+    /// Whether the child conversation completed its task.
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ContextCompactedEvent {
+    /// Tokens occupying the context window when compaction was triggered.
+    pub tokens_in_context_window: u64,
+    /// Threshold that triggered the compaction.
+    pub token_limit: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct UndoCompletedEvent {
+    /// Number of turns that were undone.
+    pub turns: u32,
+    /// Files restored to their checkpointed contents.
+    pub restored: Vec<PathBuf>,
+    /// Files deleted because they did not exist at the checkpoint.
+    pub removed: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct BudgetExceededEvent {
+    pub scope: BudgetScope,
+    pub kind: BudgetKind,
+    /// Configured limit for `kind`.
+    pub limit: u64,
+    /// Amount consumed when the budget was checked.
+    pub used: u64,
+}
+
+impl BudgetExceededEvent {
+    /// Human-readable explanation suitable for display in a UI.
+    pub fn message(&self) -> String {
+        let scope = match self.scope {
+            BudgetScope::Turn => "turn",
+            BudgetScope::Session => "session",
+        };
+        let what = match self.kind {
+            BudgetKind::ToolCalls => "tool call",
+            BudgetKind::Tokens => "token",
+            BudgetKind::DurationSecs => "time",
+        };
+        let unit = match self.kind {
+            BudgetKind::ToolCalls => "calls",
+            BudgetKind::Tokens => "tokens",
+            BudgetKind::DurationSecs => "s",
+        };
+        let (used, limit) = (self.used, self.limit);
+        format!("{scope} {what} budget exhausted: used {used} of {limit} {unit}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    /// Limits that apply to a single user turn.
+    Turn,
+    /// Limits that apply across every turn in the session.
+    Session,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    ToolCalls,
+    Tokens,
+    DurationSecs,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TurnDiffEvent {
     pub unified_diff: String,
@@ -1090,6 +1281,11 @@ pub enum ReviewDecision {
     /// remainder of the session.
     ApprovedForSession,
 
+    /// User has made the `grant_root` of the request writable for the
+    /// remainder of the session; the command is retried inside the sandbox
+    /// with the extended writable roots.
+    ApprovedWritableRootForSession,
+
     /// User has denied this command and the agent should not execute it, but
     /// it should continue the session and try something else.
     #[default]
@@ -1105,6 +1301,9 @@ pub enum ReviewDecision {
 pub enum FileChange {
     Add {
         content: String,
+        /// Permission bits of the new file, e.g. `0o755`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<u32>,
     },
     Delete {
         content: String,
@@ -1112,6 +1311,16 @@ pub enum FileChange {
     Update {
         unified_diff: String,
         move_path: Option<PathBuf>,
+        /// New permission bits of the file, e.g. `0o755`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<u32>,
+    },
+    /// The file is created as a byte-for-byte copy of `source`.
+    Copy {
+        source: PathBuf,
+        /// Permission bits of the copy; those of `source` if unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<u32>,
     },
 }
 
@@ -1133,6 +1342,7 @@ pub struct TurnAbortedEvent {
 pub enum TurnAbortReason {
     Interrupted,
     Replaced,
+    BudgetExceeded,
 }
 
 #[cfg(test)]
@@ -25,7 +25,7 @@ fn main() {
         .map(|s| s.trim().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    println!("cargo:rustc-env=CODEX_BUILD_SHA={}", sha);
-    println!("cargo:rustc-env=CODEX_BUILD_BRANCH={}", branch);
-    println!("cargo:rustc-env=CODEX_BUILD_TIME={}", time);
+    println!("cargo:rustc-env=CODEX_BUILD_SHA={sha}");
+    println!("cargo:rustc-env=CODEX_BUILD_BRANCH={branch}");
+    println!("cargo:rustc-env=CODEX_BUILD_TIME={time}");
 }
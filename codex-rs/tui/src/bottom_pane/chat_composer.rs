@@ -1542,6 +1542,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let needs_redraw = composer.handle_paste("hello".to_string());
@@ -1571,6 +1572,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Ensure composer is empty and press Enter.
@@ -1598,6 +1600,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let large = "x".repeat(LARGE_PASTE_CHAR_THRESHOLD + 10);
@@ -1633,6 +1636,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         composer.handle_paste(large);
@@ -1674,6 +1678,7 @@ mod tests {
                 false,
                 "Ask Codex to do anything".to_string(),
                 false,
+                None,
             );
 
             if let Some(text) = input {
@@ -1717,6 +1722,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Type "/mo" humanlike so paste-burst doesn’t interfere.
@@ -1745,6 +1751,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
         type_chars_humanlike(&mut composer, &['/', 'm', 'o']);
 
@@ -1788,6 +1795,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Type the slash command.
@@ -1825,6 +1833,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         type_chars_humanlike(&mut composer, &['/', 'c']);
@@ -1850,6 +1859,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         type_chars_humanlike(&mut composer, &['/', 'm', 'e', 'n', 't', 'i', 'o', 'n']);
@@ -1885,6 +1895,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Define test cases: (paste content, is_large)
@@ -1964,6 +1975,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Define test cases: (content, is_large)
@@ -2036,6 +2048,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Define test cases: (cursor_position_from_end, expected_pending_count)
@@ -2084,6 +2097,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
         let path = PathBuf::from("/tmp/image1.png");
         composer.attach_image(path.clone(), 32, 16, "PNG");
@@ -2108,6 +2122,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
         let path = PathBuf::from("/tmp/image2.png");
         composer.attach_image(path.clone(), 10, 5, "PNG");
@@ -2133,6 +2148,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
         let path = PathBuf::from("/tmp/image3.png");
         composer.attach_image(path.clone(), 20, 10, "PNG");
@@ -2174,6 +2190,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Insert an image placeholder at the start
@@ -2200,6 +2217,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let path1 = PathBuf::from("/tmp/image_dup1.png");
@@ -2247,6 +2265,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let needs_redraw = composer.handle_paste(tmp_path.to_string_lossy().to_string());
@@ -2269,6 +2288,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         // Inject prompts as if received via event.
@@ -2303,6 +2323,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let count = 32;
@@ -2347,6 +2368,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let count = LARGE_PASTE_CHAR_THRESHOLD + 1; // > threshold to trigger placeholder
@@ -2379,6 +2401,7 @@ mod tests {
             false,
             "Ask Codex to do anything".to_string(),
             false,
+            None,
         );
 
         let count = LARGE_PASTE_CHAR_THRESHOLD; // 1000 in current config
@@ -507,6 +507,7 @@ mod tests {
             id: "1".to_string(),
             command: vec!["echo".into(), "ok".into()],
             reason: None,
+            grant_root: None,
         }
     }
 
@@ -12,6 +12,10 @@ use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::ContextCompactedEvent;
+use codex_core::protocol::DelegateBeginEvent;
+use codex_core::protocol::DelegateEndEvent;
+use codex_core::protocol::DelegateEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -32,6 +36,7 @@ use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::UndoCompletedEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
@@ -418,6 +423,43 @@ impl ChatWidget {
         )));
     }
 
+    fn on_delegate_begin(&mut self, ev: DelegateBeginEvent) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_delegate_begin(ev.prompt));
+    }
+
+    fn on_delegate_event(&mut self, ev: DelegateEventEvent) {
+        // The child's transcript stays out of the parent's history; only
+        // surface errors so the user knows why a delegate failed.
+        if let EventMsg::Error(ErrorEvent { message }) = *ev.msg {
+            self.add_to_history(history_cell::new_error_event(format!(
+                "delegate: {message}"
+            )));
+        }
+    }
+
+    fn on_delegate_end(&mut self, ev: DelegateEndEvent) {
+        self.add_to_history(history_cell::new_delegate_end(
+            ev.last_agent_message,
+            ev.success,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_undo_completed(&mut self, ev: UndoCompletedEvent) {
+        self.add_to_history(history_cell::new_undo_completed(ev));
+        self.request_redraw();
+    }
+
+    fn on_context_compacted(&mut self, ev: ContextCompactedEvent) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_context_compacted(
+            ev.tokens_in_context_window,
+            ev.token_limit,
+        ));
+        self.request_redraw();
+    }
+
     fn on_get_history_entry_response(
         &mut self,
         event: codex_core::protocol::GetHistoryEntryResponseEvent,
@@ -556,6 +598,7 @@ impl ChatWidget {
             id,
             command: ev.command,
             reason: ev.reason,
+            grant_root: ev.grant_root,
         };
         self.bottom_pane.push_approval_request(request);
         self.request_redraw();
@@ -862,6 +905,10 @@ impl ChatWidget {
                 self.clear_token_usage();
                 self.app_event_tx.send(AppEvent::CodexOp(Op::Compact));
             }
+            SlashCommand::Undo => {
+                self.app_event_tx
+                    .send(AppEvent::CodexOp(Op::Undo { turns: 1 }));
+            }
             SlashCommand::Model => {
                 self.open_model_popup();
             }
@@ -926,6 +973,7 @@ impl ChatWidget {
                                 PathBuf::from("/tmp/test.txt"),
                                 FileChange::Add {
                                     content: "test".to_string(),
+                                    mode: None,
                                 },
                             ),
                             (
@@ -933,6 +981,7 @@ impl ChatWidget {
                                 FileChange::Update {
                                     unified_diff: "+test\n-test2".to_string(),
                                     move_path: None,
+                                    mode: None,
                                 },
                             ),
                         ]),
@@ -1083,7 +1132,12 @@ impl ChatWidget {
                 TurnAbortReason::Replaced => {
                     self.on_error("Turn aborted: replaced by a new task".to_owned())
                 }
+                TurnAbortReason::BudgetExceeded => {
+                    // Already reported by the preceding `BudgetExceeded` event.
+                }
             },
+            EventMsg::BudgetExceeded(ev) => self.on_error(ev.message()),
+            EventMsg::UndoCompleted(ev) => self.on_undo_completed(ev),
             EventMsg::PlanUpdate(update) => self.on_plan_update(update),
             EventMsg::ExecApprovalRequest(ev) => {
                 // For replayed events, synthesize an empty id (these should not occur).
@@ -1119,6 +1173,10 @@ impl ChatWidget {
                 self.app_event_tx
                     .send(crate::app_event::AppEvent::ConversationHistory(ev));
             }
+            EventMsg::DelegateBegin(ev) => self.on_delegate_begin(ev),
+            EventMsg::DelegateEvent(ev) => self.on_delegate_event(ev),
+            EventMsg::DelegateEnd(ev) => self.on_delegate_end(ev),
+            EventMsg::ContextCompacted(ev) => self.on_context_compacted(ev),
         }
     }
 
@@ -1249,7 +1307,7 @@ impl ChatWidget {
         let current_approval = self.config.approval_policy;
         let current_sandbox = self.config.sandbox_policy.clone();
         let mut items: Vec<SelectionItem> = Vec::new();
-        let presets: Vec<ApprovalPreset> = builtin_approval_presets();
+        let presets: Vec<ApprovalPreset> = builtin_approval_presets(&self.config.codex_home);
         for preset in presets.into_iter() {
             let is_current =
                 current_approval == preset.approval && current_sandbox == preset.sandbox;
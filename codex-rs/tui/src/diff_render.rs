@@ -48,6 +48,8 @@ struct Row {
     #[allow(dead_code)]
     path: PathBuf,
     move_path: Option<PathBuf>,
+    /// Permission bits the change gives the file.
+    mode: Option<u32>,
     added: usize,
     removed: usize,
     change: FileChange,
@@ -57,9 +59,16 @@ fn collect_rows(changes: &HashMap<PathBuf, FileChange>) -> Vec<Row> {
     let mut rows: Vec<Row> = Vec::new();
     for (path, change) in changes.iter() {
         let (added, removed) = match change {
-            FileChange::Add { content } => (content.lines().count(), 0),
+            FileChange::Add { content, .. } => (content.lines().count(), 0),
             FileChange::Delete { content } => (0, content.lines().count()),
             FileChange::Update { unified_diff, .. } => calculate_add_remove_from_diff(unified_diff),
+            FileChange::Copy { .. } => (0, 0),
+        };
+        let mode = match change {
+            FileChange::Add { mode, .. }
+            | FileChange::Update { mode, .. }
+            | FileChange::Copy { mode, .. } => *mode,
+            FileChange::Delete { .. } => None,
         };
         let move_path = match change {
             FileChange::Update {
@@ -71,6 +80,7 @@ fn collect_rows(changes: &HashMap<PathBuf, FileChange>) -> Vec<Row> {
         rows.push(Row {
             path: path.clone(),
             move_path,
+            mode,
             added,
             removed,
             change: change.clone(),
@@ -107,10 +117,16 @@ fn render_changes_block(
 
     let render_path = |row: &Row| -> Vec<RtSpan<'static>> {
         let mut spans = Vec::new();
+        if let FileChange::Copy { source, .. } = &row.change {
+            spans.push(format!("{} → ", display_path_for(source, cwd)).into());
+        }
         spans.push(display_path_for(&row.path, cwd).into());
         if let Some(move_path) = &row.move_path {
             spans.push(format!(" → {}", display_path_for(move_path, cwd)).into());
         }
+        if let Some(mode) = row.mode {
+            spans.push(format!(" (mode {mode:o})").dim());
+        }
         spans
     };
 
@@ -138,6 +154,7 @@ fn render_changes_block(
                 let verb = match &row.change {
                     FileChange::Add { .. } => "Added",
                     FileChange::Delete { .. } => "Deleted",
+                    FileChange::Copy { .. } => "Copied",
                     _ => "Edited",
                 };
                 header_spans.push(verb.bold());
@@ -190,7 +207,7 @@ fn render_changes_block(
         }
 
         match r.change {
-            FileChange::Add { content } => {
+            FileChange::Add { content, .. } => {
                 for (i, raw) in content.lines().enumerate() {
                     out.extend(push_wrapped_diff_line(
                         i + 1,
@@ -259,6 +276,8 @@ fn render_changes_block(
                     }
                 }
             }
+            // A copy has no lines to show beyond its header.
+            FileChange::Copy { .. } => {}
         }
     }
 
@@ -427,6 +446,7 @@ mod tests {
             PathBuf::from("README.md"),
             FileChange::Add {
                 content: "first line\nsecond line\n".to_string(),
+                mode: None,
             },
         );
 
@@ -448,6 +468,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: Some(PathBuf::from("src/lib_new.rs")),
+                mode: None,
             },
         );
 
@@ -481,6 +502,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -502,6 +524,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -524,6 +547,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -545,6 +569,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -571,6 +596,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: Some(PathBuf::from("new_name.rs")),
+                mode: None,
             },
         );
 
@@ -596,6 +622,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch_a,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -604,6 +631,7 @@ mod tests {
             PathBuf::from("b.txt"),
             FileChange::Add {
                 content: "new\n".to_string(),
+                mode: None,
             },
         );
 
@@ -624,6 +652,7 @@ mod tests {
             PathBuf::from("new_file.txt"),
             FileChange::Add {
                 content: "alpha\nbeta\n".to_string(),
+                mode: None,
             },
         );
 
@@ -677,6 +706,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -707,6 +737,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: None,
+                mode: None,
             },
         );
 
@@ -741,6 +772,7 @@ mod tests {
             FileChange::Update {
                 unified_diff: patch,
                 move_path: Some(abs_new.clone()),
+                mode: None,
             },
         );
 
@@ -27,6 +27,7 @@ use codex_core::protocol::McpInvocation;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
+use codex_core::protocol::UndoCompletedEvent;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::num_format::format_with_separators;
 use codex_protocol::parse_command::ParsedCommand;
@@ -711,6 +712,73 @@ pub(crate) fn new_active_mcp_tool_call(invocation: McpInvocation) -> PlainHistor
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_delegate_begin(prompt: String) -> PlainHistoryCell {
+    let title_line = Line::from(vec!["delegate".magenta(), " running...".dim()]);
+    let lines: Vec<Line<'static>> = vec![title_line, Line::from(vec!["  └ ".dim(), prompt.into()])];
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_delegate_end(
+    last_agent_message: Option<String>,
+    success: bool,
+) -> PlainHistoryCell {
+    let status = if success {
+        " completed".green()
+    } else {
+        " failed".red()
+    };
+    let mut lines: Vec<Line<'static>> = vec![Line::from(vec!["delegate".magenta(), status])];
+    if let Some(message) = last_agent_message {
+        for (i, line) in message.lines().take(TOOL_CALL_MAX_LINES).enumerate() {
+            let prefix = if i == 0 { "  └ " } else { "    " };
+            lines.push(Line::from(vec![prefix.dim(), line.to_string().dim()]));
+        }
+    }
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_context_compacted(
+    tokens_in_context_window: u64,
+    token_limit: u64,
+) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![Line::from(vec![
+        padded_emoji("🗜️").into(),
+        format!(
+            "Context compacted automatically ({tokens_in_context_window} tokens reached the {token_limit} token limit)"
+        )
+        .dim(),
+    ])];
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_undo_completed(event: UndoCompletedEvent) -> PlainHistoryCell {
+    let UndoCompletedEvent {
+        turns,
+        restored,
+        removed,
+    } = event;
+    let mut lines: Vec<Line<'static>> = vec![Line::from(vec![
+        "undo".magenta(),
+        format!(" reverted {turns} turn(s)").dim(),
+    ])];
+    if restored.is_empty() && removed.is_empty() {
+        lines.push(Line::from("  └ no files changed".dim()));
+    }
+    let entries = restored
+        .into_iter()
+        .map(|path| ("M ", path))
+        .chain(removed.into_iter().map(|path| ("D ", path)));
+    for (i, (marker, path)) in entries.enumerate() {
+        let prefix = if i == 0 { "  └ " } else { "    " };
+        lines.push(Line::from(vec![
+            prefix.dim(),
+            marker.dim(),
+            path.display().to_string().into(),
+        ]));
+    }
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_web_search_call(query: String) -> PlainHistoryCell {
     let lines: Vec<Line<'static>> = vec![Line::from(vec![padded_emoji("🌐").into(), query.into()])];
     PlainHistoryCell { lines }
@@ -11,14 +11,13 @@ use codex_core::RolloutRecorder;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::ConfigToml;
+use codex_core::config::default_workspace_write_policy;
 use codex_core::config::find_codex_home;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::protocol::AskForApproval;
-use codex_core::protocol::SandboxPolicy;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::mcp_protocol::AuthMode;
-use serde_json;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use tracing::error;
@@ -313,10 +312,10 @@ async fn run_ratatui_app(
     } = cli;
 
     let mut prompt = prompt;
-    if prompt.is_none() {
-        if let Some(name) = from_summarize.as_deref() {
-            prompt = read_named_summary_text(&config.codex_home, name);
-        }
+    if prompt.is_none()
+        && let Some(name) = from_summarize.as_deref()
+    {
+        prompt = read_named_summary_text(&config.codex_home, name);
     }
 
     let auth_manager = AuthManager::shared(
@@ -341,7 +340,7 @@ async fn run_ratatui_app(
         .await?;
         if let Some(TrustDirectorySelection::Trust) = directory_trust_decision {
             config.approval_policy = AskForApproval::OnRequest;
-            config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+            config.sandbox_policy = default_workspace_write_policy(&config.codex_home);
         }
     }
 
@@ -386,10 +385,6 @@ async fn run_ratatui_app(
     app_result
 }
 
-#[expect(
-    clippy::print_stderr,
-    reason = "TUI should no longer be displayed, so we can write to stderr."
-)]
 fn read_named_summary_text(codex_home: &std::path::Path, name: &str) -> Option<String> {
     let mut p = codex_home.to_path_buf();
     p.push("summaries");
@@ -401,6 +396,10 @@ fn read_named_summary_text(codex_home: &std::path::Path, name: &str) -> Option<S
         .map(|s| s.to_string())
 }
 
+#[expect(
+    clippy::print_stderr,
+    reason = "TUI should no longer be displayed, so we can write to stderr."
+)]
 fn restore() {
     if let Err(err) = tui::restore() {
         eprintln!(
@@ -461,7 +460,7 @@ fn determine_repo_trust_state(
         // if the current cwd project is trusted and no config has been set
         // skip the trust flow and set the approval policy and sandbox mode
         config.approval_policy = AskForApproval::OnRequest;
-        config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        config.sandbox_policy = default_workspace_write_policy(&config.codex_home);
         Ok(false)
     } else {
         // if none of the above conditions are met, show the trust screen
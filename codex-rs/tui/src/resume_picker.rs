@@ -7,6 +7,9 @@ use codex_core::ConversationItem;
 use codex_core::ConversationsPage;
 use codex_core::Cursor;
 use codex_core::RolloutRecorder;
+use codex_core::SessionIndex;
+use codex_core::SessionSearchHit;
+use codex_core::SessionSearchQuery;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -16,6 +19,8 @@ use ratatui::layout::Layout;
 use ratatui::layout::Rect;
 use ratatui::style::Stylize as _;
 use ratatui::text::Line;
+use ratatui::text::Span;
+use tokio::select;
 use tokio_stream::StreamExt;
 
 use crate::text_formatting::truncate_text;
@@ -28,6 +33,8 @@ use codex_protocol::protocol::InputMessageKind;
 use codex_protocol::protocol::USER_MESSAGE_BEGIN;
 
 const PAGE_SIZE: usize = 25;
+/// Most sessions shown for a search across all rollouts.
+const SEARCH_LIMIT: usize = 200;
 
 #[derive(Debug, Clone)]
 pub enum ResumeSelection {
@@ -36,31 +43,50 @@ pub enum ResumeSelection {
     Exit,
 }
 
-/// Interactive session picker that lists recorded rollout files with simple
-/// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
+/// Interactive session picker that lists recorded rollout files with
+/// pagination. Typing searches every recorded session through the session
+/// index, which is brought up to date in the background while the picker is
+/// open. Shows the first user input as the preview, relative time (e.g., "5
+/// seconds ago"), and the absolute path.
 pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let mut state = PickerState::new(codex_home.to_path_buf(), alt.tui.frame_requester());
+    let index_home = codex_home.to_path_buf();
+    let mut index_task = tokio::task::spawn_blocking(move || SessionIndex::update(&index_home));
     state.load_page(None).await?;
     state.request_frame();
 
     let mut events = alt.tui.event_stream();
-    while let Some(ev) = events.next().await {
-        match ev {
-            TuiEvent::Key(key) => {
-                if matches!(key.kind, KeyEventKind::Release) {
-                    continue;
-                }
-                if let Some(sel) = state.handle_key(key).await? {
-                    return Ok(sel);
-                }
+    loop {
+        select! {
+            result = &mut index_task, if matches!(state.index, IndexState::Building) => {
+                state.index = match result {
+                    Ok(Ok(index)) => IndexState::Ready(index),
+                    Ok(Err(err)) => IndexState::Failed(format!("search unavailable: {err}")),
+                    Err(err) => IndexState::Failed(format!("search unavailable: {err}")),
+                };
+                state.apply_filter();
             }
-            TuiEvent::Draw => {
-                draw_picker(alt.tui, &state)?;
+            ev = events.next() => {
+                let Some(ev) = ev else {
+                    break;
+                };
+                match ev {
+                    TuiEvent::Key(key) => {
+                        if matches!(key.kind, KeyEventKind::Release) {
+                            continue;
+                        }
+                        if let Some(sel) = state.handle_key(key).await? {
+                            return Ok(sel);
+                        }
+                    }
+                    TuiEvent::Draw => {
+                        draw_picker(alt.tui, &state)?;
+                    }
+                    // Ignore paste and attach-image in picker
+                    _ => {}
+                }
             }
-            // Ignore paste and attach-image in picker
-            _ => {}
         }
     }
 
@@ -97,6 +123,18 @@ struct PickerState {
     selected: usize,
     // search
     query: String,
+    index: IndexState,
+    search_error: Option<String>,
+    /// Relative `cwd:` filters are resolved against this directory, as the
+    /// `--cwd` option of `codex sessions search` is.
+    current_dir: Option<PathBuf>,
+}
+
+/// The session index, which is updated when the picker opens.
+enum IndexState {
+    Building,
+    Ready(SessionIndex),
+    Failed(String),
 }
 
 #[derive(Debug, Clone)]
@@ -112,6 +150,8 @@ struct Row {
     path: PathBuf,
     preview: String,
     ts: Option<DateTime<Utc>>,
+    /// Where the search matched, e.g. "command: cargo test".
+    detail: Option<String>,
 }
 
 impl PickerState {
@@ -129,6 +169,9 @@ impl PickerState {
             filtered_rows: Vec::new(),
             selected: 0,
             query: String::new(),
+            index: IndexState::Building,
+            search_error: None,
+            current_dir: std::env::current_dir().ok(),
         }
     }
 
@@ -235,16 +278,25 @@ impl PickerState {
     }
 
     fn apply_filter(&mut self) {
-        if self.query.is_empty() {
-            self.filtered_rows = self.all_rows.clone();
-        } else {
-            let q = self.query.to_lowercase();
-            self.filtered_rows = self
-                .all_rows
-                .iter()
-                .filter(|r| r.preview.to_lowercase().contains(&q))
-                .cloned()
-                .collect();
+        self.search_error = None;
+        match SessionSearchQuery::parse(&self.query) {
+            Ok(query) if query.is_empty() => {
+                self.filtered_rows = self.all_rows.clone();
+            }
+            Ok(mut query) => match &self.index {
+                // Drawn as "indexing…" until the index is ready.
+                IndexState::Building => self.filtered_rows.clear(),
+                IndexState::Ready(index) => {
+                    if let Some(current_dir) = &self.current_dir {
+                        resolve_cwd_filter(&mut query, current_dir);
+                    }
+                    self.filtered_rows = hits_to_rows(index.search(&query, SEARCH_LIMIT));
+                }
+                IndexState::Failed(err) => self.search_error = Some(err.clone()),
+            },
+            // Most likely a date that is still being typed; keep the
+            // previous results until it parses.
+            Err(err) => self.search_error = Some(err),
         }
         if self.selected >= self.filtered_rows.len() {
             self.selected = self.filtered_rows.len().saturating_sub(1);
@@ -253,6 +305,16 @@ impl PickerState {
     }
 }
 
+/// Makes a relative `cwd:` filter absolute, so that `cwd:.` finds the
+/// sessions started in `current_dir`.
+fn resolve_cwd_filter(query: &mut SessionSearchQuery, current_dir: &Path) {
+    if let Some(cwd) = &query.cwd
+        && cwd.is_relative()
+    {
+        query.cwd = Some(current_dir.join(cwd));
+    }
+}
+
 fn to_rows(page: ConversationsPage) -> Vec<Row> {
     page.items.into_iter().map(|it| head_to_row(&it)).collect()
 }
@@ -275,9 +337,32 @@ fn head_to_row(item: &ConversationItem) -> Row {
         path: item.path.clone(),
         preview,
         ts,
+        detail: None,
     }
 }
 
+fn hits_to_rows(hits: Vec<SessionSearchHit>) -> Vec<Row> {
+    hits.into_iter()
+        .map(|hit| Row {
+            ts: hit
+                .timestamp
+                .as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc)),
+            preview: hit
+                .preview
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| String::from("(no message yet)")),
+            detail: hit
+                .matches
+                .first()
+                .map(|m| format!("{}: {}", m.field.label(), m.snippet)),
+            path: hit.path,
+        })
+        .collect()
+}
+
 fn preview_from_head(head: &[serde_json::Value]) -> Option<String> {
     head.iter()
         .filter_map(|value| serde_json::from_value::<ResponseItem>(value.clone()).ok())
@@ -336,12 +421,18 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
         );
 
         // Search line
-        let q = if state.query.is_empty() {
-            "Type to search".dim().to_string()
+        let q: Line = if state.query.is_empty() {
+            "Type to search all sessions (filters: cwd:DIR branch:NAME since:YYYY-MM-DD until:YYYY-MM-DD)"
+                .dim()
+                .into()
         } else {
-            format!("Search: {}", state.query)
+            let mut spans = vec![Span::from(format!("Search: {}", state.query))];
+            if let Some(err) = &state.search_error {
+                spans.push(format!("  ({err})").red());
+            }
+            spans.into()
         };
-        frame.render_widget_ref(Line::from(q), search);
+        frame.render_widget_ref(q, search);
 
         // List
         render_list(frame, list, state);
@@ -367,7 +458,12 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
 fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &PickerState) {
     let rows = &state.filtered_rows;
     if rows.is_empty() {
-        frame.render_widget_ref(Line::from("No sessions found".italic().dim()), area);
+        let message = if !state.query.is_empty() && matches!(state.index, IndexState::Building) {
+            "Indexing sessions…"
+        } else {
+            "No sessions found"
+        };
+        frame.render_widget_ref(Line::from(message.italic().dim()), area);
         return;
     }
 
@@ -388,7 +484,11 @@ fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &Pi
         let max_cols = area.width.saturating_sub(6) as usize;
         let preview = truncate_text(&row.preview, max_cols);
 
-        let line: Line = vec![marker, ts, "  ".into(), preview.into()].into();
+        let mut spans = vec![marker, ts, "  ".into(), preview.into()];
+        if let Some(detail) = &row.detail {
+            spans.push(format!("  {detail}").dim());
+        }
+        let line: Line = spans.into();
         let rect = Rect::new(area.x, y, area.width, 1);
         frame.render_widget_ref(line, rect);
         y = y.saturating_add(1);
@@ -494,4 +594,34 @@ mod tests {
         assert!(rows[0].preview.contains('A'));
         assert!(rows[1].preview.contains('B'));
     }
+
+    #[test]
+    fn relative_cwd_filter_is_resolved_against_the_current_dir() {
+        let mut query = SessionSearchQuery::parse("cwd:. cargo").unwrap();
+        resolve_cwd_filter(&mut query, Path::new("/work/repo"));
+        assert!(Path::new("/work/repo/crate").starts_with(query.cwd.as_ref().unwrap()));
+
+        let mut query = SessionSearchQuery::parse("cwd:/elsewhere").unwrap();
+        resolve_cwd_filter(&mut query, Path::new("/work/repo"));
+        assert_eq!(query.cwd, Some(PathBuf::from("/elsewhere")));
+    }
+
+    #[test]
+    fn hits_to_rows_shows_first_match() {
+        let rows = hits_to_rows(vec![SessionSearchHit {
+            path: PathBuf::from("/tmp/a.jsonl"),
+            timestamp: Some("2025-01-01T00:00:00.000Z".to_string()),
+            cwd: None,
+            branch: None,
+            preview: None,
+            matches: vec![codex_core::SearchMatch {
+                field: codex_core::SearchField::Command,
+                snippet: "cargo test".to_string(),
+            }],
+        }]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].preview, "(no message yet)");
+        assert_eq!(rows[0].detail.as_deref(), Some("command: cargo test"));
+        assert!(rows[0].ts.is_some());
+    }
 }
@@ -38,6 +38,7 @@ pub(crate) enum ApprovalRequest {
         id: String,
         command: Vec<String>,
         reason: Option<String>,
+        grant_root: Option<PathBuf>,
     },
     ApplyPatch {
         id: String,
@@ -79,6 +80,31 @@ static COMMAND_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     ]
 });
 
+/// Offered when a sandboxed command failed writing to a directory that could
+/// be made writable instead of dropping the sandbox altogether.
+static COMMAND_WITH_GRANT_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Approve and run the command without sandbox",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["G".underlined(), "rant write access".into()]),
+            description: "Allow writes to the directory for the remainder of this session and retry in the sandbox",
+            key: KeyCode::Char('g'),
+            decision: ReviewDecision::ApprovedWritableRootForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
+            description: "Do not run the command; provide feedback",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Abort,
+        },
+    ]
+});
+
 static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     vec![
         SelectOption {
@@ -114,12 +140,21 @@ pub(crate) struct UserApprovalWidget {
 impl UserApprovalWidget {
     pub(crate) fn new(approval_request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
         let confirmation_prompt = match &approval_request {
-            ApprovalRequest::Exec { reason, .. } => {
+            ApprovalRequest::Exec {
+                reason, grant_root, ..
+            } => {
                 let mut contents: Vec<Line> = vec![];
                 if let Some(reason) = reason {
                     contents.push(Line::from(reason.clone().italic()));
                     contents.push(Line::from(""));
                 }
+                if let Some(root) = grant_root {
+                    contents.push(Line::from(format!(
+                        "Write access to {} can be granted for the remainder of this session.",
+                        root.display()
+                    )));
+                    contents.push(Line::from(""));
+                }
                 Paragraph::new(contents).wrap(Wrap { trim: false })
             }
             ApprovalRequest::ApplyPatch {
@@ -146,6 +181,10 @@ impl UserApprovalWidget {
 
         Self {
             select_options: match &approval_request {
+                ApprovalRequest::Exec {
+                    grant_root: Some(_),
+                    ..
+                } => &COMMAND_WITH_GRANT_SELECT_OPTIONS,
                 ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
                 ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
             },
@@ -224,7 +263,11 @@ impl UserApprovalWidget {
 
     fn send_decision_with_feedback(&mut self, decision: ReviewDecision, feedback: String) {
         match &self.approval_request {
-            ApprovalRequest::Exec { command, .. } => {
+            ApprovalRequest::Exec {
+                command,
+                grant_root,
+                ..
+            } => {
                 let full_cmd = strip_bash_lc_and_escape(command);
                 // Construct a concise, single-line summary of the command:
                 // - If multi-line, take the first line and append " ...".
@@ -258,6 +301,22 @@ impl UserApprovalWidget {
                             " every time this session".bold(),
                         ]);
                     }
+                    ReviewDecision::ApprovedWritableRootForSession => {
+                        let root = grant_root
+                            .as_ref()
+                            .map(|root| root.display().to_string())
+                            .unwrap_or_default();
+                        result_spans.extend(vec![
+                            "✔ ".fg(Color::Green),
+                            "You ".into(),
+                            "granted".bold(),
+                            " codex write access to ".into(),
+                            root.dim(),
+                            " for this session".bold(),
+                            " to run ".into(),
+                            snippet.clone().dim(),
+                        ]);
+                    }
                     ReviewDecision::Denied => {
                         result_spans.extend(vec![
                             "✗ ".fg(Color::Red),
@@ -404,6 +463,7 @@ mod tests {
             id: "1".to_string(),
             command: vec!["echo".to_string()],
             reason: None,
+            grant_root: None,
         };
         let mut widget = UserApprovalWidget::new(req, tx);
         widget.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
@@ -429,6 +489,7 @@ mod tests {
             id: "2".to_string(),
             command: vec!["echo".to_string()],
             reason: None,
+            grant_root: None,
         };
         let mut widget = UserApprovalWidget::new(req, tx);
         widget.handle_key_event(KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::NONE));
@@ -445,4 +506,30 @@ mod tests {
             })
         )));
     }
+
+    #[test]
+    fn grant_shortcut_is_offered_with_grant_root() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::Exec {
+            id: "3".to_string(),
+            command: vec!["go".to_string(), "build".to_string()],
+            reason: None,
+            grant_root: Some(PathBuf::from("/home/user/.cache/go-build")),
+        };
+        let mut widget = UserApprovalWidget::new(req, tx);
+        widget.handle_key_event(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE));
+        assert!(widget.is_complete());
+        let mut events: Vec<AppEvent> = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            events.push(ev);
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            AppEvent::CodexOp(Op::ExecApproval {
+                decision: ReviewDecision::ApprovedWritableRootForSession,
+                ..
+            })
+        )));
+    }
 }